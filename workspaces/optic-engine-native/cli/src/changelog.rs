@@ -0,0 +1,81 @@
+use super::{spec_events, SpecPathType};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use futures::TryStreamExt;
+use optic_engine::spec_changelog;
+use std::process;
+use tokio::io::{stdout, AsyncWriteExt};
+
+pub const SUBCOMMAND_NAME: &'static str = "changelog";
+
+pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name(SUBCOMMAND_NAME)
+    .about(
+      "Writes a changelog of the endpoints changed by the batch commits of the API spec to stdout",
+    )
+    .arg(
+      Arg::with_name("since-batch")
+        .long("since-batch")
+        .value_name("BATCH_ID")
+        .takes_value(true)
+        .help("Only include batch commits made after this batch commit"),
+    )
+    .arg(
+      Arg::with_name("until-batch")
+        .long("until-batch")
+        .value_name("BATCH_ID")
+        .takes_value(true)
+        .help("Only include this batch commit and the batch commits it's based on"),
+    )
+    .arg(
+      Arg::with_name("format")
+        .long("format")
+        .required(false)
+        .value_name("FORMAT")
+        .takes_value(true)
+        .possible_values(&["json", "markdown"])
+        .default_value("json")
+        .help("The format to write the changelog in"),
+    )
+}
+
+pub async fn main<'a>(
+  command_matches: &'a ArgMatches<'a>,
+  spec_path: &str,
+  spec_path_type: SpecPathType,
+) {
+  let since_batch_id = command_matches.value_of("since-batch").map(String::from);
+  let until_batch_id = command_matches.value_of("until-batch").map(String::from);
+
+  let spec_events = spec_events(spec_path, spec_path_type)
+    .try_collect()
+    .await
+    .unwrap_or_else(|err| {
+      eprintln!("Could not read specification: {}", err);
+      process::exit(1);
+    });
+
+  let changelog = spec_changelog(
+    spec_events,
+    since_batch_id.as_ref(),
+    until_batch_id.as_ref(),
+  )
+  .unwrap_or_else(|err| {
+    eprintln!("Could not create changelog: {}", err);
+    process::exit(1);
+  });
+
+  let output = match command_matches.value_of("format") {
+    Some("markdown") => changelog.to_markdown().into_bytes(),
+    _ => serde_json::to_vec_pretty(&changelog).expect("changelog should be serializable"),
+  };
+
+  let mut stdout = stdout();
+  let written = match stdout.write_all(&output).await {
+    Ok(_) => stdout.flush().await,
+    Err(err) => Err(err),
+  };
+  if let Err(err) = written {
+    eprintln!("Could not write changelog to stdout: {}", err);
+    process::exit(1);
+  }
+}
@@ -0,0 +1,34 @@
+use clap::{App, ArgMatches, SubCommand};
+use optic_engine::{ConflictsQueries, SpecProjection};
+use std::process;
+use tokio::io::{stdout, AsyncWriteExt};
+
+pub const SUBCOMMAND_NAME: &'static str = "check";
+
+pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name(SUBCOMMAND_NAME).about(
+    "Checks the API spec for conflicts, like duplicate requests, writing them to stdout. Exits with a non-zero status when any are found",
+  )
+}
+
+pub async fn main<'a>(_command_matches: &'a ArgMatches<'a>, spec_projection: SpecProjection) {
+  let conflicts = ConflictsQueries::from(spec_projection.conflicts()).list_conflicts();
+
+  let conflicts_json =
+    serde_json::to_vec_pretty(&conflicts).expect("conflicts should be serializable");
+
+  let mut stdout = stdout();
+  let written = match stdout.write_all(&conflicts_json).await {
+    Ok(_) => stdout.flush().await,
+    Err(err) => Err(err),
+  };
+  if let Err(err) = written {
+    eprintln!("Could not write conflicts to stdout: {}", err);
+    process::exit(1);
+  }
+
+  if !conflicts.is_empty() {
+    eprintln!("Found {} conflict(s) in the spec", conflicts.len());
+    process::exit(1);
+  }
+}
@@ -0,0 +1,108 @@
+use super::{replay_spec_projection, spec_events, SpecPathType};
+use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
+use futures::TryStreamExt;
+use optic_engine::{compare_specs, spec_events_at_batch_commit};
+use optic_engine::{SpecEvent, SpecProjection};
+use std::process;
+use tokio::io::{stdout, AsyncWriteExt};
+
+pub const SUBCOMMAND_NAME: &'static str = "compare";
+
+pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name(SUBCOMMAND_NAME)
+    .about(
+      "Compares another version of the API spec with this one, writing the changes and whether they could break clients to stdout",
+    )
+    .arg(
+      Arg::with_name("from-batch")
+        .long("from-batch")
+        .value_name("BATCH_ID")
+        .takes_value(true)
+        .help("Compare the spec as it was at this batch commit"),
+    )
+    .arg(
+      Arg::with_name("from-spec")
+        .long("from-spec")
+        .value_name("FROM_SPEC_PATH")
+        .takes_value(true)
+        .help("Compare the spec at this path, of the same type as SPEC_PATH"),
+    )
+    .group(
+      ArgGroup::with_name("from")
+        .args(&["from-batch", "from-spec"])
+        .multiple(false)
+        .required(true),
+    )
+    .arg(
+      Arg::with_name("to-batch")
+        .long("to-batch")
+        .value_name("BATCH_ID")
+        .takes_value(true)
+        .help("Compare with the spec as it was at this batch commit, rather than its latest version"),
+    )
+}
+
+pub async fn main<'a>(
+  command_matches: &'a ArgMatches<'a>,
+  spec_path: &str,
+  spec_path_type: SpecPathType,
+) {
+  let from_batch_id = command_matches.value_of("from-batch");
+  let to_batch_id = command_matches.value_of("to-batch");
+
+  let spec_events = if from_batch_id.is_some() || to_batch_id.is_some() {
+    Some(read_spec_events(spec_path, spec_path_type).await)
+  } else {
+    None
+  };
+
+  let from_spec = match (command_matches.value_of("from-spec"), from_batch_id) {
+    (Some(from_spec_path), _) => replay_spec_projection(from_spec_path, spec_path_type).await,
+    (None, Some(from_batch_id)) => {
+      let spec_events = spec_events
+        .clone()
+        .expect("spec events should be read to compare a batch");
+      spec_at_batch_commit(spec_events, from_batch_id)
+    }
+    (None, None) => unreachable!("either from-spec or from-batch is required"),
+  };
+  let to_spec = match (spec_events, to_batch_id) {
+    (Some(spec_events), Some(to_batch_id)) => spec_at_batch_commit(spec_events, to_batch_id),
+    (Some(spec_events), None) => SpecProjection::from(spec_events),
+    (None, _) => replay_spec_projection(spec_path, spec_path_type).await,
+  };
+
+  let changes = compare_specs(&from_spec, &to_spec);
+
+  let changes_json = serde_json::to_vec_pretty(&changes).expect("changes should be serializable");
+
+  let mut stdout = stdout();
+  let written = match stdout.write_all(&changes_json).await {
+    Ok(_) => stdout.flush().await,
+    Err(err) => Err(err),
+  };
+  if let Err(err) = written {
+    eprintln!("Could not write changes to stdout: {}", err);
+    process::exit(1);
+  }
+}
+
+async fn read_spec_events(spec_path: &str, spec_path_type: SpecPathType) -> Vec<SpecEvent> {
+  spec_events(spec_path, spec_path_type)
+    .try_collect()
+    .await
+    .unwrap_or_else(|err| {
+      eprintln!("Could not read specification: {}", err);
+      process::exit(1);
+    })
+}
+
+fn spec_at_batch_commit(spec_events: Vec<SpecEvent>, batch_id: &str) -> SpecProjection {
+  let spec_events = spec_events_at_batch_commit(spec_events, &String::from(batch_id))
+    .unwrap_or_else(|| {
+      eprintln!("Could not find batch commit '{}' in the spec", batch_id);
+      process::exit(1);
+    });
+
+  SpecProjection::from(spec_events)
+}
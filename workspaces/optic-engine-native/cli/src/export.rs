@@ -0,0 +1,73 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use optic_engine::SpecProjection;
+use optic_engine::{export_openapi, OpenApiConfig, OpenApiVersion};
+use std::process;
+use tokio::io::{stdout, AsyncWriteExt};
+
+pub const SUBCOMMAND_NAME: &'static str = "export";
+
+pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name(SUBCOMMAND_NAME)
+    .about("Exports the API spec in another format to stdout")
+    .arg(
+      Arg::with_name("format")
+        .long("format")
+        .required(true)
+        .value_name("FORMAT")
+        .takes_value(true)
+        .possible_values(&["openapi"])
+        .help("The format to export the spec as"),
+    )
+    .arg(
+      Arg::with_name("openapi-version")
+        .long("openapi-version")
+        .required(false)
+        .value_name("OPENAPI_VERSION")
+        .takes_value(true)
+        .possible_values(&["3.0", "3.1"])
+        .default_value("3.0")
+        .help("The version of the OpenAPI specification to export to"),
+    )
+    .arg(
+      Arg::with_name("title")
+        .long("title")
+        .required(false)
+        .value_name("TITLE")
+        .takes_value(true)
+        .help("Title of the API to include in the exported document"),
+    )
+    .arg(
+      Arg::with_name("api-version")
+        .long("api-version")
+        .required(false)
+        .value_name("API_VERSION")
+        .takes_value(true)
+        .help("Version of the API to include in the exported document"),
+    )
+}
+
+pub async fn main<'a>(command_matches: &'a ArgMatches<'a>, spec_projection: SpecProjection) {
+  let mut config = OpenApiConfig::default();
+  config.version = clap::value_t!(command_matches.value_of("openapi-version"), OpenApiVersion)
+    .unwrap_or_else(|e| e.exit());
+  if let Some(title) = command_matches.value_of("title") {
+    config.title = String::from(title);
+  }
+  if let Some(api_version) = command_matches.value_of("api-version") {
+    config.api_version = String::from(api_version);
+  }
+
+  let document = export_openapi(&spec_projection, &config);
+  let document_json =
+    serde_json::to_vec_pretty(&document).expect("exported document should be serializable");
+
+  let mut stdout = stdout();
+  let written = match stdout.write_all(&document_json).await {
+    Ok(_) => stdout.flush().await,
+    Err(err) => Err(err),
+  };
+  if let Err(err) = written {
+    eprintln!("Could not write exported spec to stdout: {}", err);
+    process::exit(1);
+  }
+}
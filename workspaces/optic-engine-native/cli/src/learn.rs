@@ -1,24 +1,21 @@
-use super::events_from_chunks;
-
+use super::{input_format_arg, interaction_lines, InputFormat};
 use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
 use futures::{try_join, SinkExt, Stream, StreamExt, TryStreamExt};
 use nanoid::nanoid;
 use serde_json;
 use std::sync::Arc;
-use tokio::io::{stdin, stdout, AsyncWrite};
+use tokio::io::{stdout, AsyncWrite};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 
 use optic_engine::streams;
 use optic_engine::Aggregate;
 use optic_engine::{
-  analyze_documented_bodies, analyze_undocumented_bodies, AnalyzeUndocumentedBodiesConfig,
-  InteractionDiffResult, LearnedShapeDiffAffordancesProjection,
-  LearnedUndocumentedBodiesProjection,
-};
-use optic_engine::{
-  HttpInteraction, SpecChunkEvent, SpecEvent, SpecIdGenerator, SpecProjection, TaggedInput,
+  analyze_documented_bodies, analyze_undocumented_bodies, analyze_undocumented_urls,
+  AnalyzeUndocumentedBodiesConfig, InteractionDiffResult, LearnedShapeDiffAffordancesProjection,
+  LearnedUndocumentedBodiesProjection, LearnedUndocumentedUrlsProjection,
 };
+use optic_engine::{HttpInteraction, SpecIdGenerator, SpecProjection, TaggedInput};
 
 pub const SUBCOMMAND_NAME: &'static str = "learn";
 
@@ -31,6 +28,12 @@ pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
         .takes_value(false)
         .help("Learn shapes of undocumented bodies from interactions piped to stdin"),
     )
+    .arg(
+      Arg::with_name("undocumented-urls")
+        .long("undocumented-urls")
+        .takes_value(false)
+        .help("Learn path templates of undocumented urls from interactions piped to stdin"),
+    )
     .arg(
       Arg::with_name("shape-diffs-affordances")
         .long("shape-diffs-affordances")
@@ -44,9 +47,14 @@ pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
         .takes_value(true)
         .help("Path to file containing diff results for which to learn affordances"),
     )
+    .arg(input_format_arg())
     .group(
       ArgGroup::with_name("subject")
-        .args(&["undocumented-bodies", "shape-diffs-affordances"])
+        .args(&[
+          "undocumented-bodies",
+          "undocumented-urls",
+          "shape-diffs-affordances",
+        ])
         .multiple(false)
         .required(true),
     )
@@ -54,33 +62,37 @@ pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
 
 pub async fn main<'a>(
   command_matches: &'a ArgMatches<'a>,
-  spec_chunks: Vec<SpecChunkEvent>,
+  spec_projection: SpecProjection,
   input_queue_size: usize,
 ) {
-  let spec_events = events_from_chunks(spec_chunks).await;
+  let input_format = clap::value_t!(command_matches.value_of("input-format"), InputFormat)
+    .unwrap_or_else(|e| e.exit());
 
   if command_matches.is_present("undocumented-bodies") {
-    let stdin = stdin();
-    let interaction_lines = streams::http_interaction::json_lines(stdin);
+    let interaction_lines = interaction_lines(input_format, false);
     let sink = stdout();
 
     let learner_config = AnalyzeUndocumentedBodiesConfig::default();
 
     learn_undocumented_bodies(
-      spec_events,
+      spec_projection,
       input_queue_size,
       interaction_lines,
       learner_config,
       sink,
     )
     .await;
+  } else if command_matches.is_present("undocumented-urls") {
+    let interaction_lines = interaction_lines(input_format, false);
+    let sink = stdout();
+
+    learn_undocumented_urls(spec_projection, input_queue_size, interaction_lines, sink).await;
   } else if command_matches.is_present("shape-diffs-affordances") {
     let diffs_path = command_matches
       .value_of("tagged-diff-results")
       .expect("tagged-diff-results is required for shape-diffs learning subject");
 
-    let stdin = stdin();
-    let interaction_lines = streams::http_interaction::json_lines(stdin);
+    let interaction_lines = interaction_lines(input_format, true);
     let diffs = streams::diff::tagged_from_json_line_file(diffs_path)
       .await
       .expect("could not read diffs")
@@ -90,7 +102,7 @@ pub async fn main<'a>(
     let sink = stdout();
 
     learn_shape_diff_affordances(
-      spec_events,
+      spec_projection,
       diffs,
       input_queue_size,
       interaction_lines,
@@ -103,13 +115,13 @@ pub async fn main<'a>(
 }
 
 async fn learn_undocumented_bodies<S: 'static + AsyncWrite + Unpin + Send>(
-  spec_events: Vec<SpecEvent>,
+  spec_projection: SpecProjection,
   input_queue_size: usize,
   interaction_lines: impl Stream<Item = Result<String, std::io::Error>>,
   learner_config: AnalyzeUndocumentedBodiesConfig,
   sink: S,
 ) {
-  let spec_projection = Arc::new(SpecProjection::from(spec_events));
+  let spec_projection = Arc::new(spec_projection);
   let learner_config = Arc::new(learner_config);
 
   let (analysis_sender, analysis_receiver) = mpsc::channel(32);
@@ -178,14 +190,91 @@ async fn learn_undocumented_bodies<S: 'static + AsyncWrite + Unpin + Send>(
   try_join!(analyzing_bodies, aggregating_results).expect("essential worker task panicked");
 }
 
+async fn learn_undocumented_urls<S: 'static + AsyncWrite + Unpin + Send>(
+  spec_projection: SpecProjection,
+  input_queue_size: usize,
+  interaction_lines: impl Stream<Item = Result<String, std::io::Error>>,
+  sink: S,
+) {
+  let spec_projection = Arc::new(spec_projection);
+
+  let (analysis_sender, analysis_receiver) = mpsc::channel(32);
+
+  let analyzing_urls = {
+    let spec_projection = spec_projection.clone();
+
+    async move {
+      let analyze_results = interaction_lines
+        .map(Ok)
+        .try_for_each_concurrent(input_queue_size, |interaction_json_result| {
+          let projection = spec_projection.clone();
+          let analysis_sender = analysis_sender.clone();
+
+          let analyze_task = tokio::spawn(async move {
+            let analyze_comp = tokio::task::spawn_blocking(move || {
+              let interaction_json =
+                interaction_json_result.expect("can read interaction json line form stdin");
+
+              let interaction: HttpInteraction =
+                serde_json::from_str(&interaction_json).expect("could not parse interaction json");
+
+              analyze_undocumented_urls(&projection, interaction).collect::<Vec<_>>()
+            });
+
+            match analyze_comp.await {
+              Ok(results) => {
+                for result in results {
+                  analysis_sender
+                    .send(result)
+                    .await
+                    .expect("could not send analysis result to aggregation channel")
+                }
+              }
+              Err(err) => {
+                // ignore a single interaction not being able to deserialize
+                eprintln!("interaction ignored: {}", err);
+              }
+            }
+          });
+
+          analyze_task
+        })
+        .await;
+
+      analyze_results
+    }
+  };
+
+  let aggregating_results = tokio::spawn(async move {
+    let mut analysiss = ReceiverStream::new(analysis_receiver);
+    let mut id_generator = IdGenerator::default();
+
+    let mut learned_undocumented_urls = LearnedUndocumentedUrlsProjection::default();
+
+    while let Some(analysis) = analysiss.next().await {
+      learned_undocumented_urls.apply(analysis);
+    }
+
+    let undocumented_endpoints = learned_undocumented_urls
+      .into_undocumented_endpoints(spec_projection.endpoint(), &mut id_generator)
+      .collect::<Vec<_>>();
+
+    streams::write_to_json_lines(sink, undocumented_endpoints.iter())
+      .await
+      .expect("could not write undocumented endpoints to stdout");
+  });
+
+  try_join!(analyzing_urls, aggregating_results).expect("essential worker task panicked");
+}
+
 async fn learn_shape_diff_affordances<S: 'static + AsyncWrite + Unpin + Send>(
-  spec_events: Vec<SpecEvent>,
+  spec_projection: SpecProjection,
   diffs: impl Iterator<Item = InteractionDiffResult>,
   input_queue_size: usize,
   interaction_lines: impl Stream<Item = Result<String, std::io::Error>>,
   sink: S,
 ) {
-  let spec_projection = Arc::new(SpecProjection::from(spec_events));
+  let spec_projection = Arc::new(spec_projection);
   let mut learned_shape_diff_affordances: LearnedShapeDiffAffordancesProjection = diffs.collect();
 
   let (analysis_sender, analysis_receiver) = mpsc::channel(32);
@@ -276,6 +365,7 @@ impl SpecIdGenerator for IdGenerator {
 #[cfg(test)]
 mod test {
   use super::*;
+  use optic_engine::SpecEvent;
   use path_absolutize::*;
   use serde_json::json;
   use std::path::Path;
@@ -295,7 +385,34 @@ mod test {
 
     let learner_config = AnalyzeUndocumentedBodiesConfig::default();
 
-    learn_undocumented_bodies(spec_events, 1, interaction_lines, learner_config, sink).await;
+    learn_undocumented_bodies(
+      SpecProjection::from(spec_events),
+      1,
+      interaction_lines,
+      learner_config,
+      sink,
+    )
+    .await;
+  }
+
+  #[tokio::main]
+  #[test]
+  async fn can_learn_undocumented_urls_from_interactions() {
+    let spec_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded":{"pathId":"path_id_1","parentPathId":"root","name":"todos"}}
+    ]))
+    .expect("initial spec events should be valid events");
+
+    let interaction_lines = streams::http_interaction::json_lines(tokio::io::empty());
+    let sink = tokio::io::sink();
+
+    learn_undocumented_urls(
+      SpecProjection::from(spec_events),
+      1,
+      interaction_lines,
+      sink,
+    )
+    .await;
   }
 
   #[tokio::main]
@@ -329,6 +446,13 @@ mod test {
     let interaction_lines =
       streams::http_interaction::json_lines(fs::File::open(interactions_path).await.unwrap());
 
-    learn_shape_diff_affordances(spec_events, diffs, 1, interaction_lines, tokio::io::sink()).await;
+    learn_shape_diff_affordances(
+      SpecProjection::from(spec_events),
+      diffs,
+      1,
+      interaction_lines,
+      tokio::io::sink(),
+    )
+    .await;
   }
 }
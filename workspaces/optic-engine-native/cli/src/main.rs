@@ -1,23 +1,33 @@
 use clap::{crate_version, App, Arg, ArgGroup, SubCommand};
+use futures::stream::BoxStream;
 use futures::try_join;
 use futures::SinkExt;
-use futures::{StreamExt, TryStreamExt};
+use futures::{Stream, StreamExt, TryStreamExt};
 use num_cpus;
-use optic_engine::errors;
+use optic_engine::errors::{self, SpecEventsReadError};
 use optic_engine::streams;
 use optic_engine::HttpInteraction;
 use optic_engine::InteractionDiffResult;
-use optic_engine::SpecProjection;
 use optic_engine::{diff_interaction, DiffInteractionConfig};
+use optic_engine::{SnapshottableProjection, SpecProjection, SpecProjectionSnapshot};
 use optic_engine::{SpecChunkEvent, SpecEvent};
 use std::cmp;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::str::FromStr;
 use std::sync::Arc;
-use tokio::io::{stdin, stdout};
+use tokio::io::{stdin, stdout, AsyncWriteExt};
 use tokio::sync::mpsc;
 
+mod changelog;
+mod check;
 mod commit;
+mod compare;
+mod export;
 mod learn;
+mod revert;
+mod snapshot;
 
 fn main() {
   let cli = App::new("Optic Engine CLI")
@@ -49,6 +59,12 @@ fn main() {
         .multiple(false)
         .required(false),
     )
+    .arg(
+      Arg::with_name("no-snapshot")
+        .long("no-snapshot")
+        .takes_value(false)
+        .help("Don't use or write a cached snapshot of the spec projection next to SPEC_PATH"),
+    )
     .arg(
       Arg::with_name("core-threads")
         .long("core-threads")
@@ -63,12 +79,26 @@ fn main() {
       SubCommand::with_name("assemble")
         .about("Assembles a directory of API spec files into a single events stream"),
     )
+    .subcommand(changelog::create_subcommand())
+    .subcommand(check::create_subcommand())
     .subcommand(commit::create_subcommand())
+    .subcommand(compare::create_subcommand())
+    .subcommand(export::create_subcommand())
     .subcommand(learn::create_subcommand())
+    .subcommand(revert::create_subcommand())
     .subcommand(
       SubCommand::with_name("diff")
-        .about("Detects differences between API spec and captured interactions (default)"),
-    );
+        .about("Detects differences between API spec and captured interactions (default)")
+        .arg(input_format_arg())
+        .arg(
+          Arg::with_name("config")
+            .long("config")
+            .value_name("CONFIG_PATH")
+            .takes_value(true)
+            .help("Path to a JSON file configuring which differences should be reported"),
+        ),
+    )
+    .arg(input_format_arg().hidden(true));
 
   let matches = cli.get_matches();
 
@@ -76,7 +106,7 @@ fn main() {
     .value_of("specification")
     .expect("SPEC_PATH should be required");
   let spec_path_type = match matches.subcommand_name() {
-    Some("assemble") | Some("commit") => SpecPathType::DIR,
+    Some("assemble") | Some("commit") | Some("revert") => SpecPathType::DIR,
     _ => {
       if matches.is_present("use-spec-dir") {
         SpecPathType::DIR
@@ -86,6 +116,8 @@ fn main() {
     }
   };
 
+  let use_snapshot = !matches.is_present("no-snapshot");
+
   let core_threads_count: Option<u16> = match clap::value_t!(matches.value_of("core-threads"), u16)
   {
     Ok(count) => Some(count),
@@ -111,48 +143,57 @@ fn main() {
   ) * 4;
 
   runtime.block_on(async {
-    let spec_chunks = match spec_path_type {
-      SpecPathType::FILE => streams::spec_chunks::from_root_api_file(&spec_path)
-        .await
-        .map_err(|err| match err {
-          errors::SpecChunkLoaderError::Io(err) => {
-            eprintln!("Could not read specification file: {}", err);
-            process::exit(1);
-          }
-          errors::SpecChunkLoaderError::Json(err) => {
-            eprintln!("Specification JSON file could not be parsed: {}", err);
-            process::exit(1);
-          }
-          _ => unreachable!("Specification file not currently serialized as any other but JSON"),
-        })
-        .unwrap(),
-
-      SpecPathType::DIR => streams::spec_chunks::from_api_dir(&spec_path)
-        .await
-        .expect("should be able to find spec event chunks in a folder"),
-    };
-
     match matches.subcommand() {
       ("assemble", Some(_)) => {
         // eprintln!("assembling spec folder into spec");
-        assemble(spec_chunks).await;
+        assemble(spec_events(spec_path, spec_path_type)).await;
+      }
+      (changelog::SUBCOMMAND_NAME, Some(subcommand_matches)) => {
+        changelog::main(subcommand_matches, spec_path, spec_path_type).await
+      }
+      (check::SUBCOMMAND_NAME, Some(subcommand_matches)) => {
+        let spec_projection = load_spec_projection(spec_path, spec_path_type, use_snapshot).await;
+        check::main(subcommand_matches, spec_projection).await
       }
       (commit::SUBCOMMAND_NAME, Some(subcommand_matches)) => {
+        let spec_chunks = load_spec_chunks(spec_path, spec_path_type).await;
         commit::main(subcommand_matches, spec_chunks, spec_path).await
       }
+      (compare::SUBCOMMAND_NAME, Some(subcommand_matches)) => {
+        compare::main(subcommand_matches, spec_path, spec_path_type).await
+      }
+      (export::SUBCOMMAND_NAME, Some(subcommand_matches)) => {
+        let spec_projection = load_spec_projection(spec_path, spec_path_type, use_snapshot).await;
+        export::main(subcommand_matches, spec_projection).await
+      }
       (learn::SUBCOMMAND_NAME, Some(subcommand_matches)) => {
-        learn::main(subcommand_matches, spec_chunks, input_queue_size).await
+        let spec_projection = load_spec_projection(spec_path, spec_path_type, use_snapshot).await;
+        learn::main(subcommand_matches, spec_projection, input_queue_size).await
+      }
+      (revert::SUBCOMMAND_NAME, Some(subcommand_matches)) => {
+        let spec_chunks = load_spec_chunks(spec_path, spec_path_type).await;
+        revert::main(subcommand_matches, spec_chunks, spec_path).await
       }
       _ => {
         eprintln!("diffing interations against a spec");
         eprintln!("using input queue size {}", input_queue_size);
 
-        let diff_config = DiffInteractionConfig::default();
+        let config_path = matches
+          .subcommand_matches("diff")
+          .and_then(|diff_matches| diff_matches.value_of("config"));
+        let diff_config = match config_path {
+          Some(config_path) => load_diff_config(config_path).await,
+          None => DiffInteractionConfig::default(),
+        };
+        let diff_matches = matches.subcommand_matches("diff").unwrap_or(&matches);
+        let input_format = clap::value_t!(diff_matches.value_of("input-format"), InputFormat)
+          .unwrap_or_else(|e| e.exit());
 
         diff(
-          events_from_chunks(spec_chunks).await,
+          load_spec_projection(spec_path, spec_path_type, use_snapshot).await,
           input_queue_size,
           diff_config,
+          input_format,
         )
         .await;
       }
@@ -160,13 +201,17 @@ fn main() {
   });
 }
 
-async fn diff(events: Vec<SpecEvent>, diff_queue_size: usize, diff_config: DiffInteractionConfig) {
-  let spec_projection = Arc::new(SpecProjection::from(events));
+async fn diff(
+  spec_projection: SpecProjection,
+  diff_queue_size: usize,
+  diff_config: DiffInteractionConfig,
+  input_format: InputFormat,
+) {
+  let spec_projection = Arc::new(spec_projection);
   let diff_config = Arc::new(diff_config);
 
-  let stdin = stdin(); // TODO: deal with std in never having been attached
-
-  let interaction_lines = streams::http_interaction::json_lines(stdin);
+  // TODO: deal with std in never having been attached
+  let interaction_lines = interaction_lines(input_format, true);
 
   let (results_sender, mut results_receiver) = mpsc::channel(32); // buffer 32 results
 
@@ -247,21 +292,229 @@ async fn diff(events: Vec<SpecEvent>, diff_queue_size: usize, diff_config: DiffI
   try_join!(diffing_interactions, results_manager).expect("essential worker task panicked");
 }
 
-async fn assemble(spec_chunks: Vec<SpecChunkEvent>) {
-  let spec_events = events_from_chunks(spec_chunks).await;
+async fn load_diff_config(config_path: &str) -> DiffInteractionConfig {
+  let config_json = tokio::fs::read_to_string(config_path)
+    .await
+    .unwrap_or_else(|err| {
+      eprintln!("Could not read diff configuration file: {}", err);
+      process::exit(1);
+    });
+
+  DiffInteractionConfig::from_json_str(&config_json).unwrap_or_else(|err| {
+    eprintln!("Diff configuration JSON file could not be parsed: {}", err);
+    process::exit(1);
+  })
+}
 
-  let stdout = stdout();
+async fn assemble(spec_events: impl Stream<Item = Result<SpecEvent, SpecEventsReadError>>) {
+  tokio::pin!(spec_events);
+
+  let mut events_sink = streams::into_json_array_items(stdout());
+  let written = async {
+    events_sink.get_mut().write_u8(b'[').await?;
+    while let Some(spec_event) = spec_events.next().await {
+      let spec_event = spec_event.unwrap_or_else(|err| {
+        eprintln!("Could not read specification: {}", err);
+        process::exit(1);
+      });
+      events_sink.send(&spec_event).await?;
+    }
+    events_sink.get_mut().write_u8(b']').await?;
+    events_sink.get_mut().flush().await?;
+    Ok::<_, streams::JsonLineEncoderError>(())
+  };
 
-  streams::spec_events::write_to_json_array(stdout, &spec_events)
+  written
     .await
-    .unwrap_or_else(|err| panic!("could not write new events to stdout: {}", err));
+    .unwrap_or_else(|err| panic!("could not write assembled events to stdout: {}", err));
 }
 
+/// Stream the spec's events as they're read, so they don't all have to be held in memory
+fn spec_events(
+  spec_path: &str,
+  spec_path_type: SpecPathType,
+) -> BoxStream<'static, Result<SpecEvent, SpecEventsReadError>> {
+  let spec_path = PathBuf::from(spec_path);
+  match spec_path_type {
+    SpecPathType::FILE => streams::spec_events::stream_from_file(spec_path).boxed(),
+    SpecPathType::DIR => streams::spec_chunks::stream_from_api_dir(spec_path).boxed(),
+  }
+}
+
+/// Load the spec projection, restoring it from a snapshot cached next to the spec where possible.
+/// Snapshots are used as is while the spec files are unchanged. Otherwise only the events following
+/// the snapshot are replayed, as long as the events leading up to it are unchanged too.
+async fn load_spec_projection(
+  spec_path: &str,
+  spec_path_type: SpecPathType,
+  use_snapshot: bool,
+) -> SpecProjection {
+  let cache_path = match snapshot::cache_path(Path::new(spec_path)) {
+    Some(cache_path) if use_snapshot => cache_path,
+    _ => return replay_spec_projection(spec_path, spec_path_type).await,
+  };
+
+  // fingerprint before reading any events, so changes made while loading invalidate the snapshot
+  let spec_fingerprint = snapshot::spec_fingerprint(Path::new(spec_path), &spec_path_type).ok();
+
+  let snapshottable = match snapshot::read(&cache_path) {
+    Some(cached) if Some(&cached.spec_fingerprint) == spec_fingerprint.as_ref() => {
+      return cached.snapshot.into_projection();
+    }
+    Some(cached) => {
+      let restored = streams::spec_events::restore_snapshot(
+        cached.snapshot,
+        spec_events(spec_path, spec_path_type),
+      )
+      .await
+      .unwrap_or_else(|err| {
+        eprintln!("Could not read specification: {}", err);
+        process::exit(1);
+      });
+      match restored {
+        Some(snapshottable) => snapshottable,
+        None => replay_snapshottable_projection(spec_path, spec_path_type).await,
+      }
+    }
+    None => replay_snapshottable_projection(spec_path, spec_path_type).await,
+  };
+
+  let spec_fingerprint = match spec_fingerprint {
+    Some(spec_fingerprint) => spec_fingerprint,
+    None => return snapshottable.into_projection(),
+  };
+  match SpecProjectionSnapshot::new(snapshottable) {
+    Ok(snapshot) => {
+      let cached = snapshot::CachedSnapshot {
+        spec_fingerprint,
+        snapshot,
+      };
+      if let Err(err) = snapshot::write(&cache_path, &cached) {
+        eprintln!("Could not write projection snapshot: {}", err);
+      }
+      cached.snapshot.into_projection()
+    }
+    Err(spec_projection) => *spec_projection,
+  }
+}
+
+async fn replay_spec_projection(spec_path: &str, spec_path_type: SpecPathType) -> SpecProjection {
+  streams::spec_events::into_spec_projection(spec_events(spec_path, spec_path_type))
+    .await
+    .unwrap_or_else(|err| {
+      eprintln!("Could not read specification: {}", err);
+      process::exit(1);
+    })
+}
+
+async fn replay_snapshottable_projection(
+  spec_path: &str,
+  spec_path_type: SpecPathType,
+) -> SnapshottableProjection {
+  streams::spec_events::into_snapshottable_projection(spec_events(spec_path, spec_path_type))
+    .await
+    .unwrap_or_else(|err| {
+      eprintln!("Could not read specification: {}", err);
+      process::exit(1);
+    })
+}
+
+async fn load_spec_chunks(spec_path: &str, spec_path_type: SpecPathType) -> Vec<SpecChunkEvent> {
+  match spec_path_type {
+    SpecPathType::FILE => streams::spec_chunks::from_root_api_file(&spec_path)
+      .await
+      .map_err(|err| match err {
+        errors::SpecChunkLoaderError::Io(err) => {
+          eprintln!("Could not read specification file: {}", err);
+          process::exit(1);
+        }
+        errors::SpecChunkLoaderError::Json(err) => {
+          eprintln!("Specification JSON file could not be parsed: {}", err);
+          process::exit(1);
+        }
+        _ => unreachable!("Specification file not currently serialized as any other but JSON"),
+      })
+      .unwrap(),
+
+    SpecPathType::DIR => streams::spec_chunks::from_api_dir(&spec_path)
+      .await
+      .expect("should be able to find spec event chunks in a folder"),
+  }
+}
+
+#[derive(Clone, Copy)]
 enum SpecPathType {
   FILE,
   DIR,
 }
 
+#[derive(Clone, Copy)]
+enum InputFormat {
+  JsonLines,
+  Har,
+  Avro,
+}
+
+impl FromStr for InputFormat {
+  type Err = String;
+
+  fn from_str(format: &str) -> Result<Self, Self::Err> {
+    match format {
+      "json-lines" => Ok(Self::JsonLines),
+      "har" => Ok(Self::Har),
+      "avro" => Ok(Self::Avro),
+      _ => Err(format!("unsupported input format '{}'", format)),
+    }
+  }
+}
+
+fn input_format_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("input-format")
+    .long("input-format")
+    .value_name("INPUT_FORMAT")
+    .takes_value(true)
+    .possible_values(&["json-lines", "har", "avro"])
+    .default_value("json-lines")
+    .help(
+      "The format of the interactions piped to stdin, either json lines, a HAR document or an avro capture file",
+    )
+}
+
+/// Lines of interaction json read from stdin. Interactions recorded in other formats are
+/// converted, tagged with their uuid when `tagged` interactions are expected.
+fn interaction_lines(
+  input_format: InputFormat,
+  tagged: bool,
+) -> BoxStream<'static, Result<String, io::Error>> {
+  match input_format {
+    InputFormat::JsonLines => streams::http_interaction::json_lines(stdin()).boxed(),
+    InputFormat::Har => streams::har::interactions(stdin())
+      .map(move |interaction_result| interaction_line(interaction_result, tagged))
+      .boxed(),
+    InputFormat::Avro => streams::http_interaction::avro(stdin())
+      .map(move |interaction_result| interaction_line(interaction_result, tagged))
+      .boxed(),
+  }
+}
+
+fn interaction_line<E>(
+  interaction_result: Result<HttpInteraction, E>,
+  tagged: bool,
+) -> Result<String, io::Error>
+where
+  E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+  let interaction =
+    interaction_result.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+  let interaction_json = if tagged {
+    let tags = vec![interaction.uuid.clone()];
+    serde_json::to_string(&TaggedInput(interaction, tags))
+  } else {
+    serde_json::to_string(&interaction)
+  };
+  Ok(interaction_json?)
+}
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 struct TaggedInput<T>(T, Tags);
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
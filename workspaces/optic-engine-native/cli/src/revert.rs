@@ -0,0 +1,127 @@
+use super::events_from_chunks;
+use chrono::Utc;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use optic_engine::revert_spec_batch;
+use optic_engine::streams;
+use optic_engine::CommandContext;
+use optic_engine::{SpecChunkEvent, SpecEvent};
+use std::path::Path;
+use std::process;
+use tokio::io::stdout;
+use uuid::Uuid;
+
+pub const SUBCOMMAND_NAME: &'static str = "revert";
+
+pub fn create_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name(SUBCOMMAND_NAME)
+    .about(
+      "Reverts a batch commit by committing the commands undoing its changes as a new batch commit",
+    )
+    .arg(
+      Arg::with_name("batch-id")
+        .required(true)
+        .value_name("BATCH_ID")
+        .takes_value(true)
+        .help("The id of the batch commit to revert"),
+    )
+    .arg(
+      Arg::with_name("commit-message")
+        .short("m")
+        .required(false)
+        .value_name("COMMIT_MESSAGE")
+        .takes_value(true)
+        .help("The commit message describing the revert, naming the reverted batch by default"),
+    )
+    .arg(
+      Arg::with_name("client-session-id")
+        .long("client-session-id")
+        .required(false)
+        .value_name("CLIENT_SESSION_ID")
+        .takes_value(true)
+        .default_value("unknown-session")
+        .help("The session id of the client requesting the batch commit to be reverted"),
+    )
+    .arg(
+      Arg::with_name("client-id")
+        .long("client-id")
+        .required(false)
+        .value_name("CLIENT_ID")
+        .takes_value(true)
+        .default_value("anonymous")
+        .help("Unique id of the client requesting the batch commit to be reverted"),
+    )
+}
+
+pub async fn main<'a>(
+  command_matches: &'a ArgMatches<'a>,
+  spec_chunks: Vec<SpecChunkEvent>,
+  spec_path: impl AsRef<Path>,
+) {
+  let reverted_batch_id = command_matches
+    .value_of("batch-id")
+    .expect("batch-id is required");
+
+  let commit_message = command_matches
+    .value_of("commit-message")
+    .map(String::from)
+    .unwrap_or_else(|| format!("Revert batch commit {}", reverted_batch_id));
+
+  let client_session_id = command_matches
+    .value_of("client-session-id")
+    .expect("client-session-id is required");
+
+  let client_id = command_matches
+    .value_of("client-id")
+    .expect("client-id is required");
+
+  revert(
+    events_from_chunks(spec_chunks).await,
+    &spec_path,
+    reverted_batch_id,
+    commit_message,
+    client_id,
+    client_session_id,
+  )
+  .await;
+}
+
+async fn revert(
+  spec_events: Vec<SpecEvent>,
+  spec_dir_path: impl AsRef<Path>,
+  reverted_batch_id: &str,
+  commit_message: String,
+  client_id: &str,
+  client_session_id: &str,
+) {
+  let batch_id = Uuid::new_v4().to_hyphenated().to_string();
+  let batch_command_context = CommandContext::new(
+    batch_id.clone(),
+    String::from(client_id),
+    String::from(client_session_id),
+    Utc::now(),
+  );
+
+  let batch = revert_spec_batch(
+    spec_events,
+    &String::from(reverted_batch_id),
+    commit_message,
+    batch_command_context,
+  )
+  .unwrap_or_else(|err| {
+    eprintln!("Could not revert batch commit: {}", err);
+    process::exit(1);
+  });
+
+  let spec_chunk_event = SpecChunkEvent::batch_from_events(batch_id, batch.commit())
+    .expect("valid batch chunk should have been created");
+
+  streams::spec_chunks::to_api_dir(std::iter::once(&spec_chunk_event), spec_dir_path)
+    .await
+    .unwrap_or_else(|err| {
+      panic!("could not write new spec batch chunk to api dir: {:?}", err);
+    });
+
+  streams::spec_events::write_to_json_array(stdout(), spec_chunk_event.events())
+    .await
+    .unwrap_or_else(|err| panic!("could not write new events to stdout: {}", err))
+}
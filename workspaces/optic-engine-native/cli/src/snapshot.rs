@@ -0,0 +1,81 @@
+use super::SpecPathType;
+use optic_engine::SpecProjectionSnapshot;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// A projection snapshot as cached next to the spec, along with a fingerprint of the spec files
+/// it was taken from. Cached as the fingerprint on the first line, followed by the snapshot.
+#[derive(Debug)]
+pub struct CachedSnapshot {
+  pub spec_fingerprint: String,
+  pub snapshot: SpecProjectionSnapshot,
+}
+
+/// Snapshots are cached next to the spec file or directory, rather than in it, so they don't
+/// get mistaken for chunks.
+pub fn cache_path(spec_path: &Path) -> Option<PathBuf> {
+  let file_name = spec_path.file_name()?.to_str()?;
+  let parent = spec_path.parent().unwrap_or_else(|| Path::new(""));
+  Some(parent.join(format!("{}.projection-snapshot.json", file_name)))
+}
+
+/// Fingerprint the files making up a spec by their names, sizes and modification times, so
+/// any change to them invalidates a cached snapshot.
+pub fn spec_fingerprint(spec_path: &Path, spec_path_type: &SpecPathType) -> io::Result<String> {
+  let mut files = match spec_path_type {
+    SpecPathType::FILE => vec![spec_path.to_owned()],
+    SpecPathType::DIR => fs::read_dir(spec_path)?
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect::<Result<Vec<_>, _>>()?,
+  };
+  files.sort();
+
+  let mut hasher = DefaultHasher::new();
+  for file in files {
+    let metadata = fs::metadata(&file)?;
+    let modified = metadata
+      .modified()?
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default();
+
+    file.file_name().hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    modified.hash(&mut hasher);
+  }
+
+  Ok(format!("{:016x}", hasher.finish()))
+}
+
+pub fn read(cache_path: &Path) -> Option<CachedSnapshot> {
+  let file = fs::File::open(cache_path).ok()?;
+  let mut reader = BufReader::new(file);
+
+  let mut spec_fingerprint = String::new();
+  reader.read_line(&mut spec_fingerprint).ok()?;
+  let spec_fingerprint = spec_fingerprint.trim_end().to_owned();
+
+  match SpecProjectionSnapshot::from_reader(reader) {
+    Ok(snapshot) => Some(CachedSnapshot {
+      spec_fingerprint,
+      snapshot,
+    }),
+    Err(err) => {
+      eprintln!("Ignoring unusable projection snapshot: {}", err);
+      None
+    }
+  }
+}
+
+pub fn write(cache_path: &Path, cached: &CachedSnapshot) -> io::Result<()> {
+  let mut writer = BufWriter::new(fs::File::create(cache_path)?);
+  writeln!(writer, "{}", cached.spec_fingerprint)?;
+  cached
+    .snapshot
+    .to_writer(&mut writer)
+    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+  writer.flush()
+}
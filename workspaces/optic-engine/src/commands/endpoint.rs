@@ -6,12 +6,14 @@ use crate::projections::endpoint::ROOT_PATH_ID;
 use crate::projections::EndpointProjection;
 use crate::queries::EndpointQueries;
 use crate::state::endpoint::{
-  HttpMethod, PathComponentId, QueryParametersId, QueryParametersShapeDescriptor, RequestId,
-  RequestParameterId, ResponseId, ShapedBodyDescriptor, ShapedRequestParameterShapeDescriptor,
+  HttpMethod, HttpStatusRange, PathComponentId, QueryParametersId, QueryParametersShapeDescriptor,
+  RequestId, RequestParameterId, ResponseId, ShapedBodyDescriptor,
+  ShapedRequestParameterShapeDescriptor,
 };
 use crate::state::shape::ShapeId;
 use crate::{events::endpoint as endpoint_events, state::body};
 use cqrs_core::{Aggregate, AggregateCommand};
+use std::convert::TryFrom;
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub enum EndpointCommand {
@@ -37,6 +39,8 @@ pub enum EndpointCommand {
   // Responses
   AddResponse(AddResponse),
   AddResponseByPathAndMethod(AddResponseByPathAndMethod),
+  AddResponseRangeByPathAndMethod(AddResponseRangeByPathAndMethod),
+  AddDefaultResponseByPathAndMethod(AddDefaultResponseByPathAndMethod),
   SetResponseContentType(SetResponseContentType),
   SetResponseStatusCode(SetResponseStatusCode),
   SetResponseBodyShape(SetResponseBodyShape),
@@ -50,6 +54,7 @@ pub enum EndpointCommand {
 
   // Headers
   AddHeaderParameter(AddHeaderParameter),
+  AddResponseHeaderParameter(AddResponseHeaderParameter),
   SetHeaderParameterShape(SetHeaderParameterShape),
   RenameHeaderParameter(RenameHeaderParameter),
   UnsetHeaderParameterShape(UnsetHeaderParameterShape),
@@ -57,10 +62,34 @@ pub enum EndpointCommand {
 }
 
 impl EndpointCommand {
+  pub fn add_path_component(
+    path_id: PathComponentId,
+    parent_path_id: PathComponentId,
+    name: String,
+  ) -> EndpointCommand {
+    EndpointCommand::AddPathComponent(AddPathComponent {
+      path_id,
+      parent_path_id,
+      name,
+    })
+  }
+
   pub fn remove_path_component(path_id: PathComponentId) -> EndpointCommand {
     EndpointCommand::RemovePathComponent(RemovePathComponent { path_id })
   }
 
+  pub fn add_path_parameter(
+    path_id: PathComponentId,
+    parent_path_id: PathComponentId,
+    name: String,
+  ) -> EndpointCommand {
+    EndpointCommand::AddPathParameter(AddPathParameter {
+      path_id,
+      parent_path_id,
+      name,
+    })
+  }
+
   pub fn set_path_parameter_shape(path_id: PathComponentId, shape_id: ShapeId) -> EndpointCommand {
     EndpointCommand::SetPathParameterShape(SetPathParameterShape {
       path_id,
@@ -141,6 +170,10 @@ impl EndpointCommand {
     })
   }
 
+  pub fn unset_request_body_shape(request_id: RequestId) -> EndpointCommand {
+    EndpointCommand::UnsetRequestBodyShape(UnsetRequestBodyShape { request_id })
+  }
+
   pub fn remove_request(request_id: RequestId) -> EndpointCommand {
     EndpointCommand::RemoveRequest(RemoveRequest { request_id })
   }
@@ -162,6 +195,32 @@ impl EndpointCommand {
     })
   }
 
+  pub fn add_response_range_by_path_and_method(
+    response_id: ResponseId,
+    path_id: PathComponentId,
+    http_method: String,
+    http_status_range: HttpStatusRange,
+  ) -> EndpointCommand {
+    EndpointCommand::AddResponseRangeByPathAndMethod(AddResponseRangeByPathAndMethod {
+      response_id,
+      path_id,
+      http_method,
+      http_status_range,
+    })
+  }
+
+  pub fn add_default_response_by_path_and_method(
+    response_id: ResponseId,
+    path_id: PathComponentId,
+    http_method: String,
+  ) -> EndpointCommand {
+    EndpointCommand::AddDefaultResponseByPathAndMethod(AddDefaultResponseByPathAndMethod {
+      response_id,
+      path_id,
+      http_method,
+    })
+  }
+
   pub fn set_response_body_shape(
     response_id: ResponseId,
     shape_id: ShapeId,
@@ -178,9 +237,57 @@ impl EndpointCommand {
     })
   }
 
+  pub fn unset_response_body_shape(response_id: ResponseId) -> EndpointCommand {
+    EndpointCommand::UnsetResponseBodyShape(UnsetResponseBodyShape { response_id })
+  }
+
   pub fn remove_response(response_id: ResponseId) -> EndpointCommand {
     EndpointCommand::RemoveResponse(RemoveResponse { response_id })
   }
+
+  // Headers
+  // -------
+
+  pub fn add_header_parameter(
+    parameter_id: RequestParameterId,
+    request_id: RequestId,
+    name: String,
+  ) -> EndpointCommand {
+    EndpointCommand::AddHeaderParameter(AddHeaderParameter {
+      parameter_id,
+      request_id,
+      name,
+    })
+  }
+
+  pub fn add_response_header_parameter(
+    parameter_id: RequestParameterId,
+    response_id: ResponseId,
+    name: String,
+  ) -> EndpointCommand {
+    EndpointCommand::AddResponseHeaderParameter(AddResponseHeaderParameter {
+      parameter_id,
+      response_id,
+      name,
+    })
+  }
+
+  pub fn set_header_parameter_shape(
+    parameter_id: RequestParameterId,
+    shape_id: ShapeId,
+  ) -> EndpointCommand {
+    EndpointCommand::SetHeaderParameterShape(SetHeaderParameterShape {
+      parameter_id,
+      parameter_descriptor: ShapedRequestParameterShapeDescriptor {
+        shape_id,
+        is_removed: false,
+      },
+    })
+  }
+
+  pub fn remove_header_parameter(parameter_id: RequestParameterId) -> EndpointCommand {
+    EndpointCommand::RemoveHeaderParameter(RemoveHeaderParameter { parameter_id })
+  }
 }
 
 // Path components
@@ -273,7 +380,7 @@ pub struct SetRequestBodyShape {
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnsetRequestBodyShape {
-  request_id: RequestId,
+  pub request_id: RequestId,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -302,6 +409,23 @@ pub struct AddResponseByPathAndMethod {
   pub http_status_code: u16,
 }
 
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddResponseRangeByPathAndMethod {
+  pub response_id: ResponseId,
+  pub path_id: PathComponentId,
+  pub http_method: String,
+  pub http_status_range: HttpStatusRange,
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddDefaultResponseByPathAndMethod {
+  pub response_id: ResponseId,
+  pub path_id: PathComponentId,
+  pub http_method: String,
+}
+
 //@GOTCHA #leftovers-from-designer-ui @TODO we should probably not support this command anymore, or enforce uniqueness of content types across multiple requests
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -330,7 +454,7 @@ pub struct SetResponseBodyShape {
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnsetResponseBodyShape {
-  response_id: ResponseId,
+  pub response_id: ResponseId,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -369,35 +493,162 @@ pub struct RemoveQueryParameters {
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AddHeaderParameter {
-  parameter_id: RequestParameterId,
-  request_id: RequestId,
-  name: String,
+  pub parameter_id: RequestParameterId,
+  pub request_id: RequestId,
+  pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddResponseHeaderParameter {
+  pub parameter_id: RequestParameterId,
+  pub response_id: ResponseId,
+  pub name: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SetHeaderParameterShape {
-  parameter_id: RequestParameterId,
-  parameter_descriptor: ShapedRequestParameterShapeDescriptor,
+  pub parameter_id: RequestParameterId,
+  pub parameter_descriptor: ShapedRequestParameterShapeDescriptor,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenameHeaderParameter {
-  parameter_id: RequestParameterId,
-  name: String,
+  pub parameter_id: RequestParameterId,
+  pub name: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnsetHeaderParameterShape {
-  parameter_id: RequestParameterId,
+  pub parameter_id: RequestParameterId,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoveHeaderParameter {
-  parameter_id: RequestParameterId,
+  pub parameter_id: RequestParameterId,
+}
+
+// Conversion from events
+// ----------------------
+
+/// The command an event could have been produced by, so it can be validated again, like when
+/// replaying the events of another branch of the spec's history. Gives back events that no
+/// implemented command produces.
+impl TryFrom<EndpointEvent> for EndpointCommand {
+  type Error = EndpointEvent;
+
+  fn try_from(endpoint_event: EndpointEvent) -> Result<Self, Self::Error> {
+    let command = match endpoint_event {
+      EndpointEvent::PathComponentAdded(e) => {
+        Self::add_path_component(e.path_id, e.parent_path_id, e.name)
+      }
+      EndpointEvent::PathComponentRenamed(e) => Self::RenamePathComponent(RenamePathComponent {
+        path_id: e.path_id,
+        name: e.name,
+      }),
+      EndpointEvent::PathComponentRemoved(e) => Self::remove_path_component(e.path_id),
+
+      EndpointEvent::PathParameterAdded(e) => {
+        Self::add_path_parameter(e.path_id, e.parent_path_id, e.name)
+      }
+      EndpointEvent::PathParameterShapeSet(e) => {
+        Self::SetPathParameterShape(SetPathParameterShape {
+          path_id: e.path_id,
+          shaped_request_parameter_shape_descriptor: e.shape_descriptor,
+        })
+      }
+      EndpointEvent::PathParameterRenamed(e) => Self::RenamePathParameter(RenamePathParameter {
+        path_id: e.path_id,
+        name: e.name,
+      }),
+      EndpointEvent::PathParameterRemoved(e) => Self::remove_path_parameter(e.path_id),
+
+      EndpointEvent::QueryParametersAdded(e) => {
+        Self::add_query_parameters(e.query_parameters_id, e.path_id, e.http_method)
+      }
+      EndpointEvent::QueryParametersShapeSet(e) => {
+        Self::SetQueryParametersShape(SetQueryParametersShape {
+          query_parameters_id: e.query_parameters_id,
+          shape_descriptor: e.shape_descriptor,
+        })
+      }
+      EndpointEvent::QueryParametersRemoved(e) => {
+        Self::remove_query_parameters(e.query_parameters_id)
+      }
+
+      EndpointEvent::HeaderParameterAdded(e) => {
+        Self::add_header_parameter(e.parameter_id, e.request_id, e.name)
+      }
+      EndpointEvent::ResponseHeaderParameterAdded(e) => {
+        Self::add_response_header_parameter(e.parameter_id, e.response_id, e.name)
+      }
+      EndpointEvent::HeaderParameterRenamed(e) => {
+        Self::RenameHeaderParameter(RenameHeaderParameter {
+          parameter_id: e.parameter_id,
+          name: e.name,
+        })
+      }
+      EndpointEvent::HeaderParameterShapeSet(e) => {
+        Self::SetHeaderParameterShape(SetHeaderParameterShape {
+          parameter_id: e.parameter_id,
+          parameter_descriptor: e.parameter_descriptor,
+        })
+      }
+      EndpointEvent::HeaderParameterShapeUnset(e) => {
+        Self::UnsetHeaderParameterShape(UnsetHeaderParameterShape {
+          parameter_id: e.parameter_id,
+        })
+      }
+      EndpointEvent::HeaderParameterRemoved(e) => Self::remove_header_parameter(e.parameter_id),
+
+      EndpointEvent::RequestAdded(e) => Self::add_request(e.request_id, e.path_id, e.http_method),
+      EndpointEvent::RequestBodySet(e) => Self::SetRequestBodyShape(SetRequestBodyShape {
+        request_id: e.request_id,
+        body_descriptor: e.body_descriptor,
+      }),
+      EndpointEvent::RequestBodyUnset(e) => Self::unset_request_body_shape(e.request_id),
+      EndpointEvent::RequestRemoved(e) => Self::remove_request(e.request_id),
+
+      EndpointEvent::ResponseAddedByPathAndMethod(e) => Self::add_response_by_path_and_method(
+        e.response_id,
+        e.path_id,
+        e.http_method,
+        e.http_status_code,
+      ),
+      EndpointEvent::ResponseRangeAddedByPathAndMethod(e) => {
+        Self::add_response_range_by_path_and_method(
+          e.response_id,
+          e.path_id,
+          e.http_method,
+          e.http_status_range,
+        )
+      }
+      EndpointEvent::DefaultResponseAddedByPathAndMethod(e) => {
+        Self::add_default_response_by_path_and_method(e.response_id, e.path_id, e.http_method)
+      }
+      EndpointEvent::ResponseBodySet(e) => Self::SetResponseBodyShape(SetResponseBodyShape {
+        response_id: e.response_id,
+        body_descriptor: e.body_descriptor,
+      }),
+      EndpointEvent::ResponseBodyUnset(e) => Self::unset_response_body_shape(e.response_id),
+      EndpointEvent::ResponseRemoved(e) => Self::remove_response(e.response_id),
+
+      EndpointEvent::RequestParameterAddedByPathAndMethod(_)
+      | EndpointEvent::RequestParameterRenamed(_)
+      | EndpointEvent::RequestParameterShapeSet(_)
+      | EndpointEvent::RequestParameterShapeUnset(_)
+      | EndpointEvent::RequestParameterRemoved(_)
+      | EndpointEvent::RequestContentTypeSet(_)
+      | EndpointEvent::ResponseStatusCodeSet(_)
+      | EndpointEvent::ResponseContentTypeSet(_) => return Err(endpoint_event),
+    };
+
+    Ok(command)
+  }
 }
 
 // Command handling
@@ -628,6 +879,17 @@ impl AggregateCommand<EndpointProjection> for EndpointCommand {
         ))]
       }
 
+      EndpointCommand::UnsetRequestBodyShape(command) => {
+        validation.require(
+          validation.request_exists(&command.request_id),
+          "request must exist to unset request body shape",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::RequestBodyUnset::from(command),
+        )]
+      }
+
       EndpointCommand::RemoveRequest(command) => {
         validation.require(
           validation.request_exists(&command.request_id),
@@ -656,6 +918,36 @@ impl AggregateCommand<EndpointProjection> for EndpointCommand {
         )]
       }
 
+      EndpointCommand::AddResponseRangeByPathAndMethod(command) => {
+        validation.require(
+          !validation.response_exists(&command.response_id),
+          "response id must be assignable to add response range by path and method",
+        )?;
+        validation.require(
+          validation.path_component_id_exists(&command.path_id),
+          "path component must exist to add response range by path and method",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::ResponseRangeAddedByPathAndMethod::from(command),
+        )]
+      }
+
+      EndpointCommand::AddDefaultResponseByPathAndMethod(command) => {
+        validation.require(
+          !validation.response_exists(&command.response_id),
+          "response id must be assignable to add default response by path and method",
+        )?;
+        validation.require(
+          validation.path_component_id_exists(&command.path_id),
+          "path component must exist to add default response by path and method",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::DefaultResponseAddedByPathAndMethod::from(command),
+        )]
+      }
+
       EndpointCommand::SetResponseBodyShape(command) => {
         validation.require(
           validation.response_exists(&command.response_id),
@@ -667,6 +959,17 @@ impl AggregateCommand<EndpointProjection> for EndpointCommand {
         ))]
       }
 
+      EndpointCommand::UnsetResponseBodyShape(command) => {
+        validation.require(
+          validation.response_exists(&command.response_id),
+          "response must exist to unset response body shape",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::ResponseBodyUnset::from(command),
+        )]
+      }
+
       EndpointCommand::RemoveResponse(command) => {
         validation.require(
           validation.response_exists(&command.response_id),
@@ -678,6 +981,94 @@ impl AggregateCommand<EndpointProjection> for EndpointCommand {
         ))]
       }
 
+      // Headers
+      // -------
+      EndpointCommand::AddHeaderParameter(command) => {
+        validation.require(
+          !validation.header_parameter_exists(&command.parameter_id),
+          "header parameter id must be assignable to add header parameter",
+        )?;
+        validation.require(
+          validation.request_exists(&command.request_id),
+          "request must exist to add header parameter",
+        )?;
+        validation.require(
+          !validation.header_name_in_use(&command.request_id, &command.name),
+          "header name must not already be documented for request to add header parameter",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::HeaderParameterAdded::from(command),
+        )]
+      }
+
+      EndpointCommand::AddResponseHeaderParameter(command) => {
+        validation.require(
+          !validation.header_parameter_exists(&command.parameter_id),
+          "header parameter id must be assignable to add response header parameter",
+        )?;
+        validation.require(
+          validation.response_exists(&command.response_id),
+          "response must exist to add response header parameter",
+        )?;
+        validation.require(
+          !validation.header_name_in_use(&command.response_id, &command.name),
+          "header name must not already be documented for response to add response header parameter",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::ResponseHeaderParameterAdded::from(command),
+        )]
+      }
+
+      EndpointCommand::SetHeaderParameterShape(command) => {
+        validation.require(
+          validation.header_parameter_exists(&command.parameter_id),
+          "header parameter must exist to set header parameter shape",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::HeaderParameterShapeSet::from(command),
+        )]
+      }
+
+      EndpointCommand::RenameHeaderParameter(command) => {
+        validation.require(
+          validation.header_parameter_exists(&command.parameter_id),
+          "header parameter must exist to rename header parameter",
+        )?;
+        validation.require(
+          !validation.header_parameter_sibling_named(&command.parameter_id, &command.name),
+          "header name must not already be documented for request or response to rename header parameter",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::HeaderParameterRenamed::from(command),
+        )]
+      }
+
+      EndpointCommand::UnsetHeaderParameterShape(command) => {
+        validation.require(
+          validation.header_parameter_exists(&command.parameter_id),
+          "header parameter must exist to unset header parameter shape",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::HeaderParameterShapeUnset::from(command),
+        )]
+      }
+
+      EndpointCommand::RemoveHeaderParameter(command) => {
+        validation.require(
+          validation.header_parameter_exists(&command.parameter_id),
+          "header parameter id must be in use to be removed",
+        )?;
+
+        vec![EndpointEvent::from(
+          endpoint_events::HeaderParameterRemoved::from(command),
+        )]
+      }
+
       _ => Err(SpecCommandError::Unimplemented(
         "endpoint command not implemented for endpoint projection",
         SpecCommand::EndpointCommand(self),
@@ -776,6 +1167,45 @@ impl<'a> CommandValidationQueries<'a> {
       .get_response_node_index(response_id)
       .is_some()
   }
+
+  pub fn header_parameter_exists(&self, parameter_id: &RequestParameterId) -> bool {
+    self
+      .endpoint_projection
+      .get_header_parameter_node_index(parameter_id)
+      .is_some()
+  }
+
+  pub fn header_name_in_use(&self, parent_id: &str, name: &str) -> bool {
+    self
+      .endpoint_projection
+      .get_header_parameter_nodes(parent_id)
+      .map(|mut headers| headers.any(|(_, descriptor)| descriptor.name.eq_ignore_ascii_case(name)))
+      .unwrap_or(false)
+  }
+
+  pub fn header_parameter_sibling_named(
+    &self,
+    parameter_id: &RequestParameterId,
+    name: &str,
+  ) -> bool {
+    let parent_id = match self
+      .endpoint_projection
+      .get_header_parameter_parent_id(parameter_id)
+    {
+      Some(parent_id) => parent_id,
+      None => return false,
+    };
+
+    self
+      .endpoint_projection
+      .get_header_parameter_nodes(parent_id)
+      .map(|mut headers| {
+        headers.any(|(sibling_id, descriptor)| {
+          sibling_id != parameter_id && descriptor.name.eq_ignore_ascii_case(name)
+        })
+      })
+      .unwrap_or(false)
+  }
 }
 
 impl<'a> From<(&'a EndpointProjection, &EndpointCommand)> for CommandValidationQueries<'a> {
@@ -793,6 +1223,7 @@ impl<'a> From<(&'a EndpointProjection, &EndpointCommand)> for CommandValidationQ
 mod test {
   use super::*;
   use crate::events::SpecEvent;
+  use crate::state::endpoint::HttpStatusSpecifier;
   use cqrs_core::Aggregate;
   use insta::assert_debug_snapshot;
   use serde_json::json;
@@ -1436,6 +1867,66 @@ mod test {
     }
   }
 
+  #[test]
+  pub fn can_handle_add_response_range_and_default_response_commands() {
+    let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"RequestAdded": {"requestId": "request_1", "pathId": "path_1", "httpMethod": "GET"}},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "GET", "httpStatusCode": 200}}
+    ]))
+    .expect("initial events should be valid endpoint events");
+
+    let mut projection = EndpointProjection::from(initial_events);
+
+    let range_command: EndpointCommand = serde_json::from_value(json!(
+      {"AddResponseRangeByPathAndMethod": {"responseId": "response_2", "pathId": "path_1", "httpMethod": "GET", "httpStatusRange": "4XX"}}
+    ))
+    .expect("example command should be a valid command");
+    let default_command: EndpointCommand = serde_json::from_value(json!(
+      {"AddDefaultResponseByPathAndMethod": {"responseId": "response_3", "pathId": "path_1", "httpMethod": "GET"}}
+    ))
+    .expect("example command should be a valid command");
+
+    let mut new_events = projection
+      .execute(range_command)
+      .expect("valid command should yield new events");
+    new_events.extend(
+      projection
+        .execute(default_command)
+        .expect("valid command should yield new events"),
+    );
+    assert_eq!(new_events.len(), 2);
+    assert_debug_snapshot!(
+      "can_handle_add_response_range_and_default_response_commands__new_events",
+      new_events
+    );
+
+    let invalid_range = serde_json::from_value::<EndpointCommand>(json!(
+      {"AddResponseRangeByPathAndMethod": {"responseId": "response_2", "pathId": "path_1", "httpMethod": "GET", "httpStatusRange": "42X"}}
+    ));
+    assert!(invalid_range.is_err());
+
+    let unassignable_response: EndpointCommand = serde_json::from_value(json!(
+      {"AddDefaultResponseByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "GET"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(unassignable_response).is_err());
+
+    let unexisting_path: EndpointCommand = serde_json::from_value(json!(
+      {"AddResponseRangeByPathAndMethod": {"responseId": "response_2", "pathId": "not-a-path", "httpMethod": "GET", "httpStatusRange": "5XX"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(unexisting_path).is_err());
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+    assert_eq!(
+      projection.get_response_status_specifier(&String::from("response_3")),
+      Some(HttpStatusSpecifier::Default)
+    );
+  }
+
   #[test]
   pub fn can_handle_set_response_body_shape_command() {
     let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
@@ -1477,6 +1968,56 @@ mod test {
     }
   }
 
+  #[test]
+  pub fn can_handle_unset_request_and_response_body_shape_commands() {
+    let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"RequestAdded": {"requestId": "request_1", "pathId": "path_1", "httpMethod": "POST"}},
+      {"RequestBodySet": {"requestId": "request_1", "bodyDescriptor": { "httpContentType": "application/json", "shapeId": "shape_1", "isRemoved": false }}},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "POST", "httpStatusCode": 201}},
+      {"ResponseBodySet": {"responseId": "response_1", "bodyDescriptor": { "httpContentType": "application/json", "shapeId": "shape_2", "isRemoved": false }}}
+    ]))
+    .expect("initial events should be valid endpoint events");
+
+    let mut projection = EndpointProjection::from(initial_events);
+
+    let valid_commands: Vec<EndpointCommand> = serde_json::from_value(json!([
+      {"UnsetRequestBodyShape": {"requestId": "request_1"}},
+      {"UnsetResponseBodyShape": {"responseId": "response_1"}}
+    ]))
+    .expect("example commands should be valid commands");
+
+    let new_events = valid_commands
+      .into_iter()
+      .flat_map(|command| {
+        projection
+          .execute(command)
+          .expect("valid command should yield new events")
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(new_events.len(), 2);
+    assert_debug_snapshot!(
+      "can_handle_unset_request_and_response_body_shape_commands__new_events",
+      new_events
+    );
+
+    let unexisting_request: EndpointCommand = serde_json::from_value(json!(
+      {"UnsetRequestBodyShape": {"requestId": "not-a-request"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(unexisting_request).is_err());
+    let unexisting_response: EndpointCommand = serde_json::from_value(json!(
+      {"UnsetResponseBodyShape": {"responseId": "not-a-response"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(unexisting_response).is_err());
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+    assert!(projection.get_referenced_shape_ids().next().is_none());
+  }
+
   #[test]
   pub fn can_handle_remove_response_command() {
     let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
@@ -1519,4 +2060,251 @@ mod test {
       projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
     }
   }
+
+  #[test]
+  pub fn can_handle_add_header_parameter_command() {
+    let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"RequestAdded": {"requestId": "request_1", "pathId": "path_1", "httpMethod": "GET"}},
+      {"HeaderParameterAdded": {"parameterId": "header_1", "requestId": "request_1", "name": "Authorization"}}
+    ]))
+    .expect("initial events should be valid endpoint events");
+
+    let mut projection = EndpointProjection::from(initial_events);
+
+    let valid_command: EndpointCommand = serde_json::from_value(json!(
+      {"AddHeaderParameter": {"parameterId": "header_2", "requestId": "request_1", "name": "X-Request-Id"}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_add_header_parameter_command__new_events",
+      new_events
+    );
+
+    let unassignable_parameter: EndpointCommand = serde_json::from_value(json!(
+      {"AddHeaderParameter": {"parameterId": "header_1", "requestId": "request_1", "name": "X-Request-Id"}}
+    ))
+    .unwrap();
+    let unassignable_parameter_result = projection.execute(unassignable_parameter);
+    assert!(unassignable_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_add_header_parameter_command__unassignable_parameter_result",
+      unassignable_parameter_result.unwrap_err()
+    );
+
+    let unexisting_request: EndpointCommand = serde_json::from_value(json!(
+      {"AddHeaderParameter": {"parameterId": "header_2", "requestId": "not-a-request", "name": "X-Request-Id"}}
+    ))
+    .unwrap();
+    let unexisting_request_result = projection.execute(unexisting_request);
+    assert!(unexisting_request_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_add_header_parameter_command__unexisting_request_result",
+      unexisting_request_result.unwrap_err()
+    );
+
+    let duplicate_name: EndpointCommand = serde_json::from_value(json!(
+      {"AddHeaderParameter": {"parameterId": "header_2", "requestId": "request_1", "name": "authorization"}}
+    ))
+    .unwrap();
+    let duplicate_name_result = projection.execute(duplicate_name);
+    assert!(duplicate_name_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_add_header_parameter_command__duplicate_name_result",
+      duplicate_name_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+  }
+
+  #[test]
+  pub fn can_handle_add_response_header_parameter_command() {
+    let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "GET", "httpStatusCode": 200}}
+    ]))
+    .expect("initial events should be valid endpoint events");
+
+    let mut projection = EndpointProjection::from(initial_events);
+
+    let valid_command: EndpointCommand = serde_json::from_value(json!(
+      {"AddResponseHeaderParameter": {"parameterId": "header_1", "responseId": "response_1", "name": "X-RateLimit-Remaining"}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_add_response_header_parameter_command__new_events",
+      new_events
+    );
+
+    let unexisting_response: EndpointCommand = serde_json::from_value(json!(
+      {"AddResponseHeaderParameter": {"parameterId": "header_1", "responseId": "not-a-response", "name": "X-RateLimit-Remaining"}}
+    ))
+    .unwrap();
+    let unexisting_response_result = projection.execute(unexisting_response);
+    assert!(unexisting_response_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_add_response_header_parameter_command__unexisting_response_result",
+      unexisting_response_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+  }
+
+  #[test]
+  pub fn can_handle_rename_header_parameter_command() {
+    let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"RequestAdded": {"requestId": "request_1", "pathId": "path_1", "httpMethod": "GET"}},
+      {"HeaderParameterAdded": {"parameterId": "header_1", "requestId": "request_1", "name": "Authorization"}},
+      {"HeaderParameterAdded": {"parameterId": "header_2", "requestId": "request_1", "name": "X-Request-Id"}}
+    ]))
+    .expect("initial events should be valid endpoint events");
+
+    let mut projection = EndpointProjection::from(initial_events);
+
+    let valid_command: EndpointCommand = serde_json::from_value(json!(
+      {"RenameHeaderParameter": {"parameterId": "header_2", "name": "X-Correlation-Id"}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_rename_header_parameter_command__new_events",
+      new_events
+    );
+
+    let unexisting_parameter: EndpointCommand = serde_json::from_value(json!(
+      {"RenameHeaderParameter": {"parameterId": "not-a-parameter", "name": "X-Correlation-Id"}}
+    ))
+    .unwrap();
+    let unexisting_parameter_result = projection.execute(unexisting_parameter);
+    assert!(unexisting_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_rename_header_parameter_command__unexisting_parameter_result",
+      unexisting_parameter_result.unwrap_err()
+    );
+
+    let duplicate_name: EndpointCommand = serde_json::from_value(json!(
+      {"RenameHeaderParameter": {"parameterId": "header_2", "name": "AUTHORIZATION"}}
+    ))
+    .unwrap();
+    let duplicate_name_result = projection.execute(duplicate_name);
+    assert!(duplicate_name_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_rename_header_parameter_command__duplicate_name_result",
+      duplicate_name_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+    let renamed = projection
+      .get_header_parameter_descriptor(&String::from("header_2"))
+      .unwrap();
+    assert_eq!(renamed.name, "X-Correlation-Id");
+  }
+
+  #[test]
+  pub fn can_handle_set_header_parameter_shape_command() {
+    let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"RequestAdded": {"requestId": "request_1", "pathId": "path_1", "httpMethod": "GET"}},
+      {"HeaderParameterAdded": {"parameterId": "header_1", "requestId": "request_1", "name": "Authorization"}}
+    ]))
+    .expect("initial events should be valid endpoint events");
+
+    let mut projection = EndpointProjection::from(initial_events);
+
+    let valid_command: EndpointCommand = serde_json::from_value(json!(
+      {"SetHeaderParameterShape": {"parameterId": "header_1", "parameterDescriptor": { "shapeId": "shape_1", "isRemoved": false }}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_set_header_parameter_shape_command__new_events",
+      new_events
+    );
+
+    let unexisting_parameter: EndpointCommand = serde_json::from_value(json!(
+      {"SetHeaderParameterShape": {"parameterId": "not-a-parameter", "parameterDescriptor": { "shapeId": "shape_1", "isRemoved": false }}}
+    ))
+    .unwrap();
+    let unexisting_parameter_result = projection.execute(unexisting_parameter);
+    assert!(unexisting_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_set_header_parameter_shape_command__unexisting_parameter_result",
+      unexisting_parameter_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+  }
+
+  #[test]
+  pub fn can_handle_remove_header_parameter_command() {
+    let initial_events: Vec<EndpointEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"RequestAdded": {"requestId": "request_1", "pathId": "path_1", "httpMethod": "GET"}},
+      {"HeaderParameterAdded": {"parameterId": "header_1", "requestId": "request_1", "name": "Authorization"}}
+    ]))
+    .expect("initial events should be valid endpoint events");
+
+    let mut projection = EndpointProjection::from(initial_events);
+
+    let valid_command: EndpointCommand = serde_json::from_value(json!(
+      {"RemoveHeaderParameter": {"parameterId": "header_1"}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_remove_header_parameter_command__new_events",
+      new_events
+    );
+
+    let unexisting_parameter: EndpointCommand = serde_json::from_value(json!(
+      {"RemoveHeaderParameter": {"parameterId": "not-a-parameter"}}
+    ))
+    .unwrap();
+    let unexisting_parameter_result = projection.execute(unexisting_parameter);
+    assert!(unexisting_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_remove_header_parameter_command__unexisting_parameter_result",
+      unexisting_parameter_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+    assert!(projection
+      .get_header_parameter_nodes("request_1")
+      .unwrap()
+      .next()
+      .is_none());
+  }
 }
@@ -1,4 +1,5 @@
 use chrono::{DateTime, TimeZone, Utc};
+use std::convert::TryFrom;
 use std::process::Command;
 use uuid::Uuid;
 
@@ -49,6 +50,20 @@ impl From<ShapeCommand> for SpecCommand {
   }
 }
 
+impl TryFrom<SpecEvent> for SpecCommand {
+  type Error = SpecEvent;
+
+  fn try_from(spec_event: SpecEvent) -> Result<Self, Self::Error> {
+    match spec_event {
+      SpecEvent::EndpointEvent(event) => EndpointCommand::try_from(event)
+        .map(Self::from)
+        .map_err(SpecEvent::from),
+      SpecEvent::ShapeEvent(event) => Ok(Self::from(ShapeCommand::from(event))),
+      SpecEvent::RfcEvent(_) => Err(spec_event),
+    }
+  }
+}
+
 // Errors
 // ------
 
@@ -271,6 +286,20 @@ impl AggregateCommand<SpecProjection> for SpecCommand {
           .collect::<Vec<_>>()
       }
 
+      SpecCommand::EndpointCommand(EndpointCommand::SetHeaderParameterShape(command)) => {
+        spec_projection
+          .shape()
+          .execute(EndpointCommand::SetHeaderParameterShape(command.clone()))?; // validate shape exists
+        let endpoint_events = spec_projection
+          .endpoint()
+          .execute(EndpointCommand::SetHeaderParameterShape(command))?;
+
+        endpoint_events
+          .into_iter()
+          .map(SpecEvent::from)
+          .collect::<Vec<_>>()
+      }
+
       // endpoint commands that can be purely handled by the endpoint projection
       SpecCommand::EndpointCommand(endpoint_command) => spec_projection
         .endpoint()
@@ -286,6 +315,27 @@ impl AggregateCommand<SpecProjection> for SpecCommand {
         .map(|rfc_event| SpecEvent::from(rfc_event))
         .collect::<Vec<_>>(),
 
+      SpecCommand::ShapeCommand(ShapeCommand::RemoveShape(command)) => {
+        let is_used_by_endpoints = spec_projection
+          .endpoint()
+          .get_referenced_shape_ids()
+          .any(|shape_id| *shape_id == command.shape_id);
+        let shape_command = ShapeCommand::RemoveShape(command);
+        if is_used_by_endpoints {
+          return Err(SpecCommandError::Validation(format!(
+            "Command failed validation: shape must not be used by endpoints to remove shape, {:?}",
+            format!("{:?}", shape_command)
+          )));
+        }
+
+        spec_projection
+          .shape()
+          .execute(shape_command)?
+          .into_iter()
+          .map(SpecEvent::from)
+          .collect::<Vec<_>>()
+      }
+
       SpecCommand::ShapeCommand(shape_command) => spec_projection
         .shape()
         .execute(shape_command)?
@@ -532,6 +582,89 @@ mod test {
     }
   }
 
+  #[test]
+  pub fn can_handle_remove_shape_command() {
+    let initial_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "GET", "httpStatusCode": 200}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_2","baseShapeId":"$string","name":""}},
+      {"ResponseBodySet": {"responseId": "response_1", "bodyDescriptor": { "httpContentType": "application/json", "shapeId": "string_shape_1", "isRemoved": false }}}
+    ]))
+    .expect("initial events should be valid spec events");
+
+    let projection = SpecProjection::from(initial_events);
+
+    let valid_command: SpecCommand = serde_json::from_value(json!(
+      {"RemoveShape": {"shapeId": "string_shape_2"}}
+    ))
+    .expect("example command should be a valid command");
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+
+    let body_shape: SpecCommand = serde_json::from_value(json!(
+      {"RemoveShape": {"shapeId": "string_shape_1"}}
+    ))
+    .unwrap();
+    let body_shape_result = projection.execute(body_shape);
+    assert!(body_shape_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_remove_shape_command__body_shape_result",
+      body_shape_result.unwrap_err()
+    );
+  }
+
+  #[test]
+  pub fn remove_shape_command_considers_path_parameters() {
+    let initial_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"PathParameterAdded": {"pathId": "path_2","parentPathId": "path_1","name": "todoId"}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","name":""}},
+      {"PathParameterShapeSet": {"pathId": "path_2", "shapeDescriptor": { "shapeId": "string_shape_1", "isRemoved": false }}}
+    ]))
+    .expect("initial events should be valid spec events");
+
+    let projection = SpecProjection::from(initial_events);
+
+    let path_parameter_shape: SpecCommand = serde_json::from_value(json!(
+      {"RemoveShape": {"shapeId": "string_shape_1"}}
+    ))
+    .unwrap();
+    assert!(
+      projection.execute(path_parameter_shape).is_err(),
+      "shapes of path parameters should not be removable"
+    );
+  }
+
+  #[test]
+  pub fn remove_shape_command_ignores_removed_bodies() {
+    let initial_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": {"pathId": "path_1","parentPathId": "root","name": "todos"}},
+      {"RequestAdded": {"requestId": "request_1", "pathId": "path_1", "httpMethod": "POST"}},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "POST", "httpStatusCode": 200}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_2","baseShapeId":"$string","name":""}},
+      {"RequestBodySet": {"requestId": "request_1", "bodyDescriptor": { "httpContentType": "application/json", "shapeId": "string_shape_1", "isRemoved": true }}},
+      {"ResponseBodySet": {"responseId": "response_1", "bodyDescriptor": { "httpContentType": "application/json", "shapeId": "string_shape_2", "isRemoved": true }}}
+    ]))
+    .expect("initial events should be valid spec events");
+
+    let projection = SpecProjection::from(initial_events);
+
+    for shape_id in ["string_shape_1", "string_shape_2"] {
+      let removed_body_shape: SpecCommand = serde_json::from_value(json!(
+        {"RemoveShape": {"shapeId": shape_id}}
+      ))
+      .unwrap();
+      assert!(
+        projection.execute(removed_body_shape).is_ok(),
+        "shapes of removed bodies should be removable"
+      );
+    }
+  }
+
   #[test]
   pub fn spec_handler_provides_event_context_from_capture_context() {
     let initial_events: Vec<SpecEvent> = serde_json::from_value(json!([
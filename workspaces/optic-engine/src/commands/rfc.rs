@@ -19,6 +19,8 @@ pub enum RfcCommand {
   EndBatchCommit(EndBatchCommit),
   #[serde(skip)]
   AppendBatch(AppendBatch),
+  #[serde(skip)]
+  MergeBatch(MergeBatch),
 }
 
 impl RfcCommand {
@@ -33,6 +35,21 @@ impl RfcCommand {
     Self::StartBatchCommit(StartBatchCommit {
       batch_id,
       parent_id,
+      merged_parent_ids: vec![],
+      commit_message,
+    })
+  }
+
+  pub fn merge_batch_commit(
+    batch_id: String,
+    parent_id: String,
+    merged_parent_id: String,
+    commit_message: String,
+  ) -> Self {
+    Self::MergeBatch(MergeBatch {
+      batch_id,
+      parent_id,
+      merged_parent_id,
       commit_message,
     })
   }
@@ -78,6 +95,8 @@ pub struct MarkSetupStageComplete {
 pub struct StartBatchCommit {
   pub batch_id: String,
   pub parent_id: String,
+  #[serde(default)]
+  pub merged_parent_ids: Vec<String>,
   pub commit_message: String,
 }
 
@@ -93,6 +112,14 @@ pub struct AppendBatch {
   pub commit_message: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct MergeBatch {
+  pub batch_id: String,
+  pub parent_id: String,
+  pub merged_parent_id: String,
+  pub commit_message: String,
+}
+
 // ComandHandling
 // --------------
 
@@ -118,6 +145,30 @@ impl AggregateCommand<HistoryProjection> for RfcCommand {
         ))]
       }
 
+      RfcCommand::MergeBatch(command) => {
+        validation.require(
+          !validation.batch_commit_id_exists(&command.batch_id),
+          "batch commit id must be unassigned to merge batch commit",
+        )?;
+        validation.require(
+          validation.batch_commit_completed(&command.parent_id),
+          "parent batch commit must have ended to merge batch commit",
+        )?;
+        validation.require(
+          validation.batch_commit_completed(&command.merged_parent_id),
+          "merged batch commit must have ended to merge batch commit",
+        )?;
+
+        vec![RfcEvent::from(RfcCommand::StartBatchCommit(
+          StartBatchCommit {
+            batch_id: command.batch_id,
+            parent_id: command.parent_id,
+            merged_parent_ids: vec![command.merged_parent_id],
+            commit_message: command.commit_message,
+          },
+        ))]
+      }
+
       RfcCommand::EndBatchCommit(command) => {
         validation.require(
           validation.batch_commit_id_exists(&command.batch_id),
@@ -1,14 +1,18 @@
 use super::{EndpointCommand, SpecCommand, SpecCommandError};
 use crate::events::ShapeEvent;
+use crate::projections::shape::{Edge, Node};
 use crate::projections::ShapeProjection;
 use crate::state::shape::{
-  FieldId, FieldShapeDescriptor, FieldShapeFromShape, ParameterShapeDescriptor, ProviderDescriptor,
-  ProviderInShape, ShapeId, ShapeKind, ShapeParameterId, ShapeParametersDescriptor,
+  FieldId, FieldShapeDescriptor, FieldShapeFromShape, OneOfDiscriminatorDescriptor,
+  ParameterShapeDescriptor, ProviderDescriptor, ProviderInShape, ShapeConstraintDescriptor,
+  ShapeConstraintId, ShapeId, ShapeKind, ShapeParameterId, ShapeParametersDescriptor,
 };
 use crate::{
   events::shape as shape_events, shapehash::ShapeDescriptor, state::shape::ShapeProvider,
 };
 use cqrs_core::AggregateCommand;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -29,6 +33,13 @@ pub enum ShapeCommand {
   RenameField(RenameField),
   RemoveField(RemoveField),
   SetFieldShape(SetFieldShape),
+
+  // Constraints
+  AddShapeConstraint(AddShapeConstraint),
+  RemoveShapeConstraint(RemoveShapeConstraint),
+
+  // Discriminators
+  SetOneOfDiscriminator(SetOneOfDiscriminator),
 }
 
 impl ShapeCommand {
@@ -40,6 +51,14 @@ impl ShapeCommand {
     ))
   }
 
+  pub fn rename_shape(shape_id: ShapeId, name: String) -> Self {
+    Self::RenameShape(RenameShape { shape_id, name })
+  }
+
+  pub fn remove_shape(shape_id: ShapeId) -> Self {
+    Self::RemoveShape(RemoveShape { shape_id })
+  }
+
   pub fn add_shape_parameter(
     shape_parameter_id: ShapeParameterId,
     shape_id: ShapeId,
@@ -52,6 +71,17 @@ impl ShapeCommand {
     })
   }
 
+  pub fn rename_shape_parameter(shape_parameter_id: ShapeParameterId, name: String) -> Self {
+    Self::RenameShapeParameter(RenameShapeParameter {
+      shape_parameter_id,
+      name,
+    })
+  }
+
+  pub fn remove_shape_parameter(shape_parameter_id: ShapeParameterId) -> Self {
+    Self::RemoveShapeParameter(RemoveShapeParameter { shape_parameter_id })
+  }
+
   pub fn set_parameter_shape(
     shape_id: ShapeId,
     consuming_parameter_id: ShapeParameterId,
@@ -96,9 +126,41 @@ impl ShapeCommand {
     })
   }
 
+  pub fn rename_field(field_id: FieldId, name: String) -> Self {
+    Self::RenameField(RenameField { field_id, name })
+  }
+
   pub fn remove_field(field_id: FieldId) -> Self {
     Self::RemoveField(RemoveField { field_id })
   }
+
+  pub fn add_shape_constraint(
+    shape_constraint_id: ShapeConstraintId,
+    shape_id: ShapeId,
+    descriptor: ShapeConstraintDescriptor,
+  ) -> Self {
+    Self::AddShapeConstraint(AddShapeConstraint {
+      shape_constraint_id,
+      shape_id,
+      descriptor,
+    })
+  }
+
+  pub fn remove_shape_constraint(shape_constraint_id: ShapeConstraintId) -> Self {
+    Self::RemoveShapeConstraint(RemoveShapeConstraint {
+      shape_constraint_id,
+    })
+  }
+
+  pub fn set_one_of_discriminator(
+    shape_id: ShapeId,
+    descriptor: OneOfDiscriminatorDescriptor,
+  ) -> Self {
+    Self::SetOneOfDiscriminator(SetOneOfDiscriminator {
+      shape_id,
+      descriptor,
+    })
+  }
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -129,14 +191,14 @@ pub struct SetBaseShape {
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenameShape {
-  shape_id: ShapeId,
-  name: String,
+  pub shape_id: ShapeId,
+  pub name: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoveShape {
-  shape_id: ShapeId,
+  pub shape_id: ShapeId,
 }
 
 // Shape parameters
@@ -153,14 +215,14 @@ pub struct AddShapeParameter {
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoveShapeParameter {
-  shape_parameter_id: ShapeParameterId,
+  pub shape_parameter_id: ShapeParameterId,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenameShapeParameter {
-  shape_parameter_id: ShapeParameterId,
-  name: String,
+  pub shape_parameter_id: ShapeParameterId,
+  pub name: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -184,8 +246,8 @@ pub struct AddField {
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenameField {
-  field_id: FieldId,
-  name: String,
+  pub field_id: FieldId,
+  pub name: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -203,6 +265,76 @@ pub struct SetFieldShape {
 // Command handling
 // ----------------
 
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddShapeConstraint {
+  pub shape_constraint_id: ShapeConstraintId,
+  pub shape_id: ShapeId,
+  pub descriptor: ShapeConstraintDescriptor,
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveShapeConstraint {
+  pub shape_constraint_id: ShapeConstraintId,
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetOneOfDiscriminator {
+  pub shape_id: ShapeId,
+  pub descriptor: OneOfDiscriminatorDescriptor,
+}
+
+// Conversion from events
+// ----------------------
+
+/// The command an event could have been produced by, so it can be validated again, like when
+/// replaying the events of another branch of the spec's history
+impl From<ShapeEvent> for ShapeCommand {
+  fn from(shape_event: ShapeEvent) -> Self {
+    match shape_event {
+      ShapeEvent::ShapeAdded(e) => {
+        Self::AddShape(AddShape::new(e.shape_id, e.base_shape_id, e.name))
+      }
+      ShapeEvent::BaseShapeSet(e) => Self::SetBaseShape(SetBaseShape {
+        shape_id: e.shape_id,
+        base_shape_id: e.base_shape_id,
+      }),
+      ShapeEvent::ShapeRenamed(e) => Self::rename_shape(e.shape_id, e.name),
+      ShapeEvent::ShapeRemoved(e) => Self::remove_shape(e.shape_id),
+      ShapeEvent::ShapeParameterAdded(e) => {
+        Self::add_shape_parameter(e.shape_parameter_id, e.shape_id, e.name)
+      }
+      ShapeEvent::ShapeParameterShapeSet(e) => Self::SetParameterShape(SetParameterShape {
+        shape_descriptor: e.shape_descriptor,
+      }),
+      ShapeEvent::ShapeParameterRenamed(e) => {
+        Self::rename_shape_parameter(e.shape_parameter_id, e.name)
+      }
+      ShapeEvent::ShapeParameterRemoved(e) => Self::remove_shape_parameter(e.shape_parameter_id),
+      ShapeEvent::FieldAdded(e) => Self::AddField(AddField {
+        field_id: e.field_id,
+        shape_id: e.shape_id,
+        name: e.name,
+        shape_descriptor: e.shape_descriptor,
+      }),
+      ShapeEvent::FieldShapeSet(e) => Self::SetFieldShape(SetFieldShape {
+        shape_descriptor: e.shape_descriptor,
+      }),
+      ShapeEvent::FieldRenamed(e) => Self::rename_field(e.field_id, e.name),
+      ShapeEvent::FieldRemoved(e) => Self::remove_field(e.field_id),
+      ShapeEvent::ShapeConstraintAdded(e) => {
+        Self::add_shape_constraint(e.shape_constraint_id, e.shape_id, e.descriptor)
+      }
+      ShapeEvent::ShapeConstraintRemoved(e) => Self::remove_shape_constraint(e.shape_constraint_id),
+      ShapeEvent::OneOfDiscriminatorSet(e) => {
+        Self::set_one_of_discriminator(e.shape_id, e.descriptor)
+      }
+    }
+  }
+}
+
 impl AggregateCommand<ShapeProjection> for EndpointCommand {
   type Error = SpecCommandError;
   type Event = ShapeEvent;
@@ -248,6 +380,15 @@ impl AggregateCommand<ShapeProjection> for EndpointCommand {
         vec![] // validation only
       }
 
+      EndpointCommand::SetHeaderParameterShape(command) => {
+        validation.require(
+          validation.shape_id_exists(&command.parameter_descriptor.shape_id),
+          "shape must exist to set the header parameter shape",
+        )?;
+
+        vec![] // validation only
+      }
+
       _ => Err(SpecCommandError::Unimplemented(
         "endpoint command not implemented for shape projection",
         SpecCommand::EndpointCommand(self),
@@ -299,6 +440,36 @@ impl AggregateCommand<ShapeProjection> for ShapeCommand {
         vec![ShapeEvent::from(shape_events::BaseShapeSet::from(command))]
       }
 
+      ShapeCommand::RenameShape(command) => {
+        validation.require(
+          validation.shape_id_exists(&command.shape_id),
+          "shape must exist to rename shape",
+        )?;
+        validation.require(
+          !validation.base_shape_id_exists(&command.shape_id),
+          "shape must not be base shape to rename shape",
+        )?;
+
+        vec![ShapeEvent::from(shape_events::ShapeRenamed::from(command))]
+      }
+
+      ShapeCommand::RemoveShape(command) => {
+        validation.require(
+          validation.shape_id_exists(&command.shape_id),
+          "shape must exist to remove shape",
+        )?;
+        validation.require(
+          !validation.base_shape_id_exists(&command.shape_id),
+          "shape must not be base shape to remove shape",
+        )?;
+        validation.require(
+          !validation.shape_is_referenced(&command.shape_id),
+          "shape must not be referenced by other shapes to remove shape",
+        )?;
+
+        vec![ShapeEvent::from(shape_events::ShapeRemoved::from(command))]
+      }
+
       // Fields
       // ------
       ShapeCommand::AddField(command) => {
@@ -317,10 +488,16 @@ impl AggregateCommand<ShapeProjection> for ShapeCommand {
 
         let events = match &command.shape_descriptor {
           FieldShapeDescriptor::FieldShapeFromParameter(descriptor) => {
-            Err(SpecCommandError::Unimplemented(
-              "FieldShapeFromParameter shape descriptor not implemented for add field command",
-              SpecCommand::ShapeCommand(ShapeCommand::AddField(command)),
-            ))?
+            validation.require(
+              validation.shape_parameter_id_exists(&descriptor.shape_parameter_id),
+              "shape parameter of shape descriptor must exist to add field",
+            )?;
+            validation.require(
+              validation
+                .shape_parameter_is_of_shape(&descriptor.shape_parameter_id, &command.shape_id),
+              "shape parameter of shape descriptor must be of the field's shape to add field",
+            )?;
+            vec![ShapeEvent::from(shape_events::FieldAdded::from(command))]
           }
           FieldShapeDescriptor::FieldShapeFromShape(descriptor) => {
             validation.require(
@@ -336,10 +513,21 @@ impl AggregateCommand<ShapeProjection> for ShapeCommand {
 
       ShapeCommand::SetFieldShape(command) => match &command.shape_descriptor {
         FieldShapeDescriptor::FieldShapeFromParameter(descriptor) => {
-          Err(SpecCommandError::Unimplemented(
-            "FieldShapeFromParameter shape descriptor not implemented for set field shape",
-            SpecCommand::ShapeCommand(ShapeCommand::SetFieldShape(command)),
-          ))?
+          validation.require(
+            validation.field_id_exists(&descriptor.field_id),
+            "field of shape descriptor must exist to set field shape",
+          )?;
+          validation.require(
+            validation.shape_parameter_id_exists(&descriptor.shape_parameter_id),
+            "shape parameter of shape descriptor must exist to set field shape",
+          )?;
+          validation.require(
+            validation
+              .field_can_use_shape_parameter(&descriptor.field_id, &descriptor.shape_parameter_id),
+            "shape parameter of shape descriptor must be of the field's shape to set field shape",
+          )?;
+
+          vec![ShapeEvent::from(shape_events::FieldShapeSet::from(command))]
         }
         FieldShapeDescriptor::FieldShapeFromShape(descriptor) => {
           validation.require(
@@ -355,6 +543,15 @@ impl AggregateCommand<ShapeProjection> for ShapeCommand {
         }
       },
 
+      ShapeCommand::RenameField(command) => {
+        validation.require(
+          validation.field_id_exists(&command.field_id),
+          "field must exist to rename field",
+        )?;
+
+        vec![ShapeEvent::from(shape_events::FieldRenamed::from(command))]
+      }
+
       ShapeCommand::RemoveField(command) => {
         validation.require(
           validation.field_id_exists(&command.field_id),
@@ -383,46 +580,163 @@ impl AggregateCommand<ShapeProjection> for ShapeCommand {
         ))]
       }
 
-      ShapeCommand::SetParameterShape(command) => match &command.shape_descriptor {
-        ParameterShapeDescriptor::ProviderInShape(descriptor) => {
-          validation.require(
-            validation.shape_id_exists(&descriptor.shape_id),
-            "shape must exist to set parameter shape",
-          )?;
-          validation.require(
-            validation.shape_parameter_id_exists(&descriptor.consuming_parameter_id),
-            "consuming parameter must exist to set shape parameter shape",
-          )?;
+      ShapeCommand::RenameShapeParameter(command) => {
+        validation.require(
+          validation.shape_parameter_id_exists(&command.shape_parameter_id),
+          "shape parameter must exist to rename shape parameter",
+        )?;
+        validation.require(
+          !validation.shape_parameter_is_of_base_shape(&command.shape_parameter_id),
+          "shape parameter must not be of a base shape to rename shape parameter",
+        )?;
 
-          match &descriptor.provider_descriptor {
-            ProviderDescriptor::NoProvider(_) | ProviderDescriptor::ParameterProvider(_) => {
-              Err(SpecCommandError::Unimplemented(
-                "only ShapeProvider provider descriptor is implemented for set shape parameter shape",
-                SpecCommand::ShapeCommand(ShapeCommand::SetParameterShape(command)),
-              ))?
-            }
-            ProviderDescriptor::ShapeProvider(provider) => {
+        vec![ShapeEvent::from(shape_events::ShapeParameterRenamed::from(
+          command,
+        ))]
+      }
+
+      ShapeCommand::RemoveShapeParameter(command) => {
+        validation.require(
+          validation.shape_parameter_id_exists(&command.shape_parameter_id),
+          "shape parameter must exist to remove shape parameter",
+        )?;
+        validation.require(
+          !validation.shape_parameter_is_of_base_shape(&command.shape_parameter_id),
+          "shape parameter must not be of a base shape to remove shape parameter",
+        )?;
+        validation.require(
+          !validation.shape_parameter_is_referenced(&command.shape_parameter_id),
+          "shape parameter must not be used by fields or bindings to remove shape parameter",
+        )?;
+
+        vec![ShapeEvent::from(shape_events::ShapeParameterRemoved::from(
+          command,
+        ))]
+      }
+
+      ShapeCommand::SetParameterShape(command) => {
+        let (consuming_parameter_id, provider_descriptor) = match &command.shape_descriptor {
+          ParameterShapeDescriptor::ProviderInShape(descriptor) => {
+            validation.require(
+              validation.shape_id_exists(&descriptor.shape_id),
+              "shape must exist to set parameter shape",
+            )?;
+            (
+              &descriptor.consuming_parameter_id,
+              &descriptor.provider_descriptor,
+            )
+          }
+          ParameterShapeDescriptor::ProviderInField(descriptor) => {
+            validation.require(
+              validation.field_id_exists(&descriptor.field_id),
+              "field must exist to set parameter shape",
+            )?;
+            (
+              &descriptor.consuming_parameter_id,
+              &descriptor.provider_descriptor,
+            )
+          }
+        };
+        validation.require(
+          validation.shape_parameter_id_exists(consuming_parameter_id),
+          "consuming parameter must exist to set shape parameter shape",
+        )?;
+
+        match provider_descriptor {
+          ProviderDescriptor::ShapeProvider(provider) => validation.require(
+            validation.shape_id_exists(&provider.shape_id),
+            "provided shape must exist to set shape parameter shape",
+          )?,
+          ProviderDescriptor::ParameterProvider(provider) => {
+            validation.require(
+              validation.shape_parameter_id_exists(&provider.shape_parameter_id),
+              "provided shape parameter must exist to set shape parameter shape",
+            )?;
+            if let ParameterShapeDescriptor::ProviderInField(descriptor) = &command.shape_descriptor
+            {
               validation.require(
-                validation.shape_id_exists(&provider.shape_id),
-                "provided shape must exist to set shape parameter shape",
+                validation.field_can_use_shape_parameter(
+                  &descriptor.field_id,
+                  &provider.shape_parameter_id,
+                ),
+                "provided shape parameter must be of the field's shape to set shape parameter shape",
               )?;
-
-              vec![ShapeEvent::from(shape_events::ShapeParameterShapeSet::from(command))]
             }
           }
+          ProviderDescriptor::NoProvider(_) => {}
         }
-        ParameterShapeDescriptor::ProviderInField(provider) => {
-          Err(SpecCommandError::Unimplemented(
-            "ProviderInFIeld shape descriptor not implemented for set parameter shape",
-            SpecCommand::ShapeCommand(ShapeCommand::SetParameterShape(command)),
-          ))?
-        }
-      },
 
-      _ => Err(SpecCommandError::Unimplemented(
-        "shape command not implemented for shape projection",
-        SpecCommand::ShapeCommand(self),
-      ))?,
+        vec![ShapeEvent::from(
+          shape_events::ShapeParameterShapeSet::from(command),
+        )]
+      }
+
+      // Constraints
+      // -----------
+      ShapeCommand::AddShapeConstraint(command) => {
+        validation.require(
+          !validation.shape_constraint_id_exists(&command.shape_constraint_id),
+          "shape constraint id must be assignable to add shape constraint",
+        )?;
+        validation.require(
+          validation.shape_id_exists(&command.shape_id),
+          "shape must exist to add shape constraint",
+        )?;
+        validation.require(
+          !validation.base_shape_id_exists(&command.shape_id),
+          "shape must not be base shape to add shape constraint",
+        )?;
+        validation.require(
+          validation.shape_can_have_constraint(&command.shape_id, &command.descriptor),
+          "shape must support the constraint to add shape constraint",
+        )?;
+
+        vec![ShapeEvent::from(shape_events::ShapeConstraintAdded::from(
+          command,
+        ))]
+      }
+
+      ShapeCommand::RemoveShapeConstraint(command) => {
+        validation.require(
+          validation.shape_constraint_id_exists(&command.shape_constraint_id),
+          "shape constraint must exist to remove shape constraint",
+        )?;
+
+        vec![ShapeEvent::from(
+          shape_events::ShapeConstraintRemoved::from(command),
+        )]
+      }
+
+      // Discriminators
+      // --------------
+      ShapeCommand::SetOneOfDiscriminator(command) => {
+        validation.require(
+          validation.shape_id_exists(&command.shape_id),
+          "shape must exist to set one-of discriminator",
+        )?;
+        validation.require(
+          !validation.base_shape_id_exists(&command.shape_id),
+          "shape must not be base shape to set one-of discriminator",
+        )?;
+        validation.require(
+          validation.shape_is_of_kind(&command.shape_id, ShapeKind::OneOfKind),
+          "shape must be a one-of to set one-of discriminator",
+        )?;
+        validation.require(
+          !command.descriptor.field_name.is_empty(),
+          "field name must not be empty to set one-of discriminator",
+        )?;
+        validation.require(
+          command.descriptor.mapping.values().all(|shape_param_id| {
+            validation.shape_parameter_is_of_shape(shape_param_id, &command.shape_id)
+          }),
+          "mapped branches must be shape parameters of the one-of to set one-of discriminator",
+        )?;
+
+        vec![ShapeEvent::from(shape_events::OneOfDiscriminatorSet::from(
+          command,
+        ))]
+      }
     };
 
     Ok(events)
@@ -474,6 +788,164 @@ impl<'a> CommandValidationQueries<'a> {
       .is_some()
   }
 
+  fn shape_constraint_id_exists(&self, shape_constraint_id: &ShapeConstraintId) -> bool {
+    self
+      .shape_projection
+      .get_shape_constraint_node(shape_constraint_id)
+      .is_some()
+  }
+
+  fn shape_parameter_is_of_base_shape(&self, shape_param_id: &ShapeParameterId) -> bool {
+    let projection = self.shape_projection;
+    match projection.get_shape_parameter_node_index(shape_param_id) {
+      Some(shape_param_node_index) => projection
+        .graph
+        .edges_directed(*shape_param_node_index, petgraph::Direction::Outgoing)
+        .filter(|edge| matches!(edge.weight(), Edge::IsParameterOf))
+        .any(|edge| {
+          matches!(
+            projection.graph.node_weight(edge.target()),
+            Some(Node::CoreShape(_))
+          )
+        }),
+      None => false,
+    }
+  }
+
+  fn shape_parameter_is_of_shape(
+    &self,
+    shape_param_id: &ShapeParameterId,
+    shape_id: &ShapeId,
+  ) -> bool {
+    let projection = self.shape_projection;
+    match (
+      projection.get_shape_parameter_node_index(shape_param_id),
+      projection.get_shape_node_index(shape_id),
+    ) {
+      (Some(shape_param_node_index), Some(shape_node_index)) => projection
+        .graph
+        .edges_connecting(*shape_param_node_index, *shape_node_index)
+        .any(|edge| matches!(edge.weight(), Edge::IsParameterOf)),
+      _ => false,
+    }
+  }
+
+  fn field_can_use_shape_parameter(
+    &self,
+    field_id: &FieldId,
+    shape_param_id: &ShapeParameterId,
+  ) -> bool {
+    let projection = self.shape_projection;
+    let field_node_index = match projection.get_field_node_index(field_id) {
+      Some(field_node_index) => field_node_index,
+      None => return false,
+    };
+    match projection.get_owner_node(field_node_index) {
+      Some((_, Node::Shape(object_node))) => {
+        self.shape_parameter_is_of_shape(shape_param_id, &object_node.shape_id)
+      }
+      _ => false,
+    }
+  }
+
+  // whether any field takes its shape from or any binding is provided by the shape parameter
+  fn shape_parameter_is_referenced(&self, shape_param_id: &ShapeParameterId) -> bool {
+    let projection = self.shape_projection;
+    let shape_param_node_index = match projection.get_shape_parameter_node_index(shape_param_id) {
+      Some(shape_param_node_index) => *shape_param_node_index,
+      None => return false,
+    };
+
+    let is_live = |node_index: NodeIndex| match projection.graph.node_weight(node_index) {
+      Some(Node::Shape(node)) => projection.get_shape_node_index(&node.shape_id).is_some(),
+      Some(Node::Field(node)) => projection.get_field_node_index(&node.field_id).is_some(),
+      _ => false,
+    };
+
+    let has_fields = projection
+      .graph
+      .edges_directed(shape_param_node_index, petgraph::Direction::Outgoing)
+      .filter(|edge| matches!(edge.weight(), Edge::BelongsTo))
+      .any(|edge| is_live(edge.target()));
+
+    let is_bound = projection
+      .graph
+      .edge_references()
+      .any(|edge| match edge.weight() {
+        Edge::HasParameterBinding(binding) => {
+          binding.shape_parameter_id == *shape_param_id && is_live(edge.source())
+        }
+        _ => false,
+      });
+
+    has_fields || is_bound
+  }
+
+  // whether any shape descends from, any field is of or any shape parameter is bound to the shape
+  fn shape_is_referenced(&self, shape_id: &ShapeId) -> bool {
+    let projection = self.shape_projection;
+    let shape_node_index = match projection.get_shape_node_index(shape_id) {
+      Some(shape_node_index) => *shape_node_index,
+      None => return false,
+    };
+
+    let is_live = |node_index: NodeIndex| match projection.graph.node_weight(node_index) {
+      Some(Node::Shape(node)) => projection.get_shape_node_index(&node.shape_id).is_some(),
+      Some(Node::Field(node)) => projection.get_field_node_index(&node.field_id).is_some(),
+      _ => false,
+    };
+
+    let has_descendants_or_fields = projection
+      .graph
+      .edges_directed(shape_node_index, petgraph::Direction::Incoming)
+      .filter(|edge| matches!(edge.weight(), Edge::IsDescendantOf))
+      .map(|edge| edge.source())
+      .chain(
+        projection
+          .graph
+          .edges_directed(shape_node_index, petgraph::Direction::Outgoing)
+          .filter(|edge| matches!(edge.weight(), Edge::BelongsTo))
+          .map(|edge| edge.target()),
+      )
+      .any(is_live);
+
+    let is_bound = projection
+      .graph
+      .edge_references()
+      .any(|edge| match edge.weight() {
+        Edge::HasBinding(binding) => binding.shape_id == *shape_id && is_live(edge.source()),
+        _ => false,
+      });
+
+    has_descendants_or_fields || is_bound
+  }
+
+  fn shape_can_have_constraint(
+    &self,
+    shape_id: &ShapeId,
+    descriptor: &ShapeConstraintDescriptor,
+  ) -> bool {
+    match self.shape_projection.get_shape_node_index(shape_id) {
+      Some(shape_node_index) => self
+        .shape_projection
+        .get_core_shape_kinds(shape_node_index)
+        .expect("shape node exists as we just resolved it, so we get iterator")
+        .any(|shape_kind| descriptor.applies_to(shape_kind)),
+      None => false,
+    }
+  }
+
+  fn shape_is_of_kind(&self, shape_id: &ShapeId, kind: ShapeKind) -> bool {
+    match self.shape_projection.get_shape_node_index(shape_id) {
+      Some(shape_node_index) => self
+        .shape_projection
+        .get_core_shape_kinds(shape_node_index)
+        .expect("shape node exists as we just resolved it, so we get iterator")
+        .any(|shape_kind| *shape_kind == kind),
+      None => false,
+    }
+  }
+
   fn shape_can_have_fields(&self, shape_id: &ShapeId) -> bool {
     let shape_node_index = self.shape_projection.get_shape_node_index(shape_id);
 
@@ -832,6 +1304,70 @@ mod test {
     }
   }
 
+  #[test]
+  pub fn can_handle_set_one_of_discriminator_command() {
+    let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"one_off_shape_1","baseShapeId":"$oneOf","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"" }},
+      {"ShapeParameterAdded": {"shapeParameterId": "shape_parameter_1", "shapeId": "one_off_shape_1","name": "","shapeDescriptor": {"ProviderInShape": {"shapeId": "one_off_shape_1","providerDescriptor": {"NoProvider": {}},"consumingParameterId": "shape_parameter_1"}}}},
+      {"ShapeParameterAdded": {"shapeParameterId": "shape_parameter_2", "shapeId": "one_off_shape_1","name": "","shapeDescriptor": {"ProviderInShape": {"shapeId": "one_off_shape_1","providerDescriptor": {"NoProvider": {}},"consumingParameterId": "shape_parameter_2"}}}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"" }},
+    ]))
+    .expect("initial events should be valid shape events");
+
+    let mut projection = ShapeProjection::from(initial_events);
+
+    let valid_command: ShapeCommand = serde_json::from_value(json!(
+      {"SetOneOfDiscriminator": {"shapeId": "one_off_shape_1", "descriptor": {"fieldName": "type", "mapping": {"card": "shape_parameter_1", "bank": "shape_parameter_2"}}}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_set_one_of_discriminator_command__new_events",
+      new_events
+    );
+
+    let not_a_one_of: ShapeCommand = serde_json::from_value(json!(
+      {"SetOneOfDiscriminator": {"shapeId": "string_shape_1", "descriptor": {"fieldName": "type", "mapping": {"card": "shape_parameter_1"}}}}
+    ))
+    .unwrap();
+    let not_a_one_of_result = projection.execute(not_a_one_of);
+    assert!(not_a_one_of_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_set_one_of_discriminator_command__not_a_one_of_result",
+      not_a_one_of_result.unwrap_err()
+    );
+
+    let unexisting_branch: ShapeCommand = serde_json::from_value(json!(
+      {"SetOneOfDiscriminator": {"shapeId": "one_off_shape_1", "descriptor": {"fieldName": "type", "mapping": {"card": "not-a-parameter"}}}}
+    ))
+    .unwrap();
+    let unexisting_branch_result = projection.execute(unexisting_branch);
+    assert!(unexisting_branch_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_set_one_of_discriminator_command__unexisting_branch_result",
+      unexisting_branch_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+
+    let one_of_node_index = *projection
+      .get_shape_node_index(&String::from("one_off_shape_1"))
+      .unwrap();
+    let discriminator = projection
+      .get_one_of_discriminator(&one_of_node_index)
+      .expect("discriminator should have been set on the one-of");
+    assert_eq!(
+      discriminator.branch_for("bank"),
+      Some(&String::from("shape_parameter_2"))
+    );
+  }
+
   #[test]
   pub fn can_handle_set_parameter_shape_command() {
     let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
@@ -892,15 +1428,417 @@ mod test {
       {"SetParameterShape": {"shapeDescriptor": {"ProviderInShape": {"shapeId": "nullable_shape_1","providerDescriptor": {"NoProvider": {}},"consumingParameterId": "$nullableInner"}}}}
     ))
     .unwrap();
-    let no_shape_provider_result = projection.execute(no_shape_provider);
-    assert!(no_shape_provider_result.is_err());
+    let no_shape_provider_events = projection
+      .execute(no_shape_provider)
+      .expect("unbinding a parameter should yield new events");
+    assert_debug_snapshot!(
+      "can_handle_set_parameter_shape_command__no_shape_provider_events",
+      no_shape_provider_events
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+  }
+
+  #[test]
+  pub fn can_handle_generic_shape_commands() {
+    let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"page_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"Page"}},
+      {"ShapeParameterAdded":{"shapeParameterId":"page_item_1","shapeId":"page_1","name":"T","shapeDescriptor":{"ProviderInShape":{"shapeId":"page_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"page_item_1"}}}},
+      {"ShapeAdded":{"shapeId":"list_1","baseShapeId":"$list","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"page_1","name":"items","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"list_1"}}}},
+      {"ShapeAdded":{"shapeId":"other_object_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"other_field_1","shapeId":"other_object_1","name":"items","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"other_field_1","shapeId":"list_1"}}}},
+    ]))
+    .expect("initial events should be valid shape events");
+
+    let mut projection = ShapeProjection::from(initial_events);
+
+    let valid_commands: Vec<ShapeCommand> = serde_json::from_value(json!([
+      {"AddField":{"fieldId":"field_2","shapeId":"page_1","name":"first","shapeDescriptor":{"FieldShapeFromParameter":{"fieldId":"field_2","shapeParameterId":"page_item_1"}}}},
+      {"SetParameterShape":{"shapeDescriptor":{"ProviderInField":{"fieldId":"field_1","providerDescriptor":{"ParameterProvider":{"shapeParameterId":"page_item_1"}},"consumingParameterId":"$listItem"}}}},
+      {"SetFieldShape":{"shapeDescriptor":{"FieldShapeFromParameter":{"fieldId":"field_1","shapeParameterId":"page_item_1"}}}},
+      {"SetParameterShape":{"shapeDescriptor":{"ProviderInField":{"fieldId":"field_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"$listItem"}}}},
+    ]))
+    .expect("example commands should be valid commands");
+
+    let mut new_events = vec![];
+    for command in valid_commands {
+      let events = projection
+        .execute(command)
+        .expect("valid command should yield new events");
+      for event in events {
+        projection.apply(event.clone()); // verify this doesn't panic goes a long way to verifying the events
+        new_events.push(event);
+      }
+    }
+    assert_debug_snapshot!("can_handle_generic_shape_commands__new_events", new_events);
+
+    let parameter_of_other_shape: ShapeCommand = serde_json::from_value(json!(
+      {"AddField":{"fieldId":"field_3","shapeId":"other_object_1","name":"first","shapeDescriptor":{"FieldShapeFromParameter":{"fieldId":"field_3","shapeParameterId":"page_item_1"}}}}
+    ))
+    .unwrap();
+    let parameter_of_other_shape_result = projection.execute(parameter_of_other_shape);
+    assert!(parameter_of_other_shape_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_generic_shape_commands__parameter_of_other_shape_result",
+      parameter_of_other_shape_result.unwrap_err()
+    );
+
+    let unexisting_field: ShapeCommand = serde_json::from_value(json!(
+      {"SetParameterShape":{"shapeDescriptor":{"ProviderInField":{"fieldId":"not-a-field","providerDescriptor":{"ParameterProvider":{"shapeParameterId":"page_item_1"}},"consumingParameterId":"$listItem"}}}}
+    ))
+    .unwrap();
+    let unexisting_field_result = projection.execute(unexisting_field);
+    assert!(unexisting_field_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_generic_shape_commands__unexisting_field_result",
+      unexisting_field_result.unwrap_err()
+    );
+
+    let unexisting_provided_parameter: ShapeCommand = serde_json::from_value(json!(
+      {"SetParameterShape":{"shapeDescriptor":{"ProviderInField":{"fieldId":"field_1","providerDescriptor":{"ParameterProvider":{"shapeParameterId":"not-a-parameter"}},"consumingParameterId":"$listItem"}}}}
+    ))
+    .unwrap();
+    let unexisting_provided_parameter_result = projection.execute(unexisting_provided_parameter);
+    assert!(unexisting_provided_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_generic_shape_commands__unexisting_provided_parameter_result",
+      unexisting_provided_parameter_result.unwrap_err()
+    );
+
+    let out_of_scope_provided_parameter: ShapeCommand = serde_json::from_value(json!(
+      {"SetParameterShape":{"shapeDescriptor":{"ProviderInField":{"fieldId":"other_field_1","providerDescriptor":{"ParameterProvider":{"shapeParameterId":"page_item_1"}},"consumingParameterId":"$listItem"}}}}
+    ))
+    .unwrap();
+    let out_of_scope_provided_parameter_result =
+      projection.execute(out_of_scope_provided_parameter);
+    assert!(out_of_scope_provided_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_generic_shape_commands__out_of_scope_provided_parameter_result",
+      out_of_scope_provided_parameter_result.unwrap_err()
+    );
+
+    let remove_used_parameter: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShapeParameter":{"shapeParameterId":"page_item_1"}}
+    ))
+    .unwrap();
+    let remove_used_parameter_result = projection.execute(remove_used_parameter);
+    assert!(remove_used_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_generic_shape_commands__remove_used_parameter_result",
+      remove_used_parameter_result.unwrap_err()
+    );
+  }
+
+  #[test]
+  pub fn can_handle_add_shape_constraint_command() {
+    let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeConstraintAdded":{"shapeConstraintId":"shape_constraint_1","shapeId":"number_shape_1","descriptor":{"Integer":{}}}}
+    ]))
+    .expect("initial events should be valid shape events");
+
+    let mut projection = ShapeProjection::from(initial_events);
+
+    let valid_command: ShapeCommand = serde_json::from_value(json!(
+      {"AddShapeConstraint":{"shapeConstraintId":"shape_constraint_2","shapeId":"string_shape_1","descriptor":{"StringFormat":{"format":"uuid"}}}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_add_shape_constraint_command__new_events",
+      new_events
+    );
+
+    let existing_constraint: ShapeCommand = serde_json::from_value(json!(
+      {"AddShapeConstraint":{"shapeConstraintId":"shape_constraint_1","shapeId":"string_shape_1","descriptor":{"StringFormat":{"format":"uuid"}}}}
+    ))
+    .unwrap();
+    let existing_constraint_result = projection.execute(existing_constraint);
+    assert!(existing_constraint_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_add_shape_constraint_command__existing_constraint_result",
+      existing_constraint_result.unwrap_err()
+    );
+
+    let unsupported_constraint: ShapeCommand = serde_json::from_value(json!(
+      {"AddShapeConstraint":{"shapeConstraintId":"shape_constraint_2","shapeId":"string_shape_1","descriptor":{"Integer":{}}}}
+    ))
+    .unwrap();
+    let unsupported_constraint_result = projection.execute(unsupported_constraint);
+    assert!(unsupported_constraint_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_add_shape_constraint_command__unsupported_constraint_result",
+      unsupported_constraint_result.unwrap_err()
+    );
+
+    let base_shape_constraint: ShapeCommand = serde_json::from_value(json!(
+      {"AddShapeConstraint":{"shapeConstraintId":"shape_constraint_2","shapeId":"$string","descriptor":{"StringFormat":{"format":"uuid"}}}}
+    ))
+    .unwrap();
+    let base_shape_constraint_result = projection.execute(base_shape_constraint);
+    assert!(base_shape_constraint_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_add_shape_constraint_command__base_shape_constraint_result",
+      base_shape_constraint_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+
+    let remove_command: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShapeConstraint":{"shapeConstraintId":"shape_constraint_2"}}
+    ))
+    .unwrap();
+    let removed_events = projection
+      .execute(remove_command.clone())
+      .expect("removing existing constraint should yield new events");
+    assert_eq!(removed_events.len(), 1);
+
+    for event in removed_events {
+      projection.apply(event);
+    }
+
+    let already_removed_result = projection.execute(remove_command);
+    assert!(already_removed_result.is_err());
+  }
+
+  #[test]
+  pub fn can_handle_rename_shape_command() {
+    let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"shape_1","baseShapeId":"$object","name":"User"}}
+    ]))
+    .expect("initial events should be valid shape events");
+
+    let mut projection = ShapeProjection::from(initial_events);
+
+    let valid_command: ShapeCommand = serde_json::from_value(json!(
+      {"RenameShape":{"shapeId":"shape_1","name":"Account"}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!("can_handle_rename_shape_command__new_events", new_events);
+
+    let base_shape: ShapeCommand = serde_json::from_value(json!(
+      {"RenameShape":{"shapeId":"$object","name":"Account"}}
+    ))
+    .unwrap();
+    let base_shape_result = projection.execute(base_shape);
+    assert!(base_shape_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_rename_shape_command__base_shape_result",
+      base_shape_result.unwrap_err()
+    );
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+
+    let queries = crate::queries::ShapeQueries::new(&projection);
+    assert_eq!(
+      queries.resolve_shape_name(&String::from("shape_1")),
+      Some(&String::from("Account"))
+    );
+  }
+
+  #[test]
+  pub fn can_handle_remove_shape_command() {
+    let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"object_shape_1","baseShapeId":"$object","name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_2","baseShapeId":"$string","name":""}},
+      {"ShapeAdded":{"shapeId":"list_shape_1","baseShapeId":"$list","name":""}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"list_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"number_shape_1"}},"consumingParameterId":"$listItem"}}}},
+      {"FieldAdded":{"fieldId": "field_1", "shapeId": "object_shape_1", "name": "firstName", "shapeDescriptor":{ "FieldShapeFromShape": { "shapeId": "string_shape_1", "fieldId": "field_1"}}}},
+    ]))
+    .expect("initial events should be valid shape events");
+
+    let mut projection = ShapeProjection::from(initial_events);
+
+    let valid_command: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShape":{"shapeId":"string_shape_2"}}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!("can_handle_remove_shape_command__new_events", new_events);
+
+    let field_shape: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShape":{"shapeId":"string_shape_1"}}
+    ))
+    .unwrap();
+    let field_shape_result = projection.execute(field_shape);
+    assert!(field_shape_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_remove_shape_command__field_shape_result",
+      field_shape_result.unwrap_err()
+    );
+
+    let bound_shape: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShape":{"shapeId":"number_shape_1"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(bound_shape).is_err());
+
+    let base_shape: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShape":{"shapeId":"$string"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(base_shape).is_err());
+
+    for event in new_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+
+    let already_removed_shape: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShape":{"shapeId":"string_shape_2"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(already_removed_shape).is_err());
+
+    // once the field is removed, its shape is free to be removed as well
+    let field_removed: ShapeEvent =
+      serde_json::from_value(json!({"FieldRemoved":{"fieldId": "field_1"}})).unwrap();
+    projection.apply(field_removed);
+    let unreferenced_shape: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShape":{"shapeId":"string_shape_1"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(unreferenced_shape).is_ok());
+  }
+
+  #[test]
+  pub fn can_handle_rename_field_command() {
+    let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"object_shape_1","baseShapeId":"$object", "name": "" }},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string", "name": "" }},
+      {"FieldAdded":{"fieldId": "field_1", "shapeId": "object_shape_1", "name": "firstName", "shapeDescriptor":{ "FieldShapeFromShape": { "shapeId": "string_shape_1", "fieldId": "field_1"}}}},
+    ]))
+    .expect("initial events should be valid shape events");
+
+    let mut projection = ShapeProjection::from(initial_events);
+
+    let valid_command: ShapeCommand = serde_json::from_value(json!(
+      {"RenameField":{ "fieldId": "field_1", "name": "givenName" }}
+    ))
+    .expect("example command should be a valid command");
+
+    let new_events = projection
+      .execute(valid_command)
+      .expect("valid command should yield new events");
+    assert_eq!(new_events.len(), 1);
+    assert_debug_snapshot!("can_handle_rename_field_command__new_events", new_events);
+
+    let unexisting_field: ShapeCommand = serde_json::from_value(json!(
+      {"RenameField":{ "fieldId": "not_a_field", "name": "givenName" }}
+    ))
+    .unwrap();
+    let unexisting_field_result = projection.execute(unexisting_field);
+    assert!(unexisting_field_result.is_err());
     assert_debug_snapshot!(
-      "can_handle_set_parameter_shape_command__no_shape_provider_result",
-      no_shape_provider_result.unwrap_err()
+      "can_handle_rename_field_command__unexisting_field_result",
+      unexisting_field_result.unwrap_err()
     );
 
     for event in new_events {
       projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
     }
+
+    let queries = crate::queries::ShapeQueries::new(&projection);
+    let object_shape_id = String::from("object_shape_1");
+    assert_eq!(
+      queries.resolve_field_id(&object_shape_id, &String::from("givenName")),
+      Some(String::from("field_1"))
+    );
+    assert_eq!(
+      queries.resolve_field_id(&object_shape_id, &String::from("firstName")),
+      None
+    );
+  }
+
+  #[test]
+  pub fn can_handle_rename_and_remove_shape_parameter_commands() {
+    let initial_events: Vec<ShapeEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"one_off_shape_1","baseShapeId":"$oneOf","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"" }},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","name":"" }},
+      {"ShapeParameterAdded": {"shapeParameterId": "shape_parameter_1", "shapeId": "one_off_shape_1","name": "","shapeDescriptor": {"ProviderInShape": {"shapeId": "one_off_shape_1","providerDescriptor": {"NoProvider": {}},"consumingParameterId": "shape_parameter_1"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"one_off_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_shape_1"}},"consumingParameterId":"shape_parameter_1"}}}},
+    ]))
+    .expect("initial events should be valid shape events");
+
+    let mut projection = ShapeProjection::from(initial_events);
+
+    let rename_command: ShapeCommand = serde_json::from_value(json!(
+      {"RenameShapeParameter":{ "shapeParameterId": "shape_parameter_1", "name": "name" }}
+    ))
+    .expect("example command should be a valid command");
+    let rename_events = projection
+      .execute(rename_command)
+      .expect("valid command should yield new events");
+    assert_eq!(rename_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_rename_and_remove_shape_parameter_commands__rename_events",
+      rename_events
+    );
+
+    let base_shape_parameter: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShapeParameter":{ "shapeParameterId": "$listItem" }}
+    ))
+    .unwrap();
+    let base_shape_parameter_result = projection.execute(base_shape_parameter);
+    assert!(base_shape_parameter_result.is_err());
+    assert_debug_snapshot!(
+      "can_handle_rename_and_remove_shape_parameter_commands__base_shape_parameter_result",
+      base_shape_parameter_result.unwrap_err()
+    );
+
+    for event in rename_events {
+      projection.apply(event); // verify this doesn't panic goes a long way to verifying the events
+    }
+
+    let remove_command: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShapeParameter":{ "shapeParameterId": "shape_parameter_1" }}
+    ))
+    .expect("example command should be a valid command");
+    let remove_events = projection
+      .execute(remove_command)
+      .expect("valid command should yield new events");
+    assert_eq!(remove_events.len(), 1);
+    assert_debug_snapshot!(
+      "can_handle_rename_and_remove_shape_parameter_commands__remove_events",
+      remove_events
+    );
+
+    for event in remove_events {
+      projection.apply(event);
+    }
+
+    let queries = crate::queries::ShapeQueries::new(&projection);
+    assert!(queries
+      .resolve_parameters_to_shapes(&String::from("one_off_shape_1"))
+      .is_empty());
+
+    // with the binding gone, the provided shape is no longer referenced
+    let unbound_shape: ShapeCommand = serde_json::from_value(json!(
+      {"RemoveShape":{"shapeId":"string_shape_1"}}
+    ))
+    .unwrap();
+    assert!(projection.execute(unbound_shape).is_ok());
   }
 }
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::commands::endpoint as endpoint_commands;
 use crate::commands::EndpointCommand;
 use crate::state::endpoint::{
-  PathComponentId, QueryParametersId, QueryParametersShapeDescriptor, RequestId,
+  HttpStatusRange, PathComponentId, QueryParametersId, QueryParametersShapeDescriptor, RequestId,
   RequestParameterId, ResponseId, ShapedBodyDescriptor, ShapedRequestParameterShapeDescriptor,
 };
 
@@ -35,6 +35,14 @@ pub enum EndpointEvent {
   RequestParameterShapeUnset(RequestParameterShapeUnset),
   RequestParameterRemoved(RequestParameterRemoved),
 
+  // header parameters
+  HeaderParameterAdded(HeaderParameterAdded),
+  ResponseHeaderParameterAdded(ResponseHeaderParameterAdded),
+  HeaderParameterRenamed(HeaderParameterRenamed),
+  HeaderParameterShapeSet(HeaderParameterShapeSet),
+  HeaderParameterShapeUnset(HeaderParameterShapeUnset),
+  HeaderParameterRemoved(HeaderParameterRemoved),
+
   // Request events
   RequestAdded(RequestAdded),
   RequestContentTypeSet(RequestContentTypeSet),
@@ -44,6 +52,8 @@ pub enum EndpointEvent {
 
   // Response events
   ResponseAddedByPathAndMethod(ResponseAddedByPathAndMethod),
+  ResponseRangeAddedByPathAndMethod(ResponseRangeAddedByPathAndMethod),
+  DefaultResponseAddedByPathAndMethod(DefaultResponseAddedByPathAndMethod),
   ResponseStatusCodeSet(ResponseStatusCodeSet),
   ResponseContentTypeSet(ResponseContentTypeSet),
   ResponseBodySet(ResponseBodySet),
@@ -173,6 +183,54 @@ pub struct RequestParameterRemoved {
   pub event_context: Option<EventContext>,
 }
 
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)] // header parameters
+#[serde(rename_all = "camelCase")]
+pub struct HeaderParameterAdded {
+  pub parameter_id: RequestParameterId,
+  pub request_id: RequestId,
+  pub name: String,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseHeaderParameterAdded {
+  pub parameter_id: RequestParameterId,
+  pub response_id: ResponseId,
+  pub name: String,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderParameterRenamed {
+  pub parameter_id: RequestParameterId,
+  pub name: String,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderParameterShapeSet {
+  pub parameter_id: RequestParameterId,
+  pub parameter_descriptor: ShapedRequestParameterShapeDescriptor,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderParameterShapeUnset {
+  pub parameter_id: RequestParameterId,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderParameterRemoved {
+  pub parameter_id: RequestParameterId,
+  pub event_context: Option<EventContext>,
+}
+
 #[derive(Deserialize, Debug, PartialEq, Serialize, Clone)] // Request events
 #[serde(rename_all = "camelCase")]
 pub struct RequestAdded {
@@ -222,6 +280,25 @@ pub struct ResponseAddedByPathAndMethod {
   pub event_context: Option<EventContext>,
 }
 
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseRangeAddedByPathAndMethod {
+  pub response_id: ResponseId,
+  pub path_id: PathComponentId,
+  pub http_method: String,
+  pub http_status_range: HttpStatusRange,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultResponseAddedByPathAndMethod {
+  pub response_id: ResponseId,
+  pub path_id: PathComponentId,
+  pub http_method: String,
+  pub event_context: Option<EventContext>,
+}
+
 #[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ResponseStatusCodeSet {
@@ -285,6 +362,14 @@ impl Event for EndpointEvent {
       EndpointEvent::RequestParameterShapeUnset(evt) => evt.event_type(),
       EndpointEvent::RequestParameterRemoved(evt) => evt.event_type(),
 
+      // header parameters
+      EndpointEvent::HeaderParameterAdded(evt) => evt.event_type(),
+      EndpointEvent::ResponseHeaderParameterAdded(evt) => evt.event_type(),
+      EndpointEvent::HeaderParameterRenamed(evt) => evt.event_type(),
+      EndpointEvent::HeaderParameterShapeSet(evt) => evt.event_type(),
+      EndpointEvent::HeaderParameterShapeUnset(evt) => evt.event_type(),
+      EndpointEvent::HeaderParameterRemoved(evt) => evt.event_type(),
+
       // Request events
       EndpointEvent::RequestAdded(evt) => evt.event_type(),
       EndpointEvent::RequestContentTypeSet(evt) => evt.event_type(),
@@ -294,6 +379,8 @@ impl Event for EndpointEvent {
 
       // Response events
       EndpointEvent::ResponseAddedByPathAndMethod(evt) => evt.event_type(),
+      EndpointEvent::ResponseRangeAddedByPathAndMethod(evt) => evt.event_type(),
+      EndpointEvent::DefaultResponseAddedByPathAndMethod(evt) => evt.event_type(),
       EndpointEvent::ResponseStatusCodeSet(evt) => evt.event_type(),
       EndpointEvent::ResponseContentTypeSet(evt) => evt.event_type(),
       EndpointEvent::ResponseBodySet(evt) => evt.event_type(),
@@ -330,6 +417,14 @@ impl WithEventContext for EndpointEvent {
       EndpointEvent::RequestParameterShapeUnset(evt) => evt.event_context.replace(event_context),
       EndpointEvent::RequestParameterRemoved(evt) => evt.event_context.replace(event_context),
 
+      // header parameters
+      EndpointEvent::HeaderParameterAdded(evt) => evt.event_context.replace(event_context),
+      EndpointEvent::ResponseHeaderParameterAdded(evt) => evt.event_context.replace(event_context),
+      EndpointEvent::HeaderParameterRenamed(evt) => evt.event_context.replace(event_context),
+      EndpointEvent::HeaderParameterShapeSet(evt) => evt.event_context.replace(event_context),
+      EndpointEvent::HeaderParameterShapeUnset(evt) => evt.event_context.replace(event_context),
+      EndpointEvent::HeaderParameterRemoved(evt) => evt.event_context.replace(event_context),
+
       // Request events
       EndpointEvent::RequestAdded(evt) => evt.event_context.replace(event_context),
       EndpointEvent::RequestContentTypeSet(evt) => evt.event_context.replace(event_context),
@@ -339,6 +434,12 @@ impl WithEventContext for EndpointEvent {
 
       // Response events
       EndpointEvent::ResponseAddedByPathAndMethod(evt) => evt.event_context.replace(event_context),
+      EndpointEvent::ResponseRangeAddedByPathAndMethod(evt) => {
+        evt.event_context.replace(event_context)
+      }
+      EndpointEvent::DefaultResponseAddedByPathAndMethod(evt) => {
+        evt.event_context.replace(event_context)
+      }
       EndpointEvent::ResponseStatusCodeSet(evt) => evt.event_context.replace(event_context),
       EndpointEvent::ResponseContentTypeSet(evt) => evt.event_context.replace(event_context),
       EndpointEvent::ResponseBodySet(evt) => evt.event_context.replace(event_context),
@@ -438,6 +539,42 @@ impl Event for RequestParameterRemoved {
   }
 }
 
+impl Event for HeaderParameterAdded {
+  fn event_type(&self) -> &'static str {
+    "HeaderParameterAdded"
+  }
+}
+
+impl Event for ResponseHeaderParameterAdded {
+  fn event_type(&self) -> &'static str {
+    "ResponseHeaderParameterAdded"
+  }
+}
+
+impl Event for HeaderParameterRenamed {
+  fn event_type(&self) -> &'static str {
+    "HeaderParameterRenamed"
+  }
+}
+
+impl Event for HeaderParameterShapeSet {
+  fn event_type(&self) -> &'static str {
+    "HeaderParameterShapeSet"
+  }
+}
+
+impl Event for HeaderParameterShapeUnset {
+  fn event_type(&self) -> &'static str {
+    "HeaderParameterShapeUnset"
+  }
+}
+
+impl Event for HeaderParameterRemoved {
+  fn event_type(&self) -> &'static str {
+    "HeaderParameterRemoved"
+  }
+}
+
 impl Event for RequestAdded {
   fn event_type(&self) -> &'static str {
     "RequestAdded"
@@ -474,6 +611,18 @@ impl Event for ResponseAddedByPathAndMethod {
   }
 }
 
+impl Event for ResponseRangeAddedByPathAndMethod {
+  fn event_type(&self) -> &'static str {
+    "ResponseRangeAddedByPathAndMethod"
+  }
+}
+
+impl Event for DefaultResponseAddedByPathAndMethod {
+  fn event_type(&self) -> &'static str {
+    "DefaultResponseAddedByPathAndMethod"
+  }
+}
+
 impl Event for ResponseStatusCodeSet {
   fn event_type(&self) -> &'static str {
     "ResponseStatusCodeSet"
@@ -564,6 +713,42 @@ impl From<QueryParametersRemoved> for EndpointEvent {
   }
 }
 
+impl From<HeaderParameterAdded> for EndpointEvent {
+  fn from(event: HeaderParameterAdded) -> Self {
+    Self::HeaderParameterAdded(event)
+  }
+}
+
+impl From<ResponseHeaderParameterAdded> for EndpointEvent {
+  fn from(event: ResponseHeaderParameterAdded) -> Self {
+    Self::ResponseHeaderParameterAdded(event)
+  }
+}
+
+impl From<HeaderParameterRenamed> for EndpointEvent {
+  fn from(event: HeaderParameterRenamed) -> Self {
+    Self::HeaderParameterRenamed(event)
+  }
+}
+
+impl From<HeaderParameterShapeSet> for EndpointEvent {
+  fn from(event: HeaderParameterShapeSet) -> Self {
+    Self::HeaderParameterShapeSet(event)
+  }
+}
+
+impl From<HeaderParameterShapeUnset> for EndpointEvent {
+  fn from(event: HeaderParameterShapeUnset) -> Self {
+    Self::HeaderParameterShapeUnset(event)
+  }
+}
+
+impl From<HeaderParameterRemoved> for EndpointEvent {
+  fn from(event: HeaderParameterRemoved) -> Self {
+    Self::HeaderParameterRemoved(event)
+  }
+}
+
 impl From<RequestAdded> for EndpointEvent {
   fn from(event: RequestAdded) -> Self {
     Self::RequestAdded(event)
@@ -576,6 +761,12 @@ impl From<RequestBodySet> for EndpointEvent {
   }
 }
 
+impl From<RequestBodyUnset> for EndpointEvent {
+  fn from(event: RequestBodyUnset) -> Self {
+    Self::RequestBodyUnset(event)
+  }
+}
+
 impl From<RequestRemoved> for EndpointEvent {
   fn from(event: RequestRemoved) -> Self {
     Self::RequestRemoved(event)
@@ -588,12 +779,30 @@ impl From<ResponseAddedByPathAndMethod> for EndpointEvent {
   }
 }
 
+impl From<ResponseRangeAddedByPathAndMethod> for EndpointEvent {
+  fn from(event: ResponseRangeAddedByPathAndMethod) -> Self {
+    Self::ResponseRangeAddedByPathAndMethod(event)
+  }
+}
+
+impl From<DefaultResponseAddedByPathAndMethod> for EndpointEvent {
+  fn from(event: DefaultResponseAddedByPathAndMethod) -> Self {
+    Self::DefaultResponseAddedByPathAndMethod(event)
+  }
+}
+
 impl From<ResponseBodySet> for EndpointEvent {
   fn from(event: ResponseBodySet) -> Self {
     Self::ResponseBodySet(event)
   }
 }
 
+impl From<ResponseBodyUnset> for EndpointEvent {
+  fn from(event: ResponseBodyUnset) -> Self {
+    Self::ResponseBodyUnset(event)
+  }
+}
+
 impl From<ResponseRemoved> for EndpointEvent {
   fn from(event: ResponseRemoved) -> Self {
     Self::ResponseRemoved(event)
@@ -644,12 +853,36 @@ impl From<EndpointCommand> for EndpointEvent {
       EndpointCommand::AddResponseByPathAndMethod(command) => {
         EndpointEvent::from(ResponseAddedByPathAndMethod::from(command))
       }
+      EndpointCommand::AddResponseRangeByPathAndMethod(command) => {
+        EndpointEvent::from(ResponseRangeAddedByPathAndMethod::from(command))
+      }
+      EndpointCommand::AddDefaultResponseByPathAndMethod(command) => {
+        EndpointEvent::from(DefaultResponseAddedByPathAndMethod::from(command))
+      }
       EndpointCommand::SetResponseBodyShape(command) => {
         EndpointEvent::from(ResponseBodySet::from(command))
       }
       EndpointCommand::RemoveResponse(command) => {
         EndpointEvent::from(ResponseRemoved::from(command))
       }
+      EndpointCommand::AddHeaderParameter(command) => {
+        EndpointEvent::from(HeaderParameterAdded::from(command))
+      }
+      EndpointCommand::AddResponseHeaderParameter(command) => {
+        EndpointEvent::from(ResponseHeaderParameterAdded::from(command))
+      }
+      EndpointCommand::RenameHeaderParameter(command) => {
+        EndpointEvent::from(HeaderParameterRenamed::from(command))
+      }
+      EndpointCommand::SetHeaderParameterShape(command) => {
+        EndpointEvent::from(HeaderParameterShapeSet::from(command))
+      }
+      EndpointCommand::UnsetHeaderParameterShape(command) => {
+        EndpointEvent::from(HeaderParameterShapeUnset::from(command))
+      }
+      EndpointCommand::RemoveHeaderParameter(command) => {
+        EndpointEvent::from(HeaderParameterRemoved::from(command))
+      }
       _ => unimplemented!(
         "conversion from endpoint command to endpoint event not implemented for variant: {:?}",
         endpoint_command
@@ -780,6 +1013,15 @@ impl From<endpoint_commands::SetRequestBodyShape> for RequestBodySet {
   }
 }
 
+impl From<endpoint_commands::UnsetRequestBodyShape> for RequestBodyUnset {
+  fn from(command: endpoint_commands::UnsetRequestBodyShape) -> Self {
+    Self {
+      request_id: command.request_id,
+      event_context: None,
+    }
+  }
+}
+
 impl From<endpoint_commands::RemoveRequest> for RequestRemoved {
   fn from(command: endpoint_commands::RemoveRequest) -> Self {
     Self {
@@ -801,6 +1043,33 @@ impl From<endpoint_commands::AddResponseByPathAndMethod> for ResponseAddedByPath
   }
 }
 
+impl From<endpoint_commands::AddResponseRangeByPathAndMethod>
+  for ResponseRangeAddedByPathAndMethod
+{
+  fn from(command: endpoint_commands::AddResponseRangeByPathAndMethod) -> Self {
+    Self {
+      http_method: command.http_method,
+      http_status_range: command.http_status_range,
+      path_id: command.path_id,
+      response_id: command.response_id,
+      event_context: None,
+    }
+  }
+}
+
+impl From<endpoint_commands::AddDefaultResponseByPathAndMethod>
+  for DefaultResponseAddedByPathAndMethod
+{
+  fn from(command: endpoint_commands::AddDefaultResponseByPathAndMethod) -> Self {
+    Self {
+      http_method: command.http_method,
+      path_id: command.path_id,
+      response_id: command.response_id,
+      event_context: None,
+    }
+  }
+}
+
 impl From<endpoint_commands::SetResponseBodyShape> for ResponseBodySet {
   fn from(command: endpoint_commands::SetResponseBodyShape) -> Self {
     Self {
@@ -811,6 +1080,15 @@ impl From<endpoint_commands::SetResponseBodyShape> for ResponseBodySet {
   }
 }
 
+impl From<endpoint_commands::UnsetResponseBodyShape> for ResponseBodyUnset {
+  fn from(command: endpoint_commands::UnsetResponseBodyShape) -> Self {
+    Self {
+      response_id: command.response_id,
+      event_context: None,
+    }
+  }
+}
+
 impl From<endpoint_commands::RemoveResponse> for ResponseRemoved {
   fn from(command: endpoint_commands::RemoveResponse) -> Self {
     Self {
@@ -819,3 +1097,63 @@ impl From<endpoint_commands::RemoveResponse> for ResponseRemoved {
     }
   }
 }
+
+impl From<endpoint_commands::AddHeaderParameter> for HeaderParameterAdded {
+  fn from(command: endpoint_commands::AddHeaderParameter) -> Self {
+    Self {
+      parameter_id: command.parameter_id,
+      request_id: command.request_id,
+      name: command.name,
+      event_context: None,
+    }
+  }
+}
+
+impl From<endpoint_commands::AddResponseHeaderParameter> for ResponseHeaderParameterAdded {
+  fn from(command: endpoint_commands::AddResponseHeaderParameter) -> Self {
+    Self {
+      parameter_id: command.parameter_id,
+      response_id: command.response_id,
+      name: command.name,
+      event_context: None,
+    }
+  }
+}
+
+impl From<endpoint_commands::RenameHeaderParameter> for HeaderParameterRenamed {
+  fn from(command: endpoint_commands::RenameHeaderParameter) -> Self {
+    Self {
+      parameter_id: command.parameter_id,
+      name: command.name,
+      event_context: None,
+    }
+  }
+}
+
+impl From<endpoint_commands::SetHeaderParameterShape> for HeaderParameterShapeSet {
+  fn from(command: endpoint_commands::SetHeaderParameterShape) -> Self {
+    Self {
+      parameter_id: command.parameter_id,
+      parameter_descriptor: command.parameter_descriptor,
+      event_context: None,
+    }
+  }
+}
+
+impl From<endpoint_commands::UnsetHeaderParameterShape> for HeaderParameterShapeUnset {
+  fn from(command: endpoint_commands::UnsetHeaderParameterShape) -> Self {
+    Self {
+      parameter_id: command.parameter_id,
+      event_context: None,
+    }
+  }
+}
+
+impl From<endpoint_commands::RemoveHeaderParameter> for HeaderParameterRemoved {
+  fn from(command: endpoint_commands::RemoveHeaderParameter) -> Self {
+    Self {
+      parameter_id: command.parameter_id,
+      event_context: None,
+    }
+  }
+}
@@ -1,6 +1,7 @@
 use super::EventLoadingError;
 use crate::shapehash;
-use crate::state::body::{BodyDescriptor, ParsedQueryString};
+use crate::state::body::{BodyDescriptor, ParsedMultipart, ParsedQueryString, ParsedXml};
+use crate::state::endpoint::MediaType;
 use base64;
 use cqrs_core::Event;
 use protobuf::Message;
@@ -67,6 +68,12 @@ pub struct QueryParametersData {
   data: ArbitraryData,
 }
 
+impl From<ArbitraryData> for QueryParametersData {
+  fn from(data: ArbitraryData) -> Self {
+    QueryParametersData { data }
+  }
+}
+
 impl From<&ArbitraryData> for Option<serde_json::value::Value> {
   fn from(data: &ArbitraryData) -> Option<serde_json::value::Value> {
     if let Some(json_string) = &data.as_json_string {
@@ -109,6 +116,51 @@ impl From<&ArbitraryData> for Option<BodyDescriptor> {
   }
 }
 
+impl From<&Body> for Option<BodyDescriptor> {
+  // Text bodies of form, multipart and XML content types are decoded, so their fields can be
+  // diffed and learned like those of JSON bodies. Anything that can't be decoded is described
+  // by its value as is.
+  fn from(body: &Body) -> Self {
+    let data = &body.value;
+    let decoded = match (
+      &data.as_text,
+      &data.as_json_string,
+      &data.shape_hash_v1_base64,
+    ) {
+      (Some(text), None, None) => body
+        .content_type
+        .as_deref()
+        .and_then(MediaType::parse)
+        .and_then(|media_type| decode_text_body(text, &media_type)),
+      _ => None,
+    };
+
+    decoded.or_else(|| data.into())
+  }
+}
+
+fn decode_text_body(text: &str, media_type: &MediaType) -> Option<BodyDescriptor> {
+  match (
+    media_type.type_name.as_str(),
+    media_type.subtype.as_str(),
+    media_type.suffix.as_deref(),
+  ) {
+    ("application", "x-www-form-urlencoded", None) => ParsedQueryString::from_str(text)
+      .ok()
+      .map(BodyDescriptor::from),
+    ("multipart", "form-data", None) => {
+      let boundary = media_type.parameter("boundary")?;
+      ParsedMultipart::from_str(text, boundary)
+        .ok()
+        .map(BodyDescriptor::from)
+    }
+    ("application", "xml", None) | ("text", "xml", None) | ("application", _, Some("xml")) => {
+      ParsedXml::from_str(text).ok().map(BodyDescriptor::from)
+    }
+    _ => None,
+  }
+}
+
 impl From<&QueryParametersData> for Option<BodyDescriptor> {
   fn from(query_param_data: &QueryParametersData) -> Self {
     let data = &query_param_data.data;
@@ -123,6 +175,79 @@ impl From<&QueryParametersData> for Option<BodyDescriptor> {
   }
 }
 
+/// Header name and value pairs of a request or response, as recorded in its `ArbitraryData`.
+/// Names are matched case-insensitively, as per HTTP semantics.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpHeaders(Vec<(String, String)>);
+
+impl HttpHeaders {
+  pub fn get(&self, name: &str) -> Option<&String> {
+    self
+      .0
+      .iter()
+      .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value)
+  }
+
+  pub fn contains(&self, name: &str) -> bool {
+    self.get(name).is_some()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &(String, String)> {
+    self.0.iter()
+  }
+}
+
+impl From<&ArbitraryData> for HttpHeaders {
+  // Headers are captured either as a json array of `{ "name", "value" }` pairs, a json object
+  // keyed by header name or as raw `Name: value` lines of text.
+  fn from(data: &ArbitraryData) -> Self {
+    use serde_json::Value;
+
+    let json: Option<Value> = data
+      .as_json_string
+      .as_ref()
+      .and_then(|json_string| serde_json::from_str(json_string).ok());
+
+    let headers = match (json, &data.as_text) {
+      (Some(Value::Array(pairs)), _) => pairs
+        .into_iter()
+        .filter_map(|pair| {
+          let name = pair.get("name")?.as_str()?.to_owned();
+          let value = match pair.get("value")? {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+          };
+          Some((name, value))
+        })
+        .collect(),
+      (Some(Value::Object(map)), _) => map
+        .into_iter()
+        .map(|(name, value)| match value {
+          Value::String(value) => (name, value),
+          value => (name, value.to_string()),
+        })
+        .collect(),
+      (_, Some(text)) => text
+        .lines()
+        .filter_map(|line| {
+          let mut parts = line.splitn(2, ':');
+          let name = parts.next()?.trim();
+          let value = parts.next()?.trim();
+          if name.is_empty() {
+            None
+          } else {
+            Some((String::from(name), String::from(value)))
+          }
+        })
+        .collect(),
+      _ => vec![],
+    };
+
+    HttpHeaders(headers)
+  }
+}
+
 impl From<shapehash::ShapeDescriptor> for serde_json::value::Value {
   fn from(mut shape_descriptor: shapehash::ShapeDescriptor) -> serde_json::value::Value {
     use serde_json::map::Map;
@@ -183,10 +308,31 @@ impl HttpInteraction {
   }
 }
 
+impl Request {
+  pub fn headers(&self) -> HttpHeaders {
+    HttpHeaders::from(&self.headers)
+  }
+}
+
+impl Response {
+  pub fn headers(&self) -> HttpHeaders {
+    HttpHeaders::from(&self.headers)
+  }
+}
+
+#[cfg(feature = "avro")]
+pub use avro::{HttpInteractionAvroDeserializer, HttpInteractionAvroReader};
+
 #[cfg(feature = "avro")]
 mod avro {
   use super::*;
-  use avro_rs;
+  use avro_rs::{self, types::Value, Codec, Schema};
+  use std::convert::TryFrom;
+  use std::io::{BufRead, Read};
+  use std::str::FromStr;
+
+  // 100 megabytes, well beyond the blocks of batches written by capture agents
+  const MAX_BLOCK_SIZE: usize = 100 * 1024 * 1024;
 
   impl HttpInteraction {
     pub fn from_avro() -> HttpInteractionAvroDeserializer {
@@ -207,12 +353,208 @@ mod avro {
       Ok(Self { schema })
     }
 
-    pub fn reader<'a, R>(&'a mut self, source: R) -> Result<avro_rs::Reader<'a, R>, avro_rs::Error>
+    pub fn schema(&self) -> &avro_rs::Schema {
+      &self.schema
+    }
+
+    pub fn reader<'a, R>(
+      &'a self,
+      source: R,
+    ) -> Result<HttpInteractionAvroReader<'a, R>, EventLoadingError>
     where
       R: io::Read,
       R: 'a,
     {
-      avro_rs::Reader::with_schema(&self.schema, source)
+      HttpInteractionAvroReader::new(&self.schema, source)
+    }
+  }
+
+  /// Reads the interactions of the batches in an avro object container file, like those written
+  /// by capture agents. Their writer schema refers to named types like `ArbitraryData` after
+  /// defining them once, which `avro_rs` can't parse, so batches are decoded with the embedded
+  /// interaction schema instead of the schema in the file's header.
+  pub struct HttpInteractionAvroReader<'a, R> {
+    schema: &'a avro_rs::Schema,
+    source: io::BufReader<R>,
+    codec: Codec,
+    marker: [u8; 16],
+    block: Vec<u8>,
+    block_position: usize,
+    block_batches_count: usize,
+    interactions: std::vec::IntoIter<HttpInteraction>,
+    is_failed: bool,
+  }
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct InteractionBatch {
+    batch_items: Vec<HttpInteraction>,
+  }
+
+  impl<'a, R> HttpInteractionAvroReader<'a, R>
+  where
+    R: io::Read,
+  {
+    fn new(schema: &'a avro_rs::Schema, source: R) -> Result<Self, EventLoadingError> {
+      let mut source = io::BufReader::new(source);
+
+      let mut magic = [0u8; 4];
+      source.read_exact(&mut magic)?;
+      if magic != [b'O', b'b', b'j', 1u8] {
+        return Err(EventLoadingError::Avro(avro_rs::Error::HeaderMagic));
+      }
+
+      let metadata =
+        match avro_rs::from_avro_datum(&Schema::Map(Box::new(Schema::Bytes)), &mut source, None)? {
+          Value::Map(metadata) => metadata,
+          _ => return Err(EventLoadingError::Avro(avro_rs::Error::GetHeaderMetadata)),
+        };
+      let codec = match metadata.get("avro.codec") {
+        Some(Value::Bytes(codec)) => {
+          let codec = String::from_utf8_lossy(codec);
+          Codec::from_str(&codec).map_err(|_| {
+            io::Error::new(
+              io::ErrorKind::InvalidData,
+              format!("unsupported avro codec '{}'", codec),
+            )
+          })?
+        }
+        _ => Codec::Null,
+      };
+
+      let mut marker = [0u8; 16];
+      source.read_exact(&mut marker)?;
+
+      Ok(Self {
+        schema,
+        source,
+        codec,
+        marker,
+        block: vec![],
+        block_position: 0,
+        block_batches_count: 0,
+        interactions: vec![].into_iter(),
+        is_failed: false,
+      })
+    }
+
+    /// Read the next block of batches, returning false once the end of the file is reached
+    fn read_block(&mut self) -> Result<bool, EventLoadingError> {
+      if self.source.fill_buf()?.is_empty() {
+        return Ok(false);
+      }
+
+      let batches_count = read_long(&mut self.source)?;
+      let block_size = read_long(&mut self.source)?;
+      // sizes are checked before allocating the block, as corrupt files can claim any size
+      let (batches_count, block_size) =
+        match (usize::try_from(batches_count), usize::try_from(block_size)) {
+          (Ok(batches_count), Ok(block_size)) if block_size <= MAX_BLOCK_SIZE => {
+            (batches_count, block_size)
+          }
+          _ => {
+            return Err(EventLoadingError::from(io::Error::new(
+              io::ErrorKind::InvalidData,
+              format!(
+                "invalid avro block of {} batches in {} bytes",
+                batches_count, block_size
+              ),
+            )))
+          }
+        };
+      self.block.resize(block_size, 0);
+      self.source.read_exact(&mut self.block)?;
+
+      let mut marker = [0u8; 16];
+      self.source.read_exact(&mut marker)?;
+      if marker != self.marker {
+        return Err(EventLoadingError::Avro(avro_rs::Error::GetBlockMarker));
+      }
+
+      self.codec.decompress(&mut self.block)?;
+      self.block_position = 0;
+      self.block_batches_count = batches_count;
+      Ok(true)
+    }
+
+    fn read_batch(&mut self) -> Result<Vec<HttpInteraction>, EventLoadingError> {
+      let mut block = &self.block[self.block_position..];
+      let block_size = block.len();
+      let value = avro_rs::from_avro_datum(self.schema, &mut block, None)?;
+      self.block_position += block_size - block.len();
+      self.block_batches_count -= 1;
+
+      let batch: InteractionBatch = serde_json::from_value(json_from_avro(value))?;
+      Ok(batch.batch_items)
+    }
+
+    fn read_next(&mut self) -> Result<Option<HttpInteraction>, EventLoadingError> {
+      loop {
+        if let Some(interaction) = self.interactions.next() {
+          return Ok(Some(interaction));
+        }
+        if self.block_batches_count == 0 && !self.read_block()? {
+          return Ok(None);
+        }
+        if self.block_batches_count > 0 {
+          self.interactions = self.read_batch()?.into_iter();
+        }
+      }
+    }
+  }
+
+  impl<'a, R> Iterator for HttpInteractionAvroReader<'a, R>
+  where
+    R: io::Read,
+  {
+    type Item = Result<HttpInteraction, EventLoadingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      if self.is_failed {
+        return None;
+      }
+
+      let next = self.read_next();
+      self.is_failed = next.is_err();
+      next.transpose()
+    }
+  }
+
+  fn read_long<R: io::Read>(source: &mut R) -> Result<i64, EventLoadingError> {
+    match avro_rs::from_avro_datum(&Schema::Long, source, None)? {
+      Value::Long(n) => Ok(n),
+      _ => unreachable!("long schema should decode to a long"),
+    }
+  }
+
+  // Decoded values are converted to json, so interactions are deserialized the same way as when
+  // they're read from json lines, including the flattening of their query parameters
+  fn json_from_avro(value: Value) -> serde_json::Value {
+    use serde_json::Value as JsonValue;
+
+    match value {
+      Value::Boolean(b) => JsonValue::from(b),
+      Value::Int(n) => JsonValue::from(n),
+      Value::Long(n) => JsonValue::from(n),
+      Value::Float(n) => JsonValue::from(n),
+      Value::Double(n) => JsonValue::from(n),
+      Value::String(s) | Value::Enum(_, s) => JsonValue::from(s),
+      Value::Union(inner) => json_from_avro(*inner),
+      Value::Array(items) => JsonValue::from_iter(items.into_iter().map(json_from_avro)),
+      Value::Map(entries) => JsonValue::Object(
+        entries
+          .into_iter()
+          .map(|(key, value)| (key, json_from_avro(value)))
+          .collect(),
+      ),
+      Value::Record(fields) => JsonValue::Object(
+        fields
+          .into_iter()
+          .map(|(key, value)| (key, json_from_avro(value)))
+          .collect(),
+      ),
+      // the interaction schema doesn't use bytes or logical types
+      _ => JsonValue::Null,
     }
   }
 
@@ -471,4 +813,177 @@ mod test {
     let interaction = HttpInteraction::from_json_str(&json);
     interaction.expect("Valid JSON should be able to deserialize into an HttpInteraction");
   }
+
+  #[test]
+  fn can_read_headers_from_arbitrary_data() {
+    let as_pairs = ArbitraryData {
+      as_json_string: Some(String::from(
+        r#"[{"name":"Authorization","value":"Bearer abc"},{"name":"X-RateLimit-Remaining","value":"10"}]"#,
+      )),
+      ..ArbitraryData::default()
+    };
+    let headers = HttpHeaders::from(&as_pairs);
+    assert_eq!(headers.get("authorization").unwrap(), "Bearer abc");
+    assert!(headers.contains("x-ratelimit-remaining"));
+
+    let as_object = ArbitraryData {
+      as_json_string: Some(String::from(r#"{"content-length": 42}"#)),
+      ..ArbitraryData::default()
+    };
+    assert_eq!(
+      HttpHeaders::from(&as_object).get("Content-Length").unwrap(),
+      "42"
+    );
+
+    let as_text = ArbitraryData {
+      as_text: Some(String::from("Accept: */*\r\nX-Request-Id: 1:2")),
+      ..ArbitraryData::default()
+    };
+    let headers = HttpHeaders::from(&as_text);
+    assert_eq!(headers.get("accept").unwrap(), "*/*");
+    assert_eq!(headers.get("x-request-id").unwrap(), "1:2");
+
+    assert_eq!(
+      HttpHeaders::from(&ArbitraryData::default()),
+      HttpHeaders::default()
+    );
+  }
+
+  #[test]
+  fn can_describe_bodies_by_content_type() {
+    let body = |content_type: &str, text: &str| Body {
+      content_type: Some(String::from(content_type)),
+      value: ArbitraryData {
+        as_text: Some(String::from(text)),
+        ..ArbitraryData::default()
+      },
+    };
+
+    let form = body(
+      "application/x-www-form-urlencoded; charset=utf-8",
+      "title=Buy+milk&tags=groceries&tags=errands",
+    );
+    assert_eq!(
+      Option::<BodyDescriptor>::from(&form),
+      Some(BodyDescriptor::from(serde_json::json!({
+        "tags": ["groceries", "errands"],
+        "title": "Buy milk"
+      })))
+    );
+
+    let multipart = body(
+      "multipart/form-data; boundary=\"XyZ\"",
+      "--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nBuy milk\r\n--XyZ--\r\n",
+    );
+    assert_eq!(
+      Option::<BodyDescriptor>::from(&multipart),
+      Some(BodyDescriptor::from(
+        serde_json::json!({ "title": ["Buy milk"] })
+      ))
+    );
+
+    let xml = body(
+      "application/atom+xml",
+      r#"<feed><title>Todos</title><entry id="1"/></feed>"#,
+    );
+    assert_eq!(
+      Option::<BodyDescriptor>::from(&xml),
+      Some(BodyDescriptor::from(serde_json::json!({
+        "feed": { "entry": [{ "@id": "1" }], "title": ["Todos"] }
+      })))
+    );
+
+    // bodies that can't be decoded, or aren't of a decodable type, are described as text
+    let malformed_xml = body("text/xml", "<feed><title>Todos</feed>");
+    assert_eq!(
+      Option::<BodyDescriptor>::from(&malformed_xml),
+      Some(BodyDescriptor::from(String::from(
+        "<feed><title>Todos</feed>"
+      )))
+    );
+    let plain = body("text/plain", "title=Buy+milk");
+    assert_eq!(
+      Option::<BodyDescriptor>::from(&plain),
+      Some(BodyDescriptor::from(String::from("title=Buy+milk")))
+    );
+  }
+
+  #[cfg(feature = "avro")]
+  #[test]
+  fn can_round_trip_interactions_through_avro() {
+    let interactions: Vec<HttpInteraction> = serde_json::from_value(serde_json::json!([
+      {
+        "uuid": "1",
+        "request": {
+          "host": "localhost",
+          "method": "POST",
+          "path": "/todos",
+          "query": { "asText": "done=false" },
+          "headers": { "asJsonString": "[{\"name\":\"Accept\",\"value\":\"*/*\"}]" },
+          "body": {
+            "contentType": "application/json",
+            "value": { "asJsonString": "{\"title\":\"Buy milk\"}" }
+          }
+        },
+        "response": {
+          "statusCode": 201,
+          "headers": {},
+          "body": { "contentType": null, "value": {} }
+        },
+        "tags": [{ "name": "captureId", "value": "capture-1" }]
+      },
+      {
+        "uuid": "2",
+        "request": {
+          "host": "localhost",
+          "method": "GET",
+          "path": "/todos/1",
+          "query": {},
+          "headers": {},
+          "body": { "contentType": null, "value": {} }
+        },
+        "response": {
+          "statusCode": 404,
+          "headers": { "asText": "Content-Type: text/plain" },
+          "body": { "contentType": "text/plain", "value": { "asText": "Not found" } }
+        },
+        "tags": []
+      }
+    ]))
+    .expect("example interactions should be valid");
+
+    let deserializer = HttpInteraction::from_avro();
+    let mut writer = avro_rs::Writer::new(deserializer.schema(), Vec::new());
+    for (batch_id, batch_items) in interactions.chunks(1).enumerate() {
+      let batch = serde_json::json!({
+        "groupingIdentifiers": {
+          "agentGroupId": "agent-group-1",
+          "captureId": "capture-1",
+          "agentId": "agent-1",
+          "batchId": batch_id.to_string(),
+        },
+        "batchItems": batch_items,
+      });
+      let value = avro_rs::types::Value::from(batch)
+        .resolve(deserializer.schema())
+        .expect("batch should match the interaction schema");
+      writer
+        .append(value)
+        .expect("batch should be written to avro");
+    }
+    let avro_bytes = writer
+      .into_inner()
+      .expect("avro container should be flushed");
+
+    let read_interactions = deserializer
+      .reader(avro_bytes.as_slice())
+      .expect("avro container header should be readable")
+      .collect::<Result<Vec<_>, _>>()
+      .expect("interactions should be read from avro container");
+
+    assert_eq!(
+      serde_json::to_value(&read_interactions).unwrap(),
+      serde_json::to_value(&interactions).unwrap()
+    );
+  }
 }
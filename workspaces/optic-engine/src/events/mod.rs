@@ -7,15 +7,18 @@ use serde_json;
 use std::fs;
 use std::io;
 use std::path::Path;
+use thiserror::Error;
 
 pub mod endpoint;
 pub mod http_interaction;
+pub mod reader;
 pub mod rfc;
 pub mod shape;
 pub mod spec_chunk;
 
 pub use endpoint::EndpointEvent;
 pub use http_interaction::HttpInteraction;
+pub use reader::{SpecChunksEventsReader, SpecEventsReadError, SpecEventsReader};
 pub use rfc::RfcEvent;
 pub use shape::ShapeEvent;
 pub use spec_chunk::SpecChunkEvent;
@@ -58,6 +61,13 @@ impl SpecEvent {
 
     Ok(events)
   }
+
+  /// Read the events of a spec file one at a time, rather than loading them all at once
+  pub fn read_from_file(
+    filename: impl AsRef<Path>,
+  ) -> Result<SpecEventsReader<io::BufReader<fs::File>>, SpecEventsReadError> {
+    SpecEventsReader::from_file(filename)
+  }
 }
 
 impl From<EndpointEvent> for SpecEvent {
@@ -78,11 +88,14 @@ impl From<ShapeEvent> for SpecEvent {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum EventLoadingError {
   #[cfg(feature = "avro")]
+  #[error("avro error: {}", .0)]
   Avro(avro_rs::Error),
+  #[error("io error: {}", .0)]
   Io(io::Error),
+  #[error("json error: {}", .0)]
   Json(serde_json::Error),
 }
 
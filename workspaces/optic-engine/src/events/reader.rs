@@ -0,0 +1,437 @@
+use super::spec_chunk::BatchChunkOrder;
+use super::{EventLoadingError, RfcEvent, SpecEvent};
+use serde::de::Error as _;
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const ROOT_CHUNK_FILE_NAME: &str = "specification.json";
+
+/// Reads the spec events of a JSON array one at a time, so a spec can be loaded without
+/// holding all of its events (or the text they were parsed from) in memory.
+pub struct SpecEventsReader<R> {
+  reader: R,
+  file_name: String,
+  offset: usize,
+  state: ReaderState,
+}
+
+#[derive(Debug, PartialEq)]
+enum ReaderState {
+  Start,
+  Events,
+  Done,
+}
+
+impl SpecEventsReader<BufReader<File>> {
+  pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SpecEventsReadError> {
+    let path = path.as_ref();
+    let file_name = path.display().to_string();
+    let file = File::open(path).map_err(|err| SpecEventsReadError::Event {
+      file_name: file_name.clone(),
+      offset: 0,
+      source: EventLoadingError::from(err),
+    })?;
+
+    Ok(Self::new(BufReader::new(file), file_name))
+  }
+}
+
+impl<R> SpecEventsReader<R>
+where
+  R: BufRead,
+{
+  /// Read events from any buffered source. The file name is only used to report errors.
+  pub fn new(reader: R, file_name: impl Into<String>) -> Self {
+    Self {
+      reader,
+      file_name: file_name.into(),
+      offset: 0,
+      state: ReaderState::Start,
+    }
+  }
+
+  pub fn file_name(&self) -> &str {
+    &self.file_name
+  }
+
+  /// Amount of events read so far, which is also the offset of the next event
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  fn next_event(&mut self) -> Result<Option<SpecEvent>, EventLoadingError> {
+    if self.state == ReaderState::Start {
+      match self.peek_byte()? {
+        Some(b'[') => {
+          self.reader.consume(1);
+          self.state = ReaderState::Events;
+        }
+        _ => return Err(syntax_error("expected an array of spec events")),
+      }
+    }
+
+    match self.peek_byte()? {
+      Some(b']') => {
+        self.reader.consume(1);
+        self.state = ReaderState::Done;
+        return match self.peek_byte()? {
+          None => Ok(None),
+          Some(_) => Err(syntax_error("trailing characters after spec events")),
+        };
+      }
+      Some(b',') if self.offset > 0 => self.reader.consume(1),
+      Some(_) if self.offset == 0 => {}
+      Some(_) => return Err(syntax_error("expected ',' or ']' after spec event")),
+      None => return Err(syntax_error("unexpected end of spec events")),
+    }
+
+    // The deserializer reads no further than the closing brace of the event, so whatever follows
+    // is left in the reader for the next event.
+    let mut deserializer = serde_json::Deserializer::from_reader(&mut self.reader);
+    let event = SpecEvent::deserialize(&mut deserializer)?;
+
+    Ok(Some(event))
+  }
+
+  /// Skip any whitespace and peek at the next byte, without consuming it
+  fn peek_byte(&mut self) -> io::Result<Option<u8>> {
+    loop {
+      let buffer = self.reader.fill_buf()?;
+      if buffer.is_empty() {
+        return Ok(None);
+      }
+
+      match buffer.iter().position(|byte| !byte.is_ascii_whitespace()) {
+        Some(position) => {
+          let byte = buffer[position];
+          self.reader.consume(position);
+          return Ok(Some(byte));
+        }
+        None => {
+          let skipped = buffer.len();
+          self.reader.consume(skipped);
+        }
+      }
+    }
+  }
+}
+
+impl<R> Iterator for SpecEventsReader<R>
+where
+  R: BufRead,
+{
+  type Item = Result<SpecEvent, SpecEventsReadError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.state == ReaderState::Done {
+      return None;
+    }
+
+    match self.next_event() {
+      Ok(Some(event)) => {
+        self.offset += 1;
+        Some(Ok(event))
+      }
+      Ok(None) => None,
+      Err(source) => {
+        self.state = ReaderState::Done;
+        Some(Err(SpecEventsReadError::Event {
+          file_name: self.file_name.clone(),
+          offset: self.offset,
+          source,
+        }))
+      }
+    }
+  }
+}
+
+/// Reads the events of a directory of spec chunks one at a time, in the order the chunks are
+/// assembled in: the root chunk followed by each batch chunk after the batches it builds on.
+///
+/// Only the first event of each batch chunk is read up front, to find out which batches it builds
+/// on. As with assembling chunks all at once, chunks that aren't batches are ignored, as are
+/// chunks building on batches that can't be found.
+pub struct SpecChunksEventsReader {
+  current_chunk: Option<CurrentChunk>,
+  batch_chunks: BatchChunkOrder<BatchChunkFile>,
+}
+
+struct BatchChunkFile {
+  batch_id: String,
+  path: PathBuf,
+}
+
+struct CurrentChunk {
+  reader: SpecEventsReader<BufReader<File>>,
+  batch_id: Option<String>,
+  last_batch_id: String,
+  is_ended: bool,
+}
+
+impl SpecChunksEventsReader {
+  pub fn from_api_dir(path: impl AsRef<Path>) -> Result<Self, SpecEventsReadError> {
+    let mut root_chunk_path = None;
+    let mut batch_chunks = BatchChunkOrder::default();
+
+    for dir_entry in fs::read_dir(path)? {
+      let dir_entry = dir_entry?;
+      if !dir_entry.metadata()?.is_file() {
+        continue;
+      }
+
+      let chunk_path = dir_entry.path();
+      if dir_entry.file_name() == ROOT_CHUNK_FILE_NAME {
+        root_chunk_path = Some(chunk_path);
+        continue;
+      }
+
+      let first_event = SpecEventsReader::from_file(&chunk_path)?.next();
+      match first_event {
+        Some(Ok(SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(e)))) if e.parent_id.is_some() => {
+          let parent_ids = e.parent_id.into_iter().chain(e.merged_parent_ids).collect();
+          batch_chunks.with_chunk(
+            e.batch_id.clone(),
+            dir_entry.file_name().to_string_lossy().into_owned(),
+            parent_ids,
+            BatchChunkFile {
+              batch_id: e.batch_id,
+              path: chunk_path,
+            },
+          )
+        }
+        // not a batch chunk
+        _ => continue,
+      }
+    }
+
+    let root_chunk_path = root_chunk_path.ok_or(SpecEventsReadError::RootChunkRequired)?;
+
+    Ok(Self {
+      current_chunk: Some(CurrentChunk {
+        reader: SpecEventsReader::from_file(root_chunk_path)?,
+        batch_id: None,
+        last_batch_id: String::from("root"),
+        is_ended: false,
+      }),
+      batch_chunks,
+    })
+  }
+
+  fn next_chunk(&mut self) -> Result<Option<CurrentChunk>, SpecEventsReadError> {
+    let next = match self.batch_chunks.next_chunk() {
+      Some(next) => next,
+      None => return Ok(None),
+    };
+
+    Ok(Some(CurrentChunk {
+      reader: SpecEventsReader::from_file(&next.path)?,
+      last_batch_id: next.batch_id.clone(),
+      batch_id: Some(next.batch_id),
+      is_ended: false,
+    }))
+  }
+}
+
+impl CurrentChunk {
+  fn invalid(&self, reason: &'static str) -> SpecEventsReadError {
+    SpecEventsReadError::InvalidChunk {
+      file_name: String::from(self.reader.file_name()),
+      offset: self.reader.offset().saturating_sub(1),
+      reason,
+    }
+  }
+
+  /// Track the batches of a chunk, making sure batch chunks consist of exactly one batch
+  fn observe(&mut self, event: &SpecEvent) -> Result<(), SpecEventsReadError> {
+    if let SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(e)) = event {
+      self.last_batch_id = e.batch_id.clone();
+    }
+
+    let batch_id = match &self.batch_id {
+      Some(batch_id) => batch_id,
+      None => return Ok(()),
+    };
+    if self.is_ended {
+      return Err(self.invalid("Chunk cannot include events after its BatchCommitEnded event"));
+    }
+    match event {
+      SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(_)) if self.reader.offset() > 1 => {
+        Err(self.invalid("Chunk cannot include nested BatchCommitStarted events"))
+      }
+      SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(e)) if &e.batch_id != batch_id => {
+        Err(self.invalid("BatchCommitEnded event does not have matching batch_id"))
+      }
+      SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(_)) => {
+        self.is_ended = true;
+        Ok(())
+      }
+      _ => Ok(()),
+    }
+  }
+}
+
+impl Iterator for SpecChunksEventsReader {
+  type Item = Result<SpecEvent, SpecEventsReadError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let current_chunk = self.current_chunk.as_mut()?;
+
+      let result = match current_chunk.reader.next() {
+        Some(Ok(event)) => current_chunk.observe(&event).map(|_| Some(event)),
+        Some(Err(err)) => Err(err),
+        None if current_chunk.batch_id.is_some() && !current_chunk.is_ended => {
+          Err(current_chunk.invalid("Chunk does not end in a BatchCommitEnded event"))
+        }
+        None => {
+          // chunks build on the root itself only when the root chunk doesn't have any batches
+          let last_batch_id = current_chunk.last_batch_id.clone();
+          self.batch_chunks.with_completed_batch(last_batch_id);
+          self
+            .next_chunk()
+            .map(|next_chunk| {
+              self.current_chunk = next_chunk;
+            })
+            .map(|_| None)
+        }
+      };
+
+      // any earlier batches of the root chunk can be built on as well
+      if let Ok(Some(SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(e)))) = &result {
+        self.batch_chunks.with_completed_batch(e.batch_id.clone());
+      }
+
+      match result {
+        Ok(Some(event)) => return Some(Ok(event)),
+        Ok(None) => continue,
+        Err(err) => {
+          self.current_chunk = None;
+          return Some(Err(err));
+        }
+      }
+    }
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum SpecEventsReadError {
+  #[error("could not read spec event {offset} of '{file_name}': {source}")]
+  Event {
+    file_name: String,
+    offset: usize,
+    source: EventLoadingError,
+  },
+  #[error("spec event {offset} of '{file_name}' is not valid for a batch chunk: {reason}")]
+  InvalidChunk {
+    file_name: String,
+    offset: usize,
+    reason: &'static str,
+  },
+  #[error("root chunk '{}' required to assemble events", ROOT_CHUNK_FILE_NAME)]
+  RootChunkRequired,
+  #[error("could not read spec chunks directory: {}", .0)]
+  Dir(#[from] io::Error),
+}
+
+fn syntax_error(msg: &str) -> EventLoadingError {
+  EventLoadingError::Json(serde_json::Error::custom(msg))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  pub fn reads_events_one_at_a_time() {
+    let events_json = r#"
+      [
+        {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"todos"}}
+        ,{"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"GET"}}
+      ]
+    "#;
+    let mut reader = SpecEventsReader::new(events_json.as_bytes(), "events.json");
+
+    assert!(matches!(reader.next(), Some(Ok(_))));
+    assert_eq!(reader.offset(), 1);
+    assert!(matches!(reader.next(), Some(Ok(_))));
+    assert!(reader.next().is_none());
+    assert_eq!(reader.offset(), 2);
+  }
+
+  #[test]
+  pub fn reports_file_name_and_offset_of_invalid_events() {
+    let events_json = r#"[
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"todos"}},
+      {"NotAnEvent":{}}
+    ]"#;
+    let reader = SpecEventsReader::new(events_json.as_bytes(), "events.json");
+
+    let results = reader.collect::<Vec<_>>();
+
+    assert_eq!(results.len(), 2);
+    match &results[1] {
+      Err(SpecEventsReadError::Event {
+        file_name, offset, ..
+      }) => {
+        assert_eq!(file_name, "events.json");
+        assert_eq!(*offset, 1);
+      }
+      result => panic!("expected event error, got {:?}", result),
+    }
+  }
+
+  #[test]
+  pub fn reads_empty_and_truncated_arrays() {
+    let empty = SpecEventsReader::new(" [ ] ".as_bytes(), "empty.json");
+    assert_eq!(empty.count(), 0);
+
+    let truncated = SpecEventsReader::new(
+      r#"[{"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"todos"}}"#
+        .as_bytes(),
+      "truncated.json",
+    );
+    let results = truncated.collect::<Vec<_>>();
+    assert!(matches!(
+      results.last(),
+      Some(Err(SpecEventsReadError::Event { offset: 1, .. }))
+    ));
+  }
+
+  fn assert_reads_events_of_api_dir_in_assembled_order(api_dir: &str) {
+    let api_dir_path = std::env::current_dir().unwrap().join(api_dir);
+    let chunks = crate::projections::SpecAssemblerProjection::from(
+      fs::read_dir(&api_dir_path)
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.metadata().unwrap().is_file())
+        .filter_map(|entry| {
+          let events = SpecEvent::from_file(entry.path()).ok()?;
+          let name = entry.file_name().into_string().unwrap();
+          let is_root = name == ROOT_CHUNK_FILE_NAME;
+          Some(super::super::SpecChunkEvent::from((name, is_root, events)))
+        })
+        .collect::<Vec<_>>(),
+    );
+    let assembled_events = chunks.into_events().unwrap();
+
+    let streamed_events = SpecChunksEventsReader::from_api_dir(&api_dir_path)
+      .unwrap()
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+
+    assert_eq!(streamed_events, assembled_events);
+  }
+
+  #[test]
+  pub fn reads_events_of_api_dir_in_assembled_order() {
+    assert_reads_events_of_api_dir_in_assembled_order("tests/fixtures/split-spec-changes/");
+  }
+
+  #[test]
+  pub fn reads_events_of_branching_api_dir_in_assembled_order() {
+    assert_reads_events_of_api_dir_in_assembled_order("tests/fixtures/branching-spec-changes/");
+  }
+}
@@ -49,6 +49,10 @@ pub struct BatchCommitStarted {
 
   #[serde(skip_serializing_if = "Option::is_none")]
   pub parent_id: Option<String>,
+
+  // the other parents of a merge commit, besides the one it was started on
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub merged_parent_ids: Vec<String>,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
@@ -152,6 +156,7 @@ impl From<rfc_commands::StartBatchCommit> for BatchCommitStarted {
     Self {
       batch_id: command.batch_id,
       parent_id: Some(command.parent_id),
+      merged_parent_ids: command.merged_parent_ids,
       commit_message: command.commit_message,
       event_context: None,
     }
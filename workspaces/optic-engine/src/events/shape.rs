@@ -1,6 +1,7 @@
 use super::{EventContext, WithEventContext};
 use crate::state::shape::{
-  FieldShapeDescriptor, ParameterShapeDescriptor, ShapeParametersDescriptor,
+  FieldShapeDescriptor, OneOfDiscriminatorDescriptor, ParameterShapeDescriptor,
+  ShapeConstraintDescriptor, ShapeConstraintId, ShapeParametersDescriptor,
 };
 use crate::{
   commands::shape as shape_commands,
@@ -29,6 +30,11 @@ pub enum ShapeEvent {
   FieldShapeSet(FieldShapeSet),
   FieldRenamed(FieldRenamed),
   FieldRemoved(FieldRemoved),
+
+  ShapeConstraintAdded(ShapeConstraintAdded),
+  ShapeConstraintRemoved(ShapeConstraintRemoved),
+
+  OneOfDiscriminatorSet(OneOfDiscriminatorSet),
 }
 
 #[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
@@ -131,6 +137,30 @@ pub struct FieldRemoved {
   pub event_context: Option<EventContext>,
 }
 
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeConstraintAdded {
+  pub shape_constraint_id: ShapeConstraintId,
+  pub shape_id: ShapeId,
+  pub descriptor: ShapeConstraintDescriptor,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeConstraintRemoved {
+  pub shape_constraint_id: ShapeConstraintId,
+  pub event_context: Option<EventContext>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OneOfDiscriminatorSet {
+  pub shape_id: ShapeId,
+  pub descriptor: OneOfDiscriminatorDescriptor,
+  pub event_context: Option<EventContext>,
+}
+
 impl Event for ShapeEvent {
   fn event_type(&self) -> &'static str {
     match self {
@@ -147,6 +177,11 @@ impl Event for ShapeEvent {
       ShapeEvent::FieldShapeSet(evt) => evt.event_type(),
       ShapeEvent::FieldRenamed(evt) => evt.event_type(),
       ShapeEvent::FieldRemoved(evt) => evt.event_type(),
+
+      ShapeEvent::ShapeConstraintAdded(evt) => evt.event_type(),
+      ShapeEvent::ShapeConstraintRemoved(evt) => evt.event_type(),
+
+      ShapeEvent::OneOfDiscriminatorSet(evt) => evt.event_type(),
     }
   }
 }
@@ -167,6 +202,11 @@ impl WithEventContext for ShapeEvent {
       ShapeEvent::FieldShapeSet(evt) => evt.event_context.replace(event_context),
       ShapeEvent::FieldRenamed(evt) => evt.event_context.replace(event_context),
       ShapeEvent::FieldRemoved(evt) => evt.event_context.replace(event_context),
+
+      ShapeEvent::ShapeConstraintAdded(evt) => evt.event_context.replace(event_context),
+      ShapeEvent::ShapeConstraintRemoved(evt) => evt.event_context.replace(event_context),
+
+      ShapeEvent::OneOfDiscriminatorSet(evt) => evt.event_context.replace(event_context),
     };
   }
 }
@@ -243,6 +283,24 @@ impl Event for FieldRemoved {
   }
 }
 
+impl Event for ShapeConstraintAdded {
+  fn event_type(&self) -> &'static str {
+    "ShapeConstraintAdded"
+  }
+}
+
+impl Event for ShapeConstraintRemoved {
+  fn event_type(&self) -> &'static str {
+    "ShapeConstraintRemoved"
+  }
+}
+
+impl Event for OneOfDiscriminatorSet {
+  fn event_type(&self) -> &'static str {
+    "OneOfDiscriminatorSet"
+  }
+}
+
 impl From<ShapeAdded> for ShapeEvent {
   fn from(event: ShapeAdded) -> Self {
     Self::ShapeAdded(event)
@@ -255,6 +313,18 @@ impl From<BaseShapeSet> for ShapeEvent {
   }
 }
 
+impl From<ShapeRenamed> for ShapeEvent {
+  fn from(event: ShapeRenamed) -> Self {
+    Self::ShapeRenamed(event)
+  }
+}
+
+impl From<ShapeRemoved> for ShapeEvent {
+  fn from(event: ShapeRemoved) -> Self {
+    Self::ShapeRemoved(event)
+  }
+}
+
 impl From<FieldAdded> for ShapeEvent {
   fn from(event: FieldAdded) -> Self {
     Self::FieldAdded(event)
@@ -267,6 +337,12 @@ impl From<FieldShapeSet> for ShapeEvent {
   }
 }
 
+impl From<FieldRenamed> for ShapeEvent {
+  fn from(event: FieldRenamed) -> Self {
+    Self::FieldRenamed(event)
+  }
+}
+
 impl From<FieldRemoved> for ShapeEvent {
   fn from(event: FieldRemoved) -> Self {
     Self::FieldRemoved(event)
@@ -285,6 +361,36 @@ impl From<ShapeParameterShapeSet> for ShapeEvent {
   }
 }
 
+impl From<ShapeParameterRenamed> for ShapeEvent {
+  fn from(event: ShapeParameterRenamed) -> Self {
+    Self::ShapeParameterRenamed(event)
+  }
+}
+
+impl From<ShapeParameterRemoved> for ShapeEvent {
+  fn from(event: ShapeParameterRemoved) -> Self {
+    Self::ShapeParameterRemoved(event)
+  }
+}
+
+impl From<ShapeConstraintAdded> for ShapeEvent {
+  fn from(event: ShapeConstraintAdded) -> Self {
+    Self::ShapeConstraintAdded(event)
+  }
+}
+
+impl From<ShapeConstraintRemoved> for ShapeEvent {
+  fn from(event: ShapeConstraintRemoved) -> Self {
+    Self::ShapeConstraintRemoved(event)
+  }
+}
+
+impl From<OneOfDiscriminatorSet> for ShapeEvent {
+  fn from(event: OneOfDiscriminatorSet) -> Self {
+    Self::OneOfDiscriminatorSet(event)
+  }
+}
+
 // Conversions from commands
 // -------------------------
 
@@ -293,18 +399,33 @@ impl From<ShapeCommand> for ShapeEvent {
     match shape_command {
       ShapeCommand::AddShape(command) => ShapeEvent::from(ShapeAdded::from(command)),
       ShapeCommand::SetBaseShape(command) => ShapeEvent::from(BaseShapeSet::from(command)),
+      ShapeCommand::RenameShape(command) => ShapeEvent::from(ShapeRenamed::from(command)),
+      ShapeCommand::RemoveShape(command) => ShapeEvent::from(ShapeRemoved::from(command)),
       ShapeCommand::AddField(command) => ShapeEvent::from(FieldAdded::from(command)),
+      ShapeCommand::RenameField(command) => ShapeEvent::from(FieldRenamed::from(command)),
       ShapeCommand::RemoveField(command) => ShapeEvent::from(FieldRemoved::from(command)),
+      ShapeCommand::SetFieldShape(command) => ShapeEvent::from(FieldShapeSet::from(command)),
       ShapeCommand::AddShapeParameter(command) => {
         ShapeEvent::from(ShapeParameterAdded::from(command))
       }
+      ShapeCommand::RenameShapeParameter(command) => {
+        ShapeEvent::from(ShapeParameterRenamed::from(command))
+      }
+      ShapeCommand::RemoveShapeParameter(command) => {
+        ShapeEvent::from(ShapeParameterRemoved::from(command))
+      }
       ShapeCommand::SetParameterShape(command) => {
         ShapeEvent::from(ShapeParameterShapeSet::from(command))
       }
-      _ => unimplemented!(
-        "conversion from shape command to shape event not implemented for variant: {:?}",
-        shape_command
-      ),
+      ShapeCommand::AddShapeConstraint(command) => {
+        ShapeEvent::from(ShapeConstraintAdded::from(command))
+      }
+      ShapeCommand::RemoveShapeConstraint(command) => {
+        ShapeEvent::from(ShapeConstraintRemoved::from(command))
+      }
+      ShapeCommand::SetOneOfDiscriminator(command) => {
+        ShapeEvent::from(OneOfDiscriminatorSet::from(command))
+      }
     }
   }
 }
@@ -331,6 +452,25 @@ impl From<shape_commands::SetBaseShape> for BaseShapeSet {
   }
 }
 
+impl From<shape_commands::RenameShape> for ShapeRenamed {
+  fn from(command: shape_commands::RenameShape) -> Self {
+    Self {
+      shape_id: command.shape_id,
+      name: command.name,
+      event_context: None,
+    }
+  }
+}
+
+impl From<shape_commands::RemoveShape> for ShapeRemoved {
+  fn from(command: shape_commands::RemoveShape) -> Self {
+    Self {
+      shape_id: command.shape_id,
+      event_context: None,
+    }
+  }
+}
+
 impl From<shape_commands::AddField> for FieldAdded {
   fn from(command: shape_commands::AddField) -> Self {
     Self {
@@ -343,6 +483,16 @@ impl From<shape_commands::AddField> for FieldAdded {
   }
 }
 
+impl From<shape_commands::RenameField> for FieldRenamed {
+  fn from(command: shape_commands::RenameField) -> Self {
+    Self {
+      field_id: command.field_id,
+      name: command.name,
+      event_context: None,
+    }
+  }
+}
+
 impl From<shape_commands::RemoveField> for FieldRemoved {
   fn from(command: shape_commands::RemoveField) -> Self {
     Self {
@@ -385,3 +535,52 @@ impl From<shape_commands::SetParameterShape> for ShapeParameterShapeSet {
     }
   }
 }
+
+impl From<shape_commands::RenameShapeParameter> for ShapeParameterRenamed {
+  fn from(command: shape_commands::RenameShapeParameter) -> Self {
+    Self {
+      shape_parameter_id: command.shape_parameter_id,
+      name: command.name,
+      event_context: None,
+    }
+  }
+}
+
+impl From<shape_commands::RemoveShapeParameter> for ShapeParameterRemoved {
+  fn from(command: shape_commands::RemoveShapeParameter) -> Self {
+    Self {
+      shape_parameter_id: command.shape_parameter_id,
+      event_context: None,
+    }
+  }
+}
+
+impl From<shape_commands::AddShapeConstraint> for ShapeConstraintAdded {
+  fn from(command: shape_commands::AddShapeConstraint) -> Self {
+    Self {
+      shape_constraint_id: command.shape_constraint_id,
+      shape_id: command.shape_id,
+      descriptor: command.descriptor,
+      event_context: None,
+    }
+  }
+}
+
+impl From<shape_commands::RemoveShapeConstraint> for ShapeConstraintRemoved {
+  fn from(command: shape_commands::RemoveShapeConstraint) -> Self {
+    Self {
+      shape_constraint_id: command.shape_constraint_id,
+      event_context: None,
+    }
+  }
+}
+
+impl From<shape_commands::SetOneOfDiscriminator> for OneOfDiscriminatorSet {
+  fn from(command: shape_commands::SetOneOfDiscriminator) -> Self {
+    Self {
+      shape_id: command.shape_id,
+      descriptor: command.descriptor,
+      event_context: None,
+    }
+  }
+}
@@ -1,6 +1,7 @@
 use super::{RfcEvent, SpecEvent};
 use cqrs_core::Event;
 use serde_json;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 
 #[derive(Debug, Clone)]
@@ -22,6 +23,7 @@ pub struct BatchChunkEvent {
   pub id: String,
   pub name: String,
   pub parent_id: String,
+  pub merged_parent_ids: Vec<String>,
   pub events: Vec<SpecEvent>,
 }
 
@@ -111,6 +113,13 @@ impl Event for UnknownChunkEvent {
 }
 
 impl RootChunkEvent {
+  pub fn batch_ids(&self) -> impl Iterator<Item = &String> {
+    self.events.iter().filter_map(|event| match event {
+      SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(e)) => Some(&e.batch_id),
+      _ => None,
+    })
+  }
+
   pub fn last_batch_id(&self) -> &String {
     self
       .events
@@ -151,10 +160,11 @@ impl TryFrom<(String, Vec<SpecEvent>)> for BatchChunkEvent {
     let batch_ids = parse_batch_chunk_events(&events);
 
     match batch_ids {
-      Ok((id, parent_id)) => Ok(Self {
+      Ok((id, parent_id, merged_parent_ids)) => Ok(Self {
         id,
         name,
         parent_id,
+        merged_parent_ids,
         events,
       }),
       Err(err) => Err((err, name, events)),
@@ -162,7 +172,9 @@ impl TryFrom<(String, Vec<SpecEvent>)> for BatchChunkEvent {
   }
 }
 
-fn parse_batch_chunk_events(events: &Vec<SpecEvent>) -> Result<(String, String), &'static str> {
+fn parse_batch_chunk_events(
+  events: &Vec<SpecEvent>,
+) -> Result<(String, String, Vec<String>), &'static str> {
   let first_event = events.first().ok_or("Chunk does not have any events")?;
 
   let batch_start_event = match first_event {
@@ -198,7 +210,83 @@ fn parse_batch_chunk_events(events: &Vec<SpecEvent>) -> Result<(String, String),
     }
   }
 
-  Ok((batch_id, parent_id))
+  Ok((
+    batch_id,
+    parent_id,
+    batch_start_event.merged_parent_ids.clone(),
+  ))
+}
+
+impl BatchChunkEvent {
+  /// The batches this chunk builds on, including the other parents of a merge commit
+  pub fn parent_ids(&self) -> impl Iterator<Item = &String> {
+    std::iter::once(&self.parent_id).chain(self.merged_parent_ids.iter())
+  }
+}
+
+// BatchChunkOrder
+// ---------------
+
+/// Orders batch chunks so each chunk comes after all the batches it builds on. When several
+/// chunks are ready at once, like the first commits on two branches, the chunk with the lowest
+/// name goes first, so the same chunks are always assembled in the same order.
+#[derive(Debug)]
+pub(crate) struct BatchChunkOrder<T> {
+  completed_batch_ids: HashSet<String>,
+  pending_chunks: Vec<PendingBatchChunk<T>>,
+}
+
+#[derive(Debug)]
+struct PendingBatchChunk<T> {
+  id: String,
+  name: String,
+  parent_ids: Vec<String>,
+  chunk: T,
+}
+
+impl<T> Default for BatchChunkOrder<T> {
+  fn default() -> Self {
+    Self {
+      completed_batch_ids: HashSet::new(),
+      pending_chunks: vec![],
+    }
+  }
+}
+
+impl<T> BatchChunkOrder<T> {
+  pub fn with_completed_batch(&mut self, batch_id: String) {
+    self.completed_batch_ids.insert(batch_id);
+  }
+
+  pub fn with_chunk(&mut self, id: String, name: String, parent_ids: Vec<String>, chunk: T) {
+    self.pending_chunks.push(PendingBatchChunk {
+      id,
+      name,
+      parent_ids,
+      chunk,
+    });
+  }
+
+  /// Take the next chunk of which all parents have completed, completing its batch in turn.
+  /// Chunks building on batches that never complete are never taken.
+  pub fn next_chunk(&mut self) -> Option<T> {
+    let completed_batch_ids = &self.completed_batch_ids;
+    let (next_index, _) = self
+      .pending_chunks
+      .iter()
+      .enumerate()
+      .filter(|(_, pending)| {
+        pending
+          .parent_ids
+          .iter()
+          .all(|parent_id| completed_batch_ids.contains(parent_id))
+      })
+      .min_by(|(_, a), (_, b)| (&a.name, &a.id).cmp(&(&b.name, &b.id)))?;
+
+    let next = self.pending_chunks.remove(next_index);
+    self.completed_batch_ids.insert(next.id);
+    Some(next.chunk)
+  }
 }
 
 #[cfg(test)]
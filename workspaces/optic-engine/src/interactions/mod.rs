@@ -3,18 +3,22 @@ use crate::learn_shape::{observe_body_trails, TrailObservationsResult, TrailValu
 use crate::projections::{EndpointProjection, SpecProjection};
 use crate::protos::shapehash::ShapeDescriptor;
 use crate::queries::endpoint::EndpointQueries;
-use crate::shapes::diff as diff_shape;
-use crate::shapes::ShapeDiffResult;
+use crate::queries::shape::ShapeQueries;
+use crate::shapes::{diff as diff_shape, diff_query_parameters as diff_query_parameters_shape};
+use crate::shapes::{JsonTrail, ShapeDiffResult};
 use crate::state::body::BodyDescriptor;
+use crate::state::shape::ShapeKind;
+use serde::{Deserialize, Serialize};
 
 pub mod result;
 mod traverser;
 mod visitors;
 
-use result::InteractionTrail;
 pub use result::{
   BodyAnalysisLocation, BodyAnalysisResult, InteractionDiffResult, UnmatchedQueryParameters,
+  UrlAnalysisResult,
 };
+use result::{InteractionTrail, InteractionTrailPathComponent};
 use visitors::{InteractionVisitors, PathVisitor};
 
 /// Compute diffs based on a spec and an interaction.
@@ -27,9 +31,14 @@ pub fn diff(
   http_interaction: HttpInteraction,
   config: &DiffConfig,
 ) -> Vec<InteractionDiffResult> {
+  if config.is_ignored_interaction(&http_interaction) {
+    return vec![];
+  }
+
   let endpoint_projection = spec_projection.endpoint();
   let endpoint_queries = EndpointQueries::new(endpoint_projection);
-  let interaction_traverser = traverser::Traverser::new(&endpoint_queries);
+  let shape_queries = ShapeQueries::new(spec_projection.shape());
+  let interaction_traverser = traverser::Traverser::new(&endpoint_queries, &shape_queries);
   let mut diff_visitors = visitors::diff::DiffVisitors::new();
 
   interaction_traverser.traverse(&http_interaction, &mut diff_visitors);
@@ -43,8 +52,12 @@ pub fn diff(
         let maybe_query_params: Option<BodyDescriptor> = (&http_interaction.request.query).into();
         let query_params = maybe_query_params.or_else(|| Some(BodyDescriptor::empty_object()));
 
-        let shape_diff_results =
-          diff_shape(spec_projection.shape(), query_params, &result.root_shape_id);
+        let shape_diff_results = diff_query_parameters_shape(
+          spec_projection.shape(),
+          query_params,
+          &result.root_shape_id,
+          config,
+        );
         shape_diff_results
           .into_iter()
           .map(|shape_diff| {
@@ -56,9 +69,13 @@ pub fn diff(
       }
       InteractionDiffResult::MatchedRequestBodyContentType(result) => {
         // eprintln!("shape diffing for matched a request body content type");
-        let body = &http_interaction.request.body.value;
-        let shape_diff_results =
-          diff_shape(spec_projection.shape(), body.into(), &result.root_shape_id);
+        let body = &http_interaction.request.body;
+        let shape_diff_results = diff_shape(
+          spec_projection.shape(),
+          body.into(),
+          &result.root_shape_id,
+          config,
+        );
         shape_diff_results
           .into_iter()
           .map(|shape_diff| {
@@ -73,9 +90,13 @@ pub fn diff(
         //   "interaction-diff: shape diffing for matched a response body content type: {:?}",
         //   &http_interaction.response.body
         // );
-        let body = &http_interaction.response.body.value;
-        let shape_diff_results =
-          diff_shape(spec_projection.shape(), body.into(), &result.root_shape_id);
+        let body = &http_interaction.response.body;
+        let shape_diff_results = diff_shape(
+          spec_projection.shape(),
+          body.into(),
+          &result.root_shape_id,
+          config,
+        );
         shape_diff_results
           .into_iter()
           .map(|shape_diff| {
@@ -85,6 +106,41 @@ pub fn diff(
           })
           .collect()
       }
+      InteractionDiffResult::MatchedRequestHeader(result) => {
+        // header values are strings that can spell out numbers and booleans, like query params
+        let value = BodyDescriptor::from(serde_json::Value::String(result.value.clone()));
+        let shape_diff_results = diff_query_parameters_shape(
+          spec_projection.shape(),
+          Some(value),
+          &result.root_shape_id,
+          config,
+        );
+        shape_diff_results
+          .into_iter()
+          .map(|shape_diff| {
+            InteractionDiffResult::UnmatchedRequestHeaderShape(
+              result.clone().into_shape_diff(shape_diff),
+            )
+          })
+          .collect()
+      }
+      InteractionDiffResult::MatchedResponseHeader(result) => {
+        let value = BodyDescriptor::from(serde_json::Value::String(result.value.clone()));
+        let shape_diff_results = diff_query_parameters_shape(
+          spec_projection.shape(),
+          Some(value),
+          &result.root_shape_id,
+          config,
+        );
+        shape_diff_results
+          .into_iter()
+          .map(|shape_diff| {
+            InteractionDiffResult::UnmatchedResponseHeaderShape(
+              result.clone().into_shape_diff(shape_diff),
+            )
+          })
+          .collect()
+      }
       _ => vec![result],
     })
     .filter(|result| {
@@ -94,15 +150,161 @@ pub fn diff(
         InteractionDiffResult::UnmatchedQueryParameters(UnmatchedQueryParameters::Unobserved(_)),
       )
     })
+    .filter(|result| {
+      config.diff_query_parameters
+        || !matches!(
+          result,
+          InteractionDiffResult::UnmatchedQueryParameters(_)
+            | InteractionDiffResult::UnmatchedQueryParametersShape(_)
+        )
+    })
+    .filter(|result| match result.interaction_trail().path.last() {
+      Some(InteractionTrailPathComponent::RequestHeader { name })
+      | Some(InteractionTrailPathComponent::ResponseHeader { name, .. }) => {
+        !config.is_ignored_header(name)
+      }
+      _ => true,
+    })
+    .filter(|result| match result {
+      // headers documented as optional are allowed to be missing
+      InteractionDiffResult::UnmatchedRequestHeader(_)
+      | InteractionDiffResult::UnmatchedResponseHeader(_) => !is_optional_header(
+        spec_projection,
+        result
+          .requests_trail()
+          .get_header_parameter_id()
+          .expect("header diffs should reference a header parameter"),
+      ),
+      _ => true,
+    })
     .collect()
 }
 
-#[derive(Clone, Debug)]
-pub struct DiffConfig {}
+fn is_optional_header(spec_projection: &SpecProjection, parameter_id: &String) -> bool {
+  let endpoint_queries = EndpointQueries::new(spec_projection.endpoint());
+  let shape_queries = ShapeQueries::new(spec_projection.shape());
+
+  endpoint_queries
+    .resolve_header_parameter(parameter_id)
+    .and_then(|header_descriptor| header_descriptor.shape.as_ref())
+    .filter(|shape_descriptor| !shape_descriptor.is_removed)
+    .map(|shape_descriptor| {
+      matches!(
+        shape_queries.resolve_to_core_shape(&shape_descriptor.shape_id),
+        ShapeKind::OptionalKind
+      )
+    })
+    .unwrap_or(false)
+}
+
+/// Knobs for tuning which differences between a spec and interactions are reported.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffConfig {
+  /// Json trail patterns (`*.metadata.*`) at and below which shape diffs are ignored. A `*`
+  /// matches a single key or array index, a `**` any amount of them. Headers are matched like
+  /// top-level keys by their name, regardless of its case.
+  pub ignore_json_trails: Vec<String>,
+  /// Path patterns (`/health`, `/users/*/avatar`) of interactions that should not be diffed
+  pub ignore_paths: Vec<String>,
+  /// Http methods of interactions that should not be diffed
+  pub ignore_methods: Vec<String>,
+  /// Whether undocumented object keys are diffs, rather than allowed as long as the documented
+  /// ones match
+  pub strict: bool,
+  pub diff_query_parameters: bool,
+}
 
 impl Default for DiffConfig {
   fn default() -> Self {
-    Self {}
+    Self {
+      ignore_json_trails: vec![],
+      ignore_paths: vec![],
+      ignore_methods: vec![],
+      strict: true,
+      diff_query_parameters: true,
+    }
+  }
+}
+
+impl DiffConfig {
+  pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+    serde_json::from_str(json)
+  }
+
+  pub fn is_ignored_interaction(&self, interaction: &HttpInteraction) -> bool {
+    let method = &interaction.request.method;
+    let path_components = interaction
+      .request
+      .path
+      .split('/')
+      .filter(|component| !component.is_empty())
+      .collect::<Vec<_>>();
+
+    self
+      .ignore_methods
+      .iter()
+      .any(|ignored_method| ignored_method.eq_ignore_ascii_case(method))
+      || self.ignore_paths.iter().any(|pattern| {
+        let pattern_components = pattern
+          .split('/')
+          .filter(|component| !component.is_empty())
+          .collect::<Vec<_>>();
+        pattern_matches(&pattern_components, &path_components, false)
+      })
+  }
+
+  pub fn is_ignored_json_trail(&self, json_trail: &JsonTrail) -> bool {
+    if self.ignore_json_trails.is_empty() {
+      return false;
+    }
+
+    // keys can contain dots themselves, so the trail is matched by its components
+    let trail_identifiers = json_trail.identifiers();
+    let trail_components = trail_identifiers
+      .iter()
+      .map(String::as_str)
+      .collect::<Vec<_>>();
+
+    self.ignore_json_trails.iter().any(|pattern| {
+      let pattern_components = pattern
+        .split('.')
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>();
+      pattern_matches(&pattern_components, &trail_components, true)
+    })
+  }
+
+  pub fn is_ignored_header(&self, name: &str) -> bool {
+    let name = name.to_lowercase();
+    self.ignore_json_trails.iter().any(|pattern| {
+      let pattern = pattern.to_lowercase();
+      let pattern_components = pattern
+        .split('.')
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>();
+      pattern_matches(&pattern_components, &[name.as_str()], true)
+    })
+  }
+
+  pub fn allows_undocumented_fields(&self) -> bool {
+    !self.strict
+  }
+}
+
+fn pattern_matches(pattern: &[&str], components: &[&str], match_descendants: bool) -> bool {
+  match (pattern.split_first(), components.split_first()) {
+    (None, None) => true,
+    (None, Some(_)) => match_descendants,
+    (Some((&"**", rest_pattern)), _) => {
+      pattern_matches(rest_pattern, components, match_descendants)
+        || (!components.is_empty() && pattern_matches(pattern, &components[1..], match_descendants))
+    }
+    (Some(_), None) => false,
+    (Some((pattern_component, rest_pattern)), Some((component, rest_components))) => {
+      (*pattern_component == "*" || pattern_component == component)
+        && pattern_matches(rest_pattern, rest_components, match_descendants)
+    }
   }
 }
 
@@ -120,7 +322,8 @@ pub fn analyze_undocumented_bodies<'a>(
 ) -> impl Iterator<Item = BodyAnalysisResult> {
   let endpoint_projection = spec_projection.endpoint();
   let endpoint_queries = EndpointQueries::new(endpoint_projection);
-  let interaction_traverser = traverser::Traverser::new(&endpoint_queries);
+  let shape_queries = ShapeQueries::new(spec_projection.shape());
+  let interaction_traverser = traverser::Traverser::new(&endpoint_queries, &shape_queries);
   let mut diff_visitors = visitors::diff::DiffVisitors::new();
 
   interaction_traverser.traverse(&interaction, &mut diff_visitors);
@@ -145,7 +348,7 @@ pub fn analyze_undocumented_bodies<'a>(
     }
     InteractionDiffResult::UnmatchedRequestBodyContentType(diff) => {
       let body = &interaction.request.body;
-      let body_trail_observations = observe_body_trails(&body.value);
+      let body_trail_observations = observe_body_trails(body);
 
       vec![BodyAnalysisResult {
         body_location: BodyAnalysisLocation::from(diff.clone()),
@@ -154,7 +357,7 @@ pub fn analyze_undocumented_bodies<'a>(
     }
     InteractionDiffResult::UnmatchedResponseBodyContentType(diff) => {
       let body = &interaction.response.body;
-      let trail_observations = observe_body_trails(&body.value);
+      let trail_observations = observe_body_trails(body);
 
       vec![BodyAnalysisResult {
         body_location: BodyAnalysisLocation::from(diff),
@@ -174,14 +377,42 @@ impl Default for AnalyzeUndocumentedBodiesConfig {
   }
 }
 
+/// Analyses the urls of interactions that don't match any of the spec's paths. Aggregated by a
+/// `LearnedUndocumentedUrlsProjection`, path templates and the commands to add them to the spec
+/// can be learned from them.
+pub fn analyze_undocumented_urls(
+  spec_projection: &SpecProjection,
+  interaction: HttpInteraction,
+) -> impl Iterator<Item = UrlAnalysisResult> {
+  let endpoint_projection = spec_projection.endpoint();
+  let endpoint_queries = EndpointQueries::new(endpoint_projection);
+  let shape_queries = ShapeQueries::new(spec_projection.shape());
+  let interaction_traverser = traverser::Traverser::new(&endpoint_queries, &shape_queries);
+  let mut diff_visitors = visitors::diff::DiffVisitors::new();
+
+  interaction_traverser.traverse(&interaction, &mut diff_visitors);
+
+  let results = diff_visitors.take_results().unwrap();
+
+  results.into_iter().filter_map(move |result| match result {
+    InteractionDiffResult::UnmatchedRequestUrl(_) => Some(UrlAnalysisResult {
+      path: interaction.request.path.clone(),
+      method: interaction.request.method.clone(),
+      status_code: interaction.response.status_code,
+    }),
+    _ => None,
+  })
+}
+
 pub fn analyze_documented_bodies(
   spec_projection: &SpecProjection,
   interaction: HttpInteraction,
 ) -> impl Iterator<Item = BodyAnalysisResult> {
   let endpoint_rpojection = spec_projection.endpoint();
   let endpoint_queries = EndpointQueries::new(endpoint_rpojection);
+  let shape_queries = ShapeQueries::new(spec_projection.shape());
 
-  let interaction_traverser = traverser::Traverser::new(&endpoint_queries);
+  let interaction_traverser = traverser::Traverser::new(&endpoint_queries, &shape_queries);
   let mut diff_visitors = visitors::diff::DiffVisitors::new();
 
   interaction_traverser.traverse(&interaction, &mut diff_visitors);
@@ -202,7 +433,7 @@ pub fn analyze_documented_bodies(
 
     InteractionDiffResult::MatchedRequestBodyContentType(diff) => {
       let body = &interaction.request.body;
-      let trail_observations = observe_body_trails(&body.value);
+      let trail_observations = observe_body_trails(body);
 
       Some(BodyAnalysisResult {
         body_location: BodyAnalysisLocation::from(diff),
@@ -211,7 +442,7 @@ pub fn analyze_documented_bodies(
     }
     InteractionDiffResult::MatchedResponseBodyContentType(diff) => {
       let body = &interaction.response.body;
-      let trail_observations = observe_body_trails(&body.value);
+      let trail_observations = observe_body_trails(body);
 
       Some(BodyAnalysisResult {
         body_location: BodyAnalysisLocation::from(diff),
@@ -221,3 +452,52 @@ pub fn analyze_documented_bodies(
     _ => None,
   })
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn diff_config_can_match_json_trail_patterns() {
+    let config = DiffConfig {
+      ignore_json_trails: vec![String::from("*.metadata.*"), String::from("**.id")],
+      ..DiffConfig::default()
+    };
+
+    let ignored_trails = vec![
+      JsonTrail::empty()
+        .with_object_key(String::from("user"))
+        .with_object_key(String::from("metadata"))
+        .with_object_key(String::from("version")),
+      JsonTrail::empty()
+        .with_object_key(String::from("user"))
+        .with_object_key(String::from("metadata"))
+        .with_object_key(String::from("version"))
+        .with_object_key(String::from("major")),
+      JsonTrail::empty().with_object_key(String::from("id")),
+      JsonTrail::empty()
+        .with_array_item(3)
+        .with_object_key(String::from("author"))
+        .with_object_key(String::from("id")),
+    ];
+    let diffed_trails = vec![
+      JsonTrail::empty(),
+      JsonTrail::empty()
+        .with_object_key(String::from("user"))
+        .with_object_key(String::from("metadata")),
+      JsonTrail::empty().with_object_key(String::from("metadata")),
+      JsonTrail::empty().with_object_key(String::from("identifier")),
+      JsonTrail::empty().with_object_key(String::from("user.metadata.version")),
+      JsonTrail::empty()
+        .with_object_key(String::from("user"))
+        .with_object_key(String::from("author.id")),
+    ];
+
+    for trail in ignored_trails {
+      assert!(config.is_ignored_json_trail(&trail), "{} is ignored", trail);
+    }
+    for trail in diffed_trails {
+      assert!(!config.is_ignored_json_trail(&trail), "{} is diffed", trail);
+    }
+  }
+}
@@ -1,7 +1,9 @@
 use crate::events::http_interaction::HttpInteraction;
 use crate::learn_shape::TrailObservationsResult;
 use crate::shapes::{JsonTrail, ShapeDiffResult};
-use crate::state::endpoint::{PathComponentId, QueryParametersId, RequestId, ResponseId, ShapeId};
+use crate::state::endpoint::{
+  PathComponentId, QueryParametersId, RequestId, RequestParameterId, ResponseId, ShapeId,
+};
 use seahash::hash;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::{DefaultHasher, HashMap};
@@ -11,10 +13,15 @@ pub enum InteractionDiffResult {
   UnmatchedQueryParameters(UnmatchedQueryParameters),
   UnmatchedQueryParametersShape(UnmatchedQueryParametersShape),
   UnmatchedRequestUrl(UnmatchedRequestUrl),
+  UnmatchedPathParameterShape(UnmatchedPathParameterShape),
   UnmatchedRequestBodyContentType(UnmatchedRequestBodyContentType),
   UnmatchedRequestBodyShape(UnmatchedRequestBodyShape),
   UnmatchedResponseBodyContentType(UnmatchedResponseBodyContentType),
   UnmatchedResponseBodyShape(UnmatchedResponseBodyShape),
+  UnmatchedRequestHeader(UnmatchedRequestHeader),
+  UnmatchedRequestHeaderShape(UnmatchedRequestHeaderShape),
+  UnmatchedResponseHeader(UnmatchedResponseHeader),
+  UnmatchedResponseHeaderShape(UnmatchedResponseHeaderShape),
   //
   // Matches
   // -------
@@ -24,6 +31,10 @@ pub enum InteractionDiffResult {
   MatchedRequestBodyContentType(MatchedRequestBodyContentType),
   #[serde(skip)]
   MatchedResponseBodyContentType(MatchedResponseBodyContentType),
+  #[serde(skip)]
+  MatchedRequestHeader(MatchedRequestHeader),
+  #[serde(skip)]
+  MatchedResponseHeader(MatchedResponseHeader),
 }
 
 impl InteractionDiffResult {
@@ -38,13 +49,20 @@ impl InteractionDiffResult {
       InteractionDiffResult::UnmatchedQueryParameters(diff) => diff.interaction_trail(),
       InteractionDiffResult::UnmatchedQueryParametersShape(diff) => &diff.interaction_trail,
       InteractionDiffResult::UnmatchedRequestUrl(diff) => &diff.interaction_trail,
+      InteractionDiffResult::UnmatchedPathParameterShape(diff) => &diff.interaction_trail,
       InteractionDiffResult::UnmatchedRequestBodyContentType(diff) => &diff.interaction_trail,
       InteractionDiffResult::UnmatchedRequestBodyShape(diff) => &diff.interaction_trail,
       InteractionDiffResult::UnmatchedResponseBodyContentType(diff) => &diff.interaction_trail,
       InteractionDiffResult::UnmatchedResponseBodyShape(diff) => &diff.interaction_trail,
+      InteractionDiffResult::UnmatchedRequestHeader(diff) => &diff.interaction_trail,
+      InteractionDiffResult::UnmatchedResponseHeader(diff) => &diff.interaction_trail,
+      InteractionDiffResult::UnmatchedRequestHeaderShape(diff) => &diff.interaction_trail,
+      InteractionDiffResult::UnmatchedResponseHeaderShape(diff) => &diff.interaction_trail,
       InteractionDiffResult::MatchedQueryParameters(diff) => &diff.interaction_trail,
       InteractionDiffResult::MatchedRequestBodyContentType(diff) => &diff.interaction_trail,
       InteractionDiffResult::MatchedResponseBodyContentType(diff) => &diff.interaction_trail,
+      InteractionDiffResult::MatchedRequestHeader(diff) => &diff.interaction_trail,
+      InteractionDiffResult::MatchedResponseHeader(diff) => &diff.interaction_trail,
     }
   }
 
@@ -53,13 +71,20 @@ impl InteractionDiffResult {
       InteractionDiffResult::UnmatchedQueryParameters(diff) => diff.requests_trail(),
       InteractionDiffResult::UnmatchedQueryParametersShape(diff) => &diff.requests_trail,
       InteractionDiffResult::UnmatchedRequestUrl(diff) => &diff.requests_trail,
+      InteractionDiffResult::UnmatchedPathParameterShape(diff) => &diff.requests_trail,
       InteractionDiffResult::UnmatchedRequestBodyContentType(diff) => &diff.requests_trail,
       InteractionDiffResult::UnmatchedRequestBodyShape(diff) => &diff.requests_trail,
       InteractionDiffResult::UnmatchedResponseBodyContentType(diff) => &diff.requests_trail,
       InteractionDiffResult::UnmatchedResponseBodyShape(diff) => &diff.requests_trail,
+      InteractionDiffResult::UnmatchedRequestHeader(diff) => &diff.requests_trail,
+      InteractionDiffResult::UnmatchedResponseHeader(diff) => &diff.requests_trail,
+      InteractionDiffResult::UnmatchedRequestHeaderShape(diff) => &diff.requests_trail,
+      InteractionDiffResult::UnmatchedResponseHeaderShape(diff) => &diff.requests_trail,
       InteractionDiffResult::MatchedQueryParameters(diff) => &diff.requests_trail,
       InteractionDiffResult::MatchedRequestBodyContentType(diff) => &diff.requests_trail,
       InteractionDiffResult::MatchedResponseBodyContentType(diff) => &diff.requests_trail,
+      InteractionDiffResult::MatchedRequestHeader(diff) => &diff.requests_trail,
+      InteractionDiffResult::MatchedResponseHeader(diff) => &diff.requests_trail,
     }
   }
 
@@ -68,13 +93,12 @@ impl InteractionDiffResult {
       InteractionDiffResult::UnmatchedQueryParametersShape(diff) => Some(&diff.shape_diff_result),
       InteractionDiffResult::UnmatchedRequestBodyShape(diff) => Some(&diff.shape_diff_result),
       InteractionDiffResult::UnmatchedResponseBodyShape(diff) => Some(&diff.shape_diff_result),
+      InteractionDiffResult::UnmatchedRequestHeaderShape(diff) => Some(&diff.shape_diff_result),
+      InteractionDiffResult::UnmatchedResponseHeaderShape(diff) => Some(&diff.shape_diff_result),
       _ => None,
     }?;
 
-    match shape_diff_result {
-      ShapeDiffResult::UnmatchedShape { json_trail, .. } => Some(json_trail),
-      ShapeDiffResult::UnspecifiedShape { json_trail, .. } => Some(json_trail),
-    }
+    Some(shape_diff_result.json_trail())
   }
 }
 
@@ -95,6 +119,23 @@ impl UnmatchedRequestUrl {
   }
 }
 
+/// The value of a path parameter that doesn't fit the shape set for it
+#[derive(Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedPathParameterShape {
+  pub interaction_trail: InteractionTrail,
+  pub requests_trail: RequestSpecTrail,
+}
+
+impl UnmatchedPathParameterShape {
+  pub fn new(interaction_trail: InteractionTrail, requests_trail: RequestSpecTrail) -> Self {
+    UnmatchedPathParameterShape {
+      interaction_trail,
+      requests_trail,
+    }
+  }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Hash)]
 #[serde(rename_all = "camelCase")]
 pub struct UnmatchedQueryParametersDescriptor {
@@ -330,6 +371,129 @@ impl UnmatchedResponseBodyShape {
   }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedRequestHeader {
+  pub interaction_trail: InteractionTrail,
+  pub requests_trail: RequestSpecTrail,
+}
+
+impl UnmatchedRequestHeader {
+  pub fn new(interaction_trail: InteractionTrail, requests_trail: RequestSpecTrail) -> Self {
+    UnmatchedRequestHeader {
+      interaction_trail,
+      requests_trail,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedResponseHeader {
+  pub interaction_trail: InteractionTrail,
+  pub requests_trail: RequestSpecTrail,
+}
+
+impl UnmatchedResponseHeader {
+  pub fn new(interaction_trail: InteractionTrail, requests_trail: RequestSpecTrail) -> Self {
+    UnmatchedResponseHeader {
+      interaction_trail,
+      requests_trail,
+    }
+  }
+}
+
+/// The value of a documented request header that doesn't fit the shape set for it
+#[derive(Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedRequestHeaderShape {
+  pub interaction_trail: InteractionTrail,
+  pub requests_trail: RequestSpecTrail,
+  pub shape_diff_result: ShapeDiffResult,
+}
+
+/// The value of a documented response header that doesn't fit the shape set for it
+#[derive(Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedResponseHeaderShape {
+  pub interaction_trail: InteractionTrail,
+  pub requests_trail: RequestSpecTrail,
+  pub shape_diff_result: ShapeDiffResult,
+}
+
+#[derive(Clone, Debug, Serialize, Hash)]
+pub struct MatchedRequestHeader {
+  pub interaction_trail: InteractionTrail,
+  pub requests_trail: RequestSpecTrail,
+  pub root_shape_id: ShapeId,
+  pub value: String,
+}
+
+impl MatchedRequestHeader {
+  pub fn new(
+    interaction_trail: InteractionTrail,
+    requests_trail: RequestSpecTrail,
+    root_shape_id: ShapeId,
+    value: String,
+  ) -> Self {
+    MatchedRequestHeader {
+      interaction_trail,
+      requests_trail,
+      root_shape_id,
+      value,
+    }
+  }
+
+  pub fn into_shape_diff(self, shape_diff_result: ShapeDiffResult) -> UnmatchedRequestHeaderShape {
+    UnmatchedRequestHeaderShape {
+      interaction_trail: self.interaction_trail,
+      requests_trail: self.requests_trail,
+      shape_diff_result,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Hash)]
+pub struct MatchedResponseHeader {
+  pub interaction_trail: InteractionTrail,
+  pub requests_trail: RequestSpecTrail,
+  pub root_shape_id: ShapeId,
+  pub value: String,
+}
+
+impl MatchedResponseHeader {
+  pub fn new(
+    interaction_trail: InteractionTrail,
+    requests_trail: RequestSpecTrail,
+    root_shape_id: ShapeId,
+    value: String,
+  ) -> Self {
+    MatchedResponseHeader {
+      interaction_trail,
+      requests_trail,
+      root_shape_id,
+      value,
+    }
+  }
+
+  pub fn into_shape_diff(self, shape_diff_result: ShapeDiffResult) -> UnmatchedResponseHeaderShape {
+    UnmatchedResponseHeaderShape {
+      interaction_trail: self.interaction_trail,
+      requests_trail: self.requests_trail,
+      shape_diff_result,
+    }
+  }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// The url of an interaction that didn't match any of the spec's paths
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UrlAnalysisResult {
+  pub path: String,
+  pub method: String,
+  pub status_code: u16,
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 #[derive(Clone, Debug)]
 pub struct BodyAnalysisResult {
@@ -521,6 +685,12 @@ impl InteractionTrail {
       .push(InteractionTrailPathComponent::QueryParameters)
   }
 
+  pub fn with_path_parameter(&mut self, name: String) {
+    self
+      .path
+      .push(InteractionTrailPathComponent::PathParameter { name })
+  }
+
   pub fn with_request_body(&mut self, content_type: String) {
     self
       .path
@@ -534,6 +704,18 @@ impl InteractionTrail {
     })
   }
 
+  pub fn with_request_header(&mut self, name: String) {
+    self
+      .path
+      .push(InteractionTrailPathComponent::RequestHeader { name })
+  }
+
+  pub fn with_response_header(&mut self, name: String, status_code: u16) {
+    self
+      .path
+      .push(InteractionTrailPathComponent::ResponseHeader { name, status_code })
+  }
+
   pub fn get_method(&self) -> Option<&String> {
     self.path.iter().find_map(|component| match component {
       InteractionTrailPathComponent::Method { method } => Some(method),
@@ -559,7 +741,10 @@ impl InteractionTrail {
     self.path.iter().find_map(|component| match component {
       InteractionTrailPathComponent::ResponseBody { status_code, .. } => Some(*status_code),
       InteractionTrailPathComponent::ResponseStatusCode { status_code } => Some(*status_code),
+      InteractionTrailPathComponent::ResponseHeader { status_code, .. } => Some(*status_code),
       InteractionTrailPathComponent::Method { .. }
+      | InteractionTrailPathComponent::PathParameter { .. }
+      | InteractionTrailPathComponent::RequestHeader { .. }
       | InteractionTrailPathComponent::QueryParameters
       | InteractionTrailPathComponent::RequestBody { .. }
       | InteractionTrailPathComponent::Url { .. } => None,
@@ -632,6 +817,8 @@ pub enum RequestSpecTrail {
   SpecRequestBody(SpecRequestBody),
   SpecResponseRoot(SpecResponseRoot),
   SpecResponseBody(SpecResponseBody),
+  SpecRequestHeader(SpecRequestHeader),
+  SpecResponseHeader(SpecResponseHeader),
 }
 
 impl RequestSpecTrail {
@@ -655,6 +842,7 @@ impl RequestSpecTrail {
     match self {
       RequestSpecTrail::SpecRequestBody(spec_body) => Some(&spec_body.request_id),
       RequestSpecTrail::SpecRequestRoot(spec_body) => Some(&spec_body.request_id),
+      RequestSpecTrail::SpecRequestHeader(spec_header) => Some(&spec_header.request_id),
       _ => None,
     }
   }
@@ -663,6 +851,15 @@ impl RequestSpecTrail {
     match self {
       RequestSpecTrail::SpecResponseBody(spec_body) => Some(&spec_body.response_id),
       RequestSpecTrail::SpecResponseRoot(spec_body) => Some(&spec_body.response_id),
+      RequestSpecTrail::SpecResponseHeader(spec_header) => Some(&spec_header.response_id),
+      _ => None,
+    }
+  }
+
+  pub fn get_header_parameter_id(&self) -> Option<&String> {
+    match self {
+      RequestSpecTrail::SpecRequestHeader(spec_header) => Some(&spec_header.parameter_id),
+      RequestSpecTrail::SpecResponseHeader(spec_header) => Some(&spec_header.parameter_id),
       _ => None,
     }
   }
@@ -706,6 +903,19 @@ pub struct SpecResponseRoot {
 pub struct SpecResponseBody {
   pub response_id: ResponseId,
 }
+#[derive(Clone, Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecRequestHeader {
+  pub request_id: RequestId,
+  pub parameter_id: RequestParameterId,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecResponseHeader {
+  pub response_id: ResponseId,
+  pub parameter_id: RequestParameterId,
+}
 //@GOTCHA make sure these serialize matching the existing scala code
 #[derive(Clone, Debug, Deserialize, Serialize, Hash)]
 pub enum InteractionTrailPathComponent {
@@ -715,6 +925,9 @@ pub enum InteractionTrailPathComponent {
   Method {
     method: String,
   },
+  PathParameter {
+    name: String,
+  },
   QueryParameters,
   #[serde(rename_all = "camelCase")]
   RequestBody {
@@ -729,4 +942,12 @@ pub enum InteractionTrailPathComponent {
     content_type: String,
     status_code: u16,
   },
+  RequestHeader {
+    name: String,
+  },
+  #[serde(rename_all = "camelCase")]
+  ResponseHeader {
+    name: String,
+    status_code: u16,
+  },
 }
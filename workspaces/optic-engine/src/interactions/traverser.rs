@@ -1,20 +1,26 @@
 use super::visitors::{
   InteractionVisitors, PathVisitor, PathVisitorContext, QueryParametersVisitor,
   QueryParametersVisitorContext, RequestBodyVisitor, RequestBodyVisitorContext,
-  ResponseBodyVisitor, ResponseBodyVisitorContext,
+  RequestHeadersVisitor, RequestHeadersVisitorContext, ResponseBodyVisitor,
+  ResponseBodyVisitorContext, ResponseHeadersVisitor, ResponseHeadersVisitorContext,
 };
 use crate::events::HttpInteraction;
 use crate::projections::endpoint::ROOT_PATH_ID;
 use crate::queries::endpoint::EndpointQueries;
+use crate::queries::shape::ShapeQueries;
 use crate::state::endpoint::PathComponentId;
 
 pub struct Traverser<'a> {
   endpoint_queries: &'a EndpointQueries<'a>,
+  shape_queries: &'a ShapeQueries<'a>,
 }
 
 impl<'a> Traverser<'a> {
-  pub fn new(endpoint_queries: &'a EndpointQueries) -> Self {
-    Traverser { endpoint_queries }
+  pub fn new(endpoint_queries: &'a EndpointQueries, shape_queries: &'a ShapeQueries) -> Self {
+    Traverser {
+      endpoint_queries,
+      shape_queries,
+    }
   }
 
   pub fn traverse<R>(
@@ -23,9 +29,15 @@ impl<'a> Traverser<'a> {
     visitors: &mut impl InteractionVisitors<R>,
   ) {
     let path_visitor = visitors.path();
-    let resolved_path = self.endpoint_queries.resolve_interaction_path(&interaction);
+    let path_match = self
+      .endpoint_queries
+      .resolve_interaction_path_match(&interaction, Some(self.shape_queries));
+    let resolved_path = path_match.as_ref().map(|path_match| path_match.path_id);
     let path_context = PathVisitorContext {
       path: resolved_path,
+      path_parameters: path_match
+        .map(|path_match| path_match.parameters)
+        .unwrap_or_default(),
     };
     path_visitor.visit(interaction, &path_context);
 
@@ -97,6 +109,61 @@ impl<'a> Traverser<'a> {
     };
     response_body_visitor.end(interaction, &path_context);
     // eprintln!("ended response body visiting");
+
+    let request_headers_visitor = visitors.request_headers();
+    request_headers_visitor.begin();
+    if let Some(path_id) = resolved_path {
+      let request = self
+        .endpoint_queries
+        .resolve_request_by_method_and_content_type(
+          path_id,
+          &interaction.request.method,
+          interaction.request.body.content_type.as_ref(),
+        );
+      if let Some(request) = request {
+        let (request_id, _) = request;
+        request_headers_visitor.visit(
+          interaction,
+          &RequestHeadersVisitorContext {
+            path: path_id,
+            request,
+            headers: self
+              .endpoint_queries
+              .resolve_request_headers(request_id)
+              .collect(),
+          },
+        );
+      }
+    }
+    request_headers_visitor.end(interaction, &path_context);
+
+    let response_headers_visitor = visitors.response_headers();
+    response_headers_visitor.begin();
+    if let Some(path_id) = resolved_path {
+      let response = self
+        .endpoint_queries
+        .resolve_response_by_method_status_code_and_content_type(
+          path_id,
+          &interaction.request.method,
+          interaction.response.status_code,
+          interaction.response.body.content_type.as_ref(),
+        );
+      if let Some(response) = response {
+        let (response_id, _) = response;
+        response_headers_visitor.visit(
+          interaction,
+          &ResponseHeadersVisitorContext {
+            path: path_id,
+            response,
+            headers: self
+              .endpoint_queries
+              .resolve_response_headers(response_id)
+              .collect(),
+          },
+        );
+      }
+    }
+    response_headers_visitor.end(interaction, &path_context);
   }
 }
 
@@ -1,19 +1,26 @@
 use super::{
   InteractionVisitor, InteractionVisitors, PathVisitor, PathVisitorContext, QueryParametersVisitor,
   QueryParametersVisitorContext, RequestBodyVisitor, RequestBodyVisitorContext,
-  ResponseBodyVisitor, ResponseBodyVisitorContext, VisitorResults,
+  RequestHeadersVisitor, RequestHeadersVisitorContext, ResponseBodyVisitor,
+  ResponseBodyVisitorContext, ResponseHeadersVisitor, ResponseHeadersVisitorContext,
+  VisitorResults,
 };
 use crate::interactions::result::{
   InteractionDiffResult, MatchedQueryParameters, MatchedRequestBodyContentType,
-  MatchedResponseBodyContentType, SpecQueryParameters, SpecRoot, UnmatchedQueryParameters,
-  UnmatchedRequestBodyContentType, UnmatchedRequestUrl, UnmatchedResponseBodyContentType,
+  MatchedRequestHeader, MatchedResponseBodyContentType, MatchedResponseHeader, SpecQueryParameters,
+  SpecRoot, UnmatchedPathParameterShape, UnmatchedQueryParameters, UnmatchedRequestBodyContentType,
+  UnmatchedRequestHeader, UnmatchedRequestUrl, UnmatchedResponseBodyContentType,
+  UnmatchedResponseHeader,
 };
 use crate::interactions::result::{
   InteractionTrail, InteractionTrailPathComponent, RequestSpecTrail, SpecPath, SpecRequestBody,
-  SpecResponseBody,
+  SpecRequestHeader, SpecResponseBody, SpecResponseHeader,
 };
 use crate::state::body::BodyDescriptor;
-use crate::state::endpoint::{HttpContentType, RequestId, ResponseId};
+use crate::state::endpoint::{
+  is_better_content_type_match, match_content_types, HttpContentType, MediaTypeMatch, RequestId,
+  ResponseId,
+};
 use crate::HttpInteraction;
 
 pub struct DiffVisitors {
@@ -21,6 +28,8 @@ pub struct DiffVisitors {
   query_params: DiffQueryParametersVisitor,
   request_body: DiffRequestBodyVisitor,
   response_body: DiffResponseBodyVisitor,
+  request_headers: DiffRequestHeadersVisitor,
+  response_headers: DiffResponseHeadersVisitor,
 }
 
 impl DiffVisitors {
@@ -30,6 +39,8 @@ impl DiffVisitors {
       query_params: DiffQueryParametersVisitor::new(),
       request_body: DiffRequestBodyVisitor::new(),
       response_body: DiffResponseBodyVisitor::new(),
+      request_headers: DiffRequestHeadersVisitor::new(),
+      response_headers: DiffResponseHeadersVisitor::new(),
     }
   }
 }
@@ -41,6 +52,8 @@ impl InteractionVisitors<InteractionDiffResult> for DiffVisitors {
   type QueryParameters = DiffQueryParametersVisitor;
   type RequestBody = DiffRequestBodyVisitor;
   type ResponseBody = DiffResponseBodyVisitor;
+  type RequestHeaders = DiffRequestHeadersVisitor;
+  type ResponseHeaders = DiffResponseHeadersVisitor;
 
   fn path(&mut self) -> &mut DiffPathVisitor {
     &mut self.path
@@ -54,6 +67,12 @@ impl InteractionVisitors<InteractionDiffResult> for DiffVisitors {
   fn response_body(&mut self) -> &mut DiffResponseBodyVisitor {
     &mut self.response_body
   }
+  fn request_headers(&mut self) -> &mut DiffRequestHeadersVisitor {
+    &mut self.request_headers
+  }
+  fn response_headers(&mut self) -> &mut DiffResponseHeadersVisitor {
+    &mut self.response_headers
+  }
 }
 ///////////////////////////////////////////////////////////////////////////////
 
@@ -88,6 +107,23 @@ impl PathVisitor<InteractionDiffResult> for DiffPathVisitor {
       ));
       self.push(diff);
     }
+
+    let unfit_parameters = context
+      .path_parameters
+      .iter()
+      .filter(|parameter| !parameter.fits_shape);
+    for parameter in unfit_parameters {
+      let mut interaction_trail = InteractionTrail::empty();
+      interaction_trail.with_url(interaction.request.path.clone());
+      interaction_trail.with_method(interaction.request.method.clone());
+      interaction_trail.with_path_parameter(String::from(parameter.name));
+      let requests_trail = RequestSpecTrail::SpecPath(SpecPath {
+        path_id: String::from(parameter.path_id),
+      });
+      self.push(InteractionDiffResult::UnmatchedPathParameterShape(
+        UnmatchedPathParameterShape::new(interaction_trail, requests_trail),
+      ));
+    }
   }
 }
 ///////////////////////////////////////////////////////////////////////////////
@@ -172,6 +208,7 @@ pub struct DiffRequestBodyVisitor {
   results: DiffResults,
   visited_with_matched_content_types: std::collections::HashSet<RequestId>,
   visited_with_unmatched_content_types: std::collections::HashSet<RequestId>,
+  best_matched_content_type: Option<(MediaTypeMatch, MatchedRequestBodyContentType)>,
 }
 
 impl DiffRequestBodyVisitor {
@@ -180,6 +217,7 @@ impl DiffRequestBodyVisitor {
       results: DiffResults::new(),
       visited_with_matched_content_types: std::collections::HashSet::new(),
       visited_with_unmatched_content_types: std::collections::HashSet::new(),
+      best_matched_content_type: None,
     }
   }
 }
@@ -198,7 +236,7 @@ impl RequestBodyVisitor<InteractionDiffResult> for DiffRequestBodyVisitor {
     if let Some(operation) = context.operation {
       let maybe_interaction_content_type = &interaction.request.body.content_type;
       let maybe_interaction_body_descriptor: Option<BodyDescriptor> =
-        (&interaction.request.body.value).into();
+        (&interaction.request.body).into();
       let (request_id, request_descriptor) = operation;
       //dbg!( maybe_interaction_content_type);
       //dbg!(&request_descriptor);
@@ -228,25 +266,38 @@ impl RequestBodyVisitor<InteractionDiffResult> for DiffRequestBodyVisitor {
             .insert(request_id.clone());
         }
         (Some(body), Some(content_type), _) => {
-          if body.http_content_type == *content_type {
+          if let Some(media_type_match) = match_content_types(&body.http_content_type, content_type)
+          {
             self
               .visited_with_matched_content_types
               .insert(request_id.clone());
-            let interaction_trail_components = vec![InteractionTrailPathComponent::RequestBody {
-              content_type: String::from(content_type),
-            }];
-            let requests_trail = RequestSpecTrail::SpecRequestBody(SpecRequestBody {
-              request_id: String::from(request_id),
-            });
-            let interaction_trail = InteractionTrail::new(interaction_trail_components);
-
-            self.push(InteractionDiffResult::MatchedRequestBodyContentType(
-              MatchedRequestBodyContentType::new(
-                interaction_trail,
-                requests_trail,
-                body.root_shape_id.clone(),
-              ),
-            ));
+
+            // only the most specific of the documented content types is diffed against
+            let is_best_match = is_better_content_type_match(
+              media_type_match,
+              self
+                .best_matched_content_type
+                .as_ref()
+                .map(|(best_match, _)| *best_match),
+            );
+            if is_best_match {
+              let interaction_trail_components = vec![InteractionTrailPathComponent::RequestBody {
+                content_type: String::from(content_type),
+              }];
+              let requests_trail = RequestSpecTrail::SpecRequestBody(SpecRequestBody {
+                request_id: String::from(request_id),
+              });
+              let interaction_trail = InteractionTrail::new(interaction_trail_components);
+
+              self.best_matched_content_type = Some((
+                media_type_match,
+                MatchedRequestBodyContentType::new(
+                  interaction_trail,
+                  requests_trail,
+                  body.root_shape_id.clone(),
+                ),
+              ));
+            }
           } else {
             self
               .visited_with_unmatched_content_types
@@ -258,6 +309,12 @@ impl RequestBodyVisitor<InteractionDiffResult> for DiffRequestBodyVisitor {
   }
 
   fn end(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext) {
+    if let Some((_, matched)) = self.best_matched_content_type.take() {
+      self.push(InteractionDiffResult::MatchedRequestBodyContentType(
+        matched,
+      ));
+    }
+
     if let Some(path_id) = context.path {
       if self.visited_with_matched_content_types.is_empty() {
         let maybe_interaction_content_type = &interaction.request.body.content_type;
@@ -292,6 +349,7 @@ pub struct DiffResponseBodyVisitor {
   results: DiffResults,
   visited_with_matched_content_types: std::collections::HashSet<ResponseId>,
   visited_with_unmatched_content_types: std::collections::HashSet<ResponseId>,
+  best_matched_content_type: Option<(MediaTypeMatch, MatchedResponseBodyContentType)>,
 }
 
 impl DiffResponseBodyVisitor {
@@ -300,6 +358,7 @@ impl DiffResponseBodyVisitor {
       results: DiffResults::new(),
       visited_with_matched_content_types: std::collections::HashSet::new(),
       visited_with_unmatched_content_types: std::collections::HashSet::new(),
+      best_matched_content_type: None,
     }
   }
 }
@@ -319,7 +378,7 @@ impl ResponseBodyVisitor<InteractionDiffResult> for DiffResponseBodyVisitor {
     if let Some(response) = context.response {
       let maybe_interaction_content_type = &interaction.response.body.content_type;
       let maybe_interaction_body_descriptor: Option<BodyDescriptor> =
-        (&interaction.response.body.value).into();
+        (&interaction.response.body).into();
       let (response_id, response_descriptor) = response;
       //dbg!("actual response content type", maybe_interaction_content_type);
       // dbg!(
@@ -353,27 +412,40 @@ impl ResponseBodyVisitor<InteractionDiffResult> for DiffResponseBodyVisitor {
         }
         (Some(body), Some(content_type), _) => {
           // TODO investigate this branch
-          if body.http_content_type == *content_type {
+          if let Some(media_type_match) = match_content_types(&body.http_content_type, content_type)
+          {
             self
               .visited_with_matched_content_types
               .insert(response_id.clone());
 
-            let interaction_trail_components = vec![InteractionTrailPathComponent::ResponseBody {
-              content_type: String::from(content_type),
-              status_code: interaction.response.status_code,
-            }];
-            let requests_trail = RequestSpecTrail::SpecResponseBody(SpecResponseBody {
-              response_id: String::from(response_id),
-            });
-            let interaction_trail = InteractionTrail::new(interaction_trail_components);
-
-            self.push(InteractionDiffResult::MatchedResponseBodyContentType(
-              MatchedResponseBodyContentType::new(
-                interaction_trail,
-                requests_trail,
-                body.root_shape_id.clone(),
-              ),
-            ));
+            // only the most specific of the documented content types is diffed against
+            let is_best_match = is_better_content_type_match(
+              media_type_match,
+              self
+                .best_matched_content_type
+                .as_ref()
+                .map(|(best_match, _)| *best_match),
+            );
+            if is_best_match {
+              let interaction_trail_components =
+                vec![InteractionTrailPathComponent::ResponseBody {
+                  content_type: String::from(content_type),
+                  status_code: interaction.response.status_code,
+                }];
+              let requests_trail = RequestSpecTrail::SpecResponseBody(SpecResponseBody {
+                response_id: String::from(response_id),
+              });
+              let interaction_trail = InteractionTrail::new(interaction_trail_components);
+
+              self.best_matched_content_type = Some((
+                media_type_match,
+                MatchedResponseBodyContentType::new(
+                  interaction_trail,
+                  requests_trail,
+                  body.root_shape_id.clone(),
+                ),
+              ));
+            }
           } else {
             self
               .visited_with_unmatched_content_types
@@ -385,6 +457,12 @@ impl ResponseBodyVisitor<InteractionDiffResult> for DiffResponseBodyVisitor {
   }
 
   fn end(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext) {
+    if let Some((_, matched)) = self.best_matched_content_type.take() {
+      self.push(InteractionDiffResult::MatchedResponseBodyContentType(
+        matched,
+      ));
+    }
+
     if let Some(path_id) = context.path {
       if self.visited_with_matched_content_types.is_empty() {
         let actual_content_type = &interaction.response.body.content_type;
@@ -416,3 +494,132 @@ impl ResponseBodyVisitor<InteractionDiffResult> for DiffResponseBodyVisitor {
     }
   }
 }
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct DiffRequestHeadersVisitor {
+  results: DiffResults,
+}
+
+impl DiffRequestHeadersVisitor {
+  fn new() -> Self {
+    DiffRequestHeadersVisitor {
+      results: DiffResults::new(),
+    }
+  }
+}
+
+impl InteractionVisitor<InteractionDiffResult> for DiffRequestHeadersVisitor {
+  fn results(&mut self) -> Option<&mut DiffResults> {
+    Some(&mut self.results)
+  }
+}
+
+impl RequestHeadersVisitor<InteractionDiffResult> for DiffRequestHeadersVisitor {
+  fn begin(&mut self) {}
+
+  fn visit(&mut self, interaction: &HttpInteraction, context: &RequestHeadersVisitorContext) {
+    let interaction_headers = interaction.request.headers();
+    let (request_id, _) = context.request;
+
+    // only documented headers are diffed, as clients and proxies add plenty of their own
+    for (parameter_id, header_descriptor) in &context.headers {
+      let mut interaction_trail = InteractionTrail::empty();
+      interaction_trail.with_method(interaction.request.method.clone());
+      interaction_trail.with_request_header(header_descriptor.name.clone());
+      let requests_trail = RequestSpecTrail::SpecRequestHeader(SpecRequestHeader {
+        request_id: request_id.clone(),
+        parameter_id: String::from(*parameter_id),
+      });
+
+      match interaction_headers.get(&header_descriptor.name) {
+        Some(value) => {
+          let shape_descriptor = header_descriptor
+            .shape
+            .as_ref()
+            .filter(|shape_descriptor| !shape_descriptor.is_removed);
+          if let Some(shape_descriptor) = shape_descriptor {
+            self.push(InteractionDiffResult::MatchedRequestHeader(
+              MatchedRequestHeader::new(
+                interaction_trail,
+                requests_trail,
+                shape_descriptor.shape_id.clone(),
+                value.clone(),
+              ),
+            ));
+          }
+        }
+        None => self.push(InteractionDiffResult::UnmatchedRequestHeader(
+          UnmatchedRequestHeader::new(interaction_trail, requests_trail),
+        )),
+      }
+    }
+  }
+
+  fn end(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext) {}
+}
+///////////////////////////////////////////////////////////////////////////////
+
+pub struct DiffResponseHeadersVisitor {
+  results: DiffResults,
+}
+
+impl DiffResponseHeadersVisitor {
+  fn new() -> Self {
+    DiffResponseHeadersVisitor {
+      results: DiffResults::new(),
+    }
+  }
+}
+
+impl InteractionVisitor<InteractionDiffResult> for DiffResponseHeadersVisitor {
+  fn results(&mut self) -> Option<&mut DiffResults> {
+    Some(&mut self.results)
+  }
+}
+
+impl ResponseHeadersVisitor<InteractionDiffResult> for DiffResponseHeadersVisitor {
+  fn begin(&mut self) {}
+
+  fn visit(&mut self, interaction: &HttpInteraction, context: &ResponseHeadersVisitorContext) {
+    let interaction_headers = interaction.response.headers();
+    let (response_id, _) = context.response;
+
+    // only documented headers are diffed, as servers and proxies add plenty of their own
+    for (parameter_id, header_descriptor) in &context.headers {
+      let mut interaction_trail = InteractionTrail::empty();
+      interaction_trail.with_method(interaction.request.method.clone());
+      interaction_trail.with_response_header(
+        header_descriptor.name.clone(),
+        interaction.response.status_code,
+      );
+      let requests_trail = RequestSpecTrail::SpecResponseHeader(SpecResponseHeader {
+        response_id: response_id.clone(),
+        parameter_id: String::from(*parameter_id),
+      });
+
+      match interaction_headers.get(&header_descriptor.name) {
+        Some(value) => {
+          let shape_descriptor = header_descriptor
+            .shape
+            .as_ref()
+            .filter(|shape_descriptor| !shape_descriptor.is_removed);
+          if let Some(shape_descriptor) = shape_descriptor {
+            self.push(InteractionDiffResult::MatchedResponseHeader(
+              MatchedResponseHeader::new(
+                interaction_trail,
+                requests_trail,
+                shape_descriptor.shape_id.clone(),
+                value.clone(),
+              ),
+            ));
+          }
+        }
+        None => self.push(InteractionDiffResult::UnmatchedResponseHeader(
+          UnmatchedResponseHeader::new(interaction_trail, requests_trail),
+        )),
+      }
+    }
+  }
+
+  fn end(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext) {}
+}
@@ -1,10 +1,11 @@
 pub mod diff;
 
 use crate::projections::endpoint::{
-  QueryParametersDescriptor, RequestDescriptor, ResponseBodyDescriptor,
+  HeaderParameterDescriptor, QueryParametersDescriptor, RequestDescriptor, ResponseBodyDescriptor,
 };
+use crate::queries::endpoint::PathParameterMatch;
 use crate::state::endpoint::{
-  PathComponentId, PathComponentIdRef, QueryParametersId, RequestId, ResponseId,
+  PathComponentId, PathComponentIdRef, QueryParametersId, RequestId, RequestParameterId, ResponseId,
 };
 use crate::HttpInteraction;
 
@@ -13,6 +14,8 @@ pub trait InteractionVisitors<R> {
   type QueryParameters: QueryParametersVisitor<R>;
   type RequestBody: RequestBodyVisitor<R>;
   type ResponseBody: ResponseBodyVisitor<R>;
+  type RequestHeaders: RequestHeadersVisitor<R>;
+  type ResponseHeaders: ResponseHeadersVisitor<R>;
 
   fn path(&mut self) -> &mut Self::Path;
 
@@ -22,12 +25,18 @@ pub trait InteractionVisitors<R> {
 
   fn response_body(&mut self) -> &mut Self::ResponseBody;
 
+  fn request_headers(&mut self) -> &mut Self::RequestHeaders;
+
+  fn response_headers(&mut self) -> &mut Self::ResponseHeaders;
+
   fn take_results(&mut self) -> Option<Vec<R>> {
     let flattened = vec![
       self.path().take_results(),
       self.query_params().take_results(),
       self.request_body().take_results(),
       self.response_body().take_results(),
+      self.request_headers().take_results(),
+      self.response_headers().take_results(),
     ]
     .into_iter()
     .filter_map(|x| x)
@@ -73,12 +82,24 @@ pub trait ResponseBodyVisitor<R>: InteractionVisitor<R> {
   fn end(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext);
 }
 
+pub trait RequestHeadersVisitor<R>: InteractionVisitor<R> {
+  fn begin(&mut self);
+  fn visit(&mut self, interaction: &HttpInteraction, context: &RequestHeadersVisitorContext);
+  fn end(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext);
+}
+pub trait ResponseHeadersVisitor<R>: InteractionVisitor<R> {
+  fn begin(&mut self);
+  fn visit(&mut self, interaction: &HttpInteraction, context: &ResponseHeadersVisitorContext);
+  fn end(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext);
+}
+
 pub trait PathVisitor<R>: InteractionVisitor<R> {
   fn visit(&mut self, interaction: &HttpInteraction, context: &PathVisitorContext);
 }
 
 pub struct PathVisitorContext<'a> {
   pub path: Option<PathComponentIdRef<'a>>,
+  pub path_parameters: Vec<PathParameterMatch<'a>>,
 }
 pub struct QueryParametersVisitorContext<'a> {
   pub path: PathComponentIdRef<'a>,
@@ -92,6 +113,16 @@ pub struct ResponseBodyVisitorContext<'a> {
   pub path: PathComponentIdRef<'a>,
   pub response: Option<(&'a ResponseId, &'a ResponseBodyDescriptor)>,
 }
+pub struct RequestHeadersVisitorContext<'a> {
+  pub path: PathComponentIdRef<'a>,
+  pub request: (&'a RequestId, &'a RequestDescriptor),
+  pub headers: Vec<(&'a RequestParameterId, &'a HeaderParameterDescriptor)>,
+}
+pub struct ResponseHeadersVisitorContext<'a> {
+  pub path: PathComponentIdRef<'a>,
+  pub response: (&'a ResponseId, &'a ResponseBodyDescriptor),
+  pub headers: Vec<(&'a RequestParameterId, &'a HeaderParameterDescriptor)>,
+}
 
 // Results
 // -------
@@ -1,11 +1,16 @@
 use crate::commands::shape as shape_commands;
 use crate::commands::{ShapeCommand, SpecCommand};
 use crate::shapes::JsonTrail;
-use crate::state::shape::{FieldId, ShapeId, ShapeKind, ShapeKindDescriptor};
+use crate::state::body::{NumberValue, ObjectDescriptor, PrimitiveValues};
+use crate::state::shape::{
+  FieldId, OneOfDiscriminatorDescriptor, ShapeConstraintDescriptor, ShapeConstraintId, ShapeId,
+  ShapeKind, ShapeKindDescriptor, ShapeParameterId, StringFormat,
+};
 use crate::state::SpecIdGenerator;
 use crate::BodyDescriptor;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use serde_json::{Number as JsonNumber, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 #[derive(Clone, Debug, Default)]
 pub struct TrailObservationsResult {
@@ -44,6 +49,17 @@ impl TrailObservationsResult {
     self
   }
 
+  /// Observations as they would have been had the strings observed at a trail been parsed, if
+  /// all of them are numbers or all of them are booleans. Meant for bodies that can only ever
+  /// contain strings, like query strings, after all their observations have been collected.
+  pub fn with_coerced_strings(mut self) -> Self {
+    for trail_values in self.values_by_trail.values_mut() {
+      trail_values.coerce_strings();
+    }
+
+    self
+  }
+
   pub fn trails(&self) -> impl Iterator<Item = &JsonTrail> {
     self.values_by_trail.keys()
   }
@@ -60,11 +76,59 @@ impl TrailObservationsResult {
     self.values_by_trail.remove(trail)
   }
 
+  /// Merge the observations for all values of objects that look like maps, so a single value
+  /// shape can be learned for them. Maps are merged parents first, so nested maps get merged too.
+  fn merge_map_values(&mut self) {
+    let mut merged_map_trails = HashSet::new();
+
+    loop {
+      let next_map_trail = self
+        .values_by_trail
+        .iter()
+        .filter(|(json_trail, trail_values)| {
+          !merged_map_trails.contains(*json_trail) && trail_values.was_map()
+        })
+        .map(|(json_trail, _)| json_trail)
+        .min()
+        .cloned();
+
+      let map_trail = match next_map_trail {
+        Some(map_trail) => map_trail,
+        None => break,
+      };
+
+      let value_trails = self
+        .values_by_trail
+        .keys()
+        .filter_map(|json_trail| {
+          json_trail
+            .with_map_value_normalized(&map_trail, MAP_VALUE_KEY)
+            .map(|merged_trail| (json_trail.clone(), merged_trail))
+        })
+        .collect::<Vec<_>>();
+
+      for (json_trail, merged_trail) in value_trails {
+        let mut trail_values = self.values_by_trail.remove(&json_trail).unwrap();
+        trail_values.trail = merged_trail.clone();
+
+        self
+          .values_by_trail
+          .entry(merged_trail)
+          .or_insert_with_key(TrailValues::new)
+          .union(trail_values);
+      }
+
+      merged_map_trails.insert(map_trail);
+    }
+  }
+
   pub fn into_commands(
     mut self,
     id_generator: &mut impl SpecIdGenerator,
     root_trail: &JsonTrail,
   ) -> (Option<String>, impl Iterator<Item = SpecCommand>) {
+    self.merge_map_values();
+
     let sorted_trails = {
       let mut trails = self
         .values_by_trail
@@ -136,6 +200,19 @@ fn shape_prototypes_trails<'a>(
 
           trails
         }
+        ShapePrototypeDescriptor::MapOfShape { .. } => {
+          let value_prototype = prototypes_by_trail.get(
+            &shape_prototype
+              .trail
+              .with_object_key(String::from(MAP_VALUE_KEY)),
+          );
+          let nested_trails = shape_prototypes_trails(value_prototype, prototypes_by_trail);
+
+          let mut trails = vec![shape_prototype.trail.clone()];
+          trails.extend(nested_trails);
+
+          trails
+        }
         ShapePrototypeDescriptor::ObjectWithFields { ref fields } => {
           let nested_trails = fields.iter().flat_map(|field_prototype_descriptor| {
             let field_prototype = prototypes_by_trail.get(
@@ -166,14 +243,30 @@ fn shape_prototypes_to_commands(
     .into_iter()
     .flat_map(
       |shape_prototype| match shape_prototype.prototype_descriptor {
-        ShapePrototypeDescriptor::PrimitiveKind { base_shape_kind } => {
-          let add_command =
-            ShapeCommand::add_shape(shape_prototype.id, base_shape_kind, String::from(""));
-          Some(vec![add_command])
+        ShapePrototypeDescriptor::PrimitiveKind {
+          base_shape_kind,
+          constraints,
+        } => {
+          let mut commands = vec![ShapeCommand::add_shape(
+            shape_prototype.id.clone(),
+            base_shape_kind,
+            String::from(""),
+          )];
+
+          for (shape_constraint_id, descriptor) in constraints {
+            commands.push(ShapeCommand::add_shape_constraint(
+              shape_constraint_id,
+              shape_prototype.id.clone(),
+              descriptor,
+            ));
+          }
+
+          Some(commands)
         }
         ShapePrototypeDescriptor::OneOfShape {
           branches,
           parameter_ids,
+          discriminator,
         } => {
           let mut commands = vec![ShapeCommand::add_shape(
             shape_prototype.id.clone(),
@@ -182,6 +275,26 @@ fn shape_prototypes_to_commands(
           )];
 
           let one_off_shape_id = shape_prototype.id.clone();
+          let discriminator_command = discriminator.map(|discriminator| {
+            let mapping = discriminator
+              .values_by_branch
+              .into_iter()
+              .zip(&parameter_ids)
+              .flat_map(|(values, parameter_id)| {
+                values
+                  .into_iter()
+                  .map(move |value| (value, parameter_id.clone()))
+              })
+              .collect();
+
+            ShapeCommand::set_one_of_discriminator(
+              one_off_shape_id.clone(),
+              OneOfDiscriminatorDescriptor {
+                field_name: discriminator.field_name,
+                mapping,
+              },
+            )
+          });
 
           for (branch_shape_prototype, branch_parameter_id) in
             branches.into_iter().zip(parameter_ids)
@@ -202,6 +315,7 @@ fn shape_prototypes_to_commands(
               branch_shape_id,
             ));
           }
+          commands.extend(discriminator_command);
 
           Some(commands)
         }
@@ -266,6 +380,38 @@ fn shape_prototypes_to_commands(
 
           Some(commands)
         }
+        ShapePrototypeDescriptor::MapOfShape {
+          value_shape_id,
+          value_is_unknown,
+        } => {
+          let mut commands = vec![];
+          commands.push(ShapeCommand::add_shape(
+            shape_prototype.id.clone(),
+            ShapeKind::MapKind,
+            String::from(""),
+          ));
+
+          if value_is_unknown {
+            commands.push(ShapeCommand::add_shape(
+              value_shape_id.clone(),
+              ShapeKind::UnknownKind,
+              String::from(""),
+            ));
+          }
+
+          commands.push(ShapeCommand::set_parameter_shape(
+            shape_prototype.id,
+            String::from(
+              ShapeKind::MapKind
+                .get_parameter_descriptor()
+                .unwrap()
+                .shape_parameter_id,
+            ),
+            value_shape_id,
+          ));
+
+          Some(commands)
+        }
         ShapePrototypeDescriptor::ObjectWithFields { fields } => {
           let mut commands = vec![];
           commands.push(ShapeCommand::add_shape(
@@ -322,6 +468,22 @@ impl From<HashMap<JsonTrail, TrailValues>> for TrailObservationsResult {
 }
 
 pub type FieldSet = HashSet<String>;
+/// The discriminator values of a branch of objects, with the field sets of those objects
+type DiscriminatedBranch = (Vec<String>, Vec<FieldSet>);
+
+/// Object key under which the observations of all values of a map are merged
+const MAP_VALUE_KEY: &str = "$mapValue";
+/// Minimum amount of distinct keys an object must have been observed with to be learned as a map
+const MAP_MIN_KEY_COUNT: usize = 3;
+/// Maximum amount of distinct strings observed at a trail for them to be learned as an enum
+const ENUM_MAX_VALUES: usize = 5;
+/// Minimum amount of times each distinct string must have been observed on average to be
+/// learned as an enum, so we don't constrain free form strings we've only seen a few of
+const ENUM_MIN_OBSERVATIONS_PER_VALUE: usize = 3;
+/// Minimum amount of numbers that must have been observed to learn they're always integers
+const INTEGER_MIN_OBSERVATIONS: usize = 3;
+/// Maximum amount of distinct values of a string field for it to be learned as a discriminator
+const DISCRIMINATOR_MAX_VALUES: usize = 5;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -337,6 +499,249 @@ pub struct TrailValues {
 
   #[serde(rename = "fieldSet")]
   pub field_sets: Vec<FieldSet>,
+
+  #[serde(default)]
+  pub values: ValueObservations,
+
+  /// Observed once any object was, as it only holds string fields every object had
+  #[serde(default)]
+  pub discriminators: Option<DiscriminatorObservations>,
+}
+
+/// Summary of the string and number values observed at a trail, from which constraints on
+/// their shapes can be proposed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueObservations {
+  pub string_count: usize,
+  pub number_count: usize,
+  pub string_values: BTreeSet<String>,
+  pub string_formats: BTreeSet<StringFormat>,
+  pub was_too_many_strings: bool,
+  pub was_unformatted_string: bool,
+  pub was_non_integer_number: bool,
+  #[serde(default)]
+  pub numeric_string_count: usize,
+  #[serde(default)]
+  pub boolean_string_count: usize,
+  #[serde(default)]
+  pub was_non_integer_numeric_string: bool,
+}
+
+impl ValueObservations {
+  pub fn observe_strings(&mut self, values: &PrimitiveValues<String>) {
+    if values.is_unknown() {
+      self.string_count += 1;
+      self.was_unformatted_string = true;
+      self.was_too_many_strings = true;
+      self.string_values.clear();
+      return;
+    }
+
+    for value in values.iter() {
+      self.string_count += 1;
+
+      match value.parse::<JsonNumber>() {
+        Ok(number) => {
+          self.numeric_string_count += 1;
+          self.was_non_integer_numeric_string =
+            self.was_non_integer_numeric_string || !NumberValue::from(number).is_integer();
+        }
+        Err(_) if value == "true" || value == "false" => self.boolean_string_count += 1,
+        Err(_) => {}
+      }
+
+      match StringFormat::detect(value) {
+        Some(format) => {
+          self.string_formats.insert(format);
+        }
+        None => self.was_unformatted_string = true,
+      }
+
+      if !self.was_too_many_strings {
+        self.string_values.insert(value.clone());
+        self.limit_string_values();
+      }
+    }
+  }
+
+  pub fn observe_numbers(&mut self, values: &PrimitiveValues<NumberValue>) {
+    if values.is_unknown() {
+      self.number_count += 1;
+      self.was_non_integer_number = true;
+      return;
+    }
+
+    for value in values.iter() {
+      self.number_count += 1;
+      self.was_non_integer_number = self.was_non_integer_number || !value.is_integer();
+    }
+  }
+
+  pub fn union(&mut self, new_values: ValueObservations) {
+    self.string_count += new_values.string_count;
+    self.number_count += new_values.number_count;
+    self.numeric_string_count += new_values.numeric_string_count;
+    self.boolean_string_count += new_values.boolean_string_count;
+    self.was_non_integer_numeric_string =
+      self.was_non_integer_numeric_string || new_values.was_non_integer_numeric_string;
+    self.string_formats.extend(new_values.string_formats);
+    self.was_unformatted_string = self.was_unformatted_string || new_values.was_unformatted_string;
+    self.was_non_integer_number = self.was_non_integer_number || new_values.was_non_integer_number;
+    self.was_too_many_strings = self.was_too_many_strings || new_values.was_too_many_strings;
+    if !self.was_too_many_strings {
+      self.string_values.extend(new_values.string_values);
+    }
+    self.limit_string_values();
+  }
+
+  fn limit_string_values(&mut self) {
+    if self.was_too_many_strings || self.string_values.len() > ENUM_MAX_VALUES {
+      self.was_too_many_strings = true;
+      self.string_values.clear();
+    }
+  }
+
+  fn string_constraints(&self) -> Vec<ShapeConstraintDescriptor> {
+    if !self.was_unformatted_string && self.string_formats.len() == 1 {
+      let format = *self.string_formats.iter().next().unwrap();
+      return vec![ShapeConstraintDescriptor::StringFormat { format }];
+    }
+
+    let distinct_count = self.string_values.len();
+    if !self.was_too_many_strings
+      && distinct_count > 1
+      && self.string_count >= distinct_count * ENUM_MIN_OBSERVATIONS_PER_VALUE
+    {
+      let values = self
+        .string_values
+        .iter()
+        .map(|value| JsonValue::String(value.clone()))
+        .collect();
+      return vec![ShapeConstraintDescriptor::EnumValues { values }];
+    }
+
+    vec![]
+  }
+
+  fn number_constraints(&self) -> Vec<ShapeConstraintDescriptor> {
+    if self.was_non_integer_number || self.number_count < INTEGER_MIN_OBSERVATIONS {
+      vec![]
+    } else {
+      vec![ShapeConstraintDescriptor::Integer {}]
+    }
+  }
+}
+
+/// The string fields every observed object had, with the field sets of the objects each of their
+/// values was seen in, from which objects can be told apart into branches by a discriminator.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscriminatorObservations {
+  pub field_sets_by_value: BTreeMap<String, BTreeMap<String, Vec<FieldSet>>>,
+}
+
+impl DiscriminatorObservations {
+  pub fn intersect(&mut self, new_observations: DiscriminatorObservations) {
+    let mut new_field_sets_by_value = new_observations.field_sets_by_value;
+
+    self
+      .field_sets_by_value
+      .retain(|field_name, field_sets_by_value| {
+        let new_field_sets_by_value = match new_field_sets_by_value.remove(field_name) {
+          Some(new_field_sets_by_value) => new_field_sets_by_value,
+          None => return false,
+        };
+
+        for (value, new_field_sets) in new_field_sets_by_value {
+          let field_sets = field_sets_by_value.entry(value).or_default();
+          for new_field_set in new_field_sets {
+            if !field_sets.contains(&new_field_set) {
+              field_sets.push(new_field_set);
+            }
+          }
+        }
+
+        field_sets_by_value.len() <= DISCRIMINATOR_MAX_VALUES
+      });
+  }
+
+  /// The field by which objects can be told apart, with the values and field sets of each of the
+  /// branches it discriminates. Values of objects with the same keys share a branch, and every
+  /// branch must have a key none of the others have, or it would better be learned as an object
+  /// with optional fields.
+  fn discriminated_branches(&self) -> Option<(&String, Vec<DiscriminatedBranch>)> {
+    self
+      .field_sets_by_value
+      .iter()
+      .find_map(|(field_name, field_sets_by_value)| {
+        // formatted values like dates and ids identify objects rather than their kind
+        if field_sets_by_value
+          .keys()
+          .any(|value| StringFormat::detect(value).is_some())
+        {
+          return None;
+        }
+
+        let mut branches: Vec<(Vec<String>, Vec<FieldSet>, FieldSet)> = vec![];
+        for (value, field_sets) in field_sets_by_value {
+          let all_keys = field_sets.iter().flatten().cloned().collect::<FieldSet>();
+          match branches.iter_mut().find(|(_, _, keys)| *keys == all_keys) {
+            Some((values, branch_field_sets, _)) => {
+              values.push(value.clone());
+              branch_field_sets.extend(field_sets.iter().cloned());
+            }
+            None => branches.push((vec![value.clone()], field_sets.clone(), all_keys)),
+          }
+        }
+
+        let has_distinctive_keys = branches.iter().enumerate().all(|(index, (_, _, keys))| {
+          keys.iter().any(|key| {
+            branches
+              .iter()
+              .enumerate()
+              .all(|(other_index, (_, _, other_keys))| {
+                other_index == index || !other_keys.contains(key)
+              })
+          })
+        });
+
+        if branches.len() < 2 || !has_distinctive_keys {
+          return None;
+        }
+
+        let branches = branches
+          .into_iter()
+          .map(|(values, field_sets, _)| (values, field_sets))
+          .collect();
+        Some((field_name, branches))
+      })
+  }
+}
+
+impl From<&ObjectDescriptor> for DiscriminatorObservations {
+  fn from(object: &ObjectDescriptor) -> Self {
+    let field_set = object.keys().cloned().collect::<FieldSet>();
+
+    let field_sets_by_value = object
+      .iter()
+      .filter_map(|(field_name, field_body)| match field_body {
+        BodyDescriptor::String(values) if !values.is_unknown() => {
+          let field_sets_by_value = values
+            .iter()
+            .map(|value| (value.clone(), vec![field_set.clone()]))
+            .collect::<BTreeMap<_, _>>();
+          Some((field_name.clone(), field_sets_by_value))
+        }
+        _ => None,
+      })
+      .filter(|(_, field_sets_by_value)| field_sets_by_value.len() <= DISCRIMINATOR_MAX_VALUES)
+      .collect();
+
+    Self {
+      field_sets_by_value,
+    }
+  }
 }
 
 impl From<JsonTrail> for TrailValues {
@@ -357,6 +762,8 @@ impl TrailValues {
       was_object: false,
       was_empty_array: false,
       field_sets: Default::default(),
+      values: Default::default(),
+      discriminators: None,
     }
   }
 
@@ -372,12 +779,37 @@ impl TrailValues {
     for new_field_set in new_values.field_sets {
       self.insert_field_set(new_field_set);
     }
+
+    self.values.union(new_values.values);
+    if let Some(new_discriminators) = new_values.discriminators {
+      self.observe_discriminators(new_discriminators);
+    }
   }
 
   pub fn normalize(&mut self) {
     self.trail = self.trail.normalized();
   }
 
+  /// Observe the strings seen as numbers or booleans instead, if every one of them spelled one
+  /// out. Strings that were never seen aren't known to parse, so are left alone.
+  pub fn coerce_strings(&mut self) {
+    let values = &mut self.values;
+    if !self.was_string || values.string_count == 0 {
+      return;
+    }
+
+    if values.numeric_string_count == values.string_count {
+      self.was_string = false;
+      self.was_number = true;
+      values.number_count += values.numeric_string_count;
+      values.was_non_integer_number =
+        values.was_non_integer_number || values.was_non_integer_numeric_string;
+    } else if values.boolean_string_count == values.string_count {
+      self.was_string = false;
+      self.was_boolean = true;
+    }
+  }
+
   pub fn was_unknown(&self) -> bool {
     !self.was_string
       && !self.was_number
@@ -388,6 +820,25 @@ impl TrailValues {
       && !self.was_object
   }
 
+  /// Whether the object values seen are better described as a map than an object with fields,
+  /// which is the case when they're keyed by many distinct, ID-like keys.
+  pub fn was_map(&self) -> bool {
+    if !self.was_object {
+      return false;
+    }
+
+    let all_keys = self.field_sets.iter().flatten().collect::<HashSet<_>>();
+
+    all_keys.len() >= MAP_MIN_KEY_COUNT && all_keys.iter().all(|key| is_id_like_key(key))
+  }
+
+  pub fn observe_discriminators(&mut self, new_discriminators: DiscriminatorObservations) {
+    match &mut self.discriminators {
+      Some(discriminators) => discriminators.intersect(new_discriminators),
+      None => self.discriminators = Some(new_discriminators),
+    }
+  }
+
   pub fn insert_field_set(&mut self, field_set: FieldSet) {
     let exists = self.field_sets.iter().any(|existing_set| {
       if let None = existing_set.symmetric_difference(&field_set).next() {
@@ -411,6 +862,7 @@ impl TrailValues {
       if self.was_string {
         Some(ShapePrototypeDescriptor::PrimitiveKind {
           base_shape_kind: ShapeKind::StringKind,
+          constraints: with_constraint_ids(id_generator, self.values.string_constraints()),
         })
       } else {
         None
@@ -418,6 +870,7 @@ impl TrailValues {
       if self.was_number {
         Some(ShapePrototypeDescriptor::PrimitiveKind {
           base_shape_kind: ShapeKind::NumberKind,
+          constraints: with_constraint_ids(id_generator, self.values.number_constraints()),
         })
       } else {
         None
@@ -425,6 +878,7 @@ impl TrailValues {
       if self.was_boolean {
         Some(ShapePrototypeDescriptor::PrimitiveKind {
           base_shape_kind: ShapeKind::BooleanKind,
+          constraints: vec![],
         })
       } else {
         None
@@ -448,54 +902,19 @@ impl TrailValues {
       } else {
         None
       },
-      if self.was_object {
-        let (field_keys, optional_keys) = {
-          let all_keys_set = self
-            .field_sets
-            .iter()
-            .fold(HashSet::new(), |all_keys: HashSet<String>, field_set| {
-              all_keys.union(&field_set).cloned().collect()
-            });
-
-          let optional_keys_set =
-            self
-              .field_sets
-              .iter()
-              .fold(HashSet::new(), |optional_keys, field_set| {
-                let missing_keys = all_keys_set.difference(&field_set).cloned().collect();
-                optional_keys.union(&missing_keys).cloned().collect()
-              });
-
-          let mut all_keys = all_keys_set.into_iter().collect::<Vec<_>>();
-          all_keys.sort();
-
-          let optional_keys = optional_keys_set.into_iter().collect::<Vec<_>>();
-          (all_keys, optional_keys)
+      if self.was_map() {
+        let value_trail = self.trail.with_object_key(String::from(MAP_VALUE_KEY));
+        let value_prototype = existing_prototypes.get(&value_trail);
+
+        // map values only share a trail once merged, without it there's nothing to learn them by
+        let (value_shape_id, value_is_unknown) = match value_prototype {
+          Some(value_prototype) => (value_prototype.id.clone(), false),
+          None => (id_generator.shape(), true),
         };
 
-        let field_descriptors = field_keys
-          .into_iter()
-          .map(|key| {
-            let field_trail = self.trail.with_object_key(key.clone());
-            let field_shape_prototype = existing_prototypes.get(&field_trail).expect(
-              "object field shape prototype should have been generated before its parent object",
-            );
-            let is_optional = optional_keys.contains(&key);
-
-            FieldPrototypeDescriptor {
-              field_id: id_generator.field(),
-              key,
-              optional_shape_id: match is_optional {
-                true => Some(id_generator.shape()),
-                false => None,
-              },
-              value_shape_id: field_shape_prototype.id.clone(),
-            }
-          })
-          .collect::<Vec<_>>();
-
-        Some(ShapePrototypeDescriptor::ObjectWithFields {
-          fields: field_descriptors,
+        Some(ShapePrototypeDescriptor::MapOfShape {
+          value_shape_id,
+          value_is_unknown,
         })
       } else {
         None
@@ -505,6 +924,41 @@ impl TrailValues {
     .flatten()
     .collect();
 
+    // objects that are told apart by a discriminator are learned as a branch each
+    let mut discriminator = None;
+    if self.was_object && !self.was_map() {
+      let discriminated_branches = self
+        .discriminators
+        .as_ref()
+        .and_then(|discriminators| discriminators.discriminated_branches());
+
+      match discriminated_branches {
+        Some((field_name, branches)) => {
+          let mut values_by_branch = vec![vec![]; descriptors.len()];
+          for (values, field_sets) in branches {
+            descriptors.push(object_prototype_descriptor(
+              &self.trail,
+              &field_sets,
+              id_generator,
+              existing_prototypes,
+            ));
+            values_by_branch.push(values);
+          }
+
+          discriminator = Some(DiscriminatorPrototype {
+            field_name: field_name.clone(),
+            values_by_branch,
+          });
+        }
+        None => descriptors.push(object_prototype_descriptor(
+          &self.trail,
+          &self.field_sets,
+          id_generator,
+          existing_prototypes,
+        )),
+      }
+    }
+
     let descriptors_count = descriptors.len();
     let shape_id = id_generator.shape();
     let shape_prototype = match descriptors_count {
@@ -525,6 +979,7 @@ impl TrailValues {
           parameter_ids: (0..descriptors.len())
             .map(|_| id_generator.shape_param())
             .collect(),
+          discriminator,
           branches: descriptors
             .into_iter()
             .map(|descriptor| ShapePrototype {
@@ -551,6 +1006,84 @@ impl TrailValues {
   }
 }
 
+fn object_prototype_descriptor(
+  object_trail: &JsonTrail,
+  field_sets: &[FieldSet],
+  id_generator: &mut impl SpecIdGenerator,
+  existing_prototypes: &HashMap<JsonTrail, ShapePrototype>,
+) -> ShapePrototypeDescriptor {
+  let (field_keys, optional_keys) = {
+    let all_keys_set = field_sets
+      .iter()
+      .fold(HashSet::new(), |all_keys: HashSet<String>, field_set| {
+        all_keys.union(&field_set).cloned().collect()
+      });
+
+    let optional_keys_set = field_sets
+      .iter()
+      .fold(HashSet::new(), |optional_keys, field_set| {
+        let missing_keys = all_keys_set.difference(&field_set).cloned().collect();
+        optional_keys.union(&missing_keys).cloned().collect()
+      });
+
+    let mut all_keys = all_keys_set.into_iter().collect::<Vec<_>>();
+    all_keys.sort();
+
+    let optional_keys = optional_keys_set.into_iter().collect::<Vec<_>>();
+    (all_keys, optional_keys)
+  };
+
+  let field_descriptors = field_keys
+    .into_iter()
+    .map(|key| {
+      let field_trail = object_trail.with_object_key(key.clone());
+      let field_shape_prototype = existing_prototypes
+        .get(&field_trail)
+        .expect("object field shape prototype should have been generated before its parent object");
+      let is_optional = optional_keys.contains(&key);
+
+      FieldPrototypeDescriptor {
+        field_id: id_generator.field(),
+        key,
+        optional_shape_id: match is_optional {
+          true => Some(id_generator.shape()),
+          false => None,
+        },
+        value_shape_id: field_shape_prototype.id.clone(),
+      }
+    })
+    .collect::<Vec<_>>();
+
+  ShapePrototypeDescriptor::ObjectWithFields {
+    fields: field_descriptors,
+  }
+}
+
+fn with_constraint_ids(
+  id_generator: &mut impl SpecIdGenerator,
+  constraints: Vec<ShapeConstraintDescriptor>,
+) -> Vec<(ShapeConstraintId, ShapeConstraintDescriptor)> {
+  constraints
+    .into_iter()
+    .map(|descriptor| (id_generator.shape_constraint(), descriptor))
+    .collect()
+}
+
+/// Keys like `42`, `3f2b8c1e-7d4a-4a3e-9d2b-1c6f0e8a7b5d` or `cus_8Hn3kD92Lq` identify
+/// entities rather than describe fields.
+fn is_id_like_key(key: &str) -> bool {
+  if !key.is_empty() && key.chars().all(|c| c.is_ascii_digit()) {
+    return true;
+  }
+
+  let digit_count = key.chars().filter(|c| c.is_ascii_digit()).count();
+  key.len() >= 8
+    && digit_count >= 3
+    && key
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
 #[derive(Clone, Debug)]
 struct ShapePrototype {
   id: ShapeId,
@@ -565,7 +1098,8 @@ enum ShapePrototypeDescriptor {
   },
   OneOfShape {
     branches: Vec<ShapePrototype>,
-    parameter_ids: Vec<String>,
+    parameter_ids: Vec<ShapeParameterId>,
+    discriminator: Option<DiscriminatorPrototype>,
   },
   ObjectWithFields {
     fields: Vec<FieldPrototypeDescriptor>,
@@ -574,12 +1108,24 @@ enum ShapePrototypeDescriptor {
     item_shape_id: ShapeId,
     item_is_unknown: bool,
   },
+  MapOfShape {
+    value_shape_id: ShapeId,
+    value_is_unknown: bool,
+  },
   PrimitiveKind {
     base_shape_kind: ShapeKind,
+    constraints: Vec<(ShapeConstraintId, ShapeConstraintDescriptor)>,
   },
   Unknown,
 }
 
+#[derive(Clone, Debug)]
+struct DiscriminatorPrototype {
+  field_name: String,
+  /// The discriminator values selecting each of the branches, by the branches' position
+  values_by_branch: Vec<Vec<String>>,
+}
+
 #[derive(Clone, Debug)]
 struct FieldPrototypeDescriptor {
   field_id: FieldId,
@@ -594,7 +1140,7 @@ mod test {
   use crate::projections::SpecProjection;
   use crate::shapes::diff as diff_shapes;
   use crate::state::body::BodyDescriptor;
-  use crate::{learn_shape::observe_body_trails, Body};
+  use crate::{learn_shape::observe_body_trails, Body, DiffInteractionConfig};
   use cqrs_core::Aggregate;
   use insta::assert_debug_snapshot;
   use serde_json::json;
@@ -923,6 +1469,142 @@ mod test {
     );
   }
 
+  #[test]
+  fn trail_observations_can_generate_commands_for_map_bodies() {
+    let map_body = BodyDescriptor::from(json!({
+      "orders": {
+        "1001": { "total": 12, "items": { "sku_00123": 1 } },
+        "1002": { "total": 8, "items": { "sku_00456": 2, "sku_00789": 1 } },
+        "1003": { "total": 3, "items": { "sku_00123": 4, "sku_00456": 1 } }
+      },
+      "count": 3
+    }));
+    let object_body = BodyDescriptor::from(json!({
+      "orders": { "first": 1, "second": 2, "third": 3 }
+    }));
+
+    let mut test_id_generator = TestIdGenerator::default();
+
+    let map_observations = observe_body_trails(map_body.clone()).normalized();
+    let map_results =
+      collect_commands(map_observations.into_commands(&mut test_id_generator, &JsonTrail::empty()));
+    assert!(map_results.0.is_some());
+    let spec_projection = assert_valid_commands(map_results.1.clone());
+    assert_no_shape_diffs(
+      &spec_projection,
+      map_results.0.as_ref().unwrap(),
+      vec![
+        map_body,
+        BodyDescriptor::from(json!({
+          "orders": { "2001": { "total": 1, "items": {} } },
+          "count": 1
+        })),
+      ],
+    );
+    assert_debug_snapshot!(
+      "trail_observations_can_generate_commands_for_map_bodies__map_results",
+      &map_results
+    );
+
+    let object_observations = observe_body_trails(object_body).normalized();
+    let object_results = collect_commands(
+      object_observations.into_commands(&mut test_id_generator, &JsonTrail::empty()),
+    );
+    let object_shape_count = object_results
+      .1
+      .iter()
+      .filter(|command| {
+        matches!(
+          command,
+          SpecCommand::ShapeCommand(ShapeCommand::AddShape(add_shape))
+            if add_shape.base_shape_id == "$object"
+        )
+      })
+      .count();
+    assert_eq!(
+      object_shape_count, 2,
+      "objects with descriptive keys should not be learned as maps"
+    );
+  }
+
+  #[test]
+  fn trail_observations_can_generate_commands_for_unmerged_map_values() {
+    let map_body = BodyDescriptor::from(json!({
+      "1001": { "total": 12 },
+      "1002": { "total": 8 },
+      "1003": { "total": 3 }
+    }));
+
+    let mut test_id_generator = TestIdGenerator::default();
+
+    // only the map itself was observed, as when affordances are learned for a single trail
+    let map_values = observe_body_trails(map_body)
+      .remove(&JsonTrail::empty())
+      .expect("should have observed values for the root");
+    let map_observations =
+      TrailObservationsResult::from(HashMap::from([(JsonTrail::empty(), map_values)]));
+    let map_results =
+      collect_commands(map_observations.into_commands(&mut test_id_generator, &JsonTrail::empty()));
+    assert!(map_results.0.is_some());
+    assert_valid_commands(map_results.1.clone());
+
+    let unknown_shape_count = map_results
+      .1
+      .iter()
+      .filter(|command| {
+        matches!(
+          command,
+          SpecCommand::ShapeCommand(ShapeCommand::AddShape(add_shape))
+            if add_shape.base_shape_id == "$unknown"
+        )
+      })
+      .count();
+    assert_eq!(
+      unknown_shape_count, 1,
+      "map values without observations should be learned as unknown"
+    );
+  }
+
+  #[test]
+  fn trail_observations_can_generate_commands_with_constraints() {
+    let body = BodyDescriptor::from(json!([
+      { "id": "0b9c4d1e-3f2a-4c5b-8d7e-6f1a2b3c4d5e", "createdAt": "2021-03-01T10:00:00Z", "status": "active", "count": 1, "price": 2.5, "note": "first" },
+      { "id": "1c0d5e2f-4a3b-4d6c-9e8f-7a2b3c4d5e6f", "createdAt": "2021-03-02T10:00:00Z", "status": "inactive", "count": 2, "price": 3, "note": "second" },
+      { "id": "2d1e6f3a-5b4c-4e7d-8f9a-8b3c4d5e6f7a", "createdAt": "2021-03-03T10:00:00Z", "status": "active", "count": 3, "price": 4, "note": "third" },
+      { "id": "3e2f7a4b-6c5d-4f8e-9a0b-9c4d5e6f7a8b", "createdAt": "2021-03-04T10:00:00Z", "status": "active", "count": 4, "price": 5, "note": "fourth" },
+      { "id": "4f3a8b5c-7d6e-4a9f-8b1c-0d5e6f7a8b9c", "createdAt": "2021-03-05T10:00:00Z", "status": "inactive", "count": 5, "price": 6, "note": "fifth" },
+      { "id": "5a4b9c6d-8e7f-4b0a-9c2d-1e6f7a8b9c0d", "createdAt": "2021-03-06T10:00:00Z", "status": "active", "count": 6, "price": 7, "note": "sixth" }
+    ]));
+
+    let mut test_id_generator = TestIdGenerator::default();
+    let observations = observe_body_trails(body.clone()).normalized();
+    let results =
+      collect_commands(observations.into_commands(&mut test_id_generator, &JsonTrail::empty()));
+
+    let constraints = results
+      .1
+      .iter()
+      .filter_map(|command| match command {
+        SpecCommand::ShapeCommand(ShapeCommand::AddShapeConstraint(add_constraint)) => {
+          Some(add_constraint.descriptor.clone())
+        }
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      constraints.len(),
+      4,
+      "constraints for id, createdAt, status and count should be learned"
+    );
+
+    let spec_projection = assert_valid_commands(results.1.clone());
+    assert_no_shape_diffs(&spec_projection, results.0.as_ref().unwrap(), vec![body]);
+    assert_debug_snapshot!(
+      "trail_observations_can_generate_commands_with_constraints__results",
+      &results
+    );
+  }
+
   #[test]
   fn trail_observations_can_generate_commands_for_nullable_bodies() {
     let nullable_primitive_bodies = vec![
@@ -1122,6 +1804,73 @@ mod test {
     );
   }
 
+  #[test]
+  fn trail_observations_can_generate_commands_for_discriminated_object_bodies() {
+    let discriminated_bodies = vec![
+      BodyDescriptor::from(json!({ "type": "card", "last4": "4242", "amount": 10 })),
+      BodyDescriptor::from(json!({ "type": "bank", "iban": "NL91ABNA0417164300", "amount": 20 })),
+      BodyDescriptor::from(json!({ "type": "card", "last4": "1111", "amount": 5 })),
+    ];
+
+    // a field missing from some objects doesn't set them apart from the others
+    let optional_field_bodies = vec![
+      BodyDescriptor::from(json!({ "name": "alice", "email": "alice@example.com" })),
+      BodyDescriptor::from(json!({ "name": "bob" })),
+    ];
+
+    let discriminated_observations = discriminated_bodies.iter().cloned().fold(
+      TrailObservationsResult::default(),
+      |mut observations, body| {
+        observations.union(observe_body_trails(body).normalized());
+        observations
+      },
+    );
+
+    let optional_field_observations = optional_field_bodies.iter().cloned().fold(
+      TrailObservationsResult::default(),
+      |mut observations, body| {
+        observations.union(observe_body_trails(body).normalized());
+        observations
+      },
+    );
+
+    let mut test_id_generator = TestIdGenerator::default();
+
+    let discriminated_results = collect_commands(
+      discriminated_observations.into_commands(&mut test_id_generator, &JsonTrail::empty()),
+    );
+    assert!(discriminated_results.0.is_some());
+    assert!(discriminated_results.1.iter().any(|command| matches!(
+      command,
+      SpecCommand::ShapeCommand(ShapeCommand::SetOneOfDiscriminator(_))
+    )));
+    let spec_projection = assert_valid_commands(discriminated_results.1.clone());
+    assert_no_shape_diffs(
+      &spec_projection,
+      discriminated_results.0.as_ref().unwrap(),
+      discriminated_bodies,
+    );
+    assert_debug_snapshot!(
+      "trail_observations_can_generate_commands_for_discriminated_object_bodies__discriminated_results",
+      &discriminated_results
+    );
+
+    let optional_field_results = collect_commands(
+      optional_field_observations.into_commands(&mut test_id_generator, &JsonTrail::empty()),
+    );
+    assert!(optional_field_results.0.is_some());
+    assert!(!optional_field_results.1.iter().any(|command| matches!(
+      command,
+      SpecCommand::ShapeCommand(ShapeCommand::SetOneOfDiscriminator(_))
+    )));
+    let spec_projection = assert_valid_commands(optional_field_results.1.clone());
+    assert_no_shape_diffs(
+      &spec_projection,
+      optional_field_results.0.as_ref().unwrap(),
+      optional_field_bodies,
+    );
+  }
+
   #[test]
   fn trail_observations_can_generate_for_non_root_json_trails() {
     let complete_nested_object_body = BodyDescriptor::from(json!({
@@ -1294,7 +2043,12 @@ mod test {
     bodies: impl IntoIterator<Item = BodyDescriptor>,
   ) {
     for body in bodies {
-      let results = diff_shapes(spec_projection.shape(), Some(body), root_shape_id);
+      let results = diff_shapes(
+        spec_projection.shape(),
+        Some(body),
+        root_shape_id,
+        &DiffInteractionConfig::default(),
+      );
 
       if results.len() > 0 {
         panic!(
@@ -1,6 +1,6 @@
 use super::{
   BodyArrayVisitor, BodyObjectKeyVisitor, BodyObjectVisitor, BodyPrimitiveVisitor, BodyVisitor,
-  TrailValues, VisitorResults,
+  DiscriminatorObservations, TrailValues, VisitorResults,
 };
 use crate::learn_shape::visitors::BodyVisitors;
 use crate::queries::shape::ChoiceOutput;
@@ -112,8 +112,14 @@ impl BodyPrimitiveVisitor<TrailValues> for LearnPrimitiveVisitor {
 
     match body {
       BodyDescriptor::Boolean => trail_values.was_boolean = true,
-      BodyDescriptor::Number => trail_values.was_number = true,
-      BodyDescriptor::String => trail_values.was_string = true,
+      BodyDescriptor::Number(values) => {
+        trail_values.was_number = true;
+        trail_values.values.observe_numbers(&values);
+      }
+      BodyDescriptor::String(values) => {
+        trail_values.was_string = true;
+        trail_values.values.observe_strings(&values);
+      }
       BodyDescriptor::Null => trail_values.was_null = true,
       _ => unreachable!("should not call primitive visitor without a primitive value"),
     }
@@ -187,6 +193,7 @@ impl BodyObjectVisitor<TrailValues> for LearnObjectVisitor {
       let keys_set = HashSet::<String>::from_iter(keys);
 
       trail_values.insert_field_set(keys_set);
+      trail_values.observe_discriminators(DiscriminatorObservations::from(object_description));
     }
   }
 }
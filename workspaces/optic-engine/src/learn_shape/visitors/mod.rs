@@ -1,4 +1,4 @@
-pub use super::result::TrailValues;
+pub use super::result::{DiscriminatorObservations, TrailValues};
 use crate::shapes::JsonTrail;
 use crate::state::body::BodyDescriptor;
 use serde_json::Value as JsonValue;
@@ -4,6 +4,7 @@ mod commands;
 mod events;
 mod interactions;
 mod learn_shape;
+mod openapi;
 mod projections;
 mod protos;
 mod queries;
@@ -18,30 +19,52 @@ pub use commands::{CommandContext, EndpointCommand, RfcCommand, SpecCommand, Spe
 pub use cqrs_core::Aggregate;
 pub use events::{
   http_interaction::{ArbitraryData, Body, HttpInteraction, Request, Response},
-  RfcEvent, SpecChunkEvent, SpecEvent,
+  RfcEvent, SpecChunkEvent, SpecChunksEventsReader, SpecEvent, SpecEventsReader,
+};
+pub use interactions::result::{
+  BodyAnalysisLocation, BodyAnalysisResult, InteractionDiffResult, UrlAnalysisResult,
 };
-pub use interactions::result::{BodyAnalysisLocation, BodyAnalysisResult, InteractionDiffResult};
 pub use interactions::{
-  analyze_documented_bodies, analyze_undocumented_bodies, AnalyzeUndocumentedBodiesConfig,
+  analyze_documented_bodies, analyze_undocumented_bodies, analyze_undocumented_urls,
+  AnalyzeUndocumentedBodiesConfig,
 };
 pub use interactions::{diff as diff_interaction, DiffConfig as DiffInteractionConfig};
 pub use learn_shape::{TrailObservationsResult, TrailValues};
+pub use openapi::{
+  export as export_openapi, import as import_openapi, parse_document as parse_openapi_document,
+  OpenApiConfig, OpenApiImport, OpenApiImportError, OpenApiVersion, UnsupportedConstruct,
+};
 pub use projections::{
   EndpointProjection, LearnedShapeDiffAffordancesProjection, LearnedUndocumentedBodiesProjection,
-  ResponseBodyDescriptor, ShapeProjection, SpecAssemblerProjection, SpecProjection,
+  LearnedUndocumentedUrlsProjection, ResponseBodyDescriptor, ShapeProjection,
+  SnapshottableProjection, SpecAssemblerProjection, SpecProjection, SpecProjectionSnapshot,
 };
 pub use protos::shapehash;
+pub use queries::conflicts::{Conflict, ConflictsQueries};
 pub use queries::endpoint::EndpointQueries;
 pub use queries::shape::ShapeQueries;
 pub use queries::spectacle::spec_choices::{JsonType, ShapeChoiceQueries};
-pub use shapes::{diff as diff_shape, JsonTrail};
+pub use shapes::{
+  diff as diff_shape, diff_query_parameters as diff_query_parameters_shape, JsonTrail,
+};
 pub use spec::append_batch as append_batch_to_spec;
+pub use spec::events_at_batch_commit as spec_events_at_batch_commit;
+pub use spec::{
+  changelog as spec_changelog, BatchChangelog, Changelog, ChangelogEntry, EndpointChangelog,
+};
+pub use spec::{compare as compare_specs, ChangeLocation, SpecChange, SpecChangeKind};
+pub use spec::{merge_branches as merge_spec_branches, MergeConflict, MergedBatch};
+pub use spec::{revert_batch as revert_spec_batch, revert_batch_commands};
 pub use state::endpoint::ResponseId;
 pub use state::{body::BodyDescriptor, SpecIdGenerator, TaggedInput, Tags};
 
 pub mod errors {
-  pub use super::events::EventLoadingError;
+  pub use super::events::{EventLoadingError, SpecEventsReadError};
+  pub use super::projections::SpecProjectionSnapshotError;
+  pub use super::spec::{ChangelogError, MergeBranchesError, RevertBatchError};
 
+  #[cfg(feature = "streams")]
+  pub use super::streams::har::HarReadError;
   #[cfg(feature = "streams")]
   pub use super::streams::spec_chunks::{SpecChunkLoaderError, SpecChunkWriterError};
 }
@@ -0,0 +1,748 @@
+use crate::projections::endpoint::{
+  EndpointProjection, HeaderParameterDescriptor, Node as EndpointNode, ROOT_PATH_ID,
+};
+use crate::projections::{ContributionsProjection, ShapeProjection, SpecProjection};
+use crate::queries::{EndpointQueries, ShapeQueries};
+use crate::shapes::{ShapeTrail, ShapeTrailPathComponent};
+use crate::state::endpoint::{HttpMethod, HttpStatusSpecifier, PathComponentId};
+use crate::state::shape::{ShapeConstraintDescriptor, ShapeId, ShapeKind};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum OpenApiVersion {
+  #[serde(rename = "3.0")]
+  V3_0,
+  #[serde(rename = "3.1")]
+  V3_1,
+}
+
+impl OpenApiVersion {
+  fn document_version(&self) -> &'static str {
+    match self {
+      Self::V3_0 => "3.0.3",
+      Self::V3_1 => "3.1.0",
+    }
+  }
+}
+
+impl FromStr for OpenApiVersion {
+  type Err = String;
+
+  fn from_str(version: &str) -> Result<Self, Self::Err> {
+    match version {
+      "3.0" | "3.0.3" => Ok(Self::V3_0),
+      "3.1" | "3.1.0" => Ok(Self::V3_1),
+      _ => Err(format!("unsupported OpenAPI version '{}'", version)),
+    }
+  }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct OpenApiConfig {
+  pub version: OpenApiVersion,
+  /// Title of the API, as listed in the `info` object
+  pub title: String,
+  /// Version of the API (not of the OpenAPI specification), as listed in the `info` object
+  pub api_version: String,
+}
+
+impl Default for OpenApiConfig {
+  fn default() -> Self {
+    Self {
+      version: OpenApiVersion::V3_0,
+      title: String::from("API"),
+      api_version: String::from("1.0.0"),
+    }
+  }
+}
+
+/// Assemble an OpenAPI document describing every documented endpoint of a spec. Shapes that are
+/// the target of a reference are listed once under `components.schemas`, all others are inlined.
+pub fn export(spec_projection: &SpecProjection, config: &OpenApiConfig) -> JsonValue {
+  let mut exporter = Exporter::new(spec_projection, config.version);
+
+  let mut paths = Map::new();
+  exporter.collect_paths(ROOT_PATH_ID, String::new(), vec![], &mut paths);
+  let schemas = exporter.schemas.referenced_schemas();
+
+  let mut document = json!({
+    "openapi": config.version.document_version(),
+    "info": {
+      "title": config.title,
+      "version": config.api_version,
+    },
+    "paths": paths,
+  });
+  if !schemas.is_empty() {
+    document["components"] = json!({ "schemas": schemas });
+  }
+
+  document
+}
+
+struct Exporter<'a> {
+  endpoint_projection: &'a EndpointProjection,
+  endpoint_queries: EndpointQueries<'a>,
+  schemas: SchemaExporter<'a>,
+  contributions: &'a ContributionsProjection,
+}
+
+impl<'a> Exporter<'a> {
+  fn new(spec_projection: &'a SpecProjection, version: OpenApiVersion) -> Self {
+    Self {
+      endpoint_projection: spec_projection.endpoint(),
+      endpoint_queries: EndpointQueries::new(spec_projection.endpoint()),
+      schemas: SchemaExporter::new(
+        spec_projection.shape(),
+        spec_projection.contributions(),
+        version,
+      ),
+      contributions: spec_projection.contributions(),
+    }
+  }
+
+  fn collect_paths(
+    &mut self,
+    path_id: &str,
+    path: String,
+    path_parameters: Vec<PathParameter>,
+    paths: &mut Map<String, JsonValue>,
+  ) {
+    let path_id = String::from(path_id);
+    let operations = self.path_item(&path_id, &path_parameters);
+    if !operations.is_empty() {
+      let path = if path.is_empty() {
+        String::from("/")
+      } else {
+        path.clone()
+      };
+      paths.insert(path, JsonValue::Object(operations));
+    }
+
+    let children = self
+      .endpoint_projection
+      .get_child_path_component_nodes(&path_id)
+      .into_iter()
+      .flatten()
+      .filter_map(|node| match node {
+        EndpointNode::PathComponent(child_id, descriptor) => Some((child_id, descriptor)),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+
+    for (child_id, descriptor) in children {
+      let mut child_parameters = path_parameters.clone();
+      let child_path = if descriptor.is_parameter {
+        child_parameters.push(PathParameter {
+          name: descriptor.name.clone(),
+          shape_id: descriptor
+            .shape
+            .as_ref()
+            .filter(|shape| !shape.is_removed)
+            .map(|shape| shape.shape_id.clone()),
+        });
+        format!("{}/{{{}}}", path, descriptor.name)
+      } else {
+        format!("{}/{}", path, descriptor.name)
+      };
+      self.collect_paths(child_id, child_path, child_parameters, paths);
+    }
+  }
+
+  fn path_item(
+    &mut self,
+    path_id: &PathComponentId,
+    path_parameters: &[PathParameter],
+  ) -> Map<String, JsonValue> {
+    let methods = self
+      .endpoint_projection
+      .get_http_methods(path_id)
+      .into_iter()
+      .flatten()
+      .cloned()
+      .collect::<BTreeSet<_>>();
+
+    methods
+      .into_iter()
+      .filter_map(|method| {
+        let operation = self.operation(path_id, &method, path_parameters)?;
+        Some((method.to_lowercase(), operation))
+      })
+      .collect()
+  }
+
+  fn operation(
+    &mut self,
+    path_id: &PathComponentId,
+    method: &HttpMethod,
+    path_parameters: &[PathParameter],
+  ) -> Option<JsonValue> {
+    let queries = &self.endpoint_queries;
+    let requests = queries
+      .resolve_requests(path_id, method)?
+      .map(|(request_id, descriptor)| (request_id.clone(), descriptor.clone()))
+      .collect::<Vec<_>>();
+    let responses = queries
+      .resolve_responses(path_id, method)?
+      .map(|(response_id, descriptor)| (response_id.clone(), descriptor.clone()))
+      .collect::<Vec<_>>();
+    if requests.is_empty() && responses.is_empty() {
+      return None;
+    }
+
+    let mut operation = Map::new();
+    let endpoint_id = format!("{}.{}", path_id, method.to_uppercase());
+    if let Some(purpose) = self.contributions.get_contribution(&endpoint_id, "purpose") {
+      operation.insert(String::from("summary"), json!(purpose));
+    }
+    if let Some(description) = self
+      .contributions
+      .get_contribution(&endpoint_id, "description")
+    {
+      operation.insert(String::from("description"), json!(description));
+    }
+
+    let mut parameters = path_parameters
+      .iter()
+      .map(|path_parameter| {
+        let schema = match &path_parameter.shape_id {
+          Some(shape_id) => self.schemas.parameter_schema(shape_id).0,
+          None => json!({ "type": "string" }),
+        };
+        json!({
+          "name": path_parameter.name,
+          "in": "path",
+          "required": true,
+          "schema": schema,
+        })
+      })
+      .collect::<Vec<_>>();
+
+    let query_parameters_shape = self
+      .endpoint_queries
+      .resolve_endpoint_query_params(path_id, method)
+      .and_then(|(_, descriptor)| descriptor.shape.clone())
+      .filter(|shape| !shape.is_removed);
+    if let Some(query_parameters_shape) = query_parameters_shape {
+      let fields = self.schemas.object_fields(
+        &ShapeTrail::new(query_parameters_shape.shape_id.clone()),
+        &query_parameters_shape.shape_id,
+      );
+      parameters.extend(fields.into_iter().map(|field| {
+        let mut parameter = json!({
+          "name": field.name,
+          "in": "query",
+          "required": field.is_required,
+          "schema": field.schema,
+        });
+        if let Some(description) = field.description {
+          parameter["description"] = json!(description);
+        }
+        parameter
+      }));
+    }
+
+    let mut header_names = BTreeSet::new();
+    let mut request_content = Map::new();
+    for (request_id, descriptor) in requests {
+      for (_, header) in self
+        .endpoint_projection
+        .get_header_parameter_nodes(&request_id)
+        .into_iter()
+        .flatten()
+      {
+        if header_names.insert(header.name.to_lowercase()) {
+          let (schema, is_required) = self.header_schema(header);
+          parameters.push(json!({
+            "name": header.name,
+            "in": "header",
+            "required": is_required,
+            "schema": schema,
+          }));
+        }
+      }
+
+      if let Some(body) = descriptor.body {
+        let schema = self.schemas.schema(&body.root_shape_id);
+        request_content.insert(body.http_content_type, json!({ "schema": schema }));
+      }
+    }
+
+    if !parameters.is_empty() {
+      operation.insert(String::from("parameters"), JsonValue::Array(parameters));
+    }
+    if !request_content.is_empty() {
+      operation.insert(
+        String::from("requestBody"),
+        json!({ "content": request_content }),
+      );
+    }
+
+    let mut responses_by_status_code: BTreeMap<HttpStatusSpecifier, Map<String, JsonValue>> =
+      BTreeMap::new();
+    for (response_id, descriptor) in responses {
+      let status_code = match self
+        .endpoint_projection
+        .get_response_status_specifier(&response_id)
+      {
+        Some(status_code) => status_code,
+        None => continue,
+      };
+      let response = responses_by_status_code
+        .entry(status_code)
+        .or_insert_with(|| {
+          let mut response = Map::new();
+          response.insert(String::from("description"), json!(""));
+          response
+        });
+      if let Some(description) = self
+        .contributions
+        .get_contribution(&response_id, "description")
+      {
+        response.insert(String::from("description"), json!(description));
+      }
+
+      let headers = self
+        .endpoint_projection
+        .get_header_parameter_nodes(&response_id)
+        .into_iter()
+        .flatten()
+        .map(|(_, header)| {
+          let (schema, is_required) = self.header_schema(header);
+          (
+            header.name.clone(),
+            json!({ "required": is_required, "schema": schema }),
+          )
+        })
+        .collect::<Vec<_>>();
+      if !headers.is_empty() {
+        let response_headers = response
+          .entry("headers")
+          .or_insert_with(|| JsonValue::Object(Map::new()));
+        for (name, header) in headers {
+          response_headers[name] = header;
+        }
+      }
+
+      if let Some(body) = descriptor.body {
+        let schema = self.schemas.schema(&body.root_shape_id);
+        let content = response
+          .entry("content")
+          .or_insert_with(|| JsonValue::Object(Map::new()));
+        content[body.http_content_type] = json!({ "schema": schema });
+      }
+    }
+    let responses = responses_by_status_code
+      .into_iter()
+      .map(|(status_code, response)| (status_code.to_string(), JsonValue::Object(response)))
+      .collect::<Map<_, _>>();
+    operation.insert(String::from("responses"), JsonValue::Object(responses));
+
+    Some(JsonValue::Object(operation))
+  }
+
+  fn header_schema(&mut self, header: &HeaderParameterDescriptor) -> (JsonValue, bool) {
+    match header.shape.as_ref().filter(|shape| !shape.is_removed) {
+      Some(shape) => self.schemas.parameter_schema(&shape.shape_id),
+      None => (json!({ "type": "string" }), false),
+    }
+  }
+}
+
+#[derive(Clone)]
+struct PathParameter {
+  name: String,
+  shape_id: Option<ShapeId>,
+}
+
+struct ObjectFieldSchema {
+  name: String,
+  schema: JsonValue,
+  is_required: bool,
+  description: Option<String>,
+}
+
+struct SchemaExporter<'a> {
+  shape_projection: &'a ShapeProjection,
+  shape_queries: ShapeQueries<'a>,
+  contributions: &'a ContributionsProjection,
+  version: OpenApiVersion,
+  referenced_shape_ids: BTreeSet<ShapeId>,
+  /// Shapes of which the schemas are being exported, from the outermost in
+  exporting_shape_ids: Vec<ShapeId>,
+}
+
+impl<'a> SchemaExporter<'a> {
+  fn new(
+    shape_projection: &'a ShapeProjection,
+    contributions: &'a ContributionsProjection,
+    version: OpenApiVersion,
+  ) -> Self {
+    Self {
+      shape_projection,
+      shape_queries: ShapeQueries::new(shape_projection),
+      contributions,
+      version,
+      referenced_shape_ids: BTreeSet::new(),
+      exporting_shape_ids: vec![],
+    }
+  }
+
+  fn schema(&mut self, shape_id: &ShapeId) -> JsonValue {
+    self.schema_in_trail(&ShapeTrail::new(shape_id.clone()), shape_id)
+  }
+
+  /// Schema of the shape at the end of a trail, so parameters of generic shapes are resolved to
+  /// the shapes bound by where they're used
+  fn schema_in_trail(&mut self, shape_trail: &ShapeTrail, shape_id: &ShapeId) -> JsonValue {
+    if let Some(base_shape_id) = self.shape_queries.resolve_base_shape_id(shape_id).cloned() {
+      return self.schema_in_trail(shape_trail, &base_shape_id);
+    }
+
+    // shapes can (indirectly) contain themselves, which can only be described by a reference
+    if self.exporting_shape_ids.contains(shape_id) {
+      self.referenced_shape_ids.insert(shape_id.clone());
+      return json!({ "$ref": format!("#/components/schemas/{}", shape_id) });
+    }
+
+    self.exporting_shape_ids.push(shape_id.clone());
+    let schema = self.shape_schema(shape_trail, shape_id);
+    self.exporting_shape_ids.pop();
+
+    schema
+  }
+
+  fn shape_schema(&mut self, shape_trail: &ShapeTrail, shape_id: &ShapeId) -> JsonValue {
+    let shape_kind = self.shape_queries.resolve_to_core_shape(shape_id).clone();
+    let mut schema = match shape_kind {
+      ShapeKind::ObjectKind => {
+        let fields = self.object_fields(shape_trail, shape_id);
+        let required = fields
+          .iter()
+          .filter(|field| field.is_required)
+          .map(|field| field.name.clone())
+          .collect::<BTreeSet<_>>();
+        let properties = fields
+          .into_iter()
+          .map(|field| {
+            let mut schema = field.schema;
+            if let Some(description) = field.description {
+              schema = with_description(schema, description);
+            }
+            (field.name, schema)
+          })
+          .collect::<Map<_, _>>();
+
+        let mut schema = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+          schema["required"] = json!(required);
+        }
+        schema
+      }
+      ShapeKind::ListKind => json!({
+        "type": "array",
+        "items": self.parameter_shape_schema(shape_trail, shape_id, &shape_kind),
+      }),
+      ShapeKind::MapKind => json!({
+        "type": "object",
+        "additionalProperties": self.parameter_shape_schema(shape_trail, shape_id, &shape_kind),
+      }),
+      ShapeKind::OneOfKind => {
+        let one_of = self
+          .shape_queries
+          .resolve_parameters_to_shapes(shape_id)
+          .into_iter()
+          .map(|(parameter_id, item_shape_id)| {
+            let item_trail = shape_trail
+              .with_component(ShapeTrailPathComponent::OneOfTrail {
+                shape_id: shape_id.clone(),
+              })
+              .with_component(ShapeTrailPathComponent::OneOfItemTrail {
+                one_of_id: shape_id.clone(),
+                parameter_id,
+                item_shape_id: item_shape_id.clone(),
+              });
+            self.schema_in_trail(&item_trail, &item_shape_id)
+          })
+          .collect::<Vec<_>>();
+        json!({ "oneOf": one_of })
+      }
+      ShapeKind::NullableKind => {
+        let inner_schema = self.parameter_shape_schema(shape_trail, shape_id, &shape_kind);
+        self.nullable(inner_schema)
+      }
+      // Optionality is expressed by the object or parameter the shape is used in
+      ShapeKind::OptionalKind | ShapeKind::IdentifierKind => {
+        self.parameter_shape_schema(shape_trail, shape_id, &shape_kind)
+      }
+      ShapeKind::ReferenceKind => {
+        let target_shape_id = self.parameter_shape_id(shape_trail, shape_id, &shape_kind);
+
+        // a generic target is described by the shapes bound to its parameters here, so it can't
+        // be shared as a component
+        if self.shape_queries.has_unbound_parameters(&target_shape_id) {
+          self.parameter_shape_schema(shape_trail, shape_id, &shape_kind)
+        } else {
+          let schema = json!({ "$ref": format!("#/components/schemas/{}", target_shape_id) });
+          self.referenced_shape_ids.insert(target_shape_id);
+          schema
+        }
+      }
+      ShapeKind::StringKind => json!({ "type": "string" }),
+      ShapeKind::NumberKind => json!({ "type": "number" }),
+      ShapeKind::BooleanKind => json!({ "type": "boolean" }),
+      ShapeKind::AnyKind | ShapeKind::UnknownKind => json!({}),
+    };
+
+    self.with_constraints(shape_id, &mut schema);
+    match self.contributions.get_contribution(shape_id, "description") {
+      Some(description) => with_description(schema, description.clone()),
+      None => schema,
+    }
+  }
+
+  /// Schema for a query, header or path parameter shape, along with whether the parameter is
+  /// required
+  fn parameter_schema(&mut self, shape_id: &ShapeId) -> (JsonValue, bool) {
+    self.parameter_schema_in_trail(&ShapeTrail::new(shape_id.clone()), shape_id)
+  }
+
+  fn parameter_schema_in_trail(
+    &mut self,
+    shape_trail: &ShapeTrail,
+    shape_id: &ShapeId,
+  ) -> (JsonValue, bool) {
+    if let Some(base_shape_id) = self.shape_queries.resolve_base_shape_id(shape_id).cloned() {
+      return self.parameter_schema_in_trail(shape_trail, &base_shape_id);
+    }
+
+    let shape_kind = self.shape_queries.resolve_to_core_shape(shape_id);
+    let is_required = !matches!(shape_kind, ShapeKind::OptionalKind);
+    (self.schema_in_trail(shape_trail, shape_id), is_required)
+  }
+
+  fn object_fields(
+    &mut self,
+    shape_trail: &ShapeTrail,
+    shape_id: &ShapeId,
+  ) -> Vec<ObjectFieldSchema> {
+    let fields = self
+      .shape_queries
+      .resolve_shape_field_id_and_names(shape_id)
+      .map(|(field_id, name)| (field_id.clone(), name.clone()))
+      .collect::<Vec<_>>();
+
+    let mut fields = fields
+      .into_iter()
+      .filter_map(|(field_id, name)| {
+        let field_shape_id = self
+          .shape_queries
+          .resolve_field_shape_in_trail(shape_trail, &field_id)?;
+        let field_trail = shape_trail.with_component(ShapeTrailPathComponent::ObjectFieldTrail {
+          field_id: field_id.clone(),
+          field_shape_id: field_shape_id.clone(),
+          parent_object_shape_id: shape_id.clone(),
+        });
+        let (schema, is_required) = self.parameter_schema_in_trail(&field_trail, &field_shape_id);
+        Some(ObjectFieldSchema {
+          name,
+          schema,
+          is_required,
+          description: self
+            .contributions
+            .get_contribution(&field_id, "description")
+            .cloned(),
+        })
+      })
+      .collect::<Vec<_>>();
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+    fields
+  }
+
+  /// Schemas of all shapes referenced so far, including those referenced by other referenced
+  /// shapes
+  fn referenced_schemas(&mut self) -> Map<String, JsonValue> {
+    let mut schemas = Map::new();
+    while let Some(shape_id) = self
+      .referenced_shape_ids
+      .iter()
+      .find(|shape_id| !schemas.contains_key(*shape_id))
+      .cloned()
+    {
+      // the component itself is the schema being referenced, rather than a reference to itself
+      let schema = self.shape_schema_component(&shape_id);
+      schemas.insert(shape_id, schema);
+    }
+    schemas
+  }
+
+  fn shape_schema_component(&mut self, shape_id: &ShapeId) -> JsonValue {
+    let shape_trail = ShapeTrail::new(shape_id.clone());
+    self.exporting_shape_ids.push(shape_id.clone());
+    let schema = self.shape_schema(&shape_trail, shape_id);
+    self.exporting_shape_ids.pop();
+    schema
+  }
+
+  fn parameter_shape_id(
+    &self,
+    shape_trail: &ShapeTrail,
+    shape_id: &ShapeId,
+    shape_kind: &ShapeKind,
+  ) -> ShapeId {
+    let parameter_id = shape_kind
+      .get_parameter_descriptor()
+      .expect("expected shape kind to have a parameter descriptor")
+      .shape_parameter_id;
+    self.shape_queries.resolve_parameter_to_shape_in_trail(
+      shape_trail,
+      shape_id,
+      &String::from(parameter_id),
+    )
+  }
+
+  fn parameter_shape_schema(
+    &mut self,
+    shape_trail: &ShapeTrail,
+    shape_id: &ShapeId,
+    shape_kind: &ShapeKind,
+  ) -> JsonValue {
+    let parameter_shape_id = self.parameter_shape_id(shape_trail, shape_id, shape_kind);
+    let parameter_trail = parameter_trail(shape_trail, shape_id, shape_kind, &parameter_shape_id);
+    self.schema_in_trail(&parameter_trail, &parameter_shape_id)
+  }
+
+  fn nullable(&self, mut inner_schema: JsonValue) -> JsonValue {
+    match self.version {
+      OpenApiVersion::V3_0 => {
+        if inner_schema.get("$ref").is_some() {
+          json!({ "allOf": [inner_schema], "nullable": true })
+        } else {
+          inner_schema["nullable"] = json!(true);
+          inner_schema
+        }
+      }
+      OpenApiVersion::V3_1 => match inner_schema.get("type").and_then(JsonValue::as_str) {
+        Some(json_type) => {
+          inner_schema["type"] = json!([json_type, "null"]);
+          inner_schema
+        }
+        None => json!({ "oneOf": [inner_schema, { "type": "null" }] }),
+      },
+    }
+  }
+
+  fn with_constraints(&self, shape_id: &ShapeId, schema: &mut JsonValue) {
+    let node_index = match self.shape_projection.get_shape_node_index(shape_id) {
+      Some(node_index) => node_index,
+      None => return,
+    };
+
+    for constraint_node in self.shape_projection.get_shape_constraint_nodes(node_index) {
+      match &constraint_node.descriptor {
+        ShapeConstraintDescriptor::StringFormat { format } => {
+          schema["format"] = json!(format);
+        }
+        ShapeConstraintDescriptor::EnumValues { values } => {
+          schema["enum"] = json!(values);
+        }
+        ShapeConstraintDescriptor::NumberRange { minimum, maximum } => {
+          if let Some(minimum) = minimum {
+            schema["minimum"] = json!(minimum);
+          }
+          if let Some(maximum) = maximum {
+            schema["maximum"] = json!(maximum);
+          }
+        }
+        ShapeConstraintDescriptor::Integer {} => {
+          schema["type"] = json!("integer");
+        }
+      }
+    }
+  }
+}
+
+/// Extend a trail from a shape to the shape provided for its (single) parameter
+fn parameter_trail(
+  shape_trail: &ShapeTrail,
+  shape_id: &ShapeId,
+  shape_kind: &ShapeKind,
+  parameter_shape_id: &ShapeId,
+) -> ShapeTrail {
+  let shape_id = shape_id.clone();
+  let parameter_shape_id = parameter_shape_id.clone();
+  let components = match shape_kind {
+    ShapeKind::ListKind => vec![
+      ShapeTrailPathComponent::ListTrail {
+        shape_id: shape_id.clone(),
+      },
+      ShapeTrailPathComponent::ListItemTrail {
+        list_shape_id: shape_id,
+        item_shape_id: parameter_shape_id,
+      },
+    ],
+    ShapeKind::MapKind => vec![ShapeTrailPathComponent::MapValueTrail {
+      map_shape_id: shape_id,
+      value_shape_id: parameter_shape_id,
+    }],
+    ShapeKind::NullableKind => vec![
+      ShapeTrailPathComponent::NullableTrail {
+        shape_id: shape_id.clone(),
+      },
+      ShapeTrailPathComponent::NullableItemTrail {
+        shape_id,
+        inner_shape_id: parameter_shape_id,
+      },
+    ],
+    ShapeKind::OptionalKind => vec![
+      ShapeTrailPathComponent::OptionalTrail {
+        shape_id: shape_id.clone(),
+      },
+      ShapeTrailPathComponent::OptionalItemTrail {
+        shape_id,
+        inner_shape_id: parameter_shape_id,
+      },
+    ],
+    ShapeKind::IdentifierKind => vec![
+      ShapeTrailPathComponent::IdentifierTrail {
+        shape_id: shape_id.clone(),
+      },
+      ShapeTrailPathComponent::IdentifierInnerTrail {
+        shape_id,
+        inner_shape_id: parameter_shape_id,
+      },
+    ],
+    ShapeKind::ReferenceKind => vec![
+      ShapeTrailPathComponent::ReferenceTrail {
+        shape_id: shape_id.clone(),
+      },
+      ShapeTrailPathComponent::ReferenceTargetTrail {
+        shape_id,
+        target_shape_id: parameter_shape_id,
+      },
+    ],
+    _ => vec![],
+  };
+
+  components
+    .into_iter()
+    .fold(shape_trail.clone(), |trail, component| {
+      trail.with_component(component)
+    })
+}
+
+fn with_description(schema: JsonValue, description: String) -> JsonValue {
+  // siblings of a $ref are ignored (3.0) or discouraged (3.1), so wrap it instead
+  match schema {
+    JsonValue::Object(mut schema) if !schema.contains_key("$ref") => {
+      schema.insert(String::from("description"), json!(description));
+      JsonValue::Object(schema)
+    }
+    schema => json!({ "allOf": [schema], "description": description }),
+  }
+}
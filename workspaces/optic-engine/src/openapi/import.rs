@@ -0,0 +1,1170 @@
+use crate::commands::{EndpointCommand, RfcCommand, ShapeCommand, SpecCommand};
+use crate::projections::endpoint::{EndpointProjection, Node as EndpointNode, ROOT_PATH_ID};
+use crate::projections::SpecProjection;
+use crate::queries::EndpointQueries;
+use crate::state::endpoint::{HttpStatusSpecifier, PathComponentId, RequestId, ResponseId};
+use crate::state::shape::{ShapeConstraintDescriptor, ShapeId, ShapeKind, StringFormat};
+use crate::state::SpecIdGenerator;
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use thiserror::Error;
+
+const HTTP_METHODS: [&str; 8] = [
+  "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Schema keywords that only annotate a schema, and can be dropped without changing which
+/// values it describes.
+const ANNOTATION_KEYWORDS: [&str; 11] = [
+  "description",
+  "title",
+  "example",
+  "examples",
+  "default",
+  "readOnly",
+  "writeOnly",
+  "deprecated",
+  "externalDocs",
+  "xml",
+  "$comment",
+];
+
+const SCHEMA_KEYWORDS: [&str; 14] = [
+  "$ref",
+  "type",
+  "nullable",
+  "properties",
+  "required",
+  "additionalProperties",
+  "items",
+  "oneOf",
+  "anyOf",
+  "allOf",
+  "enum",
+  "format",
+  "minimum",
+  "maximum",
+];
+
+/// Number formats only describe the precision of a number, which shapes don't track
+const NUMBER_FORMATS: [&str; 4] = ["int32", "int64", "float", "double"];
+
+/// Commands to document the endpoints and shapes of an OpenAPI document, meant to be appended
+/// to a spec as a single batch.
+#[derive(Debug)]
+pub struct OpenApiImport {
+  pub commands: Vec<SpecCommand>,
+  pub unsupported: Vec<UnsupportedConstruct>,
+}
+
+/// Part of an OpenAPI document that could not be expressed as spec commands
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsupportedConstruct {
+  /// JSON pointer to the construct in the document
+  pub pointer: String,
+  pub reason: String,
+}
+
+#[derive(Debug, Error)]
+pub enum OpenApiImportError {
+  #[error("document is neither valid JSON nor YAML: {}", .0)]
+  Syntax(String),
+  #[error("document is not an OpenAPI 3 document: {}", .0)]
+  NotOpenApi3(String),
+}
+
+/// Parse an OpenAPI document from either JSON or YAML
+pub fn parse_document(contents: &str) -> Result<JsonValue, OpenApiImportError> {
+  serde_json::from_str(contents).or_else(|_| {
+    serde_yaml::from_str(contents).map_err(|err| OpenApiImportError::Syntax(err.to_string()))
+  })
+}
+
+/// Generate the commands to add all paths, endpoints and shapes of an OpenAPI document to a
+/// spec. Path components already in the spec are reused, but endpoints that are already
+/// documented are left alone and reported.
+pub fn import(
+  spec_projection: &SpecProjection,
+  document: &JsonValue,
+  id_generator: &mut impl SpecIdGenerator,
+) -> Result<OpenApiImport, OpenApiImportError> {
+  let version = document
+    .get("openapi")
+    .and_then(JsonValue::as_str)
+    .ok_or_else(|| OpenApiImportError::NotOpenApi3(String::from("missing 'openapi' version")))?;
+  if !version.starts_with("3.") {
+    return Err(OpenApiImportError::NotOpenApi3(format!(
+      "unsupported version '{}'",
+      version
+    )));
+  }
+
+  let mut importer = Importer::new(spec_projection.endpoint(), document, id_generator);
+  if let Some(paths) = document.get("paths").and_then(JsonValue::as_object) {
+    for (path_template, path_item) in paths {
+      let pointer = child_pointer("/paths", path_template);
+      importer.import_path(path_template, path_item, pointer);
+    }
+  }
+
+  Ok(OpenApiImport {
+    commands: importer.commands,
+    unsupported: importer.unsupported,
+  })
+}
+
+struct PathParameter {
+  name: String,
+  path_id: PathComponentId,
+  is_new: bool,
+}
+
+struct Importer<'a, G> {
+  endpoint_projection: &'a EndpointProjection,
+  endpoint_queries: EndpointQueries<'a>,
+  document: &'a JsonValue,
+  ids: &'a mut G,
+  commands: Vec<SpecCommand>,
+  unsupported: Vec<UnsupportedConstruct>,
+  path_ids: HashMap<(PathComponentId, Option<String>), PathComponentId>,
+  shaped_path_ids: HashSet<PathComponentId>,
+  component_shape_ids: HashMap<String, ShapeId>,
+}
+
+impl<'a, G> Importer<'a, G>
+where
+  G: SpecIdGenerator,
+{
+  fn new(
+    endpoint_projection: &'a EndpointProjection,
+    document: &'a JsonValue,
+    ids: &'a mut G,
+  ) -> Self {
+    Self {
+      endpoint_projection,
+      endpoint_queries: EndpointQueries::new(endpoint_projection),
+      document,
+      ids,
+      commands: vec![],
+      unsupported: vec![],
+      path_ids: HashMap::new(),
+      shaped_path_ids: HashSet::new(),
+      component_shape_ids: HashMap::new(),
+    }
+  }
+
+  fn report(&mut self, pointer: impl Into<String>, reason: impl Into<String>) {
+    self.unsupported.push(UnsupportedConstruct {
+      pointer: pointer.into(),
+      reason: reason.into(),
+    });
+  }
+
+  /// Follow local `$ref`s to the object they point to, along with the pointer to that object
+  fn resolve(&mut self, value: &'a JsonValue, pointer: String) -> Option<(&'a JsonValue, String)> {
+    let mut resolved = (value, pointer);
+    // bounded, so cyclic references between non-schema objects don't loop forever
+    for _ in 0..16 {
+      let reference = match resolved.0.get("$ref").and_then(JsonValue::as_str) {
+        Some(reference) => reference,
+        None => return Some(resolved),
+      };
+      let document = self.document;
+      match reference
+        .strip_prefix('#')
+        .and_then(|target_pointer| Some((document.pointer(target_pointer)?, target_pointer)))
+      {
+        Some((target, target_pointer)) => resolved = (target, String::from(target_pointer)),
+        None => {
+          self.report(
+            resolved.1,
+            format!(
+              "reference '{}' does not resolve within the document",
+              reference
+            ),
+          );
+          return None;
+        }
+      }
+    }
+
+    self.report(resolved.1, "references are nested too deeply");
+    None
+  }
+
+  // Paths
+  // -----
+
+  fn import_path(&mut self, path_template: &str, path_item: &'a JsonValue, pointer: String) {
+    let (path_item, pointer) = match self.resolve(path_item, pointer) {
+      Some(resolved) => resolved,
+      None => return,
+    };
+
+    let path = match self.ensure_path(path_template, &pointer) {
+      Some(path) => path,
+      None => return,
+    };
+    let (path_id, path_parameters) = path;
+    let path_item_parameters = self.resolve_parameters(
+      path_item.get("parameters"),
+      &child_pointer(&pointer, "parameters"),
+    );
+
+    for (key, value) in path_item.as_object().into_iter().flatten() {
+      let key_pointer = child_pointer(&pointer, key);
+      if HTTP_METHODS.contains(&key.as_str()) {
+        let operation_parameters = self.resolve_parameters(
+          value.get("parameters"),
+          &child_pointer(&key_pointer, "parameters"),
+        );
+        let parameters = merged_parameters(path_item_parameters.clone(), operation_parameters);
+        self.import_operation(
+          &path_id,
+          &path_parameters,
+          &key.to_uppercase(),
+          value,
+          parameters,
+          key_pointer,
+        );
+      } else if !["parameters", "summary", "description", "$ref"].contains(&key.as_str()) {
+        self.report(
+          key_pointer,
+          format!("path item field '{}' is not supported", key),
+        );
+      }
+    }
+  }
+
+  /// The parameter objects of a list of parameters, with their `$ref`s resolved
+  fn resolve_parameters(
+    &mut self,
+    parameters: Option<&'a JsonValue>,
+    pointer: &str,
+  ) -> Vec<(&'a JsonValue, String)> {
+    parameters
+      .and_then(JsonValue::as_array)
+      .into_iter()
+      .flatten()
+      .enumerate()
+      .filter_map(|(index, parameter)| {
+        self.resolve(parameter, child_pointer(pointer, &index.to_string()))
+      })
+      .collect()
+  }
+
+  /// Find or add the path components for a path template, returning the id of the last one
+  fn ensure_path(
+    &mut self,
+    path_template: &str,
+    pointer: &str,
+  ) -> Option<(PathComponentId, Vec<PathParameter>)> {
+    let mut path_id = String::from(ROOT_PATH_ID);
+    let mut path_parameters = vec![];
+
+    for segment in path_template.split('/').filter(|s| !s.is_empty()) {
+      let parameter_name = if segment.starts_with('{') && segment.ends_with('}') {
+        Some(String::from(&segment[1..segment.len() - 1]))
+      } else if segment.contains('{') || segment.contains('}') {
+        self.report(
+          pointer,
+          format!("path segment '{}' mixes parameters and text", segment),
+        );
+        return None;
+      } else {
+        None
+      };
+
+      // all parameters at a position are equivalent when matching paths, so reuse any
+      let key = (
+        path_id.clone(),
+        match parameter_name {
+          Some(_) => None,
+          None => Some(String::from(segment)),
+        },
+      );
+      let existing_path_id = self.path_ids.get(&key).cloned().or_else(|| {
+        self
+          .endpoint_projection
+          .get_child_path_component_nodes(&path_id)?
+          .find_map(|node| match node {
+            EndpointNode::PathComponent(child_id, descriptor)
+              if descriptor.is_parameter == parameter_name.is_some()
+                && (descriptor.is_parameter || descriptor.name == segment) =>
+            {
+              Some(child_id.clone())
+            }
+            _ => None,
+          })
+      });
+
+      let is_new = existing_path_id.is_none();
+      let child_path_id = match existing_path_id {
+        Some(existing_path_id) => existing_path_id,
+        None => {
+          let child_path_id = self.ids.path();
+          let command = match &parameter_name {
+            Some(name) => EndpointCommand::add_path_parameter(
+              child_path_id.clone(),
+              path_id.clone(),
+              name.clone(),
+            ),
+            None => EndpointCommand::add_path_component(
+              child_path_id.clone(),
+              path_id.clone(),
+              String::from(segment),
+            ),
+          };
+          self.commands.push(SpecCommand::from(command));
+          child_path_id
+        }
+      };
+      self.path_ids.insert(key, child_path_id.clone());
+
+      if let Some(name) = parameter_name {
+        path_parameters.push(PathParameter {
+          name,
+          path_id: child_path_id.clone(),
+          is_new,
+        });
+      }
+      path_id = child_path_id;
+    }
+
+    Some((path_id, path_parameters))
+  }
+
+  // Operations
+  // ----------
+
+  fn import_operation(
+    &mut self,
+    path_id: &PathComponentId,
+    path_parameters: &[PathParameter],
+    method: &str,
+    operation: &'a JsonValue,
+    parameters: Vec<(&'a JsonValue, String)>,
+    pointer: String,
+  ) {
+    let method = String::from(method);
+    let is_documented = self
+      .endpoint_queries
+      .resolve_requests(path_id, &method)
+      .map(|mut requests| requests.next().is_some())
+      .unwrap_or(false);
+    if is_documented {
+      self.report(pointer, "endpoint is already documented in the spec");
+      return;
+    }
+
+    let endpoint_id = format!("{}.{}", path_id, method);
+    for (key, contribution_key) in &[("summary", "purpose"), ("description", "description")] {
+      if let Some(text) = operation.get(*key).and_then(JsonValue::as_str) {
+        self.contribute(&endpoint_id, contribution_key, text);
+      }
+    }
+    for key in operation
+      .as_object()
+      .into_iter()
+      .flatten()
+      .map(|(key, _)| key)
+    {
+      if ["callbacks", "security", "servers"].contains(&key.as_str()) {
+        self.report(
+          child_pointer(&pointer, key),
+          format!("operation field '{}' is not supported", key),
+        );
+      }
+    }
+
+    let mut query_parameters = vec![];
+    let mut header_parameters = vec![];
+    for (parameter, parameter_pointer) in parameters {
+      let name = parameter.get("name").and_then(JsonValue::as_str);
+      let location = parameter.get("in").and_then(JsonValue::as_str);
+      let (name, location) = match (name, location) {
+        (Some(name), Some(location)) => (name, location),
+        _ => {
+          self.report(parameter_pointer, "parameter is missing 'name' or 'in'");
+          continue;
+        }
+      };
+      if parameter.get("schema").is_none() {
+        self.report(
+          parameter_pointer,
+          "parameters without a schema (but with content) are not supported",
+        );
+        continue;
+      }
+
+      match location {
+        "path" => {
+          let path_parameter = path_parameters
+            .iter()
+            .find(|path_parameter| path_parameter.name == name);
+          if let Some(path_parameter) = path_parameter {
+            if path_parameter.is_new && !self.shaped_path_ids.contains(&path_parameter.path_id) {
+              let path_parameter_id = path_parameter.path_id.clone();
+              let shape_id = self.schema_shape(
+                &parameter["schema"],
+                child_pointer(&parameter_pointer, "schema"),
+              );
+              self.commands.push(SpecCommand::from(
+                EndpointCommand::set_path_parameter_shape(path_parameter_id.clone(), shape_id),
+              ));
+              self.shaped_path_ids.insert(path_parameter_id);
+            }
+          }
+        }
+        "query" => query_parameters.push((name, parameter, parameter_pointer)),
+        "header" => header_parameters.push((name, parameter, parameter_pointer)),
+        _ => self.report(
+          parameter_pointer,
+          format!("parameters in '{}' are not supported", location),
+        ),
+      }
+    }
+
+    if !query_parameters.is_empty() {
+      let shape_id = self.ids.shape();
+      self
+        .commands
+        .push(SpecCommand::from(ShapeCommand::add_shape(
+          shape_id.clone(),
+          ShapeKind::ObjectKind,
+          String::from(""),
+        )));
+      for (name, parameter, parameter_pointer) in query_parameters {
+        self.add_field(
+          &shape_id,
+          name,
+          &parameter["schema"],
+          is_required(parameter),
+          parameter.get("description"),
+          child_pointer(&parameter_pointer, "schema"),
+        );
+      }
+
+      let query_parameters_id = self.ids.query_params();
+      self.commands.extend(
+        vec![
+          EndpointCommand::add_query_parameters(
+            query_parameters_id.clone(),
+            path_id.clone(),
+            method.clone(),
+          ),
+          EndpointCommand::set_query_parameters_shape(query_parameters_id, shape_id, false),
+        ]
+        .into_iter()
+        .map(SpecCommand::from),
+      );
+    }
+
+    let header_shapes = header_parameters
+      .into_iter()
+      .map(|(name, parameter, parameter_pointer)| {
+        let shape_id = self.parameter_shape(
+          &parameter["schema"],
+          is_required(parameter),
+          child_pointer(&parameter_pointer, "schema"),
+        );
+        (String::from(name), shape_id)
+      })
+      .collect::<Vec<_>>();
+
+    let request_ids = self.import_request_body(
+      path_id,
+      &method,
+      operation.get("requestBody"),
+      child_pointer(&pointer, "requestBody"),
+    );
+    for request_id in request_ids {
+      for (name, shape_id) in &header_shapes {
+        let parameter_id = self.ids.header_param();
+        self.commands.extend(
+          vec![
+            EndpointCommand::add_header_parameter(
+              parameter_id.clone(),
+              request_id.clone(),
+              name.clone(),
+            ),
+            EndpointCommand::set_header_parameter_shape(parameter_id, shape_id.clone()),
+          ]
+          .into_iter()
+          .map(SpecCommand::from),
+        );
+      }
+    }
+
+    let responses_pointer = child_pointer(&pointer, "responses");
+    for (status_code, response) in operation
+      .get("responses")
+      .and_then(JsonValue::as_object)
+      .into_iter()
+      .flatten()
+    {
+      let response_pointer = child_pointer(&responses_pointer, status_code);
+      match status_code.parse::<HttpStatusSpecifier>() {
+        Ok(HttpStatusSpecifier::Code(code)) if !(100..600).contains(&code) => self.report(
+          response_pointer,
+          format!("response status code '{}' is not supported", status_code),
+        ),
+        Ok(http_status) => {
+          self.import_response(path_id, &method, http_status, response, response_pointer)
+        }
+        _ => self.report(
+          response_pointer,
+          format!("response status code '{}' is not supported", status_code),
+        ),
+      }
+    }
+  }
+
+  fn import_request_body(
+    &mut self,
+    path_id: &PathComponentId,
+    method: &str,
+    request_body: Option<&'a JsonValue>,
+    pointer: String,
+  ) -> Vec<RequestId> {
+    let content = request_body
+      .and_then(|request_body| self.resolve(request_body, pointer.clone()))
+      .and_then(|(request_body, pointer)| {
+        let content = request_body.get("content")?.as_object()?;
+        Some((content, child_pointer(&pointer, "content")))
+      });
+
+    let bodies = match content {
+      Some((content, content_pointer)) => self.media_type_shapes(content, &content_pointer),
+      None => vec![],
+    };
+    let bodies = if bodies.is_empty() {
+      vec![None]
+    } else {
+      bodies.into_iter().map(Some).collect()
+    };
+
+    bodies
+      .into_iter()
+      .map(|body| {
+        let request_id = self.ids.request();
+        self
+          .commands
+          .push(SpecCommand::from(EndpointCommand::add_request(
+            request_id.clone(),
+            path_id.clone(),
+            String::from(method),
+          )));
+        if let Some((content_type, shape_id)) = body {
+          self
+            .commands
+            .push(SpecCommand::from(EndpointCommand::set_request_body_shape(
+              request_id.clone(),
+              shape_id,
+              content_type,
+              false,
+            )));
+        }
+        request_id
+      })
+      .collect()
+  }
+
+  fn import_response(
+    &mut self,
+    path_id: &PathComponentId,
+    method: &str,
+    http_status: HttpStatusSpecifier,
+    response: &'a JsonValue,
+    pointer: String,
+  ) {
+    let (response, pointer) = match self.resolve(response, pointer) {
+      Some(resolved) => resolved,
+      None => return,
+    };
+    if response.get("links").is_some() {
+      self.report(
+        child_pointer(&pointer, "links"),
+        "response links are not supported",
+      );
+    }
+
+    let content_pointer = child_pointer(&pointer, "content");
+    let bodies = match response.get("content").and_then(JsonValue::as_object) {
+      Some(content) => self.media_type_shapes(content, &content_pointer),
+      None => vec![],
+    };
+    let bodies = if bodies.is_empty() {
+      vec![None]
+    } else {
+      bodies.into_iter().map(Some).collect()
+    };
+
+    let headers_pointer = child_pointer(&pointer, "headers");
+    let mut header_shapes = vec![];
+    for (name, header) in response
+      .get("headers")
+      .and_then(JsonValue::as_object)
+      .into_iter()
+      .flatten()
+    {
+      let header_pointer = child_pointer(&headers_pointer, name);
+      let (header, header_pointer) = match self.resolve(header, header_pointer) {
+        Some(resolved) => resolved,
+        None => continue,
+      };
+      match header.get("schema") {
+        Some(schema) => {
+          let shape_id = self.parameter_shape(
+            schema,
+            is_required(header),
+            child_pointer(&header_pointer, "schema"),
+          );
+          header_shapes.push((name.clone(), shape_id));
+        }
+        None => self.report(
+          header_pointer,
+          "headers without a schema (but with content) are not supported",
+        ),
+      }
+    }
+
+    let description = response
+      .get("description")
+      .and_then(JsonValue::as_str)
+      .filter(|description| !description.is_empty());
+    for body in bodies {
+      let response_id: ResponseId = self.ids.response();
+      let http_method = String::from(method);
+      let add_response = match http_status {
+        HttpStatusSpecifier::Code(status_code) => EndpointCommand::add_response_by_path_and_method(
+          response_id.clone(),
+          path_id.clone(),
+          http_method,
+          status_code,
+        ),
+        HttpStatusSpecifier::Range(status_range) => {
+          EndpointCommand::add_response_range_by_path_and_method(
+            response_id.clone(),
+            path_id.clone(),
+            http_method,
+            status_range,
+          )
+        }
+        HttpStatusSpecifier::Default => EndpointCommand::add_default_response_by_path_and_method(
+          response_id.clone(),
+          path_id.clone(),
+          http_method,
+        ),
+      };
+      self.commands.push(SpecCommand::from(add_response));
+      if let Some((content_type, shape_id)) = body {
+        self
+          .commands
+          .push(SpecCommand::from(EndpointCommand::set_response_body_shape(
+            response_id.clone(),
+            shape_id,
+            content_type,
+            false,
+          )));
+      }
+      for (name, shape_id) in &header_shapes {
+        let parameter_id = self.ids.header_param();
+        self.commands.extend(
+          vec![
+            EndpointCommand::add_response_header_parameter(
+              parameter_id.clone(),
+              response_id.clone(),
+              name.clone(),
+            ),
+            EndpointCommand::set_header_parameter_shape(parameter_id, shape_id.clone()),
+          ]
+          .into_iter()
+          .map(SpecCommand::from),
+        );
+      }
+      if let Some(description) = description {
+        self.contribute(&response_id, "description", description);
+      }
+    }
+  }
+
+  fn media_type_shapes(
+    &mut self,
+    content: &'a Map<String, JsonValue>,
+    pointer: &str,
+  ) -> Vec<(String, ShapeId)> {
+    content
+      .iter()
+      .map(|(content_type, media_type)| {
+        let media_type_pointer = child_pointer(pointer, content_type);
+        if media_type.get("encoding").is_some() {
+          self.report(
+            child_pointer(&media_type_pointer, "encoding"),
+            "media type encodings are not supported",
+          );
+        }
+        let shape_id = match media_type.get("schema") {
+          Some(schema) => self.schema_shape(schema, child_pointer(&media_type_pointer, "schema")),
+          None => self.add_shape(ShapeKind::UnknownKind),
+        };
+        (content_type.clone(), shape_id)
+      })
+      .collect()
+  }
+
+  fn contribute(&mut self, id: &str, key: &str, value: &str) {
+    self
+      .commands
+      .push(SpecCommand::from(RfcCommand::add_contribution(
+        String::from(id),
+        String::from(key),
+        String::from(value),
+      )));
+  }
+
+  // Shapes
+  // ------
+
+  fn add_shape(&mut self, shape_kind: ShapeKind) -> ShapeId {
+    let shape_id = self.ids.shape();
+    self
+      .commands
+      .push(SpecCommand::from(ShapeCommand::add_shape(
+        shape_id.clone(),
+        shape_kind,
+        String::from(""),
+      )));
+    shape_id
+  }
+
+  /// Add a shape wrapping an inner shape, like optionals and nullables
+  fn add_wrapper_shape(
+    &mut self,
+    shape_id: ShapeId,
+    shape_kind: ShapeKind,
+    name: &str,
+    inner_shape_id: ShapeId,
+  ) {
+    let parameter_id = shape_kind
+      .get_parameter_descriptor()
+      .expect("wrapper shapes should have a parameter descriptor")
+      .shape_parameter_id;
+    self.commands.extend(
+      vec![
+        ShapeCommand::add_shape(shape_id.clone(), shape_kind, String::from(name)),
+        ShapeCommand::set_parameter_shape(shape_id, String::from(parameter_id), inner_shape_id),
+      ]
+      .into_iter()
+      .map(SpecCommand::from),
+    );
+  }
+
+  fn parameter_shape(
+    &mut self,
+    schema: &'a JsonValue,
+    is_required: bool,
+    pointer: String,
+  ) -> ShapeId {
+    let shape_id = self.schema_shape(schema, pointer);
+    if is_required {
+      shape_id
+    } else {
+      let optional_shape_id = self.ids.shape();
+      self.add_wrapper_shape(
+        optional_shape_id.clone(),
+        ShapeKind::OptionalKind,
+        "",
+        shape_id,
+      );
+      optional_shape_id
+    }
+  }
+
+  fn add_field(
+    &mut self,
+    object_shape_id: &ShapeId,
+    name: &str,
+    schema: &'a JsonValue,
+    is_required: bool,
+    description: Option<&JsonValue>,
+    pointer: String,
+  ) {
+    let field_shape_id = self.parameter_shape(schema, is_required, pointer);
+    let field_id = self.ids.field();
+    self
+      .commands
+      .push(SpecCommand::from(ShapeCommand::add_field(
+        String::from(name),
+        field_id.clone(),
+        object_shape_id.clone(),
+        field_shape_id,
+      )));
+    if let Some(description) = description.and_then(JsonValue::as_str) {
+      self.contribute(&field_id, "description", description);
+    }
+  }
+
+  fn schema_shape(&mut self, schema: &'a JsonValue, pointer: String) -> ShapeId {
+    let shape_id = self.ids.shape();
+    self.add_schema_shape(shape_id.clone(), schema, "", pointer);
+    shape_id
+  }
+
+  /// Shape for a component schema, shared by every reference to it
+  fn component_shape(&mut self, reference: &str, pointer: &str) -> Option<ShapeId> {
+    if let Some(shape_id) = self.component_shape_ids.get(reference) {
+      return Some(shape_id.clone());
+    }
+
+    let document = self.document;
+    let target = reference
+      .strip_prefix('#')
+      .and_then(|target_pointer| document.pointer(target_pointer));
+    let target = match target {
+      Some(target) => target,
+      None => {
+        self.report(
+          pointer,
+          format!(
+            "reference '{}' does not resolve within the document",
+            reference
+          ),
+        );
+        return None;
+      }
+    };
+
+    let shape_id = self.ids.shape();
+    self
+      .component_shape_ids
+      .insert(String::from(reference), shape_id.clone());
+    let name = reference.rsplit('/').next().unwrap_or("");
+    self.add_schema_shape(
+      shape_id.clone(),
+      target,
+      name,
+      String::from(&reference[1..]),
+    );
+    Some(shape_id)
+  }
+
+  fn add_schema_shape(
+    &mut self,
+    shape_id: ShapeId,
+    schema: &'a JsonValue,
+    name: &str,
+    pointer: String,
+  ) {
+    let is_nullable = schema.get("nullable").and_then(JsonValue::as_bool) == Some(true)
+      || schema_types(schema).contains(&"null");
+
+    if is_nullable && schema.get("$ref").is_none() {
+      let inner_shape_id = self.ids.shape();
+      self.add_core_schema_shape(inner_shape_id.clone(), schema, "", pointer);
+      self.add_wrapper_shape(shape_id, ShapeKind::NullableKind, name, inner_shape_id);
+    } else {
+      self.add_core_schema_shape(shape_id, schema, name, pointer);
+    }
+  }
+
+  fn add_core_schema_shape(
+    &mut self,
+    shape_id: ShapeId,
+    schema: &'a JsonValue,
+    name: &str,
+    pointer: String,
+  ) {
+    let schema_object = match schema.as_object() {
+      Some(schema_object) => schema_object,
+      None => {
+        if schema.as_bool() != Some(true) {
+          self.report(
+            pointer.clone(),
+            "boolean schemas other than 'true' are not supported",
+          );
+        }
+        self.add_named_shape(shape_id, ShapeKind::UnknownKind, name);
+        return;
+      }
+    };
+
+    for keyword in schema_object.keys() {
+      if !SCHEMA_KEYWORDS.contains(&keyword.as_str())
+        && !ANNOTATION_KEYWORDS.contains(&keyword.as_str())
+      {
+        self.report(
+          child_pointer(&pointer, keyword),
+          format!("schema keyword '{}' is not supported", keyword),
+        );
+      }
+    }
+
+    if let Some(reference) = schema.get("$ref").and_then(JsonValue::as_str) {
+      match self.component_shape(reference, &pointer) {
+        Some(target_shape_id) => {
+          self.add_wrapper_shape(shape_id, ShapeKind::ReferenceKind, name, target_shape_id)
+        }
+        None => self.add_named_shape(shape_id, ShapeKind::UnknownKind, name),
+      }
+      return;
+    }
+
+    if let Some(all_of) = schema.get("allOf").and_then(JsonValue::as_array) {
+      if all_of.len() == 1 {
+        return self.add_core_schema_shape(
+          shape_id,
+          &all_of[0],
+          name,
+          child_pointer(&pointer, "allOf"),
+        );
+      }
+      self.report(
+        child_pointer(&pointer, "allOf"),
+        "allOf with more than one schema is not supported",
+      );
+      self.add_named_shape(shape_id, ShapeKind::UnknownKind, name);
+      return;
+    }
+
+    let one_of = ["oneOf", "anyOf"].iter().find_map(|keyword| {
+      let schemas = schema.get(*keyword)?.as_array()?;
+      Some((keyword, schemas))
+    });
+    if let Some((keyword, schemas)) = one_of {
+      let one_of_pointer = child_pointer(&pointer, keyword);
+      self.add_named_shape(shape_id.clone(), ShapeKind::OneOfKind, name);
+      for (index, branch_schema) in schemas.iter().enumerate() {
+        let branch_shape_id = self.schema_shape(
+          branch_schema,
+          child_pointer(&one_of_pointer, &index.to_string()),
+        );
+        let parameter_id = self.ids.shape_param();
+        self.commands.extend(
+          vec![
+            ShapeCommand::add_shape_parameter(
+              parameter_id.clone(),
+              shape_id.clone(),
+              String::from(""),
+            ),
+            ShapeCommand::set_parameter_shape(shape_id.clone(), parameter_id, branch_shape_id),
+          ]
+          .into_iter()
+          .map(SpecCommand::from),
+        );
+      }
+      return;
+    }
+
+    let types = schema_types(schema)
+      .into_iter()
+      .filter(|json_type| *json_type != "null")
+      .collect::<Vec<_>>();
+    let json_type = match types.len() {
+      0 if schema.get("properties").is_some() || schema.get("additionalProperties").is_some() => {
+        "object"
+      }
+      0 if schema.get("items").is_some() => "array",
+      0 => {
+        self.add_named_shape(shape_id, ShapeKind::UnknownKind, name);
+        return;
+      }
+      1 => types[0],
+      _ => {
+        self.report(
+          child_pointer(&pointer, "type"),
+          "multiple types are not supported, use oneOf instead",
+        );
+        self.add_named_shape(shape_id, ShapeKind::UnknownKind, name);
+        return;
+      }
+    };
+
+    match json_type {
+      "object" => self.add_object_schema_shape(shape_id, schema_object, name, pointer),
+      "array" => {
+        let item_shape_id = match schema.get("items") {
+          Some(items) => self.schema_shape(items, child_pointer(&pointer, "items")),
+          None => self.add_shape(ShapeKind::UnknownKind),
+        };
+        self.add_wrapper_shape(shape_id, ShapeKind::ListKind, name, item_shape_id);
+      }
+      "string" | "number" | "integer" | "boolean" => {
+        let shape_kind = match json_type {
+          "string" => ShapeKind::StringKind,
+          "boolean" => ShapeKind::BooleanKind,
+          _ => ShapeKind::NumberKind,
+        };
+        self.add_named_shape(shape_id.clone(), shape_kind.clone(), name);
+        for descriptor in self.schema_constraints(schema, json_type, &pointer) {
+          if descriptor.applies_to(&shape_kind) {
+            let shape_constraint_id = self.ids.shape_constraint();
+            self
+              .commands
+              .push(SpecCommand::from(ShapeCommand::add_shape_constraint(
+                shape_constraint_id,
+                shape_id.clone(),
+                descriptor,
+              )));
+          } else {
+            self.report(
+              pointer.clone(),
+              format!("constraints are not supported for '{}' schemas", json_type),
+            );
+          }
+        }
+      }
+      _ => {
+        self.report(
+          child_pointer(&pointer, "type"),
+          format!("type '{}' is not supported", json_type),
+        );
+        self.add_named_shape(shape_id, ShapeKind::UnknownKind, name);
+      }
+    }
+  }
+
+  fn add_object_schema_shape(
+    &mut self,
+    shape_id: ShapeId,
+    schema: &'a Map<String, JsonValue>,
+    name: &str,
+    pointer: String,
+  ) {
+    let properties = schema.get("properties").and_then(JsonValue::as_object);
+    let additional_properties = schema
+      .get("additionalProperties")
+      .filter(|additional_properties| additional_properties.as_bool() != Some(false));
+
+    match (properties, additional_properties) {
+      (None, Some(value_schema)) => {
+        let value_shape_id = self.schema_shape(
+          value_schema,
+          child_pointer(&pointer, "additionalProperties"),
+        );
+        self.add_wrapper_shape(shape_id, ShapeKind::MapKind, name, value_shape_id);
+      }
+      (properties, additional_properties) => {
+        if additional_properties.is_some() {
+          self.report(
+            child_pointer(&pointer, "additionalProperties"),
+            "additionalProperties next to properties is not supported",
+          );
+        }
+
+        self.add_named_shape(shape_id.clone(), ShapeKind::ObjectKind, name);
+        let required = schema
+          .get("required")
+          .and_then(JsonValue::as_array)
+          .into_iter()
+          .flatten()
+          .filter_map(JsonValue::as_str)
+          .collect::<HashSet<_>>();
+        let properties_pointer = child_pointer(&pointer, "properties");
+        for (key, property_schema) in properties.into_iter().flatten() {
+          self.add_field(
+            &shape_id,
+            key,
+            property_schema,
+            required.contains(key.as_str()),
+            property_schema.get("description"),
+            child_pointer(&properties_pointer, key),
+          );
+        }
+      }
+    }
+  }
+
+  fn add_named_shape(&mut self, shape_id: ShapeId, shape_kind: ShapeKind, name: &str) {
+    self
+      .commands
+      .push(SpecCommand::from(ShapeCommand::add_shape(
+        shape_id,
+        shape_kind,
+        String::from(name),
+      )));
+  }
+
+  fn schema_constraints(
+    &mut self,
+    schema: &JsonValue,
+    json_type: &str,
+    pointer: &str,
+  ) -> Vec<ShapeConstraintDescriptor> {
+    let mut constraints = vec![];
+
+    if json_type == "integer" {
+      constraints.push(ShapeConstraintDescriptor::Integer {});
+    }
+    if let Some(format) = schema.get("format").and_then(JsonValue::as_str) {
+      match serde_json::from_value::<StringFormat>(JsonValue::from(format)) {
+        Ok(format) => constraints.push(ShapeConstraintDescriptor::StringFormat { format }),
+        Err(_) if NUMBER_FORMATS.contains(&format) => {}
+        Err(_) => self.report(
+          child_pointer(pointer, "format"),
+          format!("format '{}' is not supported", format),
+        ),
+      }
+    }
+    if let Some(values) = schema.get("enum").and_then(JsonValue::as_array) {
+      constraints.push(ShapeConstraintDescriptor::EnumValues {
+        values: values
+          .iter()
+          .filter(|value| !value.is_null())
+          .cloned()
+          .collect(),
+      });
+    }
+    let minimum = schema.get("minimum").and_then(JsonValue::as_f64);
+    let maximum = schema.get("maximum").and_then(JsonValue::as_f64);
+    if minimum.is_some() || maximum.is_some() {
+      constraints.push(ShapeConstraintDescriptor::NumberRange { minimum, maximum });
+    }
+
+    constraints
+  }
+}
+
+fn is_required(parameter: &JsonValue) -> bool {
+  parameter.get("required").and_then(JsonValue::as_bool) == Some(true)
+}
+
+fn schema_types(schema: &JsonValue) -> Vec<&str> {
+  match schema.get("type") {
+    Some(JsonValue::String(json_type)) => vec![json_type.as_str()],
+    Some(JsonValue::Array(json_types)) => json_types.iter().filter_map(JsonValue::as_str).collect(),
+    _ => vec![],
+  }
+}
+
+/// Parameters of an operation, where those of the operation override those of its path item
+/// with the same name and location. Parameters are expected to be resolved already, so they're
+/// told apart by what they are rather than how they're referenced.
+fn merged_parameters<'a>(
+  path_item_parameters: Vec<(&'a JsonValue, String)>,
+  operation_parameters: Vec<(&'a JsonValue, String)>,
+) -> Vec<(&'a JsonValue, String)> {
+  let mut parameters: BTreeMap<(String, String), (&'a JsonValue, String)> = BTreeMap::new();
+  let mut unnamed = vec![];
+  for (parameter, pointer) in path_item_parameters.into_iter().chain(operation_parameters) {
+    let name = parameter.get("name").and_then(JsonValue::as_str);
+    let location = parameter.get("in").and_then(JsonValue::as_str);
+    match (name, location) {
+      (Some(name), Some(location)) => {
+        parameters.insert(
+          (String::from(location), String::from(name)),
+          (parameter, pointer),
+        );
+      }
+      _ => unnamed.push((parameter, pointer)),
+    }
+  }
+
+  parameters.into_values().chain(unnamed).collect()
+}
+
+fn child_pointer(pointer: &str, key: &str) -> String {
+  format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"))
+}
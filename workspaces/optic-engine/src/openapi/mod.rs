@@ -0,0 +1,5 @@
+mod export;
+mod import;
+
+pub use export::{export, OpenApiConfig, OpenApiVersion};
+pub use import::{import, parse_document, OpenApiImport, OpenApiImportError, UnsupportedConstruct};
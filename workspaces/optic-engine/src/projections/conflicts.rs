@@ -1,15 +1,18 @@
 use crate::events::{EndpointEvent, Event, ShapeEvent, SpecEvent};
 use crate::projections::endpoint::ROOT_PATH_ID;
 use crate::state::endpoint::{
-  HttpContentType, HttpMethod, HttpStatusCode, PathComponentId, RequestId, ResponseId,
+  HttpContentType, HttpMethod, HttpStatusCode, HttpStatusSpecifier, PathComponentId, RequestId,
+  ResponseId,
 };
+use crate::state::shape::{FieldId, ShapeId};
 use cqrs_core::{Aggregate, AggregateEvent};
 use petgraph::csr::NodeIndex;
 use petgraph::Direction::Incoming;
 use petgraph::Graph;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConflictsProjection {
   pub graph: Graph<Node, Edge>,
 
@@ -50,7 +53,20 @@ impl Aggregate for ConflictsProjection {
 }
 
 impl AggregateEvent<ConflictsProjection> for ShapeEvent {
-  fn apply_to(self, projection: &mut ConflictsProjection) {}
+  fn apply_to(self, projection: &mut ConflictsProjection) {
+    match self {
+      ShapeEvent::FieldAdded(e) => {
+        projection.with_field(e.field_id, e.shape_id, e.name);
+      }
+      ShapeEvent::FieldRenamed(e) => {
+        projection.with_field_name(e.field_id, e.name);
+      }
+      ShapeEvent::FieldRemoved(e) => {
+        projection.without_domain_id(&e.field_id);
+      }
+      _ => {}
+    }
+  }
 }
 
 impl AggregateEvent<ConflictsProjection> for EndpointEvent {
@@ -66,13 +82,73 @@ impl AggregateEvent<ConflictsProjection> for EndpointEvent {
         projection.with_request(e.request_id, e.path_id, e.http_method);
       }
       EndpointEvent::ResponseAddedByPathAndMethod(e) => {
-        projection.with_response(e.response_id, e.path_id, e.http_method, e.http_status_code);
+        projection.with_response(
+          e.response_id,
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Code(e.http_status_code),
+        );
+      }
+      EndpointEvent::ResponseRangeAddedByPathAndMethod(e) => {
+        projection.with_response(
+          e.response_id,
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Range(e.http_status_range),
+        );
+      }
+      EndpointEvent::DefaultResponseAddedByPathAndMethod(e) => {
+        projection.with_response(
+          e.response_id,
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Default,
+        );
+      }
+      EndpointEvent::PathComponentRemoved(e) => {
+        projection.without_domain_id(&e.path_id);
+      }
+      EndpointEvent::PathParameterRemoved(e) => {
+        projection.without_domain_id(&e.path_id);
+      }
+      EndpointEvent::RequestContentTypeSet(e) => {
+        projection.with_content_type(&e.request_id, Some(e.http_content_type));
       }
       EndpointEvent::RequestBodySet(e) => {
-        //@TODO: projection.with_request_body_content_type(...)
+        let body = e.body_descriptor;
+        let http_content_type = if body.is_removed {
+          None
+        } else {
+          Some(body.http_content_type)
+        };
+        projection.with_content_type(&e.request_id, http_content_type);
+      }
+      EndpointEvent::RequestBodyUnset(e) => {
+        projection.with_content_type(&e.request_id, None);
+      }
+      EndpointEvent::RequestRemoved(e) => {
+        projection.without_domain_id(&e.request_id);
+      }
+      EndpointEvent::ResponseStatusCodeSet(e) => {
+        projection.with_response_status_code(e.response_id, e.http_status_code);
+      }
+      EndpointEvent::ResponseContentTypeSet(e) => {
+        projection.with_content_type(&e.response_id, Some(e.http_content_type));
       }
       EndpointEvent::ResponseBodySet(e) => {
-        //@TODO: projection.with_response_body_content_type(...)
+        let body = e.body_descriptor;
+        let http_content_type = if body.is_removed {
+          None
+        } else {
+          Some(body.http_content_type)
+        };
+        projection.with_content_type(&e.response_id, http_content_type);
+      }
+      EndpointEvent::ResponseBodyUnset(e) => {
+        projection.with_content_type(&e.response_id, None);
+      }
+      EndpointEvent::ResponseRemoved(e) => {
+        projection.without_domain_id(&e.response_id);
       }
       _ => eprintln!(
         "Ignoring applying event of type '{}' for ConflictsProjection",
@@ -83,7 +159,13 @@ impl AggregateEvent<ConflictsProjection> for EndpointEvent {
 }
 
 impl AggregateEvent<ConflictsProjection> for SpecEvent {
-  fn apply_to(self, projection: &mut ConflictsProjection) {}
+  fn apply_to(self, projection: &mut ConflictsProjection) {
+    match self {
+      SpecEvent::EndpointEvent(event) => event.apply_to(projection),
+      SpecEvent::ShapeEvent(event) => event.apply_to(projection),
+      SpecEvent::RfcEvent(_) => {}
+    }
+  }
 }
 
 impl<I> From<I> for ConflictsProjection
@@ -264,7 +346,10 @@ impl ConflictsProjection {
       }
       _ => {}
     }
-    self.domain_id_to_index.insert(request_id, method_index);
+
+    // requests start out without a body
+    let content_type_index = self.with_http_content_type_node(method_index, None);
+    self.with_id(content_type_index, request_id);
   }
   ////////////////////////////////////////////////////////////////////////////////////////////////////
   pub fn with_response(
@@ -272,7 +357,7 @@ impl ConflictsProjection {
     response_id: ResponseId,
     path_id: PathComponentId,
     http_method: HttpMethod,
-    http_status_code: HttpStatusCode,
+    http_status: HttpStatusSpecifier,
   ) {
     let method_index = self.with_http_method_node(&path_id, &http_method);
 
@@ -285,8 +370,8 @@ impl ConflictsProjection {
           .node_weight(neighbor_node_index)
           .expect("expected node to exist at index");
         match neighbor {
-          Node::HttpStatusCode(status_code, ids) => {
-            if *status_code == http_status_code {
+          Node::HttpStatus(status, ids) => {
+            if *status == http_status {
               Some(neighbor_node_index)
             } else {
               None
@@ -303,13 +388,13 @@ impl ConflictsProjection {
           .node_weight_mut(x)
           .expect("expected node to exist");
         match node {
-          Node::HttpStatusCode(_, ids) => ids.push(response_id.clone()),
+          Node::HttpStatus(_, ids) => ids.push(response_id.clone()),
           _ => {}
         }
         x
       }
       None => {
-        let status_code_node = Node::HttpStatusCode(http_status_code, vec![response_id.clone()]);
+        let status_code_node = Node::HttpStatus(http_status, vec![response_id.clone()]);
         let status_code_node_index = self.graph.add_node(status_code_node);
         self
           .graph
@@ -318,9 +403,193 @@ impl ConflictsProjection {
       }
     };
 
-    self
+    // responses start out without a body
+    let content_type_index = self.with_http_content_type_node(status_code_index, None);
+    self.with_id(content_type_index, response_id);
+  }
+
+  pub fn with_response_status_code(
+    &mut self,
+    response_id: ResponseId,
+    http_status_code: HttpStatusCode,
+  ) {
+    let content_type_index = *self
+      .domain_id_to_index
+      .get(&response_id)
+      .expect("expected response_id to exist in the graph");
+    let http_content_type = match self.graph.node_weight(content_type_index) {
+      Some(Node::HttpContentType(http_content_type, _)) => http_content_type.clone(),
+      _ => panic!("expected response_id to be tracked by a content type node"),
+    };
+    let status_code_index = self
+      .get_parent_node_index(content_type_index)
+      .expect("expected content type node to be a child of a status code node");
+    let method_index = self
+      .get_parent_node_index(status_code_index)
+      .expect("expected status code node to be a child of a method node");
+
+    self.without_domain_id(&response_id);
+
+    let http_status = HttpStatusSpecifier::Code(http_status_code);
+    let status_code_index = self.with_child_node(
+      method_index,
+      |node| matches!(node, Node::HttpStatus(existing, _) if *existing == http_status),
+      || Node::HttpStatus(http_status, vec![]),
+    );
+    self.with_ids_push(status_code_index, response_id.clone());
+    let content_type_index = self.with_http_content_type_node(status_code_index, http_content_type);
+    self.with_id(content_type_index, response_id);
+  }
+
+  ////////////////////////////////////////////////////////////////////////////////////////////////////
+  // requests and responses conflict when they share a content type, or both don't have a body
+
+  pub fn with_http_content_type_node(
+    &mut self,
+    parent_node_index: petgraph::graph::NodeIndex,
+    http_content_type: Option<HttpContentType>,
+  ) -> petgraph::graph::NodeIndex {
+    self.with_child_node(
+      parent_node_index,
+      |node| matches!(node, Node::HttpContentType(existing, _) if *existing == http_content_type),
+      || Node::HttpContentType(http_content_type.clone(), vec![]),
+    )
+  }
+
+  pub fn with_content_type(
+    &mut self,
+    request_or_response_id: &str,
+    http_content_type: Option<HttpContentType>,
+  ) {
+    let content_type_index = *self
+      .domain_id_to_index
+      .get(request_or_response_id)
+      .expect("expected request or response id to exist in the graph");
+    let parent_node_index = self
+      .get_parent_node_index(content_type_index)
+      .expect("expected content type node to be a child of a method or status code node");
+
+    self.without_id(content_type_index, request_or_response_id);
+    let content_type_index = self.with_http_content_type_node(parent_node_index, http_content_type);
+    self.with_id(content_type_index, String::from(request_or_response_id));
+  }
+
+  ////////////////////////////////////////////////////////////////////////////////////////////////////
+  // fields conflict when they share a name within the same object
+
+  pub fn with_field(&mut self, field_id: FieldId, shape_id: ShapeId, field_name: String) {
+    let shape_index = match self.domain_id_to_index.get(&shape_id) {
+      Some(shape_index) => *shape_index,
+      None => {
+        let shape_index = self.graph.add_node(Node::Shape(shape_id.clone()));
+        self.domain_id_to_index.insert(shape_id, shape_index);
+        shape_index
+      }
+    };
+
+    let field_index = self.with_child_node(
+      shape_index,
+      |node| matches!(node, Node::FieldName(existing, _) if *existing == field_name),
+      || Node::FieldName(field_name.clone(), vec![]),
+    );
+    self.with_id(field_index, field_id);
+  }
+
+  pub fn with_field_name(&mut self, field_id: FieldId, field_name: String) {
+    let field_index = *self
       .domain_id_to_index
-      .insert(response_id, status_code_index);
+      .get(&field_id)
+      .expect("expected field_id to exist in the graph");
+    let shape_id = match self
+      .get_parent_node_index(field_index)
+      .and_then(|shape_index| self.graph.node_weight(shape_index))
+    {
+      Some(Node::Shape(shape_id)) => shape_id.clone(),
+      _ => panic!("expected field name node to be a child of a shape node"),
+    };
+
+    self.without_domain_id(&field_id);
+    self.with_field(field_id, shape_id, field_name);
+  }
+
+  ////////////////////////////////////////////////////////////////////////////////////////////////////
+
+  // GOTCHA: like elsewhere, nodes are never removed so node indices stay valid. Removing an id from
+  // its node is enough for it to no longer conflict.
+  pub fn without_domain_id(&mut self, domain_id: &str) {
+    let node_index = match self.domain_id_to_index.remove(domain_id) {
+      Some(node_index) => node_index,
+      None => return,
+    };
+    self.without_id(node_index, domain_id);
+
+    // requests and responses are also listed by their method or status code
+    if let Some(Node::HttpContentType(_, _)) = self.graph.node_weight(node_index) {
+      if let Some(parent_node_index) = self.get_parent_node_index(node_index) {
+        self.without_id(parent_node_index, domain_id);
+      }
+    }
+  }
+
+  fn with_id(&mut self, node_index: petgraph::graph::NodeIndex, domain_id: String) {
+    self.with_ids_push(node_index, domain_id.clone());
+    self.domain_id_to_index.insert(domain_id, node_index);
+  }
+
+  fn with_ids_push(&mut self, node_index: petgraph::graph::NodeIndex, domain_id: String) {
+    if let Some(ids) = self
+      .graph
+      .node_weight_mut(node_index)
+      .and_then(Node::ids_mut)
+    {
+      ids.push(domain_id);
+    }
+  }
+
+  fn without_id(&mut self, node_index: petgraph::graph::NodeIndex, domain_id: &str) {
+    if let Some(ids) = self
+      .graph
+      .node_weight_mut(node_index)
+      .and_then(Node::ids_mut)
+    {
+      ids.retain(|id| id != domain_id);
+    }
+  }
+
+  fn with_child_node(
+    &mut self,
+    parent_node_index: petgraph::graph::NodeIndex,
+    is_child: impl Fn(&Node) -> bool,
+    new_child: impl FnOnce() -> Node,
+  ) -> petgraph::graph::NodeIndex {
+    let existing_child_index = self
+      .graph
+      .neighbors_directed(parent_node_index, petgraph::Incoming)
+      .find(|neighbor_node_index| {
+        self
+          .graph
+          .node_weight(*neighbor_node_index)
+          .map(&is_child)
+          .unwrap_or(false)
+      });
+
+    existing_child_index.unwrap_or_else(|| {
+      let child_index = self.graph.add_node(new_child());
+      self
+        .graph
+        .add_edge(child_index, parent_node_index, Edge::IsChildOf);
+      child_index
+    })
+  }
+
+  pub fn get_parent_node_index(
+    &self,
+    node_index: petgraph::graph::NodeIndex,
+  ) -> Option<petgraph::graph::NodeIndex> {
+    self
+      .graph
+      .neighbors_directed(node_index, petgraph::Outgoing)
+      .next()
   }
 }
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -328,15 +597,42 @@ impl ConflictsProjection {
 pub type AbsolutePathPattern = String;
 pub type ConflictingIds = Vec<String>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Node {
   Path(AbsolutePathPattern, ConflictingIds),
   HttpMethod(HttpMethod, ConflictingIds),
-  HttpStatusCode(HttpStatusCode, ConflictingIds),
-  HttpContentType(HttpContentType, ConflictingIds),
+  HttpStatus(HttpStatusSpecifier, ConflictingIds),
+  // requests and responses without a body don't have a content type
+  HttpContentType(Option<HttpContentType>, ConflictingIds),
+  Shape(ShapeId),
+  FieldName(String, ConflictingIds),
+}
+
+impl Node {
+  pub fn ids(&self) -> Option<&ConflictingIds> {
+    match self {
+      Node::Path(_, ids)
+      | Node::HttpMethod(_, ids)
+      | Node::HttpStatus(_, ids)
+      | Node::HttpContentType(_, ids)
+      | Node::FieldName(_, ids) => Some(ids),
+      Node::Shape(_) => None,
+    }
+  }
+
+  fn ids_mut(&mut self) -> Option<&mut ConflictingIds> {
+    match self {
+      Node::Path(_, ids)
+      | Node::HttpMethod(_, ids)
+      | Node::HttpStatus(_, ids)
+      | Node::HttpContentType(_, ids)
+      | Node::FieldName(_, ids) => Some(ids),
+      Node::Shape(_) => None,
+    }
+  }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Edge {
   IsChildOf,
 }
@@ -1,8 +1,9 @@
 use crate::RfcEvent;
 use cqrs_core::{Aggregate, AggregateEvent, Event};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContributionsProjection {
   pub ids_to_keys_to_values: BTreeMap<String, BTreeMap<String, String>>,
 }
@@ -19,6 +20,10 @@ impl ContributionsProjection {
       .or_insert_with(|| BTreeMap::new())
       .insert(contribution_key, value);
   }
+
+  pub fn get_contribution(&self, id: &str, contribution_key: &str) -> Option<&String> {
+    self.ids_to_keys_to_values.get(id)?.get(contribution_key)
+  }
 }
 
 impl Default for ContributionsProjection {
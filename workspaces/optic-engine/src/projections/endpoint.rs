@@ -8,54 +8,78 @@ use crate::{
 use cqrs_core::{Aggregate, AggregateCommand, AggregateEvent, Event};
 use petgraph::graph::{Graph, NodeIndex};
 use petgraph::visit::EdgeRef;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub const ROOT_PATH_ID: &str = "root";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PathComponentDescriptor {
   pub is_parameter: bool,
   pub name: String,
+  pub shape: Option<ShapedRequestParameterShapeDescriptor>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct BodyDescriptor {
   pub http_content_type: HttpContentType,
   pub root_shape_id: ShapeId,
+  #[serde(default)]
+  pub is_removed: bool,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct QueryParametersDescriptor {
   pub shape: Option<QueryParametersShapeDescriptor>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct RequestDescriptor {
   pub body: Option<BodyDescriptor>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct ResponseBodyDescriptor {
   pub body: Option<BodyDescriptor>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct HeaderParameterDescriptor {
+  pub name: String,
+  pub shape: Option<ShapedRequestParameterShapeDescriptor>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Node {
   HttpMethod(HttpMethod),
   HttpStatusCode(HttpStatusCode),
+  HttpStatusRange(HttpStatusRange),
+  HttpStatusDefault,
   PathComponent(PathComponentId, PathComponentDescriptor),
   QueryParameters(QueryParametersId, QueryParametersDescriptor),
   Request(RequestId, RequestDescriptor),
   Response(ResponseId, ResponseBodyDescriptor),
+  HeaderParameter(RequestParameterId, HeaderParameterDescriptor),
+}
+
+impl Node {
+  /// The status codes covered by responses below this node, when it's a status node
+  pub fn http_status_specifier(&self) -> Option<HttpStatusSpecifier> {
+    match self {
+      Node::HttpStatusCode(status_code) => Some(HttpStatusSpecifier::Code(*status_code)),
+      Node::HttpStatusRange(status_range) => Some(HttpStatusSpecifier::Range(*status_range)),
+      Node::HttpStatusDefault => Some(HttpStatusSpecifier::Default),
+      _ => None,
+    }
+  }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Edge {
   IsChildOf,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EndpointProjection {
   pub graph: Graph<Node, Edge>,
   // SAFETY: node indices are not stable upon removing of nodes from graph -> node indices might be referred to
@@ -94,6 +118,20 @@ impl EndpointProjection {
     self.with_path_component_node(parent_path_id, path_id, path_name, true);
   }
 
+  pub fn with_path_parameter_shape(
+    &mut self,
+    path_id: PathComponentId,
+    shape_descriptor: ShapedRequestParameterShapeDescriptor,
+  ) {
+    let node_index = match self.node_id_to_index.get(&path_id) {
+      Some(node_index) => *node_index,
+      None => return,
+    };
+    if let Some(Node::PathComponent(_, descriptor)) = self.graph.node_weight_mut(node_index) {
+      descriptor.shape = Some(shape_descriptor);
+    }
+  }
+
   pub fn without_path(&mut self, path_id: PathComponentId) {
     self.without_path_component(path_id);
   }
@@ -111,7 +149,11 @@ impl EndpointProjection {
   ) {
     let node = Node::PathComponent(
       path_id.clone(),
-      PathComponentDescriptor { is_parameter, name },
+      PathComponentDescriptor {
+        is_parameter,
+        name,
+        shape: None,
+      },
     );
     let node_index = self.graph.add_node(node);
     self.node_id_to_index.insert(path_id, node_index);
@@ -307,7 +349,7 @@ impl EndpointProjection {
   fn ensure_status_code_node(
     &mut self,
     method_node_index: petgraph::graph::NodeIndex,
-    http_status_code: HttpStatusCode,
+    http_status: HttpStatusSpecifier,
   ) -> petgraph::graph::NodeIndex {
     let mut children = self
       .graph
@@ -315,13 +357,14 @@ impl EndpointProjection {
     // ensure status_code node
     let status_code_node_index_option = children.find(|node_index| {
       let node = self.graph.node_weight(*node_index).unwrap();
-      match node {
-        Node::HttpStatusCode(status_code) => *status_code == http_status_code,
-        _ => false,
-      }
+      node.http_status_specifier() == Some(http_status)
     });
     let status_code_node_index = if let None = status_code_node_index_option {
-      let status_code_node = Node::HttpStatusCode(http_status_code);
+      let status_code_node = match http_status {
+        HttpStatusSpecifier::Code(status_code) => Node::HttpStatusCode(status_code),
+        HttpStatusSpecifier::Range(status_range) => Node::HttpStatusRange(status_range),
+        HttpStatusSpecifier::Default => Node::HttpStatusDefault,
+      };
       let status_code_node_index = self.graph.add_node(status_code_node);
       self
         .graph
@@ -338,6 +381,7 @@ impl EndpointProjection {
     request_id: RequestId,
     http_content_type: HttpContentType,
     shape_id: ShapeId,
+    is_removed: bool,
   ) {
     let request_node_index = self
       .node_id_to_index
@@ -349,6 +393,7 @@ impl EndpointProjection {
         body_descriptor.body = Some(BodyDescriptor {
           http_content_type,
           root_shape_id: shape_id,
+          is_removed,
         })
       }
       _ => {}
@@ -360,6 +405,7 @@ impl EndpointProjection {
     response_id: ResponseId,
     http_content_type: HttpContentType,
     shape_id: ShapeId,
+    is_removed: bool,
   ) {
     let response_node_index = self
       .node_id_to_index
@@ -371,17 +417,38 @@ impl EndpointProjection {
         body_descriptor.body = Some(BodyDescriptor {
           http_content_type,
           root_shape_id: shape_id,
+          is_removed,
         });
       }
       _ => {}
     }
   }
 
+  pub fn without_request_body(&mut self, request_id: RequestId) {
+    let request_node_index = self
+      .node_id_to_index
+      .get(&request_id)
+      .expect("expected request_id to have a corresponding node");
+    if let Some(Node::Request(_, descriptor)) = self.graph.node_weight_mut(*request_node_index) {
+      descriptor.body = None;
+    }
+  }
+
+  pub fn without_response_body(&mut self, response_id: ResponseId) {
+    let response_node_index = self
+      .node_id_to_index
+      .get(&response_id)
+      .expect("expected response_id to have a corresponding node");
+    if let Some(Node::Response(_, descriptor)) = self.graph.node_weight_mut(*response_node_index) {
+      descriptor.body = None;
+    }
+  }
+
   pub fn with_response(
     &mut self,
     path_id: PathComponentId,
     http_method: HttpMethod,
-    http_status_code: HttpStatusCode,
+    http_status: HttpStatusSpecifier,
     response_id: ResponseId,
   ) {
     let path_node_index = *self
@@ -389,7 +456,7 @@ impl EndpointProjection {
       .get(&path_id)
       .expect("expected path_id to have a corresponding node");
     let method_node_index = self.ensure_method_node(path_node_index, http_method);
-    let status_code_node_index = self.ensure_status_code_node(method_node_index, http_status_code);
+    let status_code_node_index = self.ensure_status_code_node(method_node_index, http_status);
 
     let response_node = Node::Response(response_id.clone(), ResponseBodyDescriptor { body: None });
     let response_node_index = self.graph.add_node(response_node);
@@ -415,7 +482,7 @@ impl EndpointProjection {
         let _status_code_node = self
           .graph
           .node_weight(status_code_node_index)
-          .filter(|node| matches!(node, Node::HttpStatusCode(_)))?;
+          .filter(|node| node.http_status_specifier().is_some())?;
 
         Some(status_code_node_index)
       })
@@ -445,6 +512,138 @@ impl EndpointProjection {
     // eventual garbage collection.
   }
 
+  pub fn with_request_header_parameter(
+    &mut self,
+    request_id: RequestId,
+    parameter_id: RequestParameterId,
+    name: String,
+  ) {
+    self.with_header_parameter_node(request_id, parameter_id, name);
+  }
+
+  pub fn with_response_header_parameter(
+    &mut self,
+    response_id: ResponseId,
+    parameter_id: RequestParameterId,
+    name: String,
+  ) {
+    self.with_header_parameter_node(response_id, parameter_id, name);
+  }
+
+  fn with_header_parameter_node(
+    &mut self,
+    parent_id: String,
+    parameter_id: RequestParameterId,
+    name: String,
+  ) {
+    let parent_node_index = *self
+      .node_id_to_index
+      .get(&parent_id)
+      .expect("expected parent_id of header parameter to have a corresponding node");
+    let header_node = Node::HeaderParameter(
+      parameter_id.clone(),
+      HeaderParameterDescriptor { name, shape: None },
+    );
+    let header_node_index = self.graph.add_node(header_node);
+    self
+      .graph
+      .add_edge(header_node_index, parent_node_index, Edge::IsChildOf);
+    self
+      .node_id_to_index
+      .insert(parameter_id, header_node_index);
+  }
+
+  pub fn with_header_parameter_name(&mut self, parameter_id: RequestParameterId, name: String) {
+    if let Some(descriptor) = self.get_header_parameter_descriptor_mut(&parameter_id) {
+      descriptor.name = name;
+    }
+  }
+
+  pub fn with_header_parameter_shape(
+    &mut self,
+    parameter_id: RequestParameterId,
+    shape_descriptor: ShapedRequestParameterShapeDescriptor,
+  ) {
+    if let Some(descriptor) = self.get_header_parameter_descriptor_mut(&parameter_id) {
+      descriptor.shape = Some(shape_descriptor);
+    }
+  }
+
+  pub fn without_header_parameter_shape(&mut self, parameter_id: RequestParameterId) {
+    if let Some(descriptor) = self.get_header_parameter_descriptor_mut(&parameter_id) {
+      descriptor.shape = None;
+    }
+  }
+
+  pub fn without_header_parameter(&mut self, parameter_id: RequestParameterId) {
+    let header_node_index = *self
+      .node_id_to_index
+      .get(&parameter_id)
+      .expect("expected parameter_id to have a corresponding node");
+
+    let parent_edge_index = self
+      .graph
+      .edges_directed(header_node_index, petgraph::Direction::Outgoing)
+      .next()
+      .map(|parent_edge| parent_edge.id());
+
+    if let Some(parent_edge_index) = parent_edge_index {
+      self.graph.remove_edge(parent_edge_index); // prevents header to be resolved from request or response
+    }
+    self.node_id_to_index.remove(&parameter_id); // prevents header node to be looked up by parameter id
+
+    // GOTCHA: we're not deleting the header node itself, as that would invalidate self.node_id_to_index
+    // as the graph indexes shift.
+  }
+
+  fn get_header_parameter_descriptor_mut(
+    &mut self,
+    parameter_id: &RequestParameterId,
+  ) -> Option<&mut HeaderParameterDescriptor> {
+    let node_index = *self.node_id_to_index.get(parameter_id)?;
+    match self.graph.node_weight_mut(node_index)? {
+      Node::HeaderParameter(_, descriptor) => Some(descriptor),
+      _ => None,
+    }
+  }
+
+  /// Shapes used by bodies, path parameters, query parameters and header parameters, unless they
+  /// have been removed
+  pub fn get_referenced_shape_ids(&self) -> impl Iterator<Item = &ShapeId> {
+    let graph = &self.graph;
+    self
+      .node_id_to_index
+      .values()
+      .filter_map(move |node_index| match graph.node_weight(*node_index)? {
+        Node::Request(_, descriptor) => descriptor
+          .body
+          .as_ref()
+          .filter(|body| !body.is_removed)
+          .map(|body| &body.root_shape_id),
+        Node::Response(_, descriptor) => descriptor
+          .body
+          .as_ref()
+          .filter(|body| !body.is_removed)
+          .map(|body| &body.root_shape_id),
+        Node::PathComponent(_, descriptor) => descriptor
+          .shape
+          .as_ref()
+          .filter(|shape| !shape.is_removed)
+          .map(|shape| &shape.shape_id),
+        Node::QueryParameters(_, descriptor) => descriptor
+          .shape
+          .as_ref()
+          .filter(|shape| !shape.is_removed)
+          .map(|shape| &shape.shape_id),
+        Node::HeaderParameter(_, descriptor) => descriptor
+          .shape
+          .as_ref()
+          .filter(|shape| !shape.is_removed)
+          .map(|shape| &shape.shape_id),
+        _ => None,
+      })
+  }
+
   pub fn get_path_component_node_index(
     &self,
     path_component_id: &PathComponentId,
@@ -504,6 +703,95 @@ impl EndpointProjection {
     }
   }
 
+  pub fn get_header_parameter_node_index(
+    &self,
+    parameter_id: &RequestParameterId,
+  ) -> Option<&NodeIndex> {
+    let node_index = self.node_id_to_index.get(parameter_id)?;
+    let node = self.graph.node_weight(*node_index)?;
+    if let &Node::HeaderParameter(_, _) = node {
+      Some(node_index)
+    } else {
+      None
+    }
+  }
+
+  pub fn get_header_parameter_descriptor(
+    &self,
+    parameter_id: &RequestParameterId,
+  ) -> Option<&HeaderParameterDescriptor> {
+    let node_index = self.get_header_parameter_node_index(parameter_id)?;
+    match self.graph.node_weight(*node_index)? {
+      Node::HeaderParameter(_, descriptor) => Some(descriptor),
+      _ => None,
+    }
+  }
+
+  /// The id of the request or response a header parameter is documented for
+  pub fn get_header_parameter_parent_id(
+    &self,
+    parameter_id: &RequestParameterId,
+  ) -> Option<&String> {
+    let node_index = self.get_header_parameter_node_index(parameter_id)?;
+    self
+      .graph
+      .neighbors_directed(*node_index, petgraph::Direction::Outgoing)
+      .find_map(|parent_index| match self.graph.node_weight(parent_index)? {
+        Node::Request(request_id, _) => Some(request_id),
+        Node::Response(response_id, _) => Some(response_id),
+        _ => None,
+      })
+  }
+
+  /// The exact status code of a response, `None` for responses documented for a range of status
+  /// codes or as the default response.
+  pub fn get_response_status_code(&self, response_id: &ResponseId) -> Option<HttpStatusCode> {
+    self
+      .get_response_status_specifier(response_id)?
+      .status_code()
+  }
+
+  pub fn get_response_status_specifier(
+    &self,
+    response_id: &ResponseId,
+  ) -> Option<HttpStatusSpecifier> {
+    let node_index = self.get_response_node_index(response_id)?;
+    self
+      .graph
+      .neighbors_directed(*node_index, petgraph::Direction::Outgoing)
+      .find_map(|parent_index| {
+        self
+          .graph
+          .node_weight(parent_index)?
+          .http_status_specifier()
+      })
+  }
+
+  /// Header parameters documented for either a request or a response
+  pub fn get_header_parameter_nodes<'a>(
+    &'a self,
+    parent_id: &str,
+  ) -> Option<impl Iterator<Item = (&'a RequestParameterId, &'a HeaderParameterDescriptor)> + 'a>
+  {
+    let parent_node_index = self.node_id_to_index.get(parent_id)?;
+    let parent_node = self.graph.node_weight(*parent_node_index)?;
+    if !matches!(parent_node, Node::Request(_, _) | Node::Response(_, _)) {
+      return None;
+    }
+
+    let header_nodes = self
+      .graph
+      .neighbors_directed(*parent_node_index, petgraph::Direction::Incoming)
+      .filter_map(
+        move |node_index| match self.graph.node_weight(node_index)? {
+          Node::HeaderParameter(parameter_id, descriptor) => Some((parameter_id, descriptor)),
+          _ => None,
+        },
+      );
+
+    Some(header_nodes)
+  }
+
   pub fn get_child_path_component_nodes<'a>(
     &'a self,
     path_id: &'a PathComponentId,
@@ -525,6 +813,26 @@ impl EndpointProjection {
     Some(child_path_components)
   }
 
+  /// Http methods for which anything was ever documented at a path
+  pub fn get_http_methods<'a>(
+    &'a self,
+    path_id: &'a PathComponentId,
+  ) -> Option<impl Iterator<Item = &'a HttpMethod> + 'a> {
+    let path_node_index = self.get_path_component_node_index(path_id)?;
+
+    let methods = self
+      .graph
+      .neighbors_directed(*path_node_index, petgraph::Direction::Incoming)
+      .filter_map(
+        move |node_index| match self.graph.node_weight(node_index)? {
+          Node::HttpMethod(http_method) => Some(http_method),
+          _ => None,
+        },
+      );
+
+    Some(methods)
+  }
+
   pub fn get_request_nodes<'a>(
     &'a self,
     path_id: &'a PathComponentId,
@@ -565,7 +873,7 @@ impl EndpointProjection {
   pub fn get_response_nodes<'a>(
     &'a self,
     path_id: &'a PathComponentId,
-  ) -> Option<impl Iterator<Item = (&'a HttpMethod, &'a HttpStatusCode, &'a Node)> + 'a> {
+  ) -> Option<impl Iterator<Item = (&'a HttpMethod, HttpStatusSpecifier, &'a Node)> + 'a> {
     let path_node_index = self.get_path_component_node_index(path_id)?;
 
     let children = self
@@ -589,11 +897,7 @@ impl EndpointProjection {
           let status_code = self
             .graph
             .node_weight(status_code_node_index)
-            .map(|node| match node {
-              Node::HttpStatusCode(status_code) => Some(status_code),
-              _ => None,
-            })
-            .flatten()
+            .and_then(|node| node.http_status_specifier())
             .unwrap();
           let response_nodes = self
             .graph
@@ -697,6 +1001,7 @@ impl Default for EndpointProjection {
       PathComponentDescriptor {
         is_parameter: false,
         name: String::from(""),
+        shape: None,
       },
     ));
     let mut node_id_to_index = HashMap::new();
@@ -744,6 +1049,9 @@ impl AggregateEvent<EndpointProjection> for EndpointEvent {
       EndpointEvent::PathParameterAdded(e) => {
         aggregate.with_path_parameter(e.parent_path_id, e.path_id, e.name);
       }
+      EndpointEvent::PathParameterShapeSet(e) => {
+        aggregate.with_path_parameter_shape(e.path_id, e.shape_descriptor);
+      }
       EndpointEvent::PathParameterRemoved(e) => {
         aggregate.without_path_parameter(e.path_id);
       }
@@ -763,7 +1071,28 @@ impl AggregateEvent<EndpointProjection> for EndpointEvent {
         aggregate.without_request(e.request_id);
       }
       EndpointEvent::ResponseAddedByPathAndMethod(e) => {
-        aggregate.with_response(e.path_id, e.http_method, e.http_status_code, e.response_id);
+        aggregate.with_response(
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Code(e.http_status_code),
+          e.response_id,
+        );
+      }
+      EndpointEvent::ResponseRangeAddedByPathAndMethod(e) => {
+        aggregate.with_response(
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Range(e.http_status_range),
+          e.response_id,
+        );
+      }
+      EndpointEvent::DefaultResponseAddedByPathAndMethod(e) => {
+        aggregate.with_response(
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Default,
+          e.response_id,
+        );
       }
       EndpointEvent::ResponseRemoved(e) => {
         aggregate.without_response(e.response_id);
@@ -773,15 +1102,41 @@ impl AggregateEvent<EndpointProjection> for EndpointEvent {
           e.request_id,
           e.body_descriptor.http_content_type,
           e.body_descriptor.shape_id,
+          e.body_descriptor.is_removed,
         );
       }
+      EndpointEvent::RequestBodyUnset(e) => {
+        aggregate.without_request_body(e.request_id);
+      }
       EndpointEvent::ResponseBodySet(e) => {
         aggregate.with_response_body(
           e.response_id,
           e.body_descriptor.http_content_type,
           e.body_descriptor.shape_id,
+          e.body_descriptor.is_removed,
         );
       }
+      EndpointEvent::ResponseBodyUnset(e) => {
+        aggregate.without_response_body(e.response_id);
+      }
+      EndpointEvent::HeaderParameterAdded(e) => {
+        aggregate.with_request_header_parameter(e.request_id, e.parameter_id, e.name);
+      }
+      EndpointEvent::ResponseHeaderParameterAdded(e) => {
+        aggregate.with_response_header_parameter(e.response_id, e.parameter_id, e.name);
+      }
+      EndpointEvent::HeaderParameterRenamed(e) => {
+        aggregate.with_header_parameter_name(e.parameter_id, e.name);
+      }
+      EndpointEvent::HeaderParameterShapeSet(e) => {
+        aggregate.with_header_parameter_shape(e.parameter_id, e.parameter_descriptor);
+      }
+      EndpointEvent::HeaderParameterShapeUnset(e) => {
+        aggregate.without_header_parameter_shape(e.parameter_id);
+      }
+      EndpointEvent::HeaderParameterRemoved(e) => {
+        aggregate.without_header_parameter(e.parameter_id);
+      }
       _ => eprintln!(
         "Ignoring applying event of type '{}' for EndpointProjection",
         self.event_type()
@@ -4,7 +4,8 @@ use petgraph::{
   graph::{Graph, NodeIndex},
   Directed,
 };
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 use crate::events::RfcEvent;
 
@@ -13,26 +14,26 @@ pub const ROOT_COMMIT_ID: &str = "root";
 pub type NodeId = String;
 pub type CommitId = String;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Node {
   BatchCommit(BatchCommitNode),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchCommitNode(CommitId, BatchCommitDescriptor);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchCommitDescriptor {
   commit_message: String,
   pub is_complete: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Edge {
   IsParentOf,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryProjection {
   pub graph: Graph<Node, Edge>,
   pub node_id_to_index: HashMap<NodeId, NodeIndex>,
@@ -66,6 +67,7 @@ impl HistoryProjection {
     &mut self,
     batch_id: CommitId,
     parent_batch_id: Option<CommitId>,
+    merged_parent_batch_ids: Vec<CommitId>,
     commit_message: String,
   ) {
     let parent_batch_id = parent_batch_id.unwrap_or_else(|| {
@@ -89,17 +91,19 @@ impl HistoryProjection {
     let node_index = self.graph.add_node(node);
     self.node_id_to_index.insert(batch_id.clone(), node_index);
 
-    let parent_node_index = *self
-      .node_id_to_index
-      .get(&parent_batch_id)
-      .expect(&*format!(
-        "expected parent commit to have a corresponding node {:?}",
-        parent_batch_id
-      ));
+    for parent_batch_id in std::iter::once(parent_batch_id).chain(merged_parent_batch_ids) {
+      let parent_node_index = *self
+        .node_id_to_index
+        .get(&parent_batch_id)
+        .expect(&*format!(
+          "expected parent commit to have a corresponding node {:?}",
+          parent_batch_id
+        ));
 
-    self
-      .graph
-      .add_edge(parent_node_index, node_index, Edge::IsParentOf);
+      self
+        .graph
+        .add_edge(parent_node_index, node_index, Edge::IsParentOf);
+    }
   }
 
   fn with_batch_commit_end(&mut self, batch_id: CommitId) {
@@ -184,30 +188,71 @@ impl HistoryProjection {
   //   Some(std::iter::empty())
   //
 
+  pub fn get_last_batch_commit_id(&self) -> Option<&CommitId> {
+    let node_index = self.find_last_batch_commit_index()?;
+    self.get_commit_id(&node_index)
+  }
+
+  /// The most recently started commit that no other commit builds on yet. With events applied
+  /// in the order their batches were assembled, that's the commit applied last.
   pub fn find_last_batch_commit_index(&self) -> Option<NodeIndex> {
-    let root_node_index = *self.get_batch_commit_node_index(&CommitId::from(ROOT_COMMIT_ID))?;
-    let commit_graph = visit::NodeFiltered::from_fn(&self.graph, |node_index| {
-      matches!(
-        self.graph.node_weight(node_index),
-        Some(Node::BatchCommit(_))
-      )
-    });
+    self.get_batch_commit_node_index(&CommitId::from(ROOT_COMMIT_ID))?;
 
-    // @GOTCHA: this assumes there's the commit graph is a commit log, with no branches
-    let mut leaf_node_index = Some(root_node_index);
-    visit::depth_first_search(&self.graph, Some(root_node_index), |event| {
-      if let visit::DfsEvent::TreeEdge(parent_node_index, child_node_index) = event {
-        leaf_node_index.replace(child_node_index);
-      }
+    // commits are never removed, so later commits always have higher node indexes
+    self.find_head_commit_indexes().max()
+  }
+
+  /// Commits that no other commit builds on, one for every branch of the history, in the order
+  /// they were started
+  pub fn get_head_commit_ids(&self) -> impl Iterator<Item = &CommitId> {
+    self
+      .find_head_commit_indexes()
+      .filter_map(move |node_index| self.get_commit_id(&node_index))
+  }
+
+  pub fn get_parent_commit_ids(&self, batch_id: &CommitId) -> Option<Vec<&CommitId>> {
+    let node_index = *self.get_batch_commit_node_index(batch_id)?;
+    let mut parent_indexes = self
+      .graph
+      .neighbors_directed(node_index, petgraph::Direction::Incoming)
+      .collect::<Vec<_>>();
+    parent_indexes.sort();
+
+    Some(
+      parent_indexes
+        .into_iter()
+        .filter_map(|parent_index| self.get_commit_id(&parent_index))
+        .collect(),
+    )
+  }
+
+  /// A commit and all the commits it builds on, through any of its parents
+  pub fn get_ancestor_commit_ids(&self, batch_id: &CommitId) -> Option<HashSet<&CommitId>> {
+    let node_index = *self.get_batch_commit_node_index(batch_id)?;
+    let parent_graph = visit::Reversed(&self.graph);
 
-      if let visit::DfsEvent::BackEdge(_, _) = event {
-        visit::Control::Break(())
-      } else {
-        visit::Control::Continue
+    let mut ancestor_ids = HashSet::new();
+    let mut dfs = visit::Dfs::new(parent_graph, node_index);
+    while let Some(ancestor_index) = dfs.next(parent_graph) {
+      if let Some(commit_id) = self.get_commit_id(&ancestor_index) {
+        ancestor_ids.insert(commit_id);
       }
-    });
+    }
+
+    Some(ancestor_ids)
+  }
 
-    leaf_node_index
+  fn find_head_commit_indexes(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+    self.graph.node_indices().filter(move |node_index| {
+      matches!(
+        self.graph.node_weight(*node_index),
+        Some(Node::BatchCommit(_))
+      ) && self
+        .graph
+        .neighbors_directed(*node_index, petgraph::Direction::Outgoing)
+        .next()
+        .is_none()
+    })
   }
 }
 
@@ -224,7 +269,12 @@ impl AggregateEvent<HistoryProjection> for RfcEvent {
   fn apply_to(self, projection: &mut HistoryProjection) {
     match self {
       RfcEvent::BatchCommitStarted(e) => {
-        projection.with_batch_commit_start(e.batch_id, e.parent_id, e.commit_message);
+        projection.with_batch_commit_start(
+          e.batch_id,
+          e.parent_id,
+          e.merged_parent_ids,
+          e.commit_message,
+        );
       }
       RfcEvent::BatchCommitEnded(e) => {
         projection.with_batch_commit_end(e.batch_id);
@@ -30,6 +30,13 @@ impl LearnedUndocumentedBodiesProjection {
   ) -> impl Iterator<Item = EndpointBodies> {
     let mut endpoints_by_endpoint = HashMap::new();
     for (body_location, observations) in self.observations_by_location {
+      // query parameter values are always strings, even if they consistently spell out another type
+      let observations = match &body_location {
+        BodyAnalysisLocation::UnmatchedQueryParameters { .. } => {
+          observations.with_coerced_strings()
+        }
+        _ => observations,
+      };
       let (root_shape_id, body_commands) =
         observations.into_commands(id_generator, &JsonTrail::empty());
       let mut endpoint_body = EndpointBody::new(&body_location, root_shape_id, body_commands);
@@ -331,10 +338,14 @@ impl EndpointBody {
 mod test {
   use super::*;
   use crate::events::SpecEvent;
+  use crate::interactions::DiffConfig;
   use crate::interactions::InteractionDiffResult;
   use crate::learn_shape::observe_body_trails;
   use crate::projections::SpecProjection;
-  use crate::state::body::BodyDescriptor;
+  use crate::queries::shape::ShapeQueries;
+  use crate::shapes::{diff as diff_body, diff_query_parameters};
+  use crate::state::body::{BodyDescriptor, ParsedMultipart, ParsedQueryString, ParsedXml};
+  use crate::state::shape::ShapeKind;
   use insta::assert_debug_snapshot;
   use serde_json::json;
 
@@ -431,6 +442,143 @@ mod test {
     assert_valid_commands(base_spec, query_param_commands);
   }
 
+  #[test]
+  fn undocumented_bodies_learns_numbers_and_booleans_from_query_parameter_strings() {
+    let query_strings = [
+      "page=1&done=true&q=milk&ids=1&ids=2",
+      "page=2&done=false&q=12&ids=3&ids=4",
+    ];
+    let query_params_body = |query_string: &str| {
+      let parsed = ParsedQueryString::from_str(query_string).expect("query string should parse");
+      BodyDescriptor::from(parsed)
+    };
+
+    let mut projection = LearnedUndocumentedBodiesProjection::default();
+    for query_string in query_strings.iter() {
+      projection.apply(BodyAnalysisResult {
+        body_location: BodyAnalysisLocation::UnmatchedQueryParameters {
+          path_id: String::from("root"),
+          method: String::from("GET"),
+        },
+        trail_observations: observe_body_trails(query_params_body(query_string)),
+      });
+    }
+
+    let mut test_id_generator = TestIdGenerator::default();
+    let query_parameters = projection
+      .into_endpoint_bodies(&mut test_id_generator)
+      .next()
+      .and_then(|endpoint_bodies| endpoint_bodies.query_parameters)
+      .expect("query parameters should have been learned");
+    let root_shape_id = query_parameters.root_shape_id.unwrap();
+    let spec_projection =
+      assert_valid_commands(SpecProjection::default(), query_parameters.commands);
+
+    let shape_queries = ShapeQueries::new(spec_projection.shape());
+    let field_kind = |field_name: &str| {
+      let field_id = shape_queries
+        .resolve_field_id(&root_shape_id, &String::from(field_name))
+        .expect("field should have been learned");
+      let field_shape_id = shape_queries.resolve_field_shape_node(&field_id).unwrap();
+      shape_queries.resolve_to_core_shape(&field_shape_id).clone()
+    };
+    assert!(matches!(field_kind("page"), ShapeKind::NumberKind));
+    assert!(matches!(field_kind("done"), ShapeKind::BooleanKind));
+    assert!(matches!(field_kind("q"), ShapeKind::StringKind));
+    assert!(matches!(field_kind("ids"), ShapeKind::ListKind));
+
+    let diff_query_string = |query_string: &str| {
+      diff_query_parameters(
+        spec_projection.shape(),
+        Some(query_params_body(query_string)),
+        &root_shape_id,
+        &DiffConfig::default(),
+      )
+    };
+    for query_string in query_strings.iter() {
+      assert!(diff_query_string(query_string).is_empty());
+    }
+    assert_eq!(
+      diff_query_string("page=1&done=true&q=milk&ids=first&ids=2").len(),
+      1,
+      "list items should have been learned as numbers"
+    );
+  }
+
+  #[test]
+  fn undocumented_bodies_learns_repeatable_xml_elements_and_multipart_parts_alike() {
+    let xml_body = |tags: &[&str]| {
+      let tags = tags
+        .iter()
+        .map(|tag| format!("<tag>{}</tag>", tag))
+        .collect::<String>();
+      let xml = format!("<todo><title>Buy milk</title>{}</todo>", tags);
+      BodyDescriptor::from(ParsedXml::from_str(&xml).expect("xml should parse"))
+    };
+    let multipart_body = |tags: &[&str]| {
+      let parts = tags
+        .iter()
+        .map(|tag| {
+          format!(
+            "--XyZ\r\nContent-Disposition: form-data; name=\"tag\"\r\n\r\n{}\r\n",
+            tag
+          )
+        })
+        .collect::<String>();
+      let multipart = format!("{}--XyZ--\r\n", parts);
+      BodyDescriptor::from(
+        ParsedMultipart::from_str(&multipart, "XyZ").expect("multipart should parse"),
+      )
+    };
+
+    let bodies: [&dyn Fn(&[&str]) -> BodyDescriptor; 2] = [&xml_body, &multipart_body];
+    for body in bodies.iter() {
+      let one_tag = body(&["groceries"]);
+      let two_tags = body(&["groceries", "errands"]);
+
+      // learned from documents with either one or two of them, repeated names describe the same
+      for learned_from in [&one_tag, &two_tags].iter() {
+        let mut projection = LearnedUndocumentedBodiesProjection::default();
+        projection.apply(BodyAnalysisResult {
+          body_location: BodyAnalysisLocation::UnmatchedResponse {
+            path_id: String::from("root"),
+            method: String::from("GET"),
+            content_type: Some(String::from("application/xml")),
+            status_code: 200,
+          },
+          trail_observations: observe_body_trails((*learned_from).clone()),
+        });
+
+        let mut test_id_generator = TestIdGenerator::default();
+        let response = projection
+          .into_endpoint_bodies(&mut test_id_generator)
+          .next()
+          .and_then(|mut endpoint_bodies| endpoint_bodies.responses.pop())
+          .expect("response body should have been learned");
+        let root_shape_id = response
+          .body_descriptor
+          .expect("response body should have a shape")
+          .root_shape_id;
+        let spec_projection = assert_valid_commands(SpecProjection::default(), response.commands);
+
+        for diffed in [&one_tag, &two_tags].iter() {
+          let diffs = diff_body(
+            spec_projection.shape(),
+            Some((*diffed).clone()),
+            &root_shape_id,
+            &DiffConfig::default(),
+          );
+          assert!(
+            diffs.is_empty(),
+            "{:?} should match {:?}",
+            diffed,
+            learned_from
+          );
+        }
+      }
+    }
+  }
+
   #[test]
   fn undocumented_bodies_can_generate_commands_for_request_with_empty_query_parameters() {
     let test_path = "root";
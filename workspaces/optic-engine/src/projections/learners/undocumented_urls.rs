@@ -0,0 +1,584 @@
+use cqrs_core::{Aggregate, AggregateEvent, Event};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::commands::{EndpointCommand, ShapeCommand, SpecCommand};
+use crate::interactions::UrlAnalysisResult;
+use crate::projections::endpoint::{EndpointProjection, Node as EndpointNode, ROOT_PATH_ID};
+use crate::state::endpoint::PathComponentId;
+use crate::state::shape::ShapeKind;
+use crate::state::SpecIdGenerator;
+
+/// The number of distinct sibling segments from which a position in a path is considered a
+/// parameter, like user names or slugs, even when they don't look like identifiers.
+const HIGH_CARDINALITY_THRESHOLD: usize = 10;
+
+/// Urls observed in interactions that didn't match any of the spec's paths, aggregated as a tree
+/// of path segments from which path templates can be learned.
+#[derive(Default, Debug)]
+pub struct LearnedUndocumentedUrlsProjection {
+  root: ObservedPathSegment,
+}
+
+#[derive(Default, Debug)]
+struct ObservedPathSegment {
+  children: BTreeMap<String, ObservedPathSegment>,
+  status_codes_by_method: BTreeMap<String, BTreeSet<u16>>,
+}
+
+impl LearnedUndocumentedUrlsProjection {
+  fn with_url_analysis_result(&mut self, analysis: UrlAnalysisResult) {
+    let observed_segment = analysis
+      .path
+      .split('/')
+      .filter(|segment| !segment.is_empty())
+      .fold(&mut self.root, |parent, segment| {
+        parent.children.entry(String::from(segment)).or_default()
+      });
+
+    observed_segment
+      .status_codes_by_method
+      .entry(analysis.method)
+      .or_default()
+      .insert(analysis.status_code);
+  }
+
+  /// Infer path templates from the observed urls, with the commands to add their path components
+  /// and stubs of requests and responses for every method and status code observed. Segments
+  /// that are numeric, uuids or of high cardinality become path parameters, and existing path
+  /// components of the spec are reused where possible.
+  ///
+  /// Path components can be shared between endpoints, so their commands are only part of the
+  /// first endpoint that needs them: the endpoints' commands are to be applied in order.
+  pub fn into_undocumented_endpoints(
+    self,
+    endpoint_projection: &EndpointProjection,
+    id_generator: &mut impl SpecIdGenerator,
+  ) -> impl Iterator<Item = UndocumentedEndpoint> {
+    let mut learner = PathTemplateLearner {
+      endpoint_projection,
+      ids: id_generator,
+      pending_commands: vec![],
+      endpoints: vec![],
+    };
+
+    learner.learn(self.root, &LearnedPath::root());
+
+    learner.endpoints.into_iter()
+  }
+}
+
+impl Aggregate for LearnedUndocumentedUrlsProjection {
+  fn aggregate_type() -> &'static str {
+    "learned_undocumented_urls"
+  }
+}
+
+impl Event for UrlAnalysisResult {
+  fn event_type(&self) -> &'static str {
+    "url_analysis_result"
+  }
+}
+
+impl AggregateEvent<LearnedUndocumentedUrlsProjection> for UrlAnalysisResult {
+  fn apply_to(self, aggregate: &mut LearnedUndocumentedUrlsProjection) {
+    aggregate.with_url_analysis_result(self)
+  }
+}
+
+// Output structs
+// --------------
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndocumentedEndpoint {
+  path_id: PathComponentId,
+  path_pattern: String,
+  method: String,
+  commands: Vec<SpecCommand>,
+}
+
+impl UndocumentedEndpoint {
+  pub fn path_pattern(&self) -> &str {
+    &self.path_pattern
+  }
+
+  pub fn into_commands(self) -> impl Iterator<Item = SpecCommand> {
+    self.commands.into_iter()
+  }
+}
+
+// Path templates
+// --------------
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum TemplateSegment {
+  Literal(String),
+  Parameter,
+}
+
+/// A path as learned so far, while descending the tree of observed segments
+#[derive(Debug, Clone)]
+struct LearnedPath {
+  path_id: PathComponentId,
+  segments: Vec<String>,
+  parameter_names: Vec<String>,
+  last_literal: Option<String>,
+}
+
+impl LearnedPath {
+  fn root() -> Self {
+    Self {
+      path_id: String::from(ROOT_PATH_ID),
+      segments: vec![],
+      parameter_names: vec![],
+      last_literal: None,
+    }
+  }
+
+  fn path_pattern(&self) -> String {
+    format!("/{}", self.segments.join("/"))
+  }
+
+  fn with_literal(&self, path_id: PathComponentId, name: &str) -> Self {
+    let mut path = self.clone();
+    path.path_id = path_id;
+    path.segments.push(String::from(name));
+    path.last_literal = Some(String::from(name));
+    path
+  }
+
+  fn with_parameter(&self, path_id: PathComponentId, name: &str) -> Self {
+    let mut path = self.clone();
+    path.path_id = path_id;
+    path.segments.push(format!("{{{}}}", name));
+    path.parameter_names.push(String::from(name));
+    path.last_literal = None;
+    path
+  }
+
+  /// A name for a new parameter, after the literal segment preceding it: `/users/{userId}`
+  fn new_parameter_name(&self) -> String {
+    let base_name = match &self.last_literal {
+      Some(literal) => format!("{}Id", singular(&camel_case(literal))),
+      None => String::from("id"),
+    };
+
+    let mut name = base_name.clone();
+    let mut suffix = 2;
+    while self.parameter_names.contains(&name) {
+      name = format!("{}{}", base_name, suffix);
+      suffix += 1;
+    }
+    name
+  }
+}
+
+struct PathTemplateLearner<'a, G> {
+  endpoint_projection: &'a EndpointProjection,
+  ids: &'a mut G,
+  pending_commands: Vec<SpecCommand>,
+  endpoints: Vec<UndocumentedEndpoint>,
+}
+
+impl<'a, G> PathTemplateLearner<'a, G>
+where
+  G: SpecIdGenerator,
+{
+  fn learn(&mut self, observed: ObservedPathSegment, path: &LearnedPath) {
+    for (method, status_codes) in observed.status_codes_by_method {
+      self.add_endpoint(path, method, status_codes);
+    }
+
+    for (template_segment, child) in self.templated_children(observed.children, &path.path_id) {
+      let child_path = self.ensure_path_component(path, template_segment);
+      self.learn(child, &child_path);
+    }
+  }
+
+  /// Group the observed children of a path by the segment of the template they belong to, where
+  /// all segments that are considered parameters are merged into a single one
+  fn templated_children(
+    &self,
+    children: BTreeMap<String, ObservedPathSegment>,
+    parent_path_id: &PathComponentId,
+  ) -> Vec<(TemplateSegment, ObservedPathSegment)> {
+    let has_spec_parameter = self
+      .existing_path_component(parent_path_id, |descriptor_is_parameter, _| {
+        descriptor_is_parameter
+      })
+      .is_some();
+
+    let (parameters, literals): (Vec<_>, Vec<_>) =
+      children.into_iter().partition(|(segment, _)| {
+        is_identifier(segment)
+          || (has_spec_parameter
+            && self
+              .existing_path_component(parent_path_id, |is_parameter, name| {
+                !is_parameter && name == segment
+              })
+              .is_none())
+      });
+
+    let (parameters, literals) = if literals.len() >= HIGH_CARDINALITY_THRESHOLD {
+      (parameters.into_iter().chain(literals).collect(), vec![])
+    } else {
+      (parameters, literals)
+    };
+
+    let merged_parameter = parameters.into_iter().map(|(_, child)| child).fold(
+      None,
+      |merged: Option<ObservedPathSegment>, child| {
+        Some(match merged {
+          Some(mut merged) => {
+            merged.merge(child);
+            merged
+          }
+          None => child,
+        })
+      },
+    );
+
+    literals
+      .into_iter()
+      .map(|(segment, child)| (TemplateSegment::Literal(segment), child))
+      .chain(
+        merged_parameter
+          .into_iter()
+          .map(|child| (TemplateSegment::Parameter, child)),
+      )
+      .collect()
+  }
+
+  /// Find or add the path component for a segment of a template. Literal segments can be
+  /// matched by an existing parameter, when there's no existing literal component by that name.
+  fn ensure_path_component(
+    &mut self,
+    parent: &LearnedPath,
+    template_segment: TemplateSegment,
+  ) -> LearnedPath {
+    let existing_parameter =
+      self.existing_path_component(&parent.path_id, |is_parameter, _| is_parameter);
+
+    match template_segment {
+      TemplateSegment::Literal(name) => {
+        let existing_literal = self
+          .existing_path_component(&parent.path_id, |is_parameter, existing_name| {
+            !is_parameter && existing_name == name
+          })
+          .map(|(path_id, _)| path_id);
+
+        match (existing_literal, existing_parameter) {
+          (Some(path_id), _) => parent.with_literal(path_id, &name),
+          (None, Some((path_id, parameter_name))) => {
+            parent.with_parameter(path_id, &parameter_name)
+          }
+          (None, None) => {
+            let path_id = self.ids.path();
+            self
+              .pending_commands
+              .push(SpecCommand::from(EndpointCommand::add_path_component(
+                path_id.clone(),
+                parent.path_id.clone(),
+                name.clone(),
+              )));
+            parent.with_literal(path_id, &name)
+          }
+        }
+      }
+      TemplateSegment::Parameter => match existing_parameter {
+        Some((path_id, parameter_name)) => parent.with_parameter(path_id, &parameter_name),
+        None => {
+          let path_id = self.ids.path();
+          let parameter_name = parent.new_parameter_name();
+          let shape_id = self.ids.shape();
+          self.pending_commands.extend(vec![
+            SpecCommand::from(EndpointCommand::add_path_parameter(
+              path_id.clone(),
+              parent.path_id.clone(),
+              parameter_name.clone(),
+            )),
+            SpecCommand::from(ShapeCommand::add_shape(
+              shape_id.clone(),
+              ShapeKind::StringKind,
+              String::from(""),
+            )),
+            SpecCommand::from(EndpointCommand::set_path_parameter_shape(
+              path_id.clone(),
+              shape_id,
+            )),
+          ]);
+          parent.with_parameter(path_id, &parameter_name)
+        }
+      },
+    }
+  }
+
+  fn add_endpoint(&mut self, path: &LearnedPath, method: String, status_codes: BTreeSet<u16>) {
+    let mut commands = std::mem::take(&mut self.pending_commands);
+
+    let request_id = self.ids.request();
+    commands.push(SpecCommand::from(EndpointCommand::add_request(
+      request_id,
+      path.path_id.clone(),
+      method.clone(),
+    )));
+
+    for status_code in status_codes {
+      let response_id = self.ids.response();
+      commands.push(SpecCommand::from(
+        EndpointCommand::add_response_by_path_and_method(
+          response_id,
+          path.path_id.clone(),
+          method.clone(),
+          status_code,
+        ),
+      ));
+    }
+
+    self.endpoints.push(UndocumentedEndpoint {
+      path_id: path.path_id.clone(),
+      path_pattern: path.path_pattern(),
+      method,
+      commands,
+    });
+  }
+
+  fn existing_path_component(
+    &self,
+    parent_path_id: &PathComponentId,
+    predicate: impl Fn(bool, &str) -> bool,
+  ) -> Option<(PathComponentId, String)> {
+    self
+      .endpoint_projection
+      .get_child_path_component_nodes(parent_path_id)?
+      .find_map(|node| match node {
+        EndpointNode::PathComponent(path_id, descriptor)
+          if predicate(descriptor.is_parameter, &descriptor.name) =>
+        {
+          Some((path_id.clone(), descriptor.name.clone()))
+        }
+        _ => None,
+      })
+  }
+}
+
+impl ObservedPathSegment {
+  fn merge(&mut self, other: ObservedPathSegment) {
+    for (method, status_codes) in other.status_codes_by_method {
+      self
+        .status_codes_by_method
+        .entry(method)
+        .or_default()
+        .extend(status_codes);
+    }
+
+    for (segment, other_child) in other.children {
+      match self.children.get_mut(&segment) {
+        Some(child) => child.merge(other_child),
+        None => {
+          self.children.insert(segment, other_child);
+        }
+      }
+    }
+  }
+}
+
+/// Whether a path segment looks like the identifier of a resource, like numbers and uuids
+fn is_identifier(segment: &str) -> bool {
+  let is_numeric = segment.chars().all(|c| c.is_ascii_digit());
+  let is_hex = |part: &str| part.chars().all(|c| c.is_ascii_hexdigit());
+  let is_uuid = {
+    let parts = segment.split('-').collect::<Vec<_>>();
+    let hyphenated = parts.iter().map(|part| part.len()).eq(vec![8, 4, 4, 4, 12]);
+    (hyphenated || segment.len() == 32) && parts.iter().all(|part| is_hex(part))
+  };
+
+  is_numeric || is_uuid
+}
+
+fn camel_case(segment: &str) -> String {
+  let mut words = segment
+    .split(|c: char| !c.is_ascii_alphanumeric())
+    .filter(|word| !word.is_empty());
+
+  let first_word = words.next().unwrap_or_default().to_lowercase();
+  words.fold(first_word, |mut camel_cased, word| {
+    let mut chars = word.chars();
+    if let Some(first_char) = chars.next() {
+      camel_cased.push(first_char.to_ascii_uppercase());
+      camel_cased.push_str(&chars.as_str().to_lowercase());
+    }
+    camel_cased
+  })
+}
+
+fn singular(word: &str) -> String {
+  if let Some(stem) = word.strip_suffix("ies") {
+    format!("{}y", stem)
+  } else if word.ends_with("ss") || word.ends_with("us") {
+    String::from(word)
+  } else if let Some(stem) = word.strip_suffix('s') {
+    String::from(stem)
+  } else {
+    String::from(word)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::events::{HttpInteraction, SpecEvent};
+  use crate::interactions::{analyze_undocumented_urls, diff, DiffConfig, InteractionDiffResult};
+  use crate::projections::SpecProjection;
+  use cqrs_core::AggregateCommand;
+  use insta::assert_debug_snapshot;
+  use serde_json::json;
+
+  #[test]
+  fn undocumented_urls_can_be_learned_as_path_templates() {
+    let spec_projection = SpecProjection::from(
+      serde_json::from_value::<Vec<SpecEvent>>(json!([
+        {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+        {"PathParameterAdded":{"pathId":"path_2","parentPathId":"path_1","name":"handle"}},
+      ]))
+      .expect("initial spec events should be valid events"),
+    );
+
+    let mut observed = vec![
+      (
+        "GET",
+        "/users/123/orders/9f1c4d2e-8b7a-4c3e-9a1f-2b3c4d5e6f70",
+        200,
+      ),
+      (
+        "GET",
+        "/users/456/orders/0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d",
+        404,
+      ),
+      ("POST", "/users/123/orders", 201),
+      ("GET", "/users/123/saved-searches", 200),
+      ("GET", "/status", 200),
+      ("GET", "/status/", 503),
+      ("DELETE", "/files/1/versions/2", 204),
+    ]
+    .into_iter()
+    .map(|(method, path, status_code)| (method, String::from(path), status_code))
+    .collect::<Vec<_>>();
+    // team names don't look like identifiers, but there's too many of them to be literals
+    observed.extend(
+      (0..HIGH_CARDINALITY_THRESHOLD).map(|index| ("GET", format!("/teams/team-{}", index), 200)),
+    );
+
+    let interactions = observed
+      .iter()
+      .map(|(method, path, status_code)| interaction(method, path, *status_code))
+      .collect::<Vec<_>>();
+
+    let mut projection = LearnedUndocumentedUrlsProjection::default();
+    for interaction in interactions.iter().cloned() {
+      for analysis in analyze_undocumented_urls(&spec_projection, interaction) {
+        projection.apply(analysis);
+      }
+    }
+
+    let mut id_generator = TestIdGenerator::default();
+    let endpoints = projection
+      .into_undocumented_endpoints(spec_projection.endpoint(), &mut id_generator)
+      .collect::<Vec<_>>();
+
+    let path_patterns = endpoints
+      .iter()
+      .map(|endpoint| format!("{} {}", endpoint.method, endpoint.path_pattern()))
+      .collect::<Vec<_>>();
+    assert_debug_snapshot!(
+      "undocumented_urls_can_be_learned_as_path_templates__path_patterns",
+      path_patterns
+    );
+    assert_debug_snapshot!(
+      "undocumented_urls_can_be_learned_as_path_templates__endpoints",
+      &endpoints
+    );
+
+    let mut spec_projection = spec_projection;
+    for command in endpoints
+      .into_iter()
+      .flat_map(UndocumentedEndpoint::into_commands)
+    {
+      let events = spec_projection
+        .execute(command)
+        .expect("generated commands must be valid");
+      for event in events {
+        spec_projection.apply(event);
+      }
+    }
+
+    for interaction in interactions {
+      let results = diff(&spec_projection, interaction, &DiffConfig::default());
+      assert!(
+        results.iter().all(|result| match result {
+          InteractionDiffResult::UnmatchedRequestUrl(_) => false,
+          _ => true,
+        }),
+        "learned paths should match the observed urls"
+      );
+    }
+  }
+
+  #[test]
+  fn parameter_names_are_derived_from_preceding_segments() {
+    let path = LearnedPath::root();
+    assert_eq!(path.new_parameter_name(), "id");
+
+    let path = path.with_literal(String::from("path_1"), "categories");
+    assert_eq!(path.new_parameter_name(), "categoryId");
+
+    let path = path
+      .with_parameter(String::from("path_2"), "categoryId")
+      .with_literal(String::from("path_3"), "sub_categories");
+    assert_eq!(path.new_parameter_name(), "subCategoryId");
+
+    let path = path.with_literal(String::from("path_4"), "categories");
+    assert_eq!(path.new_parameter_name(), "categoryId2");
+
+    assert!(is_identifier("42"));
+    assert!(is_identifier("9F1C4D2E-8B7A-4C3E-9A1F-2B3C4D5E6F70"));
+    assert!(is_identifier("9f1c4d2e8b7a4c3e9a1f2b3c4d5e6f70"));
+    assert!(!is_identifier("orders"));
+    assert!(!is_identifier("v2"));
+  }
+
+  fn interaction(method: &str, path: &str, status_code: u16) -> HttpInteraction {
+    serde_json::from_value(json!({
+      "uuid": "1",
+      "request": {
+        "host": "localhost",
+        "method": method,
+        "path": path,
+        "query": {},
+        "headers": {},
+        "body": { "contentType": null, "value": {} }
+      },
+      "response": {
+        "statusCode": status_code,
+        "headers": {},
+        "body": { "contentType": null, "value": {} }
+      },
+      "tags": []
+    }))
+    .expect("should be a valid interaction")
+  }
+
+  #[derive(Debug, Default)]
+  struct TestIdGenerator {
+    counter: usize,
+  }
+
+  impl SpecIdGenerator for TestIdGenerator {
+    fn generate_id(&mut self, prefix: &str) -> String {
+      let id = format!("test-id-{}-{}", prefix, self.counter);
+      self.counter += 1;
+      id
+    }
+  }
+}
@@ -4,6 +4,7 @@ pub mod endpoint;
 pub mod history;
 pub mod learners;
 pub mod shape;
+pub mod snapshot;
 pub mod spec_events;
 pub mod spectacle;
 
@@ -14,16 +15,19 @@ pub use history::{CommitId, HistoryProjection};
 pub use learners::{
   shape_diff_affordances::LearnedShapeDiffAffordancesProjection,
   undocumented_bodies::LearnedUndocumentedBodiesProjection,
+  undocumented_urls::LearnedUndocumentedUrlsProjection,
 };
 pub use shape::ShapeProjection;
+pub use snapshot::{SnapshottableProjection, SpecProjectionSnapshot, SpecProjectionSnapshotError};
 pub use spec_events::{SpecAssemblerError, SpecAssemblerProjection};
 pub use spectacle::endpoints::EndpointsProjection;
 
 use crate::events::{EndpointEvent, RfcEvent, ShapeEvent, SpecEvent};
 use cqrs_core::{Aggregate, AggregateCommand, AggregateEvent, CommandError};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpecProjection {
   endpoint: endpoint::EndpointProjection,
   history: history::HistoryProjection,
@@ -31,6 +35,11 @@ pub struct SpecProjection {
   conflicts: conflicts::ConflictsProjection,
   spectacle_endpoints: spectacle::endpoints::EndpointsProjection,
   contributions: contributions::ContributionsProjection,
+
+  // whether the last applied event ended a batch commit, making this a point the projection
+  // can be snapshotted at
+  #[serde(default)]
+  is_at_batch_commit_end: bool,
 }
 
 impl Default for SpecProjection {
@@ -42,6 +51,7 @@ impl Default for SpecProjection {
       conflicts: ConflictsProjection::default(),
       spectacle_endpoints: EndpointsProjection::default(),
       contributions: ContributionsProjection::default(),
+      is_at_batch_commit_end: false,
     }
   }
 }
@@ -64,6 +74,19 @@ impl SpecProjection {
   pub fn contributions(&self) -> &ContributionsProjection {
     &self.contributions
   }
+  /// Id of the last batch commit applied, if the last event applied completed it
+  pub fn completed_batch_commit_id(&self) -> Option<&CommitId> {
+    if !self.is_at_batch_commit_end {
+      return None;
+    }
+    let commit_id = self.history.get_last_batch_commit_id()?;
+    let descriptor = self.history.get_batch_commit_descriptor(commit_id)?;
+    if descriptor.is_complete {
+      Some(commit_id)
+    } else {
+      None
+    }
+  }
   pub fn spectacle_endpoints(&self) -> &EndpointsProjection {
     &self.spectacle_endpoints
   }
@@ -88,6 +111,9 @@ impl Aggregate for SpecProjection {
 
 impl AggregateEvent<SpecProjection> for SpecEvent {
   fn apply_to(self, projection: &mut SpecProjection) {
+    projection.is_at_batch_commit_end =
+      matches!(self, SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(_)));
+
     match self {
       SpecEvent::EndpointEvent(event) => {
         projection.endpoint.apply(event.clone());
@@ -116,6 +142,19 @@ impl AggregateEvent<HistoryProjection> for SpecEvent {
   }
 }
 
+impl SpecProjection {
+  /// Build a projection by folding over events as they're read, stopping at the first error
+  pub fn try_from_events<E>(
+    events: impl IntoIterator<Item = Result<SpecEvent, E>>,
+  ) -> Result<Self, E> {
+    let mut projection = SpecProjection::default();
+    for event in events.into_iter() {
+      projection.apply(event?);
+    }
+    Ok(projection)
+  }
+}
+
 impl<I> From<I> for SpecProjection
 where
   I: IntoIterator,
@@ -3,8 +3,9 @@ use crate::queries::shape::{ChoiceOutput, ShapeQueries};
 use crate::shapes::traverser::ShapeTrailPathComponent::ObjectFieldTrail;
 use crate::shapes::ShapeTrail;
 use crate::state::shape::{
-  FieldId, FieldShapeDescriptor, ParameterShapeDescriptor, ProviderDescriptor, ShapeId, ShapeIdRef,
-  ShapeKind, ShapeKindDescriptor, ShapeParameterId, ShapeParameterIdRef, ShapeParametersDescriptor,
+  FieldId, FieldShapeDescriptor, OneOfDiscriminatorDescriptor, ParameterShapeDescriptor,
+  ProviderDescriptor, ShapeConstraintDescriptor, ShapeConstraintId, ShapeId, ShapeIdRef, ShapeKind,
+  ShapeKindDescriptor, ShapeParameterId, ShapeParameterIdRef, ShapeParametersDescriptor,
 };
 use crate::{RfcEvent, SpecProjection};
 use cqrs_core::{Aggregate, AggregateEvent, Event};
@@ -14,40 +15,57 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::iter::FromIterator;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum Node {
   CoreShape(CoreShapeNode),
   Shape(ShapeNode),
   Field(FieldNode),
   ShapeParameter(ShapeParameterNode),
+  ShapeConstraint(ShapeConstraintNode),
+  OneOfDiscriminator(OneOfDiscriminatorNode),
   BatchCommit(BatchCommitNode),
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShapeNode {
   pub shape_id: ShapeId,
+  pub descriptor: ShapeNodeDescriptor,
 }
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CoreShapeNode {
   pub shape_id: ShapeId,
   pub descriptor: CoreShapeNodeDescriptor,
 }
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FieldNode {
   pub field_id: FieldId,
   pub descriptor: FieldNodeDescriptor,
 }
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShapeParameterNode {
   pub parameter_id: ShapeParameterId,
   pub descriptor: ShapeParameterNodeDescriptor,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeConstraintNode {
+  pub shape_constraint_id: ShapeConstraintId,
+  pub descriptor: ShapeConstraintDescriptor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OneOfDiscriminatorNode {
+  pub shape_id: ShapeId,
+  pub descriptor: OneOfDiscriminatorDescriptor,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BatchCommitNode {
@@ -55,41 +73,61 @@ pub struct BatchCommitNode {
   created_at: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum Edge {
   BelongsTo,
   IsDescendantOf,
   IsFieldOf,
   IsParameterOf,
+  IsConstraintOf,
+  IsDiscriminatorOf,
   HasBinding(ShapeParameterBinding),
+  HasParameterBinding(ShapeParameterParameterBinding),
   CreatedIn,
   UpdatedIn,
+  RemovedIn,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShapeParameterBinding {
   pub shape_id: ShapeId,
 }
 
+// binds a shape parameter to another shape parameter, to be provided by where the shape is used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeParameterParameterBinding {
+  pub shape_parameter_id: ShapeParameterId,
+}
+
 pub type NodeId = String;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CoreShapeNodeDescriptor {
   pub kind: ShapeKind,
 }
-#[derive(Debug, Clone, Serialize)]
-pub struct ShapeParameterNodeDescriptor {}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeNodeDescriptor {
+  pub name: String,
+}
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeParameterNodeDescriptor {
+  pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FieldNodeDescriptor {
   pub name: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShapeProjection {
   pub graph: Graph<Node, Edge>,
   pub node_id_to_index: HashMap<NodeId, petgraph::graph::NodeIndex>,
@@ -108,12 +146,15 @@ impl Default for ShapeProjection {
     add_core_shape_to_projection(&mut projection, ShapeKind::NumberKind);
     add_core_shape_to_projection(&mut projection, ShapeKind::BooleanKind);
     add_core_shape_to_projection(&mut projection, ShapeKind::ListKind);
+    add_core_shape_to_projection(&mut projection, ShapeKind::MapKind);
     //@TODO: incomplete
     add_core_shape_to_projection(&mut projection, ShapeKind::ObjectKind);
     add_core_shape_to_projection(&mut projection, ShapeKind::NullableKind);
     add_core_shape_to_projection(&mut projection, ShapeKind::UnknownKind);
     add_core_shape_to_projection(&mut projection, ShapeKind::OptionalKind);
     add_core_shape_to_projection(&mut projection, ShapeKind::OneOfKind);
+    add_core_shape_to_projection(&mut projection, ShapeKind::IdentifierKind);
+    add_core_shape_to_projection(&mut projection, ShapeKind::ReferenceKind);
     projection
   }
 }
@@ -133,7 +174,9 @@ fn add_core_shape_to_projection(shape_projection: &mut ShapeProjection, shape_ki
   if let Some(shape_parameter_descriptor) = shape_kind.get_parameter_descriptor() {
     let shape_parameter_node = Node::ShapeParameter(ShapeParameterNode {
       parameter_id: String::from(shape_parameter_descriptor.shape_parameter_id),
-      descriptor: ShapeParameterNodeDescriptor {},
+      descriptor: ShapeParameterNodeDescriptor {
+        name: String::from(shape_parameter_descriptor.shape_parameter_id),
+      },
     });
     let shape_parameter_node_index = shape_projection.graph.add_node(shape_parameter_node);
     shape_projection.node_id_to_index.insert(
@@ -158,6 +201,21 @@ impl ShapeProjection {
     let node_index = self.graph.add_node(node);
     self.node_id_to_index.insert(batch_id, node_index);
   }
+  /// Whether the history has anything created, updated or removed by a batch commit
+  pub fn has_changes_in_batch_commit(&self, batch_id: &str) -> bool {
+    match self.node_id_to_index.get(batch_id) {
+      Some(batch_node_index) => self
+        .graph
+        .edges_directed(*batch_node_index, petgraph::Direction::Incoming)
+        .any(|edge| {
+          matches!(
+            edge.weight(),
+            Edge::CreatedIn | Edge::UpdatedIn | Edge::RemovedIn
+          )
+        }),
+      None => false,
+    }
+  }
   pub fn with_creation_history(&mut self, batch_id: &str, created_node_id: &str) {
     let created_node_index = self
       .node_id_to_index
@@ -190,12 +248,34 @@ impl ShapeProjection {
       eprintln!("bad implicit batch id {}", &batch_id);
     }
   }
+  // removal history has to be recorded before the removed node is unregistered from node_id_to_index
+  pub fn with_removal_history(&mut self, batch_id: &str, removed_node_id: &str) {
+    let removed_node_index = self
+      .node_id_to_index
+      .get(removed_node_id)
+      .expect("expected removed_node_id to exist");
+
+    let batch_node_index_option = self.node_id_to_index.get(batch_id);
+
+    if let Some(batch_node_index) = batch_node_index_option {
+      self
+        .graph
+        .add_edge(*removed_node_index, *batch_node_index, Edge::RemovedIn);
+    } else {
+      eprintln!("bad implicit batch id {}", &batch_id);
+    }
+  }
   ////////////////////////////////////////////////////////////////////////////////
-  pub fn with_shape_parameter(&mut self, shape_parameter_id: ShapeParameterId, shape_id: ShapeId) {
+  pub fn with_shape_parameter(
+    &mut self,
+    shape_parameter_id: ShapeParameterId,
+    shape_id: ShapeId,
+    name: String,
+  ) {
     let shape_node_index = *self.get_shape_node_index(&shape_id).unwrap();
     let shape_parameter_node = Node::ShapeParameter(ShapeParameterNode {
       parameter_id: shape_parameter_id.clone(),
-      descriptor: ShapeParameterNodeDescriptor {},
+      descriptor: ShapeParameterNodeDescriptor { name },
     });
     let shape_parameter_node_index = self.graph.add_node(shape_parameter_node);
     self
@@ -217,6 +297,7 @@ impl ShapeProjection {
   ) {
     let shape_node = Node::Shape(ShapeNode {
       shape_id: shape_id.clone(),
+      descriptor: ShapeNodeDescriptor { name },
     });
     let shape_node_index = self.graph.add_node(shape_node);
     self.node_id_to_index.insert(shape_id, shape_node_index);
@@ -277,6 +358,82 @@ impl ShapeProjection {
     );
   }
 
+  pub fn with_shape_name(&mut self, shape_id: ShapeId, name: String) {
+    let shape_node_index = *self
+      .get_shape_node_index(&shape_id)
+      .expect("expected shape_id to have a corresponding node");
+
+    match self.graph.node_weight_mut(shape_node_index) {
+      Some(Node::Shape(shape_node)) => shape_node.descriptor.name = name,
+      _ => panic!("expected shape_id '{}' to not be a core shape", &shape_id),
+    }
+  }
+
+  pub fn without_shape(&mut self, shape_id: ShapeId) {
+    let shape_node_index = *self
+      .get_shape_node_index(&shape_id)
+      .expect("expected shape_id to have corresponding node");
+
+    let base_shape_edge_index = self
+      .graph
+      .edges_directed(shape_node_index, petgraph::Direction::Outgoing)
+      .find(|edge| matches!(edge.weight(), Edge::IsDescendantOf))
+      .map(|base_shape_edge| base_shape_edge.id());
+
+    if let Some(base_shape_edge_index) = base_shape_edge_index {
+      self.graph.remove_edge(base_shape_edge_index); // prevents shape to be resolved as descendant of its base shape
+    }
+
+    // like fields, the node itself is kept around to not invalidate self.node_id_to_index
+    self.node_id_to_index.remove(&shape_id);
+  }
+
+  pub fn with_shape_parameter_name(&mut self, shape_parameter_id: ShapeParameterId, name: String) {
+    let shape_parameter_node_index = *self
+      .get_shape_parameter_node_index(&shape_parameter_id)
+      .expect("expected shape_parameter_id to have a corresponding node");
+
+    if let Some(Node::ShapeParameter(shape_parameter_node)) =
+      self.graph.node_weight_mut(shape_parameter_node_index)
+    {
+      shape_parameter_node.descriptor.name = name;
+    }
+  }
+
+  pub fn without_shape_parameter(&mut self, shape_parameter_id: ShapeParameterId) {
+    let shape_parameter_node_index = *self
+      .get_shape_parameter_node_index(&shape_parameter_id)
+      .expect("expected shape_parameter_id to have corresponding node");
+
+    // both the parameter's link to its shape and any bindings to it go, so it's no longer resolved
+    // as a parameter of the shape
+    let mut edge_indices = self
+      .graph
+      .edges_directed(shape_parameter_node_index, petgraph::Direction::Outgoing)
+      .filter(|edge| matches!(edge.weight(), Edge::IsParameterOf))
+      .chain(
+        self
+          .graph
+          .edges_directed(shape_parameter_node_index, petgraph::Direction::Incoming)
+          .filter(|edge| {
+            matches!(
+              edge.weight(),
+              Edge::HasBinding(_) | Edge::HasParameterBinding(_)
+            )
+          }),
+      )
+      .map(|edge| edge.id())
+      .collect::<Vec<_>>();
+    edge_indices.sort();
+
+    // remove from the highest index down, as removing an edge moves the last edge into its place
+    for edge_index in edge_indices.into_iter().rev() {
+      self.graph.remove_edge(edge_index);
+    }
+
+    self.node_id_to_index.remove(&shape_parameter_id);
+  }
+
   pub fn get_ancestor_shape_node_index(&self, parent_node_index: &NodeIndex) -> Option<NodeIndex> {
     let mut edges = self
       .graph
@@ -295,59 +452,77 @@ impl ShapeProjection {
     &mut self,
     shape_parameter_descriptor: ParameterShapeDescriptor,
   ) {
-    match shape_parameter_descriptor {
-      ParameterShapeDescriptor::ProviderInShape(p) => {
-        let target_shape_id = match p.provider_descriptor {
-          ProviderDescriptor::ShapeProvider(provider) => provider.shape_id,
-          _ => panic!("expected specs to only use ProviderDescriptor::ShapeProvider"),
-        };
-        let shape_node_index = self
-          .node_id_to_index
-          .get(&p.shape_id)
-          .expect("expected shape_id to have a corresponding node");
-        let shape_parameter_node_index = self
-          .node_id_to_index
-          .get(&p.consuming_parameter_id)
-          .expect("expected consuming_parameter_id to have a corresponding node");
-        let mut outgoing_edges = self
-          .graph
-          .edges_connecting(*shape_node_index, *shape_parameter_node_index);
-        if let Some(existing_binding) = outgoing_edges.next() {
-          let edge_index = existing_binding.id();
-          let edge_weight = self.graph.edge_weight_mut(edge_index).unwrap();
-          // mutate the edge to point to the new shape_id
-          match edge_weight {
-            Edge::HasBinding(b) => {
-              b.shape_id = target_shape_id;
-            }
-            _ => panic!("expected edge to be a HasBinding"),
-          }
-        } else {
-          // add a binding edge
-          let binding_edge = self.graph.add_edge(
-            *shape_node_index,
-            *shape_parameter_node_index,
-            Edge::HasBinding(ShapeParameterBinding {
-              shape_id: target_shape_id,
-            }),
-          );
+    // parameters can be bound by the shape they're of, or by a field using that shape
+    let (binding_node_id, consuming_parameter_id, provider_descriptor) =
+      match shape_parameter_descriptor {
+        ParameterShapeDescriptor::ProviderInShape(p) => {
+          (p.shape_id, p.consuming_parameter_id, p.provider_descriptor)
         }
+        ParameterShapeDescriptor::ProviderInField(p) => {
+          (p.field_id, p.consuming_parameter_id, p.provider_descriptor)
+        }
+      };
+    let binding_node_index = *self
+      .node_id_to_index
+      .get(&binding_node_id)
+      .expect("expected shape_id or field_id to have a corresponding node");
+    let shape_parameter_node_index = *self
+      .node_id_to_index
+      .get(&consuming_parameter_id)
+      .expect("expected consuming_parameter_id to have a corresponding node");
+
+    let mut existing_binding_edges = self
+      .graph
+      .edges_connecting(binding_node_index, shape_parameter_node_index)
+      .filter(|edge| {
+        matches!(
+          edge.weight(),
+          Edge::HasBinding(_) | Edge::HasParameterBinding(_)
+        )
+      })
+      .map(|edge| edge.id())
+      .collect::<Vec<_>>();
+    existing_binding_edges.sort();
+    for edge_index in existing_binding_edges.into_iter().rev() {
+      self.graph.remove_edge(edge_index);
+    }
+
+    let binding_edge = match provider_descriptor {
+      ProviderDescriptor::ShapeProvider(provider) => Edge::HasBinding(ShapeParameterBinding {
+        shape_id: provider.shape_id,
+      }),
+      ProviderDescriptor::ParameterProvider(provider) => {
+        Edge::HasParameterBinding(ShapeParameterParameterBinding {
+          shape_parameter_id: provider.shape_parameter_id,
+        })
       }
-      _ => panic!("expected specs to only use ParameterShapeDescriptor::ProviderInShape"),
+      ProviderDescriptor::NoProvider(_) => return, // unbinding is all there's to it
+    };
+    self
+      .graph
+      .add_edge(binding_node_index, shape_parameter_node_index, binding_edge);
+  }
+
+  /// The node describing the value of a field: either a shape, or a shape parameter to be
+  /// resolved to a shape wherever the field's object is used
+  fn get_field_value_node_index(&self, shape_descriptor: &FieldShapeDescriptor) -> NodeIndex {
+    match shape_descriptor {
+      FieldShapeDescriptor::FieldShapeFromShape(field_shape) => *self
+        .get_shape_node_index(&field_shape.shape_id)
+        .expect("expected shape_id for field value to have a corresponding node"),
+      FieldShapeDescriptor::FieldShapeFromParameter(field_shape) => *self
+        .get_shape_parameter_node_index(&field_shape.shape_parameter_id)
+        .expect("expected shape_parameter_id for field value to have a corresponding node"),
     }
   }
 
   pub fn with_field_shape(&mut self, shape_descriptor: FieldShapeDescriptor) {
-    let field_shape = match shape_descriptor {
-      FieldShapeDescriptor::FieldShapeFromShape(field_shape) => field_shape,
-      _ => panic!("expected specs to only use FieldShapeDescriptor::FieldShapeFromShape"),
+    let field_id = match &shape_descriptor {
+      FieldShapeDescriptor::FieldShapeFromShape(field_shape) => &field_shape.field_id,
+      FieldShapeDescriptor::FieldShapeFromParameter(field_shape) => &field_shape.field_id,
     };
     let field_node_index = *self
-      .get_field_node_index(&field_shape.field_id)
-      .expect("expected field to exist");
-    let field_node_weight = self
-      .graph
-      .node_weight(field_node_index)
+      .get_field_node_index(field_id)
       .expect("expected field to exist");
     let existing_field_shape_edge_index = self
       .graph
@@ -361,13 +536,11 @@ impl ShapeProjection {
 
     self.graph.remove_edge(existing_field_shape_edge_index);
 
-    let target_shape_node_index = *self
-      .get_shape_node_index(&field_shape.shape_id)
-      .expect("expected shape_id for field value to have a corresponding node");
+    let field_value_node_index = self.get_field_value_node_index(&shape_descriptor);
 
     self
       .graph
-      .add_edge(target_shape_node_index, field_node_index, Edge::BelongsTo);
+      .add_edge(field_value_node_index, field_node_index, Edge::BelongsTo);
   }
 
   pub fn with_field(
@@ -381,19 +554,15 @@ impl ShapeProjection {
       .get_shape_node_index(&object_id)
       .expect("expected shape_id of field to have a corresponding node");
 
-    let field_value_shape_node_index = match shape_descriptor {
-      FieldShapeDescriptor::FieldShapeFromShape(field_shape) => {
-        assert_eq!(
-          field_shape.field_id, field_id,
-          "expect main field id of event to match one of field shape descriptor"
-        );
-
-        *self
-          .get_shape_node_index(&field_shape.shape_id)
-          .expect("expected shape_id for field value to have a corresponding node")
-      }
-      _ => panic!("expected specs to only use FieldShapeDescriptor::FieldShapeFromShape"),
+    let descriptor_field_id = match &shape_descriptor {
+      FieldShapeDescriptor::FieldShapeFromShape(field_shape) => &field_shape.field_id,
+      FieldShapeDescriptor::FieldShapeFromParameter(field_shape) => &field_shape.field_id,
     };
+    assert_eq!(
+      *descriptor_field_id, field_id,
+      "expect main field id of event to match one of field shape descriptor"
+    );
+    let field_value_shape_node_index = self.get_field_value_node_index(&shape_descriptor);
 
     let field_node = Node::Field(FieldNode {
       field_id: field_id.clone(),
@@ -415,6 +584,16 @@ impl ShapeProjection {
       .add_edge(field_node_index, object_node_index, Edge::IsFieldOf);
   }
 
+  pub fn with_field_name(&mut self, field_id: FieldId, name: String) {
+    let field_node_index = *self
+      .get_field_node_index(&field_id)
+      .expect("expected field_id to have a corresponding node");
+
+    if let Some(Node::Field(field_node)) = self.graph.node_weight_mut(field_node_index) {
+      field_node.descriptor.name = name;
+    }
+  }
+
   pub fn without_field(&mut self, field_id: FieldId) {
     let field_node_index = self
       .get_field_node_index(&field_id)
@@ -438,6 +617,83 @@ impl ShapeProjection {
     // eventual garbage collection.
   }
 
+  pub fn with_shape_constraint(
+    &mut self,
+    shape_constraint_id: ShapeConstraintId,
+    shape_id: ShapeId,
+    descriptor: ShapeConstraintDescriptor,
+  ) {
+    let shape_node_index = *self
+      .get_shape_node_index(&shape_id)
+      .expect("expected shape_id of constraint to have a corresponding node");
+
+    let constraint_node = Node::ShapeConstraint(ShapeConstraintNode {
+      shape_constraint_id: shape_constraint_id.clone(),
+      descriptor,
+    });
+    let constraint_node_index = self.graph.add_node(constraint_node);
+    self
+      .node_id_to_index
+      .insert(shape_constraint_id, constraint_node_index);
+
+    self.graph.add_edge(
+      constraint_node_index,
+      shape_node_index,
+      Edge::IsConstraintOf,
+    );
+  }
+
+  pub fn without_shape_constraint(&mut self, shape_constraint_id: ShapeConstraintId) {
+    let (constraint_node_index, _) = self
+      .get_shape_constraint_node(&shape_constraint_id)
+      .expect("expected shape_constraint_id to have corresponding node");
+    let constraint_node_index = *constraint_node_index;
+
+    let shape_edge_index = self
+      .graph
+      .edges_directed(constraint_node_index, petgraph::Direction::Outgoing)
+      .find(|edge| matches!(edge.weight(), Edge::IsConstraintOf))
+      .map(|shape_edge| shape_edge.id());
+
+    if let Some(shape_edge_index) = shape_edge_index {
+      self.graph.remove_edge(shape_edge_index);
+    }
+
+    // like fields, the node itself is kept around to not invalidate self.node_id_to_index
+    self.node_id_to_index.remove(&shape_constraint_id);
+  }
+
+  pub fn with_one_of_discriminator(
+    &mut self,
+    shape_id: ShapeId,
+    descriptor: OneOfDiscriminatorDescriptor,
+  ) {
+    let shape_node_index = *self
+      .get_shape_node_index(&shape_id)
+      .expect("expected shape_id of discriminator to have a corresponding node");
+
+    // a one-of has a single discriminator, so a previous one is replaced
+    let previous_edge_index = self
+      .graph
+      .edges_directed(shape_node_index, petgraph::Direction::Incoming)
+      .find(|edge| matches!(edge.weight(), Edge::IsDiscriminatorOf))
+      .map(|edge| edge.id());
+    if let Some(previous_edge_index) = previous_edge_index {
+      self.graph.remove_edge(previous_edge_index);
+    }
+
+    let discriminator_node = Node::OneOfDiscriminator(OneOfDiscriminatorNode {
+      shape_id,
+      descriptor,
+    });
+    let discriminator_node_index = self.graph.add_node(discriminator_node);
+    self.graph.add_edge(
+      discriminator_node_index,
+      shape_node_index,
+      Edge::IsDiscriminatorOf,
+    );
+  }
+
   pub fn get_shape_node_index(&self, node_id: &NodeId) -> Option<&NodeIndex> {
     let node_index = self.node_id_to_index.get(node_id)?;
     let node = self.graph.node_weight(*node_index);
@@ -484,6 +740,18 @@ impl ShapeProjection {
     }
   }
 
+  pub fn get_shape_constraint_node(
+    &self,
+    shape_constraint_id: &ShapeConstraintId,
+  ) -> Option<(&NodeIndex, &ShapeConstraintNode)> {
+    let node_index = self.node_id_to_index.get(shape_constraint_id)?;
+    let node = self.graph.node_weight(*node_index)?;
+    match node {
+      Node::ShapeConstraint(ref node) => Some((node_index, node)),
+      _ => None,
+    }
+  }
+
   pub fn get_shape_parameter_node_index(&self, node_id: &NodeId) -> Option<&NodeIndex> {
     let node_index = self.node_id_to_index.get(node_id)?;
     let node = self.graph.node_weight(*node_index);
@@ -584,6 +852,35 @@ impl ShapeProjection {
     }
   }
 
+  pub fn get_shape_constraint_nodes(
+    &self,
+    node_index: &NodeIndex,
+  ) -> impl Iterator<Item = &ShapeConstraintNode> {
+    let graph = &self.graph;
+    self
+      .graph
+      .edges_directed(*node_index, petgraph::Direction::Incoming)
+      .filter(|edge| matches!(edge.weight(), Edge::IsConstraintOf))
+      .filter_map(move |edge| match graph.node_weight(edge.source()) {
+        Some(Node::ShapeConstraint(node)) => Some(node),
+        _ => None,
+      })
+  }
+
+  pub fn get_one_of_discriminator(
+    &self,
+    node_index: &NodeIndex,
+  ) -> Option<&OneOfDiscriminatorDescriptor> {
+    self
+      .graph
+      .edges_directed(*node_index, petgraph::Direction::Incoming)
+      .filter(|edge| matches!(edge.weight(), Edge::IsDiscriminatorOf))
+      .find_map(|edge| match self.graph.node_weight(edge.source()) {
+        Some(Node::OneOfDiscriminator(node)) => Some(&node.descriptor),
+        _ => None,
+      })
+  }
+
   pub fn get_core_shape_kinds(
     &self,
     node_index: &NodeIndex,
@@ -626,17 +923,46 @@ impl AggregateEvent<ShapeProjection> for ShapeEvent {
           projection.with_creation_history(&c.client_command_batch_id, &e.shape_id);
         }
       }
+      ShapeEvent::ShapeRenamed(e) => {
+        projection.with_shape_name(e.shape_id.clone(), e.name);
+        if let Some(c) = e.event_context {
+          projection.with_update_history(&c.client_command_batch_id, &e.shape_id);
+        }
+      }
+      ShapeEvent::ShapeRemoved(e) => {
+        if let Some(c) = e.event_context {
+          projection.with_removal_history(&c.client_command_batch_id, &e.shape_id);
+        }
+        projection.without_shape(e.shape_id);
+      }
       ShapeEvent::ShapeParameterAdded(e) => {
-        projection.with_shape_parameter(e.shape_parameter_id.clone(), e.shape_id);
+        projection.with_shape_parameter(e.shape_parameter_id.clone(), e.shape_id, e.name);
         if let Some(c) = e.event_context {
           projection.with_creation_history(&c.client_command_batch_id, &e.shape_parameter_id);
         }
       }
+      ShapeEvent::ShapeParameterRenamed(e) => {
+        projection.with_shape_parameter_name(e.shape_parameter_id.clone(), e.name);
+        if let Some(c) = e.event_context {
+          projection.with_update_history(&c.client_command_batch_id, &e.shape_parameter_id);
+        }
+      }
+      ShapeEvent::ShapeParameterRemoved(e) => {
+        if let Some(c) = e.event_context {
+          projection.with_removal_history(&c.client_command_batch_id, &e.shape_parameter_id);
+        }
+        projection.without_shape_parameter(e.shape_parameter_id);
+      }
       ShapeEvent::ShapeParameterShapeSet(e) => {
         projection.with_shape_parameter_shape(e.shape_descriptor.clone());
         if let Some(c) = e.event_context {
-          if let ParameterShapeDescriptor::ProviderInShape(d) = &e.shape_descriptor {
-            projection.with_update_history(&c.client_command_batch_id, &d.shape_id);
+          match &e.shape_descriptor {
+            ParameterShapeDescriptor::ProviderInShape(d) => {
+              projection.with_update_history(&c.client_command_batch_id, &d.shape_id)
+            }
+            ParameterShapeDescriptor::ProviderInField(d) => {
+              projection.with_update_history(&c.client_command_batch_id, &d.field_id)
+            }
           }
         }
       }
@@ -651,14 +977,55 @@ impl AggregateEvent<ShapeProjection> for ShapeEvent {
         projection.with_field_shape(e.shape_descriptor.clone());
 
         if let Some(c) = e.event_context {
-          if let FieldShapeDescriptor::FieldShapeFromShape(d) = &e.shape_descriptor {
-            projection.with_update_history(&c.client_command_batch_id, &d.field_id);
+          match &e.shape_descriptor {
+            FieldShapeDescriptor::FieldShapeFromShape(d) => {
+              projection.with_update_history(&c.client_command_batch_id, &d.field_id)
+            }
+            FieldShapeDescriptor::FieldShapeFromParameter(d) => {
+              projection.with_update_history(&c.client_command_batch_id, &d.field_id)
+            }
           }
         }
       }
+      ShapeEvent::FieldRenamed(e) => {
+        projection.with_field_name(e.field_id.clone(), e.name);
+
+        if let Some(c) = e.event_context {
+          projection.with_update_history(&c.client_command_batch_id, &e.field_id);
+        }
+      }
       ShapeEvent::FieldRemoved(e) => {
+        if let Some(c) = e.event_context {
+          projection.with_removal_history(&c.client_command_batch_id, &e.field_id);
+        }
         projection.without_field(e.field_id);
-        // TODO: track removal history
+      }
+
+      ShapeEvent::ShapeConstraintAdded(e) => {
+        projection.with_shape_constraint(
+          e.shape_constraint_id.clone(),
+          e.shape_id.clone(),
+          e.descriptor,
+        );
+
+        if let Some(c) = e.event_context {
+          projection.with_creation_history(&c.client_command_batch_id, &e.shape_constraint_id);
+          projection.with_update_history(&c.client_command_batch_id, &e.shape_id);
+        }
+      }
+      ShapeEvent::ShapeConstraintRemoved(e) => {
+        if let Some(c) = e.event_context {
+          projection.with_removal_history(&c.client_command_batch_id, &e.shape_constraint_id);
+        }
+        projection.without_shape_constraint(e.shape_constraint_id);
+      }
+
+      ShapeEvent::OneOfDiscriminatorSet(e) => {
+        projection.with_one_of_discriminator(e.shape_id.clone(), e.descriptor);
+
+        if let Some(c) = e.event_context {
+          projection.with_update_history(&c.client_command_batch_id, &e.shape_id);
+        }
       }
 
       ShapeEvent::BaseShapeSet(e) => {
@@ -668,10 +1035,6 @@ impl AggregateEvent<ShapeProjection> for ShapeEvent {
           projection.with_update_history(&c.client_command_batch_id, &e.shape_id);
         }
       }
-      x => {
-        //dbg!("skipping ShapeEvent in ShapeProjection. warning?");
-        //dbg!(&x);
-      }
     }
   }
 }
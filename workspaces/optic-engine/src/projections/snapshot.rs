@@ -0,0 +1,355 @@
+use super::{CommitId, SpecProjection};
+use crate::events::{RfcEvent, SpecEvent};
+use cqrs_core::Aggregate;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use thiserror::Error;
+
+/// Bumped whenever the serialized layout of any of the projections changes, so snapshots taken
+/// by other versions are rejected rather than misread.
+const SNAPSHOT_FORMAT_VERSION: u32 = 9;
+
+/// A `SpecProjection` as of a completed batch commit, so it can be restored without replaying
+/// all events that led up to it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecProjectionSnapshot {
+  format_version: u32,
+  batch_commit_id: CommitId,
+  events_fingerprint: EventsFingerprint,
+  projection: SpecProjection,
+}
+
+impl SpecProjectionSnapshot {
+  /// Snapshot a projection. Only possible right after a batch commit has been completed, as
+  /// that's the only point we can find back in the events when restoring. The projection is
+  /// given back otherwise.
+  pub fn new(snapshottable: SnapshottableProjection) -> Result<Self, Box<SpecProjection>> {
+    let SnapshottableProjection {
+      projection,
+      events_fingerprint,
+    } = snapshottable;
+    let batch_commit_id = match projection.completed_batch_commit_id() {
+      Some(commit_id) => commit_id.clone(),
+      None => return Err(Box::new(projection)),
+    };
+
+    Ok(Self {
+      format_version: SNAPSHOT_FORMAT_VERSION,
+      batch_commit_id,
+      events_fingerprint,
+      projection,
+    })
+  }
+
+  pub fn batch_commit_id(&self) -> &CommitId {
+    &self.batch_commit_id
+  }
+
+  pub fn projection(&self) -> &SpecProjection {
+    &self.projection
+  }
+
+  pub fn into_projection(self) -> SpecProjection {
+    self.projection
+  }
+
+  pub fn to_writer(&self, writer: impl Write) -> Result<(), SpecProjectionSnapshotError> {
+    serde_json::to_writer(writer, self)?;
+    Ok(())
+  }
+
+  pub fn from_reader(reader: impl Read) -> Result<Self, SpecProjectionSnapshotError> {
+    let snapshot: Self = serde_json::from_reader(reader)?;
+    if snapshot.format_version != SNAPSHOT_FORMAT_VERSION {
+      return Err(SpecProjectionSnapshotError::UnsupportedVersion(
+        snapshot.format_version,
+      ));
+    }
+    Ok(snapshot)
+  }
+
+  /// Restore the projection from the full log of events, applying only those following the
+  /// snapshotted batch commit. Returns `None` when that batch commit isn't part of the events, or
+  /// the events leading up to it aren't exactly those the snapshot was taken from (like when a
+  /// batch got inserted before it, or an earlier one was edited). The snapshot is stale then, and
+  /// all events have to be replayed.
+  pub fn restore<E>(
+    self,
+    events: impl IntoIterator<Item = Result<SpecEvent, E>>,
+  ) -> Result<Option<SnapshottableProjection>, E> {
+    let mut restoring = self.into_restoring();
+    for event in events {
+      restoring.apply(event?);
+    }
+    Ok(restoring.finish())
+  }
+
+  pub(crate) fn into_restoring(self) -> RestoringSnapshot {
+    RestoringSnapshot {
+      batch_commit_id: self.batch_commit_id,
+      snapshotted: SnapshottableProjection {
+        projection: self.projection,
+        events_fingerprint: self.events_fingerprint,
+      },
+      skipped_events_fingerprint: EventsFingerprint::default(),
+      is_caught_up: false,
+    }
+  }
+}
+
+/// A `SpecProjection` along with a fingerprint of all events applied to it, as needed to snapshot
+/// it. Kept apart from the projection itself, so only those loading snapshots pay for it.
+#[derive(Debug, Default)]
+pub struct SnapshottableProjection {
+  projection: SpecProjection,
+  events_fingerprint: EventsFingerprint,
+}
+
+impl SnapshottableProjection {
+  pub fn apply(&mut self, event: SpecEvent) {
+    self.events_fingerprint.chain(&event);
+    self.projection.apply(event);
+  }
+
+  pub fn projection(&self) -> &SpecProjection {
+    &self.projection
+  }
+
+  pub fn into_projection(self) -> SpecProjection {
+    self.projection
+  }
+}
+
+impl<I> From<I> for SnapshottableProjection
+where
+  I: IntoIterator<Item = SpecEvent>,
+{
+  fn from(events: I) -> Self {
+    let mut snapshottable = Self::default();
+    for event in events {
+      snapshottable.apply(event);
+    }
+    snapshottable
+  }
+}
+
+/// Events chained into a single hash, so a snapshot can tell whether the events leading up to it
+/// are still the same. Hashed with seahash over the events as json, which unlike the std hashers
+/// is specified to be stable, as the fingerprints are persisted with the snapshots.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct EventsFingerprint(u64);
+
+impl EventsFingerprint {
+  fn chain(&mut self, event: &SpecEvent) {
+    let mut bytes = self.0.to_le_bytes().to_vec();
+    serde_json::to_writer(&mut bytes, event).expect("spec events should serialize");
+    self.0 = seahash::hash(&bytes);
+  }
+}
+
+/// Applies events to a snapshotted projection once the events leading up to the snapshot have
+/// been skipped, as long as those are the very events the snapshot was taken from.
+pub(crate) struct RestoringSnapshot {
+  batch_commit_id: CommitId,
+  snapshotted: SnapshottableProjection,
+  skipped_events_fingerprint: EventsFingerprint,
+  is_caught_up: bool,
+}
+
+impl RestoringSnapshot {
+  pub(crate) fn apply(&mut self, event: SpecEvent) {
+    if self.is_caught_up {
+      self.snapshotted.apply(event);
+      return;
+    }
+
+    self.skipped_events_fingerprint.chain(&event);
+    if let SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(e)) = &event {
+      self.is_caught_up = e.batch_id == self.batch_commit_id
+        && self.skipped_events_fingerprint == self.snapshotted.events_fingerprint;
+    }
+  }
+
+  pub(crate) fn finish(self) -> Option<SnapshottableProjection> {
+    if self.is_caught_up {
+      Some(self.snapshotted)
+    } else {
+      None
+    }
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum SpecProjectionSnapshotError {
+  #[error("snapshot could not be (de)serialized: {}", .0)]
+  Json(#[from] serde_json::Error),
+  #[error("snapshot format version {} is not supported", .0)]
+  UnsupportedVersion(u32),
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::queries::EndpointQueries;
+  use insta::assert_debug_snapshot;
+  use serde_json::json;
+
+  // a batch adding a path with a GET request
+  fn path_batch_events(
+    batch_id: &str,
+    parent_id: &str,
+    path_id: &str,
+    name: &str,
+  ) -> Vec<SpecEvent> {
+    serde_json::from_value(json!([
+      {"BatchCommitStarted":{"batchId":batch_id,"parentId":parent_id,"commitMessage":"","eventContext":{
+        "clientId":"anonymous","clientSessionId":"session","clientCommandBatchId":batch_id,"createdAt":"2021-01-01T00:00:00Z"
+      }}},
+      {"PathComponentAdded":{"pathId":path_id,"parentPathId":"root","name":name}},
+      {"RequestAdded":{"requestId":format!("{}_request",path_id),"pathId":path_id,"httpMethod":"GET"}},
+      {"BatchCommitEnded":{"batchId":batch_id}},
+    ]))
+    .expect("example events should be valid spec events")
+  }
+
+  #[test]
+  pub fn restores_snapshot_by_applying_later_events() {
+    let first_batch = path_batch_events("batch_1", "root", "path_1", "todos");
+    let second_batch = path_batch_events("batch_2", "batch_1", "path_2", "users");
+    let all_events = first_batch
+      .iter()
+      .chain(second_batch.iter())
+      .cloned()
+      .collect::<Vec<_>>();
+
+    let snapshot = SpecProjectionSnapshot::new(SnapshottableProjection::from(first_batch))
+      .expect("projection should be snapshottable after a completed batch commit");
+    assert_eq!(snapshot.batch_commit_id(), "batch_1");
+
+    let mut serialized = vec![];
+    snapshot.to_writer(&mut serialized).unwrap();
+    let snapshot = SpecProjectionSnapshot::from_reader(&serialized[..]).unwrap();
+
+    let restored = snapshot
+      .restore(all_events.clone().into_iter().map(Ok::<_, ()>))
+      .unwrap()
+      .expect("snapshotted batch commit should be found in events");
+    let replayed = SpecProjection::from(all_events);
+
+    let endpoints = |projection: &SpecProjection| {
+      let queries = EndpointQueries::new(projection.endpoint());
+      vec!["path_1", "path_2"]
+        .into_iter()
+        .map(|path_id| {
+          queries
+            .resolve_requests(&String::from(path_id), &String::from("GET"))
+            .map(|requests| requests.count())
+        })
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(endpoints(restored.projection()), endpoints(&replayed));
+    assert_eq!(endpoints(restored.projection()), vec![Some(1), Some(1)]);
+    assert_eq!(
+      restored.projection().completed_batch_commit_id(),
+      Some(&String::from("batch_2"))
+    );
+  }
+
+  #[test]
+  pub fn fingerprints_events_stably() {
+    let snapshot = SpecProjectionSnapshot::new(SnapshottableProjection::from(path_batch_events(
+      "batch_1", "root", "path_1", "todos",
+    )))
+    .unwrap();
+
+    // persisted with snapshots, so has to stay the same across builds and platforms
+    let mut serialized = vec![];
+    snapshot.to_writer(&mut serialized).unwrap();
+    let serialized: serde_json::Value = serde_json::from_slice(&serialized).unwrap();
+    assert_debug_snapshot!(serialized["eventsFingerprint"]);
+  }
+
+  #[test]
+  pub fn cannot_snapshot_outside_of_batch_commits() {
+    let mut events = path_batch_events("batch_1", "root", "path_1", "todos");
+    events.pop();
+
+    assert!(SpecProjectionSnapshot::new(SnapshottableProjection::from(events)).is_err());
+  }
+
+  #[test]
+  pub fn stale_snapshot_is_not_restored() {
+    let snapshot = SpecProjectionSnapshot::new(SnapshottableProjection::from(path_batch_events(
+      "batch_1", "root", "path_1", "todos",
+    )))
+    .unwrap();
+
+    let restored = snapshot
+      .restore(
+        path_batch_events("batch_2", "root", "path_2", "users")
+          .into_iter()
+          .map(Ok::<_, ()>),
+      )
+      .unwrap();
+
+    assert!(restored.is_none());
+  }
+
+  #[test]
+  pub fn snapshot_is_not_restored_when_earlier_events_changed() {
+    let first_batch = path_batch_events("batch_1", "root", "path_1", "todos");
+    let second_batch = path_batch_events("batch_2", "batch_1", "path_2", "users");
+    let snapshot = || {
+      SpecProjectionSnapshot::new(SnapshottableProjection::from(
+        first_batch
+          .iter()
+          .chain(second_batch.iter())
+          .cloned()
+          .collect::<Vec<_>>(),
+      ))
+      .unwrap()
+    };
+
+    // a branch off the first batch, of which the chunk sorts before the snapshotted one
+    let branch_batch = path_batch_events("batch_0", "batch_1", "path_3", "teams");
+    let branched_events = first_batch
+      .iter()
+      .chain(branch_batch.iter())
+      .chain(second_batch.iter())
+      .cloned()
+      .map(Ok::<_, ()>);
+    let restored = snapshot().restore(branched_events).unwrap();
+    assert!(
+      restored.is_none(),
+      "batches inserted before the snapshotted one should invalidate the snapshot"
+    );
+
+    let edited_batch = path_batch_events("batch_1", "root", "path_1", "tasks");
+    let edited_events = edited_batch
+      .iter()
+      .chain(second_batch.iter())
+      .cloned()
+      .map(Ok::<_, ()>);
+    let restored = snapshot().restore(edited_events).unwrap();
+    assert!(
+      restored.is_none(),
+      "edits to batches before the snapshotted one should invalidate the snapshot"
+    );
+
+    let unchanged_events = first_batch
+      .iter()
+      .chain(second_batch.iter())
+      .chain(branch_batch.iter())
+      .cloned()
+      .map(Ok::<_, ()>);
+    let restored = snapshot()
+      .restore(unchanged_events)
+      .unwrap()
+      .expect("batches after the snapshotted one should be applied to the snapshot");
+    assert_eq!(
+      restored.projection().completed_batch_commit_id(),
+      Some(&String::from("batch_0"))
+    );
+  }
+}
@@ -1,23 +1,22 @@
-use crate::events::spec_chunk::{BatchChunkEvent, RootChunkEvent};
+use crate::events::spec_chunk::{BatchChunkEvent, BatchChunkOrder, RootChunkEvent};
 use crate::events::SpecChunkEvent;
 use crate::SpecEvent;
 use std::error::Error;
 use std::fmt;
 
 use cqrs_core::{Aggregate, AggregateEvent};
-use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct SpecAssemblerProjection {
   root_chunk: Option<RootChunkEvent>,
-  chunks_by_parent_id: HashMap<String, Vec<BatchChunkEvent>>,
+  batch_chunks: BatchChunkOrder<BatchChunkEvent>,
 }
 
 impl Default for SpecAssemblerProjection {
   fn default() -> Self {
     Self {
       root_chunk: None,
-      chunks_by_parent_id: HashMap::new(),
+      batch_chunks: BatchChunkOrder::default(),
     }
   }
 }
@@ -32,39 +31,30 @@ impl SpecAssemblerProjection {
   }
 
   pub fn with_batch_chunk(&mut self, chunk: BatchChunkEvent) {
-    let batch_chunks_for_parent = self
-      .chunks_by_parent_id
-      .entry(chunk.parent_id.clone())
-      .or_insert_with(|| Vec::new());
-
-    batch_chunks_for_parent.push(chunk);
+    let parent_ids = chunk.parent_ids().cloned().collect();
+    self
+      .batch_chunks
+      .with_chunk(chunk.id.clone(), chunk.name.clone(), parent_ids, chunk);
   }
 
+  /// Events of the root chunk, followed by those of every batch chunk after the batches it
+  /// builds on. Chunks building on batches that can't be found are left out.
   // TODO: implement returning of an Iterator instead of Vec
   pub fn into_events(self) -> Result<Vec<SpecEvent>, SpecAssemblerError> {
     let root_chunk = self
       .root_chunk
       .ok_or_else(|| SpecAssemblerError::RootChunkRequired)?;
 
-    let mut current_chunk_id = root_chunk.last_batch_id().clone();
-    let mut chunks = vec![SpecChunkEvent::Root(root_chunk)];
-    let mut chunks_by_parent_id = self.chunks_by_parent_id;
-
-    loop {
-      let children = chunks_by_parent_id.remove(&current_chunk_id);
-      if let None = children {
-        break;
-      }
-      let mut children = children.unwrap();
-
-      if children.len() != 1 {
-        // if there are multiple children to a parent
-        break; // TODO: decide whether for this projection we need to do anything more
-      }
-      let child_chunk = children.pop().unwrap();
+    // chunks build on the root itself only when the root chunk doesn't have any batches
+    let mut batch_chunks = self.batch_chunks;
+    batch_chunks.with_completed_batch(root_chunk.last_batch_id().clone());
+    for batch_id in root_chunk.batch_ids() {
+      batch_chunks.with_completed_batch(batch_id.clone());
+    }
 
-      current_chunk_id = child_chunk.id.clone();
-      chunks.push(SpecChunkEvent::Batch(child_chunk));
+    let mut chunks = vec![SpecChunkEvent::Root(root_chunk)];
+    while let Some(batch_chunk) = batch_chunks.next_chunk() {
+      chunks.push(SpecChunkEvent::Batch(batch_chunk));
     }
 
     let events = chunks
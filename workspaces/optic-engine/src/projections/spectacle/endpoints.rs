@@ -1,7 +1,7 @@
 use crate::events::{EndpointEvent, Event, ShapeEvent, SpecEvent};
 use crate::projections::endpoint::ROOT_PATH_ID;
 use crate::state::endpoint::{
-  HttpContentType, HttpMethod, HttpStatusCode, PathComponentId, QueryParametersId,
+  HttpContentType, HttpMethod, HttpStatusSpecifier, PathComponentId, QueryParametersId,
   QueryParametersShapeDescriptor, RequestId, ResponseId,
 };
 use crate::state::shape::ShapeId;
@@ -20,7 +20,7 @@ fn get_endpoint_id(path_id: &str, method: &str) -> String {
   format!("{}.{}", path_id, method.to_uppercase())
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EndpointsProjection {
   pub graph: Graph<Node, Edge>,
 
@@ -151,7 +151,31 @@ impl AggregateEvent<EndpointsProjection> for EndpointEvent {
           e.response_id.clone(),
           e.path_id,
           e.http_method,
-          e.http_status_code,
+          HttpStatusSpecifier::Code(e.http_status_code),
+        );
+
+        if let Some(c) = e.event_context {
+          projection.with_creation_history(c.client_command_batch_id, e.response_id);
+        }
+      }
+      EndpointEvent::ResponseRangeAddedByPathAndMethod(e) => {
+        projection.with_response(
+          e.response_id.clone(),
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Range(e.http_status_range),
+        );
+
+        if let Some(c) = e.event_context {
+          projection.with_creation_history(c.client_command_batch_id, e.response_id);
+        }
+      }
+      EndpointEvent::DefaultResponseAddedByPathAndMethod(e) => {
+        projection.with_response(
+          e.response_id.clone(),
+          e.path_id,
+          e.http_method,
+          HttpStatusSpecifier::Default,
         );
 
         if let Some(c) = e.event_context {
@@ -175,6 +199,14 @@ impl AggregateEvent<EndpointsProjection> for EndpointEvent {
           projection.with_creation_history(c.client_command_batch_id, e.body_descriptor.shape_id);
         }
       }
+      EndpointEvent::RequestBodyUnset(e) => {
+        let removed_shape_ids = projection.without_bodies(&e.request_id);
+        if let Some(c) = e.event_context {
+          for shape_id in removed_shape_ids {
+            projection.with_remove_history(c.client_command_batch_id.clone(), shape_id);
+          }
+        }
+      }
       EndpointEvent::QueryParametersAdded(e) => {
         projection.with_query_parameters(e.path_id, e.http_method, e.query_parameters_id);
       }
@@ -202,6 +234,14 @@ impl AggregateEvent<EndpointsProjection> for EndpointEvent {
           projection.with_creation_history(c.client_command_batch_id, e.body_descriptor.shape_id);
         }
       }
+      EndpointEvent::ResponseBodyUnset(e) => {
+        let removed_shape_ids = projection.without_bodies(&e.response_id);
+        if let Some(c) = e.event_context {
+          for shape_id in removed_shape_ids {
+            projection.with_remove_history(c.client_command_batch_id.clone(), shape_id);
+          }
+        }
+      }
       _ => eprintln!(
         "Ignoring applying event of type '{}' for '{}'",
         self.event_type(),
@@ -486,7 +526,7 @@ impl EndpointsProjection {
     response_id: ResponseId,
     path_id: PathComponentId,
     http_method: HttpMethod,
-    http_status_code: HttpStatusCode,
+    http_status_code: HttpStatusSpecifier,
   ) {
     let endpoint_index = self.ensure_endpoint_node_index(path_id, http_method);
 
@@ -571,7 +611,11 @@ impl EndpointsProjection {
                 Some(Node::Body(body_node))
                   if &body_node.http_content_type == &http_content_type =>
                 {
-                  Some((child_edge.id(), request_node.request_id.clone()))
+                  Some((
+                    child_edge.id(),
+                    request_child_edge.id(),
+                    request_node.request_id.clone(),
+                  ))
                 }
                 _ => None,
               }
@@ -581,9 +625,14 @@ impl EndpointsProjection {
         }
       });
 
-    if let Some((request_edge, request_id)) = maybe_request {
-      self.graph.remove_edge(request_edge);
-      self.domain_id_to_index.remove(&request_id);
+    if let Some((request_edge, body_edge, existing_request_id)) = maybe_request {
+      if existing_request_id == request_id {
+        // a request whose body is set again keeps its node, only orphaning its previous body
+        self.graph.remove_edge(body_edge);
+      } else {
+        self.graph.remove_edge(request_edge);
+        self.domain_id_to_index.remove(&existing_request_id);
+      }
     }
 
     let node_index = self.graph.add_node(node);
@@ -740,7 +789,11 @@ impl EndpointsProjection {
                   Some(Node::Body(body_node))
                     if &body_node.http_content_type == &http_content_type =>
                   {
-                    Some((child_edge.id(), response_node.response_id.clone()))
+                    Some((
+                      child_edge.id(),
+                      response_child_edge.id(),
+                      response_node.response_id.clone(),
+                    ))
                   }
                   _ => None,
                 }
@@ -750,9 +803,14 @@ impl EndpointsProjection {
         }
       });
 
-    if let Some((response_edge, response_id)) = maybe_response {
-      self.graph.remove_edge(response_edge);
-      self.domain_id_to_index.remove(&response_id);
+    if let Some((response_edge, body_edge, existing_response_id)) = maybe_response {
+      if existing_response_id == response_id {
+        // a response whose body is set again keeps its node, only orphaning its previous body
+        self.graph.remove_edge(body_edge);
+      } else {
+        self.graph.remove_edge(response_edge);
+        self.domain_id_to_index.remove(&existing_response_id);
+      }
     }
 
     let node_index = self.graph.add_node(node);
@@ -764,6 +822,35 @@ impl EndpointsProjection {
       .add_edge(node_index, response_index, Edge::IsChildOf);
   }
   ////////////////////////////////////////////////////////////////////////////////////////////////////
+  /// Orphan the bodies of a request or response, returning the root shape ids they had
+  pub fn without_bodies(&mut self, parent_id: &str) -> Vec<ShapeId> {
+    let parent_index = *self
+      .domain_id_to_index
+      .get(parent_id)
+      .expect("expected node with domain_id $parent_id to exist in the graph");
+
+    let mut body_edges = self
+      .graph
+      .edges_directed(parent_index, petgraph::Direction::Incoming)
+      .filter_map(
+        |child_edge| match self.graph.node_weight(child_edge.source()) {
+          Some(Node::Body(body_node)) => Some((child_edge.id(), body_node.root_shape_id.clone())),
+          _ => None,
+        },
+      )
+      .collect::<Vec<_>>();
+    // removing an edge moves the last one into its place, so the last ones are removed first
+    body_edges.sort_by_key(|(body_edge, _)| std::cmp::Reverse(*body_edge));
+
+    body_edges
+      .into_iter()
+      .map(|(body_edge, root_shape_id)| {
+        self.graph.remove_edge(body_edge);
+        root_shape_id
+      })
+      .collect()
+  }
+  ////////////////////////////////////////////////////////////////////////////////////////////////////
   pub fn with_batch_commit(
     &mut self,
     batch_id: String,
@@ -778,6 +865,21 @@ impl EndpointsProjection {
     let node_index = self.graph.add_node(node);
     self.domain_id_to_index.insert(batch_id, node_index);
   }
+  /// Whether the history has anything created, updated or removed by a batch commit
+  pub fn has_changes_in_batch_commit(&self, batch_id: &str) -> bool {
+    match self.domain_id_to_index.get(batch_id) {
+      Some(batch_node_index) => self
+        .graph
+        .edges_directed(*batch_node_index, petgraph::Direction::Incoming)
+        .any(|edge| {
+          matches!(
+            edge.weight(),
+            Edge::CreatedIn | Edge::UpdatedIn | Edge::RemovedIn
+          )
+        }),
+      None => false,
+    }
+  }
   pub fn with_creation_history(&mut self, batch_id: String, created_node_id: String) {
     let created_node_index = self
       .domain_id_to_index
@@ -889,7 +991,7 @@ pub struct QueryParametersNode {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ResponseNode {
-  http_status_code: HttpStatusCode,
+  http_status_code: HttpStatusSpecifier,
   response_id: ResponseId,
   is_removed: bool,
 }
@@ -0,0 +1,187 @@
+use crate::projections::conflicts::{ConflictsProjection, Node};
+use crate::state::endpoint::{
+  HttpContentType, HttpMethod, HttpStatusSpecifier, PathComponentId, RequestId, ResponseId,
+};
+use crate::state::shape::{FieldId, ShapeId};
+use petgraph::graph::NodeIndex;
+use serde::Serialize;
+
+pub struct ConflictsQueries<'a> {
+  conflicts_projection: &'a ConflictsProjection,
+}
+
+impl<'a> From<&'a ConflictsProjection> for ConflictsQueries<'a> {
+  fn from(conflicts_projection: &'a ConflictsProjection) -> Self {
+    ConflictsQueries {
+      conflicts_projection,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum Conflict {
+  #[serde(rename_all = "camelCase")]
+  PathComponents {
+    absolute_path_pattern: String,
+    path_ids: Vec<PathComponentId>,
+  },
+  #[serde(rename_all = "camelCase")]
+  Requests {
+    absolute_path_pattern: String,
+    http_method: HttpMethod,
+    http_content_type: Option<HttpContentType>,
+    request_ids: Vec<RequestId>,
+  },
+  #[serde(rename_all = "camelCase")]
+  Responses {
+    absolute_path_pattern: String,
+    http_method: HttpMethod,
+    http_status_code: HttpStatusSpecifier,
+    http_content_type: Option<HttpContentType>,
+    response_ids: Vec<ResponseId>,
+  },
+  #[serde(rename_all = "camelCase")]
+  Fields {
+    shape_id: ShapeId,
+    field_name: String,
+    field_ids: Vec<FieldId>,
+  },
+}
+
+impl<'a> ConflictsQueries<'a> {
+  /// Every place where several ids describe the same thing, like path components matching the
+  /// same path pattern or requests for the same method and content type, in the order they were
+  /// first added
+  pub fn list_conflicts(&self) -> Vec<Conflict> {
+    let graph = &self.conflicts_projection.graph;
+
+    graph
+      .node_indices()
+      .filter_map(|node_index| {
+        let node = graph.node_weight(node_index)?;
+        let ids = node.ids().filter(|ids| ids.len() > 1)?.clone();
+
+        match node {
+          Node::Path(absolute_path_pattern, _) => Some(Conflict::PathComponents {
+            absolute_path_pattern: absolute_path_pattern.clone(),
+            path_ids: ids,
+          }),
+          Node::HttpContentType(http_content_type, _) => {
+            let parent_index = self.parent_node_index(node_index)?;
+            match graph.node_weight(parent_index)? {
+              Node::HttpMethod(http_method, _) => Some(Conflict::Requests {
+                absolute_path_pattern: self.absolute_path_pattern(parent_index)?,
+                http_method: http_method.clone(),
+                http_content_type: http_content_type.clone(),
+                request_ids: ids,
+              }),
+              Node::HttpStatus(http_status_code, _) => {
+                let method_index = self.parent_node_index(parent_index)?;
+                match graph.node_weight(method_index)? {
+                  Node::HttpMethod(http_method, _) => Some(Conflict::Responses {
+                    absolute_path_pattern: self.absolute_path_pattern(method_index)?,
+                    http_method: http_method.clone(),
+                    http_status_code: *http_status_code,
+                    http_content_type: http_content_type.clone(),
+                    response_ids: ids,
+                  }),
+                  _ => None,
+                }
+              }
+              _ => None,
+            }
+          }
+          Node::FieldName(field_name, _) => {
+            match graph.node_weight(self.parent_node_index(node_index)?)? {
+              Node::Shape(shape_id) => Some(Conflict::Fields {
+                shape_id: shape_id.clone(),
+                field_name: field_name.clone(),
+                field_ids: ids,
+              }),
+              _ => None,
+            }
+          }
+          // requests and responses for the same method or status code only conflict when they
+          // share a content type
+          Node::HttpMethod(_, _) | Node::HttpStatus(_, _) | Node::Shape(_) => None,
+        }
+      })
+      .collect()
+  }
+
+  fn parent_node_index(&self, node_index: NodeIndex) -> Option<NodeIndex> {
+    self.conflicts_projection.get_parent_node_index(node_index)
+  }
+
+  fn absolute_path_pattern(&self, method_index: NodeIndex) -> Option<String> {
+    let path_index = self.parent_node_index(method_index)?;
+    match self.conflicts_projection.graph.node_weight(path_index)? {
+      Node::Path(absolute_path_pattern, _) => Some(absolute_path_pattern.clone()),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::events::SpecEvent;
+  use insta::assert_debug_snapshot;
+  use serde_json::json;
+
+  #[test]
+  pub fn can_list_conflicts_of_merged_spec_changes() {
+    let events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+      {"PathParameterAdded":{"pathId":"path_2","parentPathId":"path_1","name":"userId"}},
+      {"PathComponentAdded":{"pathId":"path_3","parentPathId":"root","name":"users"}},
+      {"PathParameterAdded":{"pathId":"path_4","parentPathId":"path_3","name":"id"}},
+
+      {"RequestAdded":{"requestId":"request_1","pathId":"path_2","httpMethod":"GET"}},
+      {"RequestAdded":{"requestId":"request_2","pathId":"path_4","httpMethod":"GET"}},
+      {"RequestAdded":{"requestId":"request_3","pathId":"path_1","httpMethod":"POST"}},
+      {"RequestBodySet":{"requestId":"request_3","bodyDescriptor":{"httpContentType":"application/json","shapeId":"shape_1","isRemoved":false}}},
+      {"RequestAdded":{"requestId":"request_4","pathId":"path_3","httpMethod":"POST"}},
+      {"RequestBodySet":{"requestId":"request_4","bodyDescriptor":{"httpContentType":"text/plain","shapeId":"shape_2","isRemoved":false}}},
+
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_1","pathId":"path_2","httpMethod":"GET","httpStatusCode":200}},
+      {"ResponseBodySet":{"responseId":"response_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"shape_3","isRemoved":false}}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_2","pathId":"path_4","httpMethod":"GET","httpStatusCode":404}},
+      {"ResponseBodySet":{"responseId":"response_2","bodyDescriptor":{"httpContentType":"application/json","shapeId":"shape_4","isRemoved":false}}},
+      {"ResponseStatusCodeSet":{"responseId":"response_2","httpStatusCode":200}},
+
+      {"ShapeAdded":{"shapeId":"shape_3","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"shape_3","name":"email","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"$string"}}}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"shape_3","name":"mail","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"$string"}}}},
+      {"FieldRenamed":{"fieldId":"field_2","name":"email"}},
+    ]))
+    .expect("should be able to deserialize test events");
+
+    let projection = ConflictsProjection::from(events);
+    let conflicts = ConflictsQueries::from(&projection).list_conflicts();
+    assert_debug_snapshot!(
+      "can_list_conflicts_of_merged_spec_changes__conflicts",
+      conflicts
+    );
+    assert_eq!(conflicts.len(), 5);
+
+    let resolving_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"RequestRemoved":{"requestId":"request_2"}},
+      {"ResponseRemoved":{"responseId":"response_2"}},
+      {"PathParameterRemoved":{"pathId":"path_4","name":"id"}},
+      {"FieldRemoved":{"fieldId":"field_2"}},
+    ]))
+    .expect("should be able to deserialize test events");
+
+    let mut projection = projection;
+    for event in resolving_events {
+      cqrs_core::Aggregate::apply(&mut projection, event);
+    }
+    let conflicts = ConflictsQueries::from(&projection).list_conflicts();
+    assert_debug_snapshot!(
+      "can_list_conflicts_of_merged_spec_changes__resolved_conflicts",
+      conflicts
+    );
+    assert_eq!(conflicts.len(), 1);
+  }
+}
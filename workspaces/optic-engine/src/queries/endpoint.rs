@@ -1,14 +1,18 @@
 use crate::commands::{EndpointCommand, SpecCommand};
 use crate::events::HttpInteraction;
-use crate::projections::endpoint::{Edge, EndpointProjection, Node, ROOT_PATH_ID};
 use crate::projections::endpoint::{
-  QueryParametersDescriptor, RequestDescriptor, ResponseBodyDescriptor,
+  Edge, EndpointProjection, Node, PathComponentDescriptor, ROOT_PATH_ID,
 };
+use crate::projections::endpoint::{
+  HeaderParameterDescriptor, QueryParametersDescriptor, RequestDescriptor, ResponseBodyDescriptor,
+};
+use crate::queries::shape::ShapeQueries;
 use crate::state::endpoint::{
-  HttpMethod, HttpStatusCode, PathComponentId, PathComponentIdRef, QueryParametersId, RequestId,
-  ResponseId,
+  best_content_type_match, HttpMethod, HttpStatusCode, PathComponentId, PathComponentIdRef,
+  QueryParametersId, RequestId, RequestParameterId, ResponseId,
 };
-use petgraph::graph::Graph;
+use crate::state::shape::ShapeKind;
+use petgraph::graph::{Graph, NodeIndex};
 use petgraph::visit::{
   depth_first_search, Control, DfsEvent, EdgeFilteredNeighborsDirected, Reversed,
 };
@@ -40,71 +44,175 @@ impl<'a> EndpointQueries<'a> {
     &self,
     interaction: &HttpInteraction,
   ) -> Option<PathComponentIdRef> {
-    self.resolve_path(&interaction.request.path)
+    self
+      .resolve_path_match(
+        &interaction.request.path,
+        Some(&interaction.request.method),
+        None,
+      )
+      .map(|path_match| path_match.path_id)
+  }
+
+  pub fn resolve_path(&self, path: &str) -> Option<PathComponentIdRef<'a>> {
+    self
+      .resolve_path_match(path, None, None)
+      .map(|path_match| path_match.path_id)
+  }
+
+  pub fn resolve_interaction_path_match(
+    &self,
+    interaction: &HttpInteraction,
+    shape_queries: Option<&ShapeQueries>,
+  ) -> Option<PathMatch<'a>> {
+    self.resolve_path_match(
+      &interaction.request.path,
+      Some(&interaction.request.method),
+      shape_queries,
+    )
   }
 
-  pub fn resolve_path(&self, path: &str) -> Option<PathComponentIdRef> {
+  /// Resolve a path to the best matching path component. Every combination of path components
+  /// matching the segments of the path is considered, ranking them by how specifically each
+  /// segment matched, earlier segments first: literal names before parameters, and parameters
+  /// whose value fits their shape before untyped parameters. Parameters whose value doesn't
+  /// fit their shape still match, but only when no better candidate exists. Equally ranked
+  /// candidates are told apart by whether their path has a request for the `method`.
+  ///
+  /// Shapes of path parameters are only considered when `shape_queries` are provided.
+  pub fn resolve_path_match(
+    &self,
+    path: &str,
+    method: Option<&str>,
+    shape_queries: Option<&ShapeQueries>,
+  ) -> Option<PathMatch<'a>> {
     if path.eq("/") {
-      return Some(ROOT_PATH_ID);
+      return Some(PathMatch {
+        path_id: ROOT_PATH_ID,
+        parameters: vec![],
+      });
     }
 
     let path = Self::extract_normalized_path(path);
-    // eprintln!("{}", path);
-    let mut path_components = path.split('/');
     // skip leading empty
-    path_components.next();
-    let mut last_resolved_path_id = Some(ROOT_PATH_ID);
-    while let Some(s) = path_components.next() {
-      // eprintln!("trying to match segment {}", s);
-      let node_index = self
-        .graph_get_index(last_resolved_path_id.unwrap())
-        .expect("expected a node with node_id to exist");
-
-      last_resolved_path_id = None;
-
-      // first search for named path components
-      let children = self.graph_get_children(node_index);
-      for child in children {
-        let child_node = self.endpoint_projection.graph.node_weight(child).unwrap();
-
-        match child_node {
-          Node::PathComponent(id, descriptor) => {
-            // eprintln!("1 - neighbor {}", descriptor.name);
-            if !descriptor.is_parameter {
-              if descriptor.name == s {
-                last_resolved_path_id = Some(id);
-                break;
-              }
-            }
-          }
-          _ => {}
-        }
-      }
-      if let Some(x) = last_resolved_path_id {
-        continue;
+    let segments = path.split('/').skip(1).collect::<Vec<_>>();
+    let root_node_index = self
+      .graph_get_index(ROOT_PATH_ID)
+      .expect("a root path component node should exist");
+
+    let best_candidate = self.best_path_candidate(
+      *root_node_index,
+      &segments,
+      method,
+      shape_queries,
+      &mut HashMap::new(),
+    )?;
+    let graph = &self.endpoint_projection.graph;
+    let mut path_match = PathMatch {
+      path_id: ROOT_PATH_ID,
+      parameters: vec![],
+    };
+    for (segment, (node_index, rank)) in segments.iter().zip(best_candidate.components) {
+      let (path_id, descriptor) = match graph.node_weight(node_index) {
+        Some(Node::PathComponent(path_id, descriptor)) => (path_id, descriptor),
+        _ => unreachable!("candidates should only consist of path components"),
+      };
+      if descriptor.is_parameter {
+        path_match.parameters.push(PathParameterMatch {
+          path_id,
+          name: &descriptor.name,
+          value: String::from(*segment),
+          fits_shape: rank != SegmentMatchRank::UnfitParameter,
+        });
       }
-      // try path parameters since we didn't find any matches in regular path components
-      let children = self.graph_get_children(node_index);
-      for child in children {
-        let child_node = self.endpoint_projection.graph.node_weight(child).unwrap();
-        match child_node {
-          Node::PathComponent(id, descriptor) => {
-            // eprintln!("2 - neighbor {}", descriptor.name);
-
-            if descriptor.is_parameter {
-              last_resolved_path_id = Some(id);
-              break;
-            }
-          }
-          _ => {}
-        }
+      path_match.path_id = path_id;
+    }
+
+    Some(path_match)
+  }
+
+  /// The best candidate of path components below the one at `node_index` to match the
+  /// remaining segments. How the remaining segments are best matched doesn't depend on how the
+  /// preceding ones were, so candidates are memoized by component and the segments left.
+  fn best_path_candidate(
+    &self,
+    node_index: NodeIndex,
+    segments: &[&str],
+    method: Option<&str>,
+    shape_queries: Option<&ShapeQueries>,
+    best_candidates: &mut HashMap<(NodeIndex, usize), Option<PathCandidate>>,
+  ) -> Option<PathCandidate> {
+    let (segment, remaining_segments) = match segments.split_first() {
+      Some(split) => split,
+      None => {
+        return Some(PathCandidate {
+          components: vec![],
+          has_method_request: matches!(
+            method,
+            Some(method) if self.has_requests_for_method(node_index, method)
+          ),
+        })
       }
-      if let Some(x) = last_resolved_path_id {
+    };
+    if let Some(best_candidate) = best_candidates.get(&(node_index, segments.len())) {
+      return best_candidate.clone();
+    }
+
+    let mut best_candidate: Option<PathCandidate> = None;
+    for child in self.graph_get_children(&node_index) {
+      let descriptor = match self.endpoint_projection.graph.node_weight(child) {
+        Some(Node::PathComponent(_, descriptor)) => descriptor,
+        _ => continue,
+      };
+      let rank = if descriptor.is_parameter {
+        SegmentMatchRank::for_parameter(descriptor, segment, shape_queries)
+      } else if descriptor.name == *segment {
+        SegmentMatchRank::Literal
+      } else {
         continue;
+      };
+
+      let remaining_candidate = match self.best_path_candidate(
+        child,
+        remaining_segments,
+        method,
+        shape_queries,
+        best_candidates,
+      ) {
+        Some(remaining_candidate) => remaining_candidate,
+        None => continue,
+      };
+      let mut components = vec![(child, rank)];
+      components.extend(remaining_candidate.components);
+      let candidate = PathCandidate {
+        components,
+        has_method_request: remaining_candidate.has_method_request,
+      };
+
+      let is_better = match &best_candidate {
+        Some(best_candidate) => candidate.rank() > best_candidate.rank(),
+        None => true,
+      };
+      if is_better {
+        best_candidate = Some(candidate);
       }
-      return None;
     }
-    last_resolved_path_id
+
+    best_candidates.insert((node_index, segments.len()), best_candidate.clone());
+    best_candidate
+  }
+
+  fn has_requests_for_method(&self, path_node_index: NodeIndex, method: &str) -> bool {
+    let graph = &self.endpoint_projection.graph;
+    self
+      .graph_get_children(&path_node_index)
+      .any(|method_index| {
+        matches!(
+          graph.node_weight(method_index),
+          Some(Node::HttpMethod(http_method)) if http_method == method
+        ) && self
+          .graph_get_children(&method_index)
+          .any(|index| matches!(graph.node_weight(index), Some(Node::Request(..))))
+      })
   }
 
   pub fn resolve_unused_paths(&self) -> impl Iterator<Item = PathComponentId> + '_ {
@@ -276,15 +384,18 @@ impl<'a> EndpointQueries<'a> {
     method: &'a String,
     content_type: Option<&'a String>,
   ) -> Option<(&RequestId, &RequestDescriptor)> {
-    self.resolve_requests(path_id, method).and_then(|mut it| {
-      it.find(|(id, body)| match content_type {
-        Some(content_type) => match body.body {
-          Some(ref body) => body.http_content_type.eq(content_type),
-          None => false,
-        },
-        None => body.body.is_none(),
-      })
-    })
+    let mut requests = self.resolve_requests(path_id, method)?;
+    match content_type {
+      Some(content_type) => best_content_type_match(
+        requests.filter_map(|(id, request_descriptor)| {
+          let body = request_descriptor.body.as_ref()?;
+          Some((&body.http_content_type, (id, request_descriptor)))
+        }),
+        content_type,
+      )
+      .map(|(_, request)| request),
+      None => requests.find(|(id, request_descriptor)| request_descriptor.body.is_none()),
+    }
   }
 
   pub fn resolve_responses(
@@ -309,17 +420,23 @@ impl<'a> EndpointQueries<'a> {
     status_code: u16,
     content_type: Option<&'a String>,
   ) -> Option<(&ResponseId, &ResponseBodyDescriptor)> {
-    self
-      .resolve_responses_by_method_and_status_code(method, status_code, path_id)
-      .find(|(id, body)| match content_type {
-        Some(content_type) => match body.body {
-          Some(ref body) => body.http_content_type.eq(content_type),
-          None => false,
-        },
-        None => body.body.is_none(),
-      })
+    let mut responses =
+      self.resolve_responses_by_method_and_status_code(method, status_code, path_id);
+    match content_type {
+      Some(content_type) => best_content_type_match(
+        responses.filter_map(|(id, response_descriptor)| {
+          let body = response_descriptor.body.as_ref()?;
+          Some((&body.http_content_type, (id, response_descriptor)))
+        }),
+        content_type,
+      )
+      .map(|(_, response)| response),
+      None => responses.find(|(id, response_descriptor)| response_descriptor.body.is_none()),
+    }
   }
 
+  /// Responses documented for a status code. Responses for the exact status code take precedence
+  /// over those for a range of status codes (`4XX`), which take precedence over default responses.
   pub fn resolve_responses_by_method_and_status_code(
     &self,
     method: &'a str,
@@ -333,57 +450,82 @@ impl<'a> EndpointQueries<'a> {
       .endpoint_projection
       .graph
       .neighbors_directed(*path_node_index, petgraph::Direction::Incoming);
-    let matching_status_code = children
+    let status_code_nodes = children
       .filter(move |i| {
         let node = self.endpoint_projection.graph.node_weight(*i).unwrap();
-        // eprintln!("method node {:?}", node);
         match node {
           Node::HttpMethod(http_method) => method == *http_method,
           _ => false,
         }
       })
       .flat_map(move |i| {
-        // eprintln!("method node id {:?}", i);
         let children = self
           .endpoint_projection
           .graph
           .neighbors_directed(i, petgraph::Direction::Incoming);
-        let status_code_nodes = children.filter_map(move |i| {
-          // eprintln!("method child node id {:?}", i);
+        children.filter_map(move |i| {
           let node = self.endpoint_projection.graph.node_weight(i).unwrap();
-          match node {
-            Node::HttpStatusCode(http_status_code) => {
-              // eprintln!("status code {:?}", http_status_code);
-              if status_code == *http_status_code {
-                Some(i)
-              } else {
-                None
-              }
-            }
-            _ => None,
-          }
-        });
-        status_code_nodes
+          let status_match = node
+            .http_status_specifier()?
+            .match_status_code(status_code)?;
+          Some((status_match, i))
+        })
       })
-      .flat_map(move |i| {
+      .collect::<Vec<_>>();
+
+    let best_match = status_code_nodes
+      .iter()
+      .map(|(status_match, _)| *status_match)
+      .max();
+    status_code_nodes
+      .into_iter()
+      .filter(move |(status_match, _)| Some(*status_match) == best_match)
+      .flat_map(move |(_, i)| {
         let children = self
           .endpoint_projection
           .graph
           .neighbors_directed(i, petgraph::Direction::Incoming);
-        let response_nodes = children.filter_map(move |i| {
-          // eprintln!("status_code child node id {:?}", i);
+        children.filter_map(move |i| {
           let node = self.endpoint_projection.graph.node_weight(i).unwrap();
-          // eprintln!("status_code child node {:?}", node);
           match node {
             Node::Response(response_id, response_descriptor) => {
               Some((response_id, response_descriptor))
             }
             _ => None,
           }
-        });
-        response_nodes
-      });
-    matching_status_code
+        })
+      })
+  }
+
+  pub fn resolve_request_headers(
+    &self,
+    request_id: &RequestId,
+  ) -> impl Iterator<Item = (&RequestParameterId, &HeaderParameterDescriptor)> {
+    self
+      .endpoint_projection
+      .get_header_parameter_nodes(request_id)
+      .into_iter()
+      .flatten()
+  }
+
+  pub fn resolve_response_headers(
+    &self,
+    response_id: &ResponseId,
+  ) -> impl Iterator<Item = (&RequestParameterId, &HeaderParameterDescriptor)> {
+    self
+      .endpoint_projection
+      .get_header_parameter_nodes(response_id)
+      .into_iter()
+      .flatten()
+  }
+
+  pub fn resolve_header_parameter(
+    &self,
+    parameter_id: &RequestParameterId,
+  ) -> Option<&HeaderParameterDescriptor> {
+    self
+      .endpoint_projection
+      .get_header_parameter_descriptor(parameter_id)
   }
 
   pub fn delete_endpoint_commands(
@@ -462,6 +604,102 @@ impl<'a> EndpointQueries<'a> {
   }
 }
 
+/// A path resolved to a path component, with the values of the path parameters along the way
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathMatch<'a> {
+  pub path_id: PathComponentIdRef<'a>,
+  pub parameters: Vec<PathParameterMatch<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathParameterMatch<'a> {
+  pub path_id: PathComponentIdRef<'a>,
+  pub name: &'a str,
+  pub value: String,
+  pub fits_shape: bool,
+}
+
+/// Path components matching the segments of a path, with how specifically each matched
+#[derive(Debug, Clone)]
+struct PathCandidate {
+  components: Vec<(NodeIndex, SegmentMatchRank)>,
+  has_method_request: bool,
+}
+
+impl PathCandidate {
+  /// Candidates rank by how specifically their segments matched, earlier segments first, and
+  /// then by whether their path has a request for the method of what's being matched
+  fn rank(&self) -> (Vec<SegmentMatchRank>, bool) {
+    let segment_ranks = self.components.iter().map(|(_, rank)| *rank).collect();
+    (segment_ranks, self.has_method_request)
+  }
+}
+
+/// How specifically a segment of a path matched a path component, from least to most specific
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SegmentMatchRank {
+  UnfitParameter,
+  Parameter,
+  IdentifierParameter,
+  TypedParameter,
+  Literal,
+}
+
+impl SegmentMatchRank {
+  fn for_parameter(
+    descriptor: &PathComponentDescriptor,
+    segment: &str,
+    shape_queries: Option<&ShapeQueries>,
+  ) -> Self {
+    let (shape_queries, shape_id) = match (shape_queries, &descriptor.shape) {
+      (Some(shape_queries), Some(shape_descriptor))
+        if !shape_descriptor.is_removed
+          && shape_queries
+            .shape_projection
+            .get_shape_node_index(&shape_descriptor.shape_id)
+            .is_some() =>
+      {
+        (shape_queries, &shape_descriptor.shape_id)
+      }
+      _ => return Self::Parameter,
+    };
+
+    let (fits_shape, rank) = match shape_queries.resolve_to_core_shape(shape_id) {
+      ShapeKind::NumberKind => (is_number(segment), Self::TypedParameter),
+      ShapeKind::BooleanKind => (matches!(segment, "true" | "false"), Self::TypedParameter),
+      // identifiers are typed by the shape of their values, like numeric ids. Otherwise any
+      // value could identify something, but being documented as an identifier still makes
+      // the parameter a more specific match than a plain one.
+      ShapeKind::IdentifierKind => {
+        let inner_shape_id = shape_queries.resolve_parameter_to_shape(
+          shape_id,
+          &String::from(
+            ShapeKind::IdentifierKind
+              .get_parameter_descriptor()
+              .expect("identifiers should have a parameter for their inner shape")
+              .shape_parameter_id,
+          ),
+        );
+        match shape_queries.resolve_to_core_shape(&inner_shape_id) {
+          ShapeKind::NumberKind => (is_number(segment), Self::TypedParameter),
+          _ => (true, Self::IdentifierParameter),
+        }
+      }
+      _ => (true, Self::Parameter),
+    };
+
+    if fits_shape {
+      rank
+    } else {
+      Self::UnfitParameter
+    }
+  }
+}
+
+fn is_number(segment: &str) -> bool {
+  matches!(segment.parse::<f64>(), Ok(number) if number.is_finite())
+}
+
 #[derive(Debug, Serialize)]
 pub struct DeleteEndpointCommands {
   path_id: PathComponentId,
@@ -474,6 +712,7 @@ mod test {
   use super::*;
   use crate::events::SpecEvent;
   use crate::projections::SpecProjection;
+  use crate::state::endpoint::HttpStatusSpecifier;
   use crate::Aggregate;
   use insta::assert_debug_snapshot;
   use petgraph::dot::Dot;
@@ -580,6 +819,236 @@ mod test {
     assert_eq!(endpoint_queries.resolve_path("/").unwrap(), "root");
   }
 
+  #[test]
+  pub fn resolve_path_backtracks_when_literals_lead_nowhere() {
+    let events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": { "pathId": "path_1", "parentPathId": "root", "name": "users" }},
+      {"PathComponentAdded": { "pathId": "path_2", "parentPathId": "path_1", "name": "me" }},
+      {"PathComponentAdded": { "pathId": "path_3", "parentPathId": "path_2", "name": "settings" }},
+      {"PathParameterAdded": { "pathId": "path_4", "parentPathId": "path_1", "name": "userId" }},
+      {"PathComponentAdded": { "pathId": "path_5", "parentPathId": "path_4", "name": "posts" }},
+    ]))
+    .expect("should be able to deserialize test events");
+
+    let spec_projection = SpecProjection::from(events);
+    let endpoint_queries = EndpointQueries::new(spec_projection.endpoint());
+
+    assert_eq!(
+      endpoint_queries.resolve_path("/users/me").unwrap(),
+      "path_2"
+    );
+    assert_eq!(
+      endpoint_queries.resolve_path("/users/me/settings").unwrap(),
+      "path_3"
+    );
+    assert_eq!(
+      endpoint_queries.resolve_path("/users/me/posts").unwrap(),
+      "path_5"
+    );
+    assert_eq!(
+      endpoint_queries.resolve_path("/users/1/posts/").unwrap(),
+      "path_5"
+    );
+    assert!(endpoint_queries.resolve_path("/users/1/settings").is_none());
+  }
+
+  #[test]
+  pub fn resolve_path_match_ranks_parameters_by_their_shapes() {
+    let events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"" }},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"" }},
+      {"PathComponentAdded": { "pathId": "path_1", "parentPathId": "root", "name": "items" }},
+      {"PathParameterAdded": { "pathId": "path_2", "parentPathId": "path_1", "name": "slug" }},
+      {"PathParameterShapeSet":{"pathId":"path_2","shapeDescriptor":{"shapeId":"string_shape_1","isRemoved":false} }},
+      {"PathParameterAdded": { "pathId": "path_3", "parentPathId": "path_1", "name": "itemId" }},
+      {"PathParameterShapeSet":{"pathId":"path_3","shapeDescriptor":{"shapeId":"number_shape_1","isRemoved":false} }},
+      {"PathComponentAdded": { "pathId": "path_4", "parentPathId": "root", "name": "orders" }},
+      {"PathParameterAdded": { "pathId": "path_5", "parentPathId": "path_4", "name": "orderId" }},
+      {"PathParameterShapeSet":{"pathId":"path_5","shapeDescriptor":{"shapeId":"number_shape_1","isRemoved":false} }},
+      {"ShapeAdded":{"shapeId":"string_id_1","baseShapeId":"$identifier","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"string_id_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_shape_1"}},"consumingParameterId":"$identifierInner"}}}},
+      {"ShapeAdded":{"shapeId":"number_id_1","baseShapeId":"$identifier","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"number_id_1","providerDescriptor":{"ShapeProvider":{"shapeId":"number_shape_1"}},"consumingParameterId":"$identifierInner"}}}},
+      {"PathComponentAdded": { "pathId": "path_6", "parentPathId": "root", "name": "users" }},
+      {"PathParameterAdded": { "pathId": "path_7", "parentPathId": "path_6", "name": "name" }},
+      {"PathParameterAdded": { "pathId": "path_8", "parentPathId": "path_6", "name": "handle" }},
+      {"PathParameterShapeSet":{"pathId":"path_8","shapeDescriptor":{"shapeId":"string_id_1","isRemoved":false} }},
+      {"PathParameterAdded": { "pathId": "path_9", "parentPathId": "path_6", "name": "userId" }},
+      {"PathParameterShapeSet":{"pathId":"path_9","shapeDescriptor":{"shapeId":"number_id_1","isRemoved":false} }},
+    ]))
+    .expect("should be able to deserialize test events");
+
+    let spec_projection = SpecProjection::from(events);
+    let endpoint_queries = EndpointQueries::new(spec_projection.endpoint());
+    let shape_queries = ShapeQueries::new(spec_projection.shape());
+
+    let numeric_item = endpoint_queries
+      .resolve_path_match("/items/42", None, Some(&shape_queries))
+      .unwrap();
+    assert_eq!(numeric_item.path_id, "path_3");
+
+    let named_item = endpoint_queries
+      .resolve_path_match("/items/bananas", None, Some(&shape_queries))
+      .unwrap();
+    assert_eq!(named_item.path_id, "path_2");
+
+    let unfit_order = endpoint_queries
+      .resolve_path_match("/orders/latest", None, Some(&shape_queries))
+      .unwrap();
+    assert_eq!(
+      unfit_order,
+      PathMatch {
+        path_id: "path_5",
+        parameters: vec![PathParameterMatch {
+          path_id: "path_5",
+          name: "orderId",
+          value: String::from("latest"),
+          fits_shape: false,
+        }]
+      }
+    );
+
+    // identifiers rank by the shape of their values, and above plain parameters otherwise
+    let resolve_user_path_id = |path| {
+      endpoint_queries
+        .resolve_path_match(path, None, Some(&shape_queries))
+        .unwrap()
+        .path_id
+    };
+    assert_eq!(resolve_user_path_id("/users/42"), "path_9");
+    assert_eq!(resolve_user_path_id("/users/jane-doe"), "path_8");
+
+    // without shapes, parameters are all alike
+    let untyped_order = endpoint_queries
+      .resolve_path_match("/orders/latest", None, None)
+      .unwrap();
+    assert!(untyped_order.parameters[0].fits_shape);
+  }
+
+  #[test]
+  pub fn resolve_path_match_prefers_paths_with_requests_for_the_method() {
+    let events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": { "pathId": "path_1", "parentPathId": "root", "name": "items" }},
+      {"PathParameterAdded": { "pathId": "path_2", "parentPathId": "path_1", "name": "slug" }},
+      {"PathParameterAdded": { "pathId": "path_3", "parentPathId": "path_1", "name": "itemId" }},
+      {"RequestAdded": { "requestId": "request_1", "pathId": "path_2", "httpMethod": "GET"}},
+      {"RequestAdded": { "requestId": "request_2", "pathId": "path_3", "httpMethod": "POST"}},
+    ]))
+    .expect("should be able to deserialize test events");
+
+    let spec_projection = SpecProjection::from(events);
+    let endpoint_queries = EndpointQueries::new(spec_projection.endpoint());
+    let resolve_path_id = |method| {
+      endpoint_queries
+        .resolve_path_match("/items/bananas", method, None)
+        .unwrap()
+        .path_id
+    };
+
+    assert_eq!(resolve_path_id(Some("GET")), "path_2");
+    assert_eq!(resolve_path_id(Some("POST")), "path_3");
+    // without a request for the method, the first equally ranked path is as good as any
+    assert_eq!(resolve_path_id(Some("DELETE")), resolve_path_id(None));
+  }
+
+  #[test]
+  pub fn resolve_bodies_prefers_content_types_with_the_same_parameters() {
+    let events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": { "pathId": "path_1", "parentPathId": "root", "name": "todos" }},
+      {"RequestAdded": { "requestId": "request_1", "pathId": "path_1", "httpMethod": "POST"}},
+      {"RequestBodySet": {"requestId": "request_1", "bodyDescriptor": {"httpContentType": "application/json; charset=utf-8", "shapeId": "$string", "isRemoved": false}}},
+      {"RequestAdded": { "requestId": "request_2", "pathId": "path_1", "httpMethod": "POST"}},
+      {"RequestBodySet": {"requestId": "request_2", "bodyDescriptor": {"httpContentType": "application/json", "shapeId": "$string", "isRemoved": false}}},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "POST", "httpStatusCode": 201 }},
+      {"ResponseBodySet": {"responseId": "response_1", "bodyDescriptor": {"httpContentType": "application/json", "shapeId": "$string", "isRemoved": false}}},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_2", "pathId": "path_1", "httpMethod": "POST", "httpStatusCode": 201 }},
+      {"ResponseBodySet": {"responseId": "response_2", "bodyDescriptor": {"httpContentType": "application/json; charset=utf-8", "shapeId": "$string", "isRemoved": false}}},
+    ]))
+    .expect("should be able to deserialize test events");
+
+    let spec_projection = SpecProjection::from(events);
+    let endpoint_queries = EndpointQueries::new(spec_projection.endpoint());
+    let method = String::from("POST");
+    let resolve_request_id = |content_type: &str| {
+      let content_type = String::from(content_type);
+      endpoint_queries
+        .resolve_request_by_method_and_content_type("path_1", &method, Some(&content_type))
+        .map(|(request_id, _)| request_id.clone())
+    };
+    let resolve_response_id = |content_type: &str| {
+      let content_type = String::from(content_type);
+      endpoint_queries
+        .resolve_response_by_method_status_code_and_content_type(
+          "path_1",
+          "POST",
+          201,
+          Some(&content_type),
+        )
+        .map(|(response_id, _)| response_id.clone())
+    };
+
+    assert_eq!(
+      resolve_request_id("application/json; charset=utf-8").as_deref(),
+      Some("request_1")
+    );
+    assert_eq!(
+      resolve_request_id("application/json").as_deref(),
+      Some("request_2")
+    );
+    assert_eq!(
+      resolve_response_id("application/json; charset=utf-8").as_deref(),
+      Some("response_2")
+    );
+    assert_eq!(
+      resolve_response_id("application/json").as_deref(),
+      Some("response_1")
+    );
+    // without a body of the same parameters, either body of the same type and subtype will do
+    assert!(resolve_request_id("application/json; charset=iso-8859-1").is_some());
+  }
+
+  #[test]
+  pub fn resolve_responses_prefers_exact_status_codes_over_ranges_and_default() {
+    let events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"PathComponentAdded": { "pathId": "path_1", "parentPathId": "root", "name": "todos" }},
+      {"ResponseAddedByPathAndMethod": {"responseId": "response_1", "pathId": "path_1", "httpMethod": "GET", "httpStatusCode": 404 }},
+      {"ResponseRangeAddedByPathAndMethod": {"responseId": "response_2", "pathId": "path_1", "httpMethod": "GET", "httpStatusRange": "4XX" }},
+      {"DefaultResponseAddedByPathAndMethod": {"responseId": "response_3", "pathId": "path_1", "httpMethod": "GET" }},
+      {"ResponseRangeAddedByPathAndMethod": {"responseId": "response_4", "pathId": "path_1", "httpMethod": "POST", "httpStatusRange": "2XX" }},
+    ]))
+    .expect("should be able to deserialize test events");
+
+    let spec_projection = SpecProjection::from(events);
+    let endpoint_queries = EndpointQueries::new(spec_projection.endpoint());
+    let resolve = |method: &'static str, status_code: u16| {
+      endpoint_queries
+        .resolve_responses_by_method_and_status_code(method, status_code, "path_1")
+        .map(|(response_id, _)| response_id.clone())
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(resolve("GET", 404), vec![String::from("response_1")]);
+    assert_eq!(resolve("GET", 409), vec![String::from("response_2")]);
+    assert_eq!(resolve("GET", 500), vec![String::from("response_3")]);
+    assert_eq!(resolve("POST", 201), vec![String::from("response_4")]);
+    assert!(resolve("POST", 500).is_empty());
+
+    assert_eq!(
+      spec_projection
+        .endpoint()
+        .get_response_status_specifier(&String::from("response_2")),
+      Some(HttpStatusSpecifier::Range(
+        "4XX".parse().expect("should parse status code range")
+      ))
+    );
+    assert_eq!(
+      spec_projection
+        .endpoint()
+        .get_response_status_code(&String::from("response_3")),
+      None
+    );
+  }
+
   #[test]
   pub fn can_find_unused_paths() {
     let events: Vec<SpecEvent> = serde_json::from_value(json!([
@@ -1,8 +1,10 @@
+pub mod conflicts;
 pub mod endpoint;
 pub mod history;
 pub mod shape;
 pub mod spectacle;
 
+pub use conflicts::{Conflict, ConflictsQueries};
 pub use endpoint::EndpointQueries;
 pub use history::HistoryQueries;
 pub use shape::ShapeQueries;
@@ -1,8 +1,11 @@
 use crate::commands::{shape as shape_commands, ShapeCommand};
-use crate::projections::shape::{CoreShapeNode, Edge, Node};
+use crate::projections::shape::{CoreShapeNode, Edge, Node, NodeId};
 use crate::projections::shape::{FieldNode, FieldNodeDescriptor, ShapeNode, ShapeProjection};
 use crate::shapes::traverser::{ShapeTrail, ShapeTrailPathComponent};
-use crate::state::shape::{FieldId, ShapeId, ShapeKind, ShapeParameterId};
+use crate::state::shape::{
+  FieldId, OneOfDiscriminatorDescriptor, ShapeConstraintDescriptor, ShapeConstraintId, ShapeId,
+  ShapeKind, ShapeParameterId,
+};
 use crate::state::SpecIdGenerator;
 use petgraph::visit::EdgeRef;
 use std::collections::BTreeSet;
@@ -69,8 +72,11 @@ impl<'a> ShapeQueries<'a> {
               .get_parameter_descriptor()
               .unwrap()
               .shape_parameter_id;
-            let item_shape_id =
-              self.resolve_parameter_to_shape(&shape_id, &String::from(nullable_parameter_id));
+            let item_shape_id = self.resolve_parameter_to_shape_in_trail(
+              shape_trail,
+              &shape_id,
+              &String::from(nullable_parameter_id),
+            );
             let trail = shape_trail
               .with_component(ShapeTrailPathComponent::NullableTrail {
                 shape_id: shape_id.clone(),
@@ -87,6 +93,7 @@ impl<'a> ShapeQueries<'a> {
               }],
               shape_id: shape_id.clone(),
               core_shape_kind: core_shape_node.descriptor.kind.clone(),
+              constraints: vec![],
             }];
             output.append(&mut self.list_trail_choices(&trail));
             output
@@ -98,8 +105,11 @@ impl<'a> ShapeQueries<'a> {
               .get_parameter_descriptor()
               .unwrap()
               .shape_parameter_id;
-            let item_shape_id =
-              self.resolve_parameter_to_shape(&shape_id, &String::from(optional_parameter_id));
+            let item_shape_id = self.resolve_parameter_to_shape_in_trail(
+              shape_trail,
+              &shape_id,
+              &String::from(optional_parameter_id),
+            );
             let trail = shape_trail
               .with_component(ShapeTrailPathComponent::OptionalTrail {
                 shape_id: shape_id.clone(),
@@ -115,6 +125,71 @@ impl<'a> ShapeQueries<'a> {
               }],
               shape_id: shape_id.clone(),
               core_shape_kind: core_shape_node.descriptor.kind.clone(),
+              constraints: vec![],
+            }];
+            output.append(&mut self.list_trail_choices(&trail));
+            output
+          }
+          ShapeKind::IdentifierKind => {
+            let identifier_parameter_id = core_shape_node
+              .descriptor
+              .kind
+              .get_parameter_descriptor()
+              .unwrap()
+              .shape_parameter_id;
+            let inner_shape_id = self.resolve_parameter_to_shape_in_trail(
+              shape_trail,
+              &shape_id,
+              &String::from(identifier_parameter_id),
+            );
+            let trail = shape_trail
+              .with_component(ShapeTrailPathComponent::IdentifierTrail {
+                shape_id: shape_id.clone(),
+              })
+              .with_component(ShapeTrailPathComponent::IdentifierInnerTrail {
+                shape_id: shape_id.clone(),
+                inner_shape_id,
+              });
+            let mut output = vec![ChoiceOutput {
+              parent_trail: shape_trail.clone(),
+              additional_components: vec![ShapeTrailPathComponent::IdentifierTrail {
+                shape_id: shape_id.clone(),
+              }],
+              shape_id: shape_id.clone(),
+              core_shape_kind: core_shape_node.descriptor.kind.clone(),
+              constraints: vec![],
+            }];
+            output.append(&mut self.list_trail_choices(&trail));
+            output
+          }
+          ShapeKind::ReferenceKind => {
+            let reference_parameter_id = core_shape_node
+              .descriptor
+              .kind
+              .get_parameter_descriptor()
+              .unwrap()
+              .shape_parameter_id;
+            let target_shape_id = self.resolve_parameter_to_shape_in_trail(
+              shape_trail,
+              &shape_id,
+              &String::from(reference_parameter_id),
+            );
+            let trail = shape_trail
+              .with_component(ShapeTrailPathComponent::ReferenceTrail {
+                shape_id: shape_id.clone(),
+              })
+              .with_component(ShapeTrailPathComponent::ReferenceTargetTrail {
+                shape_id: shape_id.clone(),
+                target_shape_id,
+              });
+            let mut output = vec![ChoiceOutput {
+              parent_trail: shape_trail.clone(),
+              additional_components: vec![ShapeTrailPathComponent::ReferenceTrail {
+                shape_id: shape_id.clone(),
+              }],
+              shape_id: shape_id.clone(),
+              core_shape_kind: core_shape_node.descriptor.kind.clone(),
+              constraints: vec![],
             }];
             output.append(&mut self.list_trail_choices(&trail));
             output
@@ -142,6 +217,15 @@ impl<'a> ShapeQueries<'a> {
             additional_components: vec![],
             shape_id: shape_id.clone(),
             core_shape_kind: core_shape_node.descriptor.kind.clone(),
+            constraints: projection
+              .get_shape_constraint_nodes(current_node_index)
+              .map(|constraint_node| {
+                (
+                  constraint_node.shape_constraint_id.clone(),
+                  constraint_node.descriptor.clone(),
+                )
+              })
+              .collect(),
           }],
         };
         trails
@@ -159,6 +243,20 @@ impl<'a> ShapeQueries<'a> {
       .collect()
   }
 
+  /// Shapes can be based on another shape rather than on a core shape directly, in which case
+  /// they're described by the shape they're based on
+  pub fn resolve_base_shape_id(&self, shape_id: &ShapeId) -> Option<&ShapeId> {
+    let projection = &self.shape_projection;
+    let shape_node_index = projection.get_shape_node_index(shape_id)?;
+    let base_node_index = projection.get_ancestor_shape_node_index(shape_node_index)?;
+    match projection.get_node_by_index(&base_node_index) {
+      Some(Node::Shape(base_shape)) if &base_shape.shape_id != shape_id => {
+        Some(&base_shape.shape_id)
+      }
+      _ => None,
+    }
+  }
+
   pub fn resolve_to_core_shape(&self, shape_id: &ShapeId) -> &ShapeKind {
     //@TODO: use petgraph::visit::EdgeFiltered, etc.
     //@GOTCHA: this does not support multiple levels of ancestors
@@ -204,6 +302,13 @@ impl<'a> ShapeQueries<'a> {
         },
         _ => unreachable!("should only receive ListItemTrail relative to ListKind"),
       },
+      ShapeKind::MapKind => match path_component {
+        ShapeTrailPathComponent::MapValueTrail { value_shape_id, .. } => ResolvedTrail {
+          shape_id: value_shape_id.clone(),
+          core_shape_kind: self.resolve_to_core_shape(value_shape_id),
+        },
+        _ => unreachable!("should only receive MapValueTrail relative to MapKind"),
+      },
       ShapeKind::OneOfKind => match path_component {
         ShapeTrailPathComponent::OneOfTrail { shape_id } => ResolvedTrail {
           shape_id: parent.shape_id.clone(),
@@ -251,6 +356,38 @@ impl<'a> ShapeQueries<'a> {
           "should only receive OptionalTrail or OptionalItemTrail relative to OptionalKind"
         ),
       },
+      ShapeKind::IdentifierKind => match path_component {
+        ShapeTrailPathComponent::IdentifierTrail { shape_id } => ResolvedTrail {
+          shape_id: parent.shape_id.clone(),
+          core_shape_kind: &ShapeKind::IdentifierKind,
+        },
+        ShapeTrailPathComponent::IdentifierInnerTrail {
+          shape_id,
+          inner_shape_id,
+        } => ResolvedTrail {
+          shape_id: inner_shape_id.clone(),
+          core_shape_kind: self.resolve_to_core_shape(inner_shape_id),
+        },
+        _ => unreachable!(
+          "should only receive IdentifierTrail or IdentifierInnerTrail relative to IdentifierKind"
+        ),
+      },
+      ShapeKind::ReferenceKind => match path_component {
+        ShapeTrailPathComponent::ReferenceTrail { shape_id } => ResolvedTrail {
+          shape_id: parent.shape_id.clone(),
+          core_shape_kind: &ShapeKind::ReferenceKind,
+        },
+        ShapeTrailPathComponent::ReferenceTargetTrail {
+          shape_id,
+          target_shape_id,
+        } => ResolvedTrail {
+          shape_id: target_shape_id.clone(),
+          core_shape_kind: self.resolve_to_core_shape(target_shape_id),
+        },
+        _ => unreachable!(
+          "should only receive ReferenceTrail or ReferenceTargetTrail relative to ReferenceKind"
+        ),
+      },
       ShapeKind::ObjectKind => match path_component {
         ShapeTrailPathComponent::ObjectTrail { shape_id } => ResolvedTrail {
           shape_id: shape_id.clone(),
@@ -293,14 +430,29 @@ impl<'a> ShapeQueries<'a> {
     &self,
     shape_id: &ShapeId,
     shape_parameter_id: &ShapeParameterId,
+  ) -> ShapeId {
+    self.resolve_parameter_to_shape_in_trail(
+      &ShapeTrail::new(shape_id.clone()),
+      shape_id,
+      shape_parameter_id,
+    )
+  }
+
+  /// Resolve a parameter of the shape at the end of a trail. Any shape or field along the trail
+  /// can bind the parameter, so generic shapes get their parameters provided by where they're
+  /// used. Parameters nothing provides a shape for resolve to the unknown shape.
+  pub fn resolve_parameter_to_shape_in_trail(
+    &self,
+    shape_trail: &ShapeTrail,
+    shape_id: &ShapeId,
+    shape_parameter_id: &ShapeParameterId,
   ) -> ShapeId {
     let projection = &self.shape_projection;
 
-    let shape_node_index = projection
+    projection
       .get_shape_node_index(shape_id)
       .expect("shape id to resolve parameter for must exist");
-
-    let shape_parameter_node_index = projection
+    projection
       .get_shape_parameter_node_index(shape_parameter_id)
       .unwrap_or_else(|| {
         panic!(
@@ -309,21 +461,45 @@ impl<'a> ShapeQueries<'a> {
         )
       });
 
-    // @REFACTOR: move this to a method on the projection, we shouldn't have
-    // to reach into projection internals
-    let mut outgoing_edges = projection
-      .graph
-      .edges_connecting(*shape_node_index, *shape_parameter_node_index);
-
-    let existing_binding = outgoing_edges
-      .next()
-      .expect("expected a parameter binding to exist");
-    let edge_index = existing_binding.id();
-    let edge_weight = projection.graph.edge_weight(edge_index).unwrap();
-    match edge_weight {
-      Edge::HasBinding(b) => b.shape_id.clone(),
-      _ => unreachable!("expected edge to be a HasBinding"),
+    let mut binding_node_ids = trail_binding_node_ids(shape_trail);
+    if binding_node_ids.last() != Some(&shape_id) {
+      binding_node_ids.push(shape_id);
     }
+
+    self
+      .resolve_parameter_binding(&binding_node_ids, shape_parameter_id)
+      .unwrap_or_else(unknown_shape_id)
+  }
+
+  // innermost bindings take precedence, with parameters bound to parameters being provided by
+  // the bindings further out
+  fn resolve_parameter_binding(
+    &self,
+    binding_node_ids: &[&NodeId],
+    shape_parameter_id: &ShapeParameterId,
+  ) -> Option<ShapeId> {
+    let projection = &self.shape_projection;
+    let shape_parameter_node_index =
+      *projection.get_shape_parameter_node_index(shape_parameter_id)?;
+
+    binding_node_ids
+      .iter()
+      .enumerate()
+      .rev()
+      .find_map(|(i, binding_node_id)| {
+        let binding_node_index = projection.node_id_to_index.get(*binding_node_id)?;
+        projection
+          .graph
+          .edges_connecting(*binding_node_index, shape_parameter_node_index)
+          .find_map(|edge| match edge.weight() {
+            Edge::HasBinding(b) => Some(Some(b.shape_id.clone())),
+            Edge::HasParameterBinding(b) => {
+              Some(self.resolve_parameter_binding(&binding_node_ids[..i], &b.shape_parameter_id))
+            }
+            _ => None,
+          })
+      })
+      .flatten()
   }
 
   pub fn resolve_parameters_to_shapes(
@@ -360,6 +536,47 @@ impl<'a> ShapeQueries<'a> {
       .collect()
   }
 
+  /// Whether a shape declares parameters it doesn't bind itself, which makes it generic: the shapes
+  /// of those parameters are provided by wherever it's used
+  pub fn has_unbound_parameters(&self, shape_id: &ShapeId) -> bool {
+    let projection = &self.shape_projection;
+    let shape_node_index = match projection.get_shape_node_index(shape_id) {
+      Some(shape_node_index) => *shape_node_index,
+      None => return false,
+    };
+
+    projection
+      .graph
+      .edges_directed(shape_node_index, petgraph::Direction::Incoming)
+      .filter(|edge| matches!(edge.weight(), Edge::IsParameterOf))
+      .any(|parameter_edge| {
+        !projection
+          .graph
+          .edges_connecting(shape_node_index, parameter_edge.source())
+          .any(|edge| matches!(edge.weight(), Edge::HasBinding(_)))
+      })
+  }
+
+  pub fn resolve_shape_name(&self, shape_id: &ShapeId) -> Option<&String> {
+    let projection = &self.shape_projection;
+    let shape_node_index = projection.get_shape_node_index(shape_id)?;
+
+    match projection.graph.node_weight(*shape_node_index)? {
+      Node::Shape(shape_node) => Some(&shape_node.descriptor.name),
+      _ => None,
+    }
+  }
+
+  pub fn resolve_one_of_discriminator(
+    &self,
+    shape_id: &ShapeId,
+  ) -> Option<&OneOfDiscriminatorDescriptor> {
+    let projection = &self.shape_projection;
+    let shape_node_index = projection.get_shape_node_index(shape_id)?;
+
+    projection.get_one_of_discriminator(shape_node_index)
+  }
+
   pub fn resolve_field_id(&self, shape_id: &ShapeId, field_name: &String) -> Option<FieldId> {
     let projection = &self.shape_projection;
 
@@ -422,6 +639,39 @@ impl<'a> ShapeQueries<'a> {
       })
   }
 
+  /// Resolve the shape of a field of the object at the end of a trail, including fields of which
+  /// the shape is provided through a shape parameter
+  pub fn resolve_field_shape_in_trail(
+    &self,
+    shape_trail: &ShapeTrail,
+    field_id: &FieldId,
+  ) -> Option<ShapeId> {
+    let projection = &self.shape_projection;
+
+    let field_node_index = *projection
+      .get_field_node_index(field_id)
+      .expect("field id to which field belongs should exist");
+
+    projection
+      .graph
+      .edges_directed(field_node_index, petgraph::Direction::Incoming)
+      .find_map(|edge| match edge.weight() {
+        Edge::BelongsTo => match projection.graph.node_weight(edge.source()) {
+          Some(Node::Shape(ref shape_node)) => Some(shape_node.shape_id.clone()),
+          Some(Node::ShapeParameter(ref parameter_node)) => Some(
+            self
+              .resolve_parameter_binding(
+                &trail_binding_node_ids(shape_trail),
+                &parameter_node.parameter_id,
+              )
+              .unwrap_or_else(unknown_shape_id),
+          ),
+          _ => None,
+        },
+        _ => None,
+      })
+  }
+
   pub fn resolve_shape_field_id_and_names(
     &self,
     shape_id: &ShapeId,
@@ -493,9 +743,6 @@ impl<'a> ShapeQueries<'a> {
         }
         Node::Field(field_node) => {
           let field_id = field_node.field_id.clone();
-          let field_shape_id = self
-            .resolve_field_shape_node(&field_id)
-            .expect("a field should describe a shape");
 
           let owner_node = self.shape_projection.get_owner_node(&current_node_index);
           let parent_object_shape_id = match &owner_node {
@@ -503,6 +750,13 @@ impl<'a> ShapeQueries<'a> {
             _ => unreachable!("field nodes should be owned by their objects shape node"),
           };
 
+          let field_shape_id = self
+            .resolve_field_shape_in_trail(
+              &ShapeTrail::new(parent_object_shape_id.clone()),
+              &field_id,
+            )
+            .expect("a field should describe a shape");
+
           trail_components.push(ShapeTrailPathComponent::ObjectFieldTrail {
             field_id,
             field_shape_id,
@@ -517,7 +771,10 @@ impl<'a> ShapeQueries<'a> {
 
           // next_node = self.shape_projection.get_owner_node(&current_node_index);
         }
-        Node::BatchCommit(_) | Node::CoreShape(_) => {}
+        Node::BatchCommit(_)
+        | Node::CoreShape(_)
+        | Node::ShapeConstraint(_)
+        | Node::OneOfDiscriminator(_) => {}
       };
     }
 
@@ -635,6 +892,52 @@ pub struct ChoiceOutput {
   pub additional_components: Vec<ShapeTrailPathComponent>,
   pub shape_id: ShapeId,
   pub core_shape_kind: ShapeKind,
+  pub constraints: Vec<(ShapeConstraintId, ShapeConstraintDescriptor)>,
+}
+
+// the shapes and fields along a trail that can bind shape parameters, from the outside in
+fn trail_binding_node_ids(shape_trail: &ShapeTrail) -> Vec<&NodeId> {
+  let mut binding_node_ids = vec![&shape_trail.root_shape_id];
+  for component in &shape_trail.path {
+    match component {
+      ShapeTrailPathComponent::ObjectFieldTrail {
+        field_id,
+        field_shape_id,
+        ..
+      } => {
+        binding_node_ids.push(field_id);
+        binding_node_ids.push(field_shape_id);
+      }
+      ShapeTrailPathComponent::ListItemTrail { item_shape_id, .. }
+      | ShapeTrailPathComponent::OneOfItemTrail { item_shape_id, .. } => {
+        binding_node_ids.push(item_shape_id)
+      }
+      ShapeTrailPathComponent::MapValueTrail { value_shape_id, .. } => {
+        binding_node_ids.push(value_shape_id)
+      }
+      ShapeTrailPathComponent::OptionalItemTrail { inner_shape_id, .. }
+      | ShapeTrailPathComponent::NullableItemTrail { inner_shape_id, .. }
+      | ShapeTrailPathComponent::IdentifierInnerTrail { inner_shape_id, .. } => {
+        binding_node_ids.push(inner_shape_id)
+      }
+      ShapeTrailPathComponent::ReferenceTargetTrail {
+        target_shape_id, ..
+      } => binding_node_ids.push(target_shape_id),
+      ShapeTrailPathComponent::ObjectTrail { .. }
+      | ShapeTrailPathComponent::ListTrail { .. }
+      | ShapeTrailPathComponent::OneOfTrail { .. }
+      | ShapeTrailPathComponent::OptionalTrail { .. }
+      | ShapeTrailPathComponent::NullableTrail { .. }
+      | ShapeTrailPathComponent::IdentifierTrail { .. }
+      | ShapeTrailPathComponent::ReferenceTrail { .. }
+      | ShapeTrailPathComponent::UnknownTrail {} => {}
+    }
+  }
+  binding_node_ids
+}
+
+fn unknown_shape_id() -> ShapeId {
+  ShapeId::from(ShapeKind::UnknownKind.get_descriptor().base_shape_id)
 }
 
 impl ChoiceOutput {
@@ -49,12 +49,37 @@ pub struct ArrayChoice {
   item_shape_id: ShapeId,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MapChoice {
+  json_type: JsonType,
+  shape_id: ShapeId,
+  value_shape_id: ShapeId,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentifierChoice {
+  shape_id: ShapeId,
+  inner_shape_id: ShapeId,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceChoice {
+  shape_id: ShapeId,
+  target_shape_id: ShapeId,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(tag = "type")]
 pub enum ShapeChoice {
   Primitive(PrimitiveChoice),
   Object(ObjectChoice),
   Array(ArrayChoice),
+  Map(MapChoice),
+  Identifier(IdentifierChoice),
+  Reference(ReferenceChoice),
   Any,
   Unknown,
 }
@@ -65,6 +90,9 @@ impl ShapeChoice {
       ShapeChoice::Primitive(choice) => Some(&choice.json_type),
       ShapeChoice::Object(choice) => Some(&choice.json_type),
       ShapeChoice::Array(choice) => Some(&choice.json_type),
+      ShapeChoice::Map(choice) => Some(&choice.json_type),
+      ShapeChoice::Identifier(_) => None,
+      ShapeChoice::Reference(_) => None,
       ShapeChoice::Any => None,
       ShapeChoice::Unknown => None,
     }
@@ -100,10 +128,11 @@ impl<'a> ShapeChoiceQueries<'a> {
       .map(move |choice| match choice.core_shape_kind {
         ShapeKind::ObjectKind => {
           let object_fields = queries.resolve_shape_field_id_and_names(&choice.shape_id);
+          let object_trail = choice.shape_trail();
           let fields = object_fields
             .map(|(field_id, name)| {
               let field_shape_id = queries
-                .resolve_field_shape_node(field_id)
+                .resolve_field_shape_in_trail(&object_trail, field_id)
                 .expect("expected field shape to resolve");
 
               ObjectFieldChoice {
@@ -129,8 +158,11 @@ impl<'a> ShapeChoiceQueries<'a> {
               .expect("expected $list to have a parameter descriptor")
               .shape_parameter_id,
           );
-          let list_item_shape_id =
-            queries.resolve_parameter_to_shape(&choice.shape_id, shape_parameter_id);
+          let list_item_shape_id = queries.resolve_parameter_to_shape_in_trail(
+            &choice.shape_trail(),
+            &choice.shape_id,
+            shape_parameter_id,
+          );
           let output = ArrayChoice {
             shape_id: choice.shape_id.clone(),
             json_type: JsonType::Array,
@@ -138,7 +170,26 @@ impl<'a> ShapeChoiceQueries<'a> {
           };
           ShapeChoice::Array(output)
         }
-        ShapeKind::MapKind => unimplemented!(),
+        ShapeKind::MapKind => {
+          let shape_parameter_id = &String::from(
+            choice
+              .core_shape_kind
+              .get_parameter_descriptor()
+              .expect("expected $map to have a parameter descriptor")
+              .shape_parameter_id,
+          );
+          let map_value_shape_id = queries.resolve_parameter_to_shape_in_trail(
+            &choice.shape_trail(),
+            &choice.shape_id,
+            shape_parameter_id,
+          );
+          let output = MapChoice {
+            shape_id: choice.shape_id.clone(),
+            json_type: JsonType::Object,
+            value_shape_id: map_value_shape_id,
+          };
+          ShapeChoice::Map(output)
+        }
         ShapeKind::OneOfKind => unreachable!(),
         ShapeKind::AnyKind => ShapeChoice::Any,
         ShapeKind::UnknownKind => ShapeChoice::Unknown,
@@ -158,8 +209,42 @@ impl<'a> ShapeChoiceQueries<'a> {
           shape_id: choice.shape_id.clone(),
           json_type: JsonType::Null,
         }),
-        ShapeKind::IdentifierKind => unimplemented!(),
-        ShapeKind::ReferenceKind => unimplemented!(),
+        ShapeKind::IdentifierKind => {
+          let shape_parameter_id = &String::from(
+            choice
+              .core_shape_kind
+              .get_parameter_descriptor()
+              .expect("expected $identifier to have a parameter descriptor")
+              .shape_parameter_id,
+          );
+          let inner_shape_id = queries.resolve_parameter_to_shape_in_trail(
+            &choice.shape_trail(),
+            &choice.shape_id,
+            shape_parameter_id,
+          );
+          ShapeChoice::Identifier(IdentifierChoice {
+            shape_id: choice.shape_id.clone(),
+            inner_shape_id,
+          })
+        }
+        ShapeKind::ReferenceKind => {
+          let shape_parameter_id = &String::from(
+            choice
+              .core_shape_kind
+              .get_parameter_descriptor()
+              .expect("expected $reference to have a parameter descriptor")
+              .shape_parameter_id,
+          );
+          let target_shape_id = queries.resolve_parameter_to_shape_in_trail(
+            &choice.shape_trail(),
+            &choice.shape_id,
+            shape_parameter_id,
+          );
+          ShapeChoice::Reference(ReferenceChoice {
+            shape_id: choice.shape_id.clone(),
+            target_shape_id,
+          })
+        }
         ShapeKind::OptionalKind => ShapeChoice::Primitive(PrimitiveChoice {
           shape_id: choice.shape_id.clone(),
           json_type: JsonType::Undefined,
@@ -193,7 +278,7 @@ impl<'a> ShapeChoiceQueries<'a> {
 #[cfg(test)]
 mod test {
   use super::*;
-  use crate::commands::SpecCommand;
+  use crate::commands::{ShapeCommand, SpecCommand};
   use crate::events::SpecEvent;
   use crate::projections::SpecProjection;
   use crate::Aggregate;
@@ -242,6 +327,62 @@ mod test {
     );
   }
 
+  #[test]
+  pub fn can_list_choices_for_identifiers_and_references() {
+    let commands = vec![
+      ShapeCommand::add_shape(
+        "string_shape_1".to_owned(),
+        ShapeKind::StringKind,
+        "".to_owned(),
+      ),
+      ShapeCommand::add_shape(
+        "user_id_1".to_owned(),
+        ShapeKind::IdentifierKind,
+        "".to_owned(),
+      ),
+      ShapeCommand::set_parameter_shape(
+        "user_id_1".to_owned(),
+        "$identifierInner".to_owned(),
+        "string_shape_1".to_owned(),
+      ),
+      ShapeCommand::add_shape(
+        "user_1".to_owned(),
+        ShapeKind::ObjectKind,
+        "User".to_owned(),
+      ),
+      ShapeCommand::add_field(
+        "id".to_owned(),
+        "field_1".to_owned(),
+        "user_1".to_owned(),
+        "user_id_1".to_owned(),
+      ),
+      ShapeCommand::add_shape(
+        "author_id_1".to_owned(),
+        ShapeKind::ReferenceKind,
+        "".to_owned(),
+      ),
+      ShapeCommand::set_parameter_shape(
+        "author_id_1".to_owned(),
+        "$referenceTarget".to_owned(),
+        "user_id_1".to_owned(),
+      ),
+    ]
+    .into_iter()
+    .map(SpecCommand::from);
+
+    let spec_projection = assert_valid_commands(SpecProjection::default(), commands);
+    let choice_mapping = spec_projection.shape().to_choice_mapping();
+
+    assert_debug_snapshot!(
+      "can_list_choices_for_identifiers_and_references__identifier_choices",
+      choice_mapping.get("user_id_1")
+    );
+    assert_debug_snapshot!(
+      "can_list_choices_for_identifiers_and_references__reference_choices",
+      choice_mapping.get("author_id_1")
+    );
+  }
+
   fn assert_valid_commands(
     mut spec_projection: SpecProjection,
     commands: impl IntoIterator<Item = SpecCommand>,
@@ -4,6 +4,7 @@ mod result;
 pub mod traverser;
 pub mod visitors;
 
+use crate::interactions::DiffConfig;
 use crate::learn_shape::TrailObservationsResult;
 use crate::projections::shape::ShapeProjection;
 use crate::queries::shape::ShapeQueries;
@@ -19,9 +20,35 @@ pub fn diff(
   shapes_projection: &ShapeProjection,
   body: Option<BodyDescriptor>,
   shape_id: &ShapeId,
+  config: &DiffConfig,
 ) -> Vec<ShapeDiffResult> {
   let shapes_queries = ShapeQueries::new(shapes_projection);
   let shape_traverser = traverser::Traverser::new(&shapes_queries);
+
+  diff_with_traverser(shape_traverser, body, shape_id, config)
+}
+
+/// Compute the diff between (normalized) query parameters and a shape definition from a spec.
+/// As query parameter values are always strings, those spelling out a number or boolean are
+/// diffed as such when that's what the shape expects (e.g. `"42"` satisfies `$number`).
+pub fn diff_query_parameters(
+  shapes_projection: &ShapeProjection,
+  query_parameters: Option<BodyDescriptor>,
+  shape_id: &ShapeId,
+  config: &DiffConfig,
+) -> Vec<ShapeDiffResult> {
+  let shapes_queries = ShapeQueries::new(shapes_projection);
+  let shape_traverser = traverser::Traverser::new(&shapes_queries).coercing_strings();
+
+  diff_with_traverser(shape_traverser, query_parameters, shape_id, config)
+}
+
+fn diff_with_traverser(
+  shape_traverser: traverser::Traverser,
+  body: Option<BodyDescriptor>,
+  shape_id: &ShapeId,
+  config: &DiffConfig,
+) -> Vec<ShapeDiffResult> {
   let mut diff_visitors = visitors::diff::DiffVisitors::new();
 
   //dbg!(
@@ -30,7 +57,16 @@ pub fn diff(
 
   shape_traverser.traverse_root_shape(body, shape_id, &mut diff_visitors);
 
-  diff_visitors.take_results().unwrap()
+  diff_visitors
+    .take_results()
+    .unwrap()
+    .into_iter()
+    .filter(|result| match result {
+      ShapeDiffResult::UnspecifiedShape { .. } => !config.allows_undocumented_fields(),
+      _ => true,
+    })
+    .filter(|result| !config.is_ignored_json_trail(result.json_trail()))
+    .collect()
 }
 
 pub fn analyze_trail_values(
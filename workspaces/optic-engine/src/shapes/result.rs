@@ -1,4 +1,5 @@
 use crate::shapes::{JsonTrail, ShapeTrail};
+use crate::state::shape::ShapeConstraintId;
 use seahash::hash;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
@@ -16,6 +17,12 @@ pub enum ShapeDiffResult {
     json_trail: JsonTrail,
     shape_trail: ShapeTrail,
   },
+  #[serde(rename_all = "camelCase")]
+  UnmatchedShapeConstraint {
+    json_trail: JsonTrail,
+    shape_trail: ShapeTrail,
+    shape_constraint_id: ShapeConstraintId,
+  },
 }
 
 impl ShapeDiffResult {
@@ -35,8 +42,25 @@ impl ShapeDiffResult {
         json_trail: json_trail.normalized(),
         shape_trail: shape_trail.clone(),
       },
+      ShapeDiffResult::UnmatchedShapeConstraint {
+        json_trail,
+        shape_trail,
+        shape_constraint_id,
+      } => ShapeDiffResult::UnmatchedShapeConstraint {
+        json_trail: json_trail.normalized(),
+        shape_trail: shape_trail.clone(),
+        shape_constraint_id: shape_constraint_id.clone(),
+      },
+    }
+  }
+  pub fn json_trail(&self) -> &JsonTrail {
+    match self {
+      ShapeDiffResult::UnmatchedShape { json_trail, .. } => json_trail,
+      ShapeDiffResult::UnspecifiedShape { json_trail, .. } => json_trail,
+      ShapeDiffResult::UnmatchedShapeConstraint { json_trail, .. } => json_trail,
     }
   }
+
   pub fn fingerprint(&self) -> String {
     let normalized = self.normalized();
     let s = serde_json::to_vec(&normalized).expect("ShapeDiffResult should be json serializable");
@@ -64,6 +88,16 @@ impl Hash for ShapeDiffResult {
         Hash::hash(&json_trail.normalized(), hash_state);
         Hash::hash(shape_trail, hash_state);
       }
+      ShapeDiffResult::UnmatchedShapeConstraint {
+        json_trail,
+        shape_trail,
+        shape_constraint_id,
+      } => {
+        Hash::hash(&core::mem::discriminant(self), hash_state);
+        Hash::hash(&json_trail.normalized(), hash_state);
+        Hash::hash(shape_trail, hash_state);
+        Hash::hash(shape_constraint_id, hash_state);
+      }
     }
   }
 }
@@ -2,23 +2,34 @@ use super::visitors::{
   BodyArrayVisitor, BodyObjectKeyVisitor, BodyObjectVisitor, BodyPrimitiveVisitor, BodyVisitors,
 };
 use crate::queries::shape::{ChoiceOutput, ShapeQueries};
-use crate::state::body::BodyDescriptor;
+use crate::state::body::{BodyDescriptor, NumberValue, ObjectDescriptor, PrimitiveValues};
 use crate::state::shape::{
   FieldId, FieldShapeDescriptor, FieldShapeFromShape, ShapeId, ShapeKind, ShapeParameterId,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
+use serde_json::{Number as JsonNumber, Value as JsonValue};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::{cmp::Ordering, fmt::Write};
 
 pub struct Traverser<'a> {
   shape_queries: &'a ShapeQueries<'a>,
+  coerce_strings: bool,
 }
 
 impl<'a> Traverser<'a> {
   pub fn new(shape_queries: &'a ShapeQueries) -> Self {
-    Traverser { shape_queries }
+    Traverser {
+      shape_queries,
+      coerce_strings: false,
+    }
+  }
+
+  /// Traverse string values as the number or boolean they spell out, when that's what the shape
+  /// expects. Used for bodies that can only ever contain strings, like query strings.
+  pub fn coercing_strings(mut self) -> Self {
+    self.coerce_strings = true;
+    self
   }
 
   pub fn traverse_root_shape<R>(
@@ -60,7 +71,8 @@ impl<'a> Traverser<'a> {
           .iter()
           .flat_map(move |choice| {
             if let ShapeKind::ListKind = &choice.core_shape_kind {
-              let item_shape_id = self.shape_queries.resolve_parameter_to_shape(
+              let item_shape_id = self.shape_queries.resolve_parameter_to_shape_in_trail(
+                &choice.shape_trail(),
                 &choice.shape_id,
                 &String::from(
                   choice
@@ -112,9 +124,15 @@ impl<'a> Traverser<'a> {
       }
       BodyDescriptor::Object(_) => {
         // eprintln!("shape-traverser: visiting object");
+        let discriminated_choices = match &body {
+          BodyDescriptor::Object(object) => {
+            self.select_discriminated_choices(object, trail_choices)
+          }
+          _ => unreachable!("expect body to be an object"),
+        };
         let matching_choices = {
           let object_visitor = visitors.object();
-          object_visitor.visit(&body, &body_trail, &trail_origin, trail_choices)
+          object_visitor.visit(&body, &body_trail, &trail_origin, &discriminated_choices)
         };
         // eprintln!("shape-traverser: visiting object keys");
         let object_key_visitor = visitors.object_key();
@@ -124,14 +142,17 @@ impl<'a> Traverser<'a> {
           _ => unreachable!("expect body to be an object"),
         };
 
+        // maps don't document any keys, so only objects can miss some
         let object_key_choices = matching_choices
           .iter()
+          .filter(|choice| matches!(choice.core_shape_kind, ShapeKind::ObjectKind))
           .map(|choice| {
             if let ShapeKind::ObjectKind = &choice.core_shape_kind {
               // - find field node by key in object's field node edges
               let field_ids = self
                 .shape_queries
                 .resolve_shape_field_id_and_names(&choice.shape_id);
+              let object_trail = choice.shape_trail();
 
               (
                 choice,
@@ -139,7 +160,7 @@ impl<'a> Traverser<'a> {
                   .map(|(field_id, field_name)| {
                     let field_shape_id = self
                       .shape_queries
-                      .resolve_field_shape_node(&field_id)
+                      .resolve_field_shape_in_trail(&object_trail, &field_id)
                       .unwrap();
                     let field_core_shape_kind =
                       self.shape_queries.resolve_to_core_shape(&field_shape_id);
@@ -161,46 +182,75 @@ impl<'a> Traverser<'a> {
         let object_keys = object.keys().map(|x| (*x).clone()).collect::<Vec<_>>();
         object_key_visitor.visit(&body_trail, &object_keys, &object_key_choices);
 
+        let map_value_choices = matching_choices
+          .iter()
+          .filter(|choice| matches!(choice.core_shape_kind, ShapeKind::MapKind))
+          .flat_map(|choice| {
+            let value_shape_id = self.shape_queries.resolve_parameter_to_shape_in_trail(
+              &choice.shape_trail(),
+              &choice.shape_id,
+              &String::from(
+                choice
+                  .core_shape_kind
+                  .get_parameter_descriptor()
+                  .unwrap()
+                  .shape_parameter_id,
+              ),
+            );
+            let value_trail =
+              choice
+                .shape_trail()
+                .with_component(ShapeTrailPathComponent::MapValueTrail {
+                  map_shape_id: choice.shape_id.clone(),
+                  value_shape_id,
+                });
+            self.shape_queries.list_known_trail_choices(&value_trail)
+          })
+          .collect::<Vec<_>>();
+
         object.entries().for_each(|(field_key, field_body)| {
           let field_json_trail = body_trail.with_component(JsonTrailPathComponent::JsonObjectKey {
             key: field_key.clone(),
           });
 
-          let field_choices = matching_choices
-            .iter()
-            .flat_map(|choice| {
-              //dbg!("shape-traverser: object choice", choice);
-              if let ShapeKind::ObjectKind = &choice.core_shape_kind {
-                // - find field node by key in object's field node edges
-                let field_id_option = self
-                  .shape_queries
-                  .resolve_field_id(&choice.shape_id, &field_key);
-                if let None = field_id_option {
-                  //dbg!("shape-traverser: no field id could be resolved");
-                  return vec![];
-                }
-
-                let field_id = field_id_option.unwrap();
-                let field_shape_id = self
-                  .shape_queries
-                  .resolve_field_shape_node(&field_id)
-                  .expect("field node should have an edge to a shape node describing its value");
-                //dbg!("shape-traverser: field_shape_id", &field_shape_id);
-
-                let field_trail =
-                  choice
-                    .shape_trail()
-                    .with_component(ShapeTrailPathComponent::ObjectFieldTrail {
+          // every key of a map is visited against the same value shape
+          let mut field_choices = map_value_choices.clone();
+          field_choices.extend(
+            matching_choices
+              .iter()
+              .filter(|choice| matches!(choice.core_shape_kind, ShapeKind::ObjectKind))
+              .flat_map(|choice| {
+                //dbg!("shape-traverser: object choice", choice);
+                if let ShapeKind::ObjectKind = &choice.core_shape_kind {
+                  // - find field node by key in object's field node edges
+                  let field_id_option = self
+                    .shape_queries
+                    .resolve_field_id(&choice.shape_id, &field_key);
+                  if let None = field_id_option {
+                    //dbg!("shape-traverser: no field id could be resolved");
+                    return vec![];
+                  }
+
+                  let field_id = field_id_option.unwrap();
+                  let field_shape_id = self
+                    .shape_queries
+                    .resolve_field_shape_in_trail(&choice.shape_trail(), &field_id)
+                    .expect("field node should have an edge to a shape node describing its value");
+                  //dbg!("shape-traverser: field_shape_id", &field_shape_id);
+
+                  let field_trail = choice.shape_trail().with_component(
+                    ShapeTrailPathComponent::ObjectFieldTrail {
                       field_id: field_id.clone(),
                       field_shape_id: field_shape_id,
                       parent_object_shape_id: choice.shape_id.clone(),
-                    });
-                self.shape_queries.list_known_trail_choices(&field_trail)
-              } else {
-                unreachable!("should only contain choices of object kind");
-              }
-            })
-            .collect::<Vec<ChoiceOutput>>();
+                    },
+                  );
+                  self.shape_queries.list_known_trail_choices(&field_trail)
+                } else {
+                  unreachable!("should only contain choices of object kind");
+                }
+              }),
+          );
           let new_trail_origin = match field_choices.first() {
             Some(choice) => choice.parent_trail.clone(),
             None => trail_origin.clone(),
@@ -219,6 +269,11 @@ impl<'a> Traverser<'a> {
         });
       }
       primitive_value => {
+        let primitive_value = if self.coerce_strings {
+          coerce_string(primitive_value, trail_choices)
+        } else {
+          primitive_value
+        };
         let primitive_visitor = visitors.primitive();
         // eprintln!("shape-traverser: visiting primitive");
         primitive_visitor.visit(primitive_value, body_trail, trail_origin, trail_choices);
@@ -227,6 +282,111 @@ impl<'a> Traverser<'a> {
   }
 }
 
+impl<'a> Traverser<'a> {
+  /// The choices for an object body, without the branches of one-ofs that aren't selected by the
+  /// value of their discriminator field. One-ofs without a discriminator, or of which the object
+  /// doesn't carry a mapped value, have all their branches tried.
+  fn select_discriminated_choices(
+    &self,
+    object: &ObjectDescriptor,
+    choices: &[ChoiceOutput],
+  ) -> Vec<ChoiceOutput> {
+    choices
+      .iter()
+      .filter(|choice| {
+        choice
+          .shape_trail()
+          .path
+          .iter()
+          .rev()
+          // only the one-ofs describing the object itself, not those of its ancestors
+          .take_while(|component| {
+            !matches!(
+              component,
+              ShapeTrailPathComponent::ObjectFieldTrail { .. }
+                | ShapeTrailPathComponent::ListItemTrail { .. }
+                | ShapeTrailPathComponent::MapValueTrail { .. }
+            )
+          })
+          .all(|component| match component {
+            ShapeTrailPathComponent::OneOfItemTrail {
+              one_of_id,
+              parameter_id,
+              ..
+            } => match self.discriminated_branch(object, one_of_id) {
+              Some(selected_parameter_id) => selected_parameter_id == parameter_id,
+              None => true,
+            },
+            _ => true,
+          })
+      })
+      .cloned()
+      .collect()
+  }
+
+  fn discriminated_branch(
+    &self,
+    object: &ObjectDescriptor,
+    one_of_id: &ShapeId,
+  ) -> Option<&ShapeParameterId> {
+    let discriminator = self.shape_queries.resolve_one_of_discriminator(one_of_id)?;
+    let values = match object.get(&discriminator.field_name)? {
+      BodyDescriptor::String(values) if !values.is_unknown() => values,
+      _ => return None,
+    };
+
+    // values merged from several objects only select a branch when they all agree on it
+    let mut branches = values.iter().map(|value| discriminator.branch_for(value));
+    let branch = branches.next()??;
+    if branches.all(|other_branch| other_branch == Some(branch)) {
+      Some(branch)
+    } else {
+      None
+    }
+  }
+}
+
+/// A string as the number or boolean expected by the choices, if all its values spell one out.
+/// Strings are left alone when the choices accept strings.
+fn coerce_string(
+  primitive_value: BodyDescriptor,
+  trail_choices: &[ChoiceOutput],
+) -> BodyDescriptor {
+  let values = match &primitive_value {
+    BodyDescriptor::String(values) if !values.is_unknown() => values,
+    _ => return primitive_value,
+  };
+  let accepts_kind = |kind: fn(&ShapeKind) -> bool| {
+    trail_choices
+      .iter()
+      .any(|choice| kind(&choice.core_shape_kind))
+  };
+
+  if accepts_kind(|kind| matches!(kind, ShapeKind::StringKind)) {
+    return primitive_value;
+  }
+
+  if accepts_kind(|kind| matches!(kind, ShapeKind::NumberKind)) {
+    let numbers = values
+      .iter()
+      .map(|value| value.parse::<JsonNumber>().map(NumberValue::from))
+      .collect::<Result<PrimitiveValues<_>, _>>();
+    if let Ok(numbers) = numbers {
+      return BodyDescriptor::Number(numbers);
+    }
+  }
+
+  if accepts_kind(|kind| matches!(kind, ShapeKind::BooleanKind))
+    && values
+      .iter()
+      .all(|value| value == "true" || value == "false")
+  {
+    return BodyDescriptor::Boolean;
+  }
+
+  primitive_value
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Hash)]
 pub enum ShapeTrailPathComponent {
   #[serde(rename_all = "camelCase")]
@@ -245,6 +405,11 @@ pub enum ShapeTrailPathComponent {
     item_shape_id: ShapeId,
   },
   #[serde(rename_all = "camelCase")]
+  MapValueTrail {
+    map_shape_id: ShapeId,
+    value_shape_id: ShapeId,
+  },
+  #[serde(rename_all = "camelCase")]
   OneOfTrail { shape_id: ShapeId },
   #[serde(rename_all = "camelCase")]
   OneOfItemTrail {
@@ -267,6 +432,20 @@ pub enum ShapeTrailPathComponent {
     inner_shape_id: ShapeId,
   },
   #[serde(rename_all = "camelCase")]
+  IdentifierTrail { shape_id: ShapeId },
+  #[serde(rename_all = "camelCase")]
+  IdentifierInnerTrail {
+    shape_id: ShapeId,
+    inner_shape_id: ShapeId,
+  },
+  #[serde(rename_all = "camelCase")]
+  ReferenceTrail { shape_id: ShapeId },
+  #[serde(rename_all = "camelCase")]
+  ReferenceTargetTrail {
+    shape_id: ShapeId,
+    target_shape_id: ShapeId,
+  },
+  #[serde(rename_all = "camelCase")]
   UnknownTrail {},
 }
 
@@ -351,6 +530,26 @@ impl JsonTrail {
     self.with_component(JsonTrailPathComponent::JsonObjectKey { key })
   }
 
+  /// Rewrite a trail running through any of the values of the object at `map_trail` to run
+  /// through `value_key` instead, so all values of a map can be treated as one.
+  pub fn with_map_value_normalized(&self, map_trail: &JsonTrail, value_key: &str) -> Option<Self> {
+    let map_depth = map_trail.path.len();
+    if self.path.len() <= map_depth || !self.path.starts_with(&map_trail.path) {
+      return None;
+    }
+
+    match &self.path[map_depth] {
+      JsonTrailPathComponent::JsonObjectKey { key } if key != value_key => {
+        let mut path = self.path.clone();
+        path[map_depth] = JsonTrailPathComponent::JsonObjectKey {
+          key: String::from(value_key),
+        };
+        Some(Self { path })
+      }
+      _ => None,
+    }
+  }
+
   pub fn normalized(&self) -> Self {
     Self {
       path: self
@@ -383,6 +582,20 @@ impl JsonTrail {
     self.path.len() == (parent_trail.path.len() + 1) && self.is_descendant_of(parent_trail)
   }
 
+  /// The object keys and array indexes along the trail, from the root
+  pub fn identifiers(&self) -> Vec<String> {
+    self
+      .path
+      .iter()
+      .filter_map(|component| match component {
+        JsonTrailPathComponent::JsonArrayItem { index } => Some(format!("{}", index)),
+        JsonTrailPathComponent::JsonObjectKey { key } => Some(key.clone()),
+        JsonTrailPathComponent::JsonArray {} => None,
+        JsonTrailPathComponent::JsonObject {} => None,
+      })
+      .collect()
+  }
+
   pub fn last_component(&self) -> Option<&JsonTrailPathComponent> {
     self.path.last()
   }
@@ -416,17 +629,7 @@ impl Hash for JsonTrail {
 
 impl fmt::Display for JsonTrail {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let identifiers = self
-      .path
-      .iter()
-      .filter_map(|component| match component {
-        JsonTrailPathComponent::JsonArrayItem { index } => Some(format!("{}", index)),
-        JsonTrailPathComponent::JsonObjectKey { key } => Some(key.clone()),
-        JsonTrailPathComponent::JsonArray {} => None,
-        JsonTrailPathComponent::JsonObject {} => None,
-      })
-      .collect::<Vec<_>>();
-    write!(f, "{}", identifiers.join("."))
+    write!(f, "{}", self.identifiers().join("."))
   }
 }
 
@@ -6,7 +6,7 @@ use crate::queries::shape::ChoiceOutput;
 use crate::shapes::ShapeDiffResult;
 use crate::shapes::{JsonTrail, JsonTrailPathComponent, ShapeTrail, ShapeTrailPathComponent};
 use crate::state::body::BodyDescriptor;
-use crate::state::shape::{FieldId, ShapeId, ShapeKind};
+use crate::state::shape::{FieldId, ShapeConstraintId, ShapeId, ShapeKind};
 use serde_json::Value as JsonValue;
 
 pub struct DiffVisitors {
@@ -95,11 +95,11 @@ impl BodyPrimitiveVisitor<ShapeDiffResult> for DiffPrimitiveVisitor {
           ShapeKind::BooleanKind => true,
           _ => false,
         },
-        BodyDescriptor::Number => match choice.core_shape_kind {
+        BodyDescriptor::Number(_) => match choice.core_shape_kind {
           ShapeKind::NumberKind => true,
           _ => false,
         },
-        BodyDescriptor::String => match choice.core_shape_kind {
+        BodyDescriptor::String(_) => match choice.core_shape_kind {
           ShapeKind::StringKind => true,
           _ => false,
         },
@@ -113,16 +113,44 @@ impl BodyPrimitiveVisitor<ShapeDiffResult> for DiffPrimitiveVisitor {
       unmatched
         .iter()
         .filter(|choice| !matches!(choice.core_shape_kind, ShapeKind::OptionalKind)) // prevent generating two diffs for optional primitives
+        .filter(|choice| !is_wrapping_choice(choice))
         .for_each(|&choice| {
           self.results.push(ShapeDiffResult::UnmatchedShape {
             json_trail: json_trail.clone(),
             shape_trail: choice.shape_trail(),
           });
         });
+    } else if !matched
+      .iter()
+      .any(|choice| unmatched_constraints(choice, &body).next().is_none())
+    {
+      // the primitive kind matches, but none of the choices accept its value
+      matched.iter().for_each(|&choice| {
+        unmatched_constraints(choice, &body).for_each(|shape_constraint_id| {
+          self
+            .results
+            .push(ShapeDiffResult::UnmatchedShapeConstraint {
+              json_trail: json_trail.clone(),
+              shape_trail: choice.shape_trail(),
+              shape_constraint_id: shape_constraint_id.clone(),
+            });
+        });
+      });
     }
   }
 }
 
+fn unmatched_constraints<'a>(
+  choice: &'a ChoiceOutput,
+  body: &'a BodyDescriptor,
+) -> impl Iterator<Item = &'a ShapeConstraintId> {
+  choice
+    .constraints
+    .iter()
+    .filter(move |(_, constraint)| !constraint.accepts(body))
+    .map(|(shape_constraint_id, _)| shape_constraint_id)
+}
+
 // Array visitor
 // -------------
 
@@ -170,12 +198,15 @@ impl BodyArrayVisitor<ShapeDiffResult> for DiffArrayVisitor {
       });
 
     if matched.is_empty() {
-      unmatched.into_iter().for_each(|choice| {
-        self.results.push(ShapeDiffResult::UnmatchedShape {
-          json_trail: json_trail.clone(),
-          shape_trail: choice.shape_trail(),
+      unmatched
+        .into_iter()
+        .filter(|choice| !is_wrapping_choice(choice))
+        .for_each(|choice| {
+          self.results.push(ShapeDiffResult::UnmatchedShape {
+            json_trail: json_trail.clone(),
+            shape_trail: choice.shape_trail(),
+          });
         });
-      });
     }
 
     matched.into_iter().map(|x| (*x).clone()).collect()
@@ -222,19 +253,22 @@ impl BodyObjectVisitor<ShapeDiffResult> for DiffObjectVisitor {
     let (matched, unmatched): (Vec<&ChoiceOutput>, Vec<&ChoiceOutput>) =
       trail_choices.into_iter().partition(|choice| match body {
         BodyDescriptor::Object(_) => match choice.core_shape_kind {
-          ShapeKind::ObjectKind => true,
+          ShapeKind::ObjectKind | ShapeKind::MapKind => true,
           _ => false,
         },
         _ => unreachable!("should only call object visitor for object body types"),
       });
 
     if matched.is_empty() {
-      unmatched.into_iter().for_each(|choice| {
-        self.results.push(ShapeDiffResult::UnmatchedShape {
-          json_trail: json_trail.clone(),
-          shape_trail: choice.shape_trail(),
+      unmatched
+        .into_iter()
+        .filter(|choice| !is_wrapping_choice(choice))
+        .for_each(|choice| {
+          self.results.push(ShapeDiffResult::UnmatchedShape {
+            json_trail: json_trail.clone(),
+            shape_trail: choice.shape_trail(),
+          });
         });
-      });
     }
 
     matched.into_iter().map(|x| (*x).clone()).collect()
@@ -310,3 +344,11 @@ impl BodyObjectKeyVisitor<ShapeDiffResult> for DiffObjectKeyVisitor {
     })
   }
 }
+
+// identifiers and references are diffed as the shapes they wrap, which are also choices
+fn is_wrapping_choice(choice: &ChoiceOutput) -> bool {
+  matches!(
+    choice.core_shape_kind,
+    ShapeKind::IdentifierKind | ShapeKind::ReferenceKind
+  )
+}
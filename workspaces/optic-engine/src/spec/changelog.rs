@@ -0,0 +1,473 @@
+use super::{compare, events_of_commits, ChangeLocation, SpecChange, SpecChangeKind};
+use crate::events::rfc::BatchCommitStarted;
+use crate::events::{RfcEvent, SpecEvent};
+use crate::projections::{CommitId, HistoryProjection, SpecProjection};
+use crate::state::endpoint::HttpMethod;
+use cqrs_core::Aggregate;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+use thiserror::Error;
+
+/// A changelog of the batch commits after `since_batch_id`, up to and including `until_batch_id`
+/// or, by default, every batch commit after it. The changes of each batch commit are found by
+/// comparing the spec before and after it, grouped by endpoint. Batch commits that didn't change
+/// any endpoints are left out and the latest batch commits are listed first.
+///
+/// Expects the spec events in the order their batches were assembled in, with the events of each
+/// batch commit recorded with its id like those of appended batches are.
+pub fn changelog(
+  spec_events: Vec<SpecEvent>,
+  since_batch_id: Option<&CommitId>,
+  until_batch_id: Option<&CommitId>,
+) -> Result<Changelog, ChangelogError> {
+  let history = HistoryProjection::from(spec_events.iter().cloned());
+  let ancestor_ids = |batch_id: &CommitId| {
+    history
+      .get_ancestor_commit_ids(batch_id)
+      .map(|ancestor_ids| ancestor_ids.into_iter().cloned().collect::<HashSet<_>>())
+      .ok_or_else(|| ChangelogError::UnknownBatchCommit(batch_id.clone()))
+  };
+
+  let excluded_ids = match since_batch_id {
+    Some(since_batch_id) => ancestor_ids(since_batch_id)?,
+    None => HashSet::new(),
+  };
+  let included_ids = match until_batch_id {
+    Some(until_batch_id) => Some(ancestor_ids(until_batch_id)?),
+    None => None,
+  };
+
+  // the spec is folded once, diffing it before and after each batch commit, and only rebuilt when a
+  // batch commit doesn't continue from the one before it, like when it's on another branch
+  let mut spec_projection = SpecProjection::default();
+  let mut last_batch_id: Option<&CommitId> = None;
+  let mut included_batch_commit: Option<(&BatchCommitStarted, SpecProjection)> = None;
+  let mut batch_changelogs = vec![];
+  for event in &spec_events {
+    if let SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(batch_commit)) = event {
+      let batch_id = &batch_commit.batch_id;
+      let parent_id = batch_commit.parent_id.as_ref();
+      if last_batch_id.is_some() && last_batch_id != parent_id {
+        let parent_ids = parent_id
+          .and_then(|parent_id| history.get_ancestor_commit_ids(parent_id))
+          .map(|ancestor_ids| ancestor_ids.into_iter().cloned().collect())
+          .unwrap_or_default();
+        spec_projection = SpecProjection::from(events_of_commits(spec_events.clone(), &parent_ids));
+      }
+
+      let is_included = match &included_ids {
+        Some(included_ids) => included_ids.contains(batch_id),
+        None => true,
+      };
+      if is_included && !excluded_ids.contains(batch_id) {
+        included_batch_commit = Some((batch_commit, spec_projection.clone()));
+      }
+    }
+
+    spec_projection.apply(event.clone());
+
+    if let SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(e)) = event {
+      last_batch_id = Some(&e.batch_id);
+
+      let (batch_commit, before_spec) = match included_batch_commit.take() {
+        Some(included) if included.0.batch_id == e.batch_id => included,
+        _ => continue,
+      };
+      // batch commits that didn't create, update or remove anything don't have to be compared
+      if !has_changes_in_batch_commit(&spec_projection, &e.batch_id) {
+        continue;
+      }
+      let endpoints = EndpointChangelog::grouped(compare(&before_spec, &spec_projection));
+      if endpoints.is_empty() {
+        continue;
+      }
+
+      batch_changelogs.push(BatchChangelog {
+        batch_id: e.batch_id.clone(),
+        commit_message: batch_commit.commit_message.clone(),
+        created_at: batch_commit
+          .event_context
+          .as_ref()
+          .map(|event_context| event_context.created_at.clone()),
+        endpoints,
+      });
+    }
+  }
+  batch_changelogs.reverse();
+
+  Ok(Changelog {
+    batch_commits: batch_changelogs,
+  })
+}
+
+/// Whether the history of the spec's endpoints or shapes has anything created, updated or removed by
+/// a batch commit
+fn has_changes_in_batch_commit(spec_projection: &SpecProjection, batch_id: &CommitId) -> bool {
+  spec_projection
+    .spectacle_endpoints()
+    .has_changes_in_batch_commit(batch_id)
+    || spec_projection
+      .shape()
+      .has_changes_in_batch_commit(batch_id)
+}
+
+#[derive(Debug, Error)]
+pub enum ChangelogError {
+  #[error("could not find batch commit '{0}' for the changelog")]
+  UnknownBatchCommit(CommitId),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Changelog {
+  pub batch_commits: Vec<BatchChangelog>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchChangelog {
+  pub batch_id: CommitId,
+  pub commit_message: String,
+  pub created_at: Option<String>,
+  pub endpoints: Vec<EndpointChangelog>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointChangelog {
+  pub absolute_path_pattern: String,
+  pub http_method: HttpMethod,
+  pub entries: Vec<ChangelogEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangelogEntry {
+  pub description: String,
+  pub location: ChangeLocation,
+  pub kind: SpecChangeKind,
+  pub is_breaking: bool,
+}
+
+impl Changelog {
+  pub fn to_markdown(&self) -> String {
+    let mut markdown = String::new();
+    for batch_commit in &self.batch_commits {
+      let commit_message = match batch_commit.commit_message.trim() {
+        "" => "Untitled batch commit",
+        commit_message => commit_message,
+      };
+      writeln!(markdown, "## {}\n", commit_message).unwrap();
+      match &batch_commit.created_at {
+        Some(created_at) => writeln!(markdown, "_{}_ · `{}`\n", created_at, batch_commit.batch_id),
+        None => writeln!(markdown, "`{}`\n", batch_commit.batch_id),
+      }
+      .unwrap();
+
+      for endpoint in &batch_commit.endpoints {
+        writeln!(
+          markdown,
+          "### {} {}\n",
+          endpoint.http_method, endpoint.absolute_path_pattern
+        )
+        .unwrap();
+        for entry in &endpoint.entries {
+          let breaking = if entry.is_breaking {
+            " **(breaking)**"
+          } else {
+            ""
+          };
+          writeln!(markdown, "- {}{}", entry.description, breaking).unwrap();
+        }
+        markdown.push('\n');
+      }
+    }
+    markdown
+  }
+}
+
+impl EndpointChangelog {
+  /// Group changes by their endpoint, expecting changes of the same endpoint to be adjacent
+  fn grouped(changes: Vec<SpecChange>) -> Vec<Self> {
+    let mut endpoints: Vec<Self> = vec![];
+    for change in changes {
+      let entry = ChangelogEntry {
+        description: describe(&change.location, &change.kind),
+        location: change.location,
+        kind: change.kind,
+        is_breaking: change.is_breaking,
+      };
+      match endpoints.last_mut() {
+        Some(endpoint)
+          if endpoint.absolute_path_pattern == change.absolute_path_pattern
+            && endpoint.http_method == change.http_method =>
+        {
+          endpoint.entries.push(entry)
+        }
+        _ => endpoints.push(Self {
+          absolute_path_pattern: change.absolute_path_pattern,
+          http_method: change.http_method,
+          entries: vec![entry],
+        }),
+      }
+    }
+    endpoints
+  }
+}
+
+// Descriptions
+// ------------
+
+fn describe(location: &ChangeLocation, kind: &SpecChangeKind) -> String {
+  let subject = match location {
+    ChangeLocation::Endpoint => String::from("endpoint"),
+    ChangeLocation::Request {
+      http_content_type: Some(_),
+    } => String::from("request body"),
+    ChangeLocation::Request {
+      http_content_type: None,
+    } => String::from("request"),
+    ChangeLocation::Response {
+      http_status_code, ..
+    } => format!("{} response", http_status_code),
+  };
+
+  match kind {
+    SpecChangeKind::Added => format!("added {}", subject),
+    SpecChangeKind::Removed => format!("removed {}", subject),
+    SpecChangeKind::FieldAdded {
+      json_path,
+      is_required: true,
+    } => format!("{}: field {} added", subject, describe_path(json_path)),
+    SpecChangeKind::FieldAdded {
+      json_path,
+      is_required: false,
+    } => format!(
+      "{}: optional field {} added",
+      subject,
+      describe_path(json_path)
+    ),
+    SpecChangeKind::FieldRemoved { json_path } => {
+      format!("{}: field {} removed", subject, describe_path(json_path))
+    }
+    SpecChangeKind::FieldMadeOptional { json_path } => {
+      format!(
+        "{}: field {} made optional",
+        subject,
+        describe_path(json_path)
+      )
+    }
+    SpecChangeKind::FieldMadeRequired { json_path } => {
+      format!(
+        "{}: field {} made required",
+        subject,
+        describe_path(json_path)
+      )
+    }
+    SpecChangeKind::FieldMadeNullable { json_path } => {
+      format!("{}: {} made nullable", subject, describe_field(json_path))
+    }
+    SpecChangeKind::FieldMadeNonNullable { json_path } => {
+      format!(
+        "{}: {} made non-nullable",
+        subject,
+        describe_field(json_path)
+      )
+    }
+    SpecChangeKind::ShapeKindChanged {
+      json_path,
+      from_kind,
+      to_kind,
+    } => format!(
+      "{}: {} changed from {} to {}",
+      subject,
+      describe_field(json_path),
+      from_kind.get_descriptor().name,
+      to_kind.get_descriptor().name
+    ),
+  }
+}
+
+fn describe_field(json_path: &[String]) -> String {
+  if json_path.is_empty() {
+    String::from("body")
+  } else {
+    format!("field {}", describe_path(json_path))
+  }
+}
+
+fn describe_path(json_path: &[String]) -> String {
+  let mut path = String::new();
+  for key in json_path {
+    if key != "[]" && key != "{}" && !path.is_empty() {
+      path.push('.');
+    }
+    path.push_str(key);
+  }
+  format!("`{}`", path)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use insta::{assert_debug_snapshot, assert_snapshot};
+  use serde_json::json;
+
+  fn batch_events(
+    batch_id: &str,
+    parent_id: &str,
+    commit_message: &str,
+    events: serde_json::Value,
+  ) -> Vec<SpecEvent> {
+    let event_context = json!({
+      "clientId":"anonymous","clientSessionId":"session","clientCommandBatchId":batch_id,"createdAt":"2021-01-01T00:00:00Z"
+    });
+    let start_event = json!({"BatchCommitStarted":{"batchId":batch_id,"parentId":parent_id,"commitMessage":commit_message,"eventContext":event_context}});
+    let end_event = json!({"BatchCommitEnded":{"batchId":batch_id}});
+    // like the events of appended batches, each event is recorded with the batch commit it's part of
+    let events = events.as_array().unwrap().iter().cloned().map(|mut event| {
+      for event_body in event.as_object_mut().unwrap().values_mut() {
+        event_body["eventContext"] = event_context.clone();
+      }
+      event
+    });
+    let events = std::iter::once(start_event)
+      .chain(events)
+      .chain(std::iter::once(end_event))
+      .collect::<Vec<_>>();
+
+    serde_json::from_value(serde_json::Value::Array(events))
+      .expect("example events should be valid spec events")
+  }
+
+  fn order_spec_events() -> Vec<SpecEvent> {
+    vec![
+      batch_events(
+        "batch_1",
+        "root",
+        "Add orders and users",
+        json!([
+          {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"orders"}},
+          {"PathComponentAdded":{"pathId":"path_2","parentPathId":"root","name":"users"}},
+          {"ShapeAdded":{"shapeId":"number_1","baseShapeId":"$number","name":""}},
+          {"ShapeAdded":{"shapeId":"string_1","baseShapeId":"$string","name":""}},
+          {"ShapeAdded":{"shapeId":"order_1","baseShapeId":"$object","name":""}},
+          {"FieldAdded":{"fieldId":"field_1","shapeId":"order_1","name":"total","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"number_1"}}}},
+          {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"POST"}},
+          {"RequestBodySet":{"requestId":"request_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"order_1","isRemoved":false}}},
+          {"ResponseAddedByPathAndMethod":{"responseId":"response_1","pathId":"path_2","httpMethod":"GET","httpStatusCode":200}},
+        ]),
+      ),
+      batch_events(
+        "batch_2",
+        "batch_1",
+        "Document missing users and order coupons",
+        json!([
+          {"ResponseAddedByPathAndMethod":{"responseId":"response_2","pathId":"path_2","httpMethod":"GET","httpStatusCode":404}},
+          {"FieldAdded":{"fieldId":"field_2","shapeId":"order_1","name":"coupon","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"string_1"}}}},
+        ]),
+      ),
+      batch_events(
+        "batch_3",
+        "batch_2",
+        "Rename a shape",
+        json!([
+          {"ShapeRenamed":{"shapeId":"order_1","name":"Order"}},
+        ]),
+      ),
+      batch_events(
+        "batch_4",
+        "batch_3",
+        "Totals are strings",
+        json!([
+          {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_1"}}}},
+        ]),
+      ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+  }
+
+  #[test]
+  pub fn can_create_changelog() {
+    let changelog = changelog(order_spec_events(), Some(&CommitId::from("batch_1")), None)
+      .expect("changelog should be created since a known batch commit");
+
+    let batch_ids = changelog
+      .batch_commits
+      .iter()
+      .map(|batch_commit| batch_commit.batch_id.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(batch_ids, vec!["batch_4", "batch_2"]);
+
+    assert_debug_snapshot!("can_create_changelog__changelog", changelog);
+    assert_snapshot!("can_create_changelog__markdown", changelog.to_markdown());
+  }
+
+  #[test]
+  pub fn changelog_can_be_limited_to_batch_commit() {
+    let changelog = changelog(order_spec_events(), None, Some(&CommitId::from("batch_2")))
+      .expect("changelog should be created until a known batch commit");
+
+    let batch_ids = changelog
+      .batch_commits
+      .iter()
+      .map(|batch_commit| batch_commit.batch_id.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(batch_ids, vec!["batch_2", "batch_1"]);
+
+    let descriptions = changelog.batch_commits[0]
+      .endpoints
+      .iter()
+      .flat_map(|endpoint| &endpoint.entries)
+      .map(|entry| entry.description.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      descriptions,
+      vec!["request body: field `coupon` added", "added 404 response"]
+    );
+
+    assert!(matches!(
+      super::changelog(order_spec_events(), None, Some(&CommitId::from("batch_5"))),
+      Err(ChangelogError::UnknownBatchCommit(_))
+    ));
+  }
+
+  #[test]
+  pub fn changelog_compares_batch_commits_to_their_parents() {
+    // a branch off batch_2, assembled after the batch commits of the main branch
+    let spec_events = order_spec_events()
+      .into_iter()
+      .chain(batch_events(
+        "batch_5",
+        "batch_2",
+        "Orders have notes",
+        json!([
+          {"FieldAdded":{"fieldId":"field_3","shapeId":"order_1","name":"note","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_3","shapeId":"string_1"}}}},
+          // totals are still numbers on this branch
+          {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"number_1"}}}},
+        ]),
+      ))
+      .collect::<Vec<_>>();
+
+    let changelog = changelog(spec_events, Some(&CommitId::from("batch_4")), None)
+      .expect("changelog should be created since a known batch commit");
+
+    let batch_ids = changelog
+      .batch_commits
+      .iter()
+      .map(|batch_commit| batch_commit.batch_id.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(batch_ids, vec!["batch_5"]);
+
+    let descriptions = changelog.batch_commits[0]
+      .endpoints
+      .iter()
+      .flat_map(|endpoint| &endpoint.entries)
+      .map(|entry| entry.description.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(descriptions, vec!["request body: field `note` added"]);
+  }
+}
@@ -0,0 +1,699 @@
+use crate::projections::endpoint::{BodyDescriptor, Node as EndpointNode, ROOT_PATH_ID};
+use crate::projections::{EndpointProjection, SpecProjection};
+use crate::queries::{EndpointQueries, ShapeQueries};
+use crate::shapes::ShapeTrail;
+use crate::state::endpoint::{HttpContentType, HttpMethod, HttpStatusSpecifier, PathComponentId};
+use crate::state::shape::{ShapeId, ShapeKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Compare two versions of a spec, like the spec at two batch commits, listing how the endpoints
+/// and the shapes of their bodies changed between them. Endpoints are matched by their path pattern
+/// and method, requests by their content type and responses by their status code and content type,
+/// or by their ids when an endpoint has several of them with the same content type. Each change
+/// is classified by whether clients of the `from_spec` could break because of it.
+pub fn compare(from_spec: &SpecProjection, to_spec: &SpecProjection) -> Vec<SpecChange> {
+  let from_endpoints = Endpoints::from(from_spec).endpoints;
+  let mut to_endpoints = Endpoints::from(to_spec).endpoints;
+
+  let mut changes = vec![];
+  for ((absolute_path_pattern, http_method), from_endpoint) in from_endpoints {
+    let mut endpoint_changes = ChangesCollector {
+      absolute_path_pattern: &absolute_path_pattern,
+      http_method: &http_method,
+      changes: &mut changes,
+    };
+    match to_endpoints.remove(&(absolute_path_pattern.clone(), http_method.clone())) {
+      Some(to_endpoint) => endpoint_changes.with_endpoint(from_endpoint, to_endpoint),
+      None => endpoint_changes.push(ChangeLocation::Endpoint, SpecChangeKind::Removed),
+    }
+  }
+  for (absolute_path_pattern, http_method) in to_endpoints.into_keys() {
+    ChangesCollector {
+      absolute_path_pattern: &absolute_path_pattern,
+      http_method: &http_method,
+      changes: &mut changes,
+    }
+    .push(ChangeLocation::Endpoint, SpecChangeKind::Added);
+  }
+
+  changes.sort_by(|a, b| {
+    (&a.absolute_path_pattern, &a.http_method).cmp(&(&b.absolute_path_pattern, &b.http_method))
+  });
+  changes
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecChange {
+  pub absolute_path_pattern: String,
+  pub http_method: HttpMethod,
+  pub location: ChangeLocation,
+  pub kind: SpecChangeKind,
+  pub is_breaking: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum ChangeLocation {
+  Endpoint,
+  #[serde(rename_all = "camelCase")]
+  Request {
+    http_content_type: Option<HttpContentType>,
+  },
+  #[serde(rename_all = "camelCase")]
+  Response {
+    http_status_code: HttpStatusSpecifier,
+    http_content_type: Option<HttpContentType>,
+  },
+}
+
+/// What changed at a location. Fields are identified by their path through the body, with `[]`
+/// for the items of a list, `{}` for the values of a map and `oneOf[n]` for the nth shape of a
+/// one-of.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum SpecChangeKind {
+  Added,
+  Removed,
+  #[serde(rename_all = "camelCase")]
+  FieldAdded {
+    json_path: Vec<String>,
+    is_required: bool,
+  },
+  #[serde(rename_all = "camelCase")]
+  FieldRemoved {
+    json_path: Vec<String>,
+  },
+  #[serde(rename_all = "camelCase")]
+  FieldMadeOptional {
+    json_path: Vec<String>,
+  },
+  #[serde(rename_all = "camelCase")]
+  FieldMadeRequired {
+    json_path: Vec<String>,
+  },
+  #[serde(rename_all = "camelCase")]
+  FieldMadeNullable {
+    json_path: Vec<String>,
+  },
+  #[serde(rename_all = "camelCase")]
+  FieldMadeNonNullable {
+    json_path: Vec<String>,
+  },
+  #[serde(rename_all = "camelCase")]
+  ShapeKindChanged {
+    json_path: Vec<String>,
+    from_kind: ShapeKind,
+    to_kind: ShapeKind,
+  },
+}
+
+impl SpecChange {
+  fn is_breaking(location: &ChangeLocation, kind: &SpecChangeKind) -> bool {
+    let is_request = matches!(location, ChangeLocation::Request { .. });
+    match kind {
+      SpecChangeKind::Added => false,
+      SpecChangeKind::Removed => true,
+      SpecChangeKind::ShapeKindChanged { .. } => true,
+      // clients have to start sending required fields, but can rely on responses having them
+      SpecChangeKind::FieldAdded { is_required, .. } => is_request && *is_required,
+      SpecChangeKind::FieldMadeRequired { .. } => is_request,
+      SpecChangeKind::FieldMadeNonNullable { .. } => is_request,
+      // clients can keep sending fields, but can no longer rely on responses having them
+      SpecChangeKind::FieldRemoved { .. } => !is_request,
+      SpecChangeKind::FieldMadeOptional { .. } => !is_request,
+      SpecChangeKind::FieldMadeNullable { .. } => !is_request,
+    }
+  }
+}
+
+struct ChangesCollector<'a> {
+  absolute_path_pattern: &'a String,
+  http_method: &'a HttpMethod,
+  changes: &'a mut Vec<SpecChange>,
+}
+
+impl<'a> ChangesCollector<'a> {
+  fn push(&mut self, location: ChangeLocation, kind: SpecChangeKind) {
+    self.changes.push(SpecChange {
+      absolute_path_pattern: self.absolute_path_pattern.clone(),
+      http_method: self.http_method.clone(),
+      is_breaking: SpecChange::is_breaking(&location, &kind),
+      location,
+      kind,
+    });
+  }
+
+  fn with_endpoint(&mut self, from_endpoint: Endpoint, to_endpoint: Endpoint) {
+    let mut to_requests = to_endpoint.requests;
+    for (http_content_type, from_bodies) in from_endpoint.requests {
+      let to_bodies = to_requests.remove(&http_content_type).unwrap_or_default();
+      let location = ChangeLocation::Request { http_content_type };
+      self.with_bodies(location, from_bodies, to_bodies);
+    }
+    for (http_content_type, to_bodies) in to_requests {
+      let location = ChangeLocation::Request { http_content_type };
+      self.with_bodies(location, vec![], to_bodies);
+    }
+
+    let mut to_responses = to_endpoint.responses;
+    for ((http_status_code, http_content_type), from_bodies) in from_endpoint.responses {
+      let to_bodies = to_responses
+        .remove(&(http_status_code, http_content_type.clone()))
+        .unwrap_or_default();
+      let location = ChangeLocation::Response {
+        http_status_code,
+        http_content_type,
+      };
+      self.with_bodies(location, from_bodies, to_bodies);
+    }
+    for ((http_status_code, http_content_type), to_bodies) in to_responses {
+      let location = ChangeLocation::Response {
+        http_status_code,
+        http_content_type,
+      };
+      self.with_bodies(location, vec![], to_bodies);
+    }
+  }
+
+  /// Bodies at the same location are matched by the request or response they belong to, and by
+  /// the order they're in otherwise
+  fn with_bodies(&mut self, location: ChangeLocation, from_bodies: Bodies, mut to_bodies: Bodies) {
+    let mut unmatched_from_bodies = vec![];
+    for (id, from_body) in from_bodies {
+      match to_bodies.iter().position(|(to_id, _)| to_id == &id) {
+        Some(index) => {
+          let (_, to_body) = to_bodies.remove(index);
+          self.with_body(location.clone(), Some(from_body), Some(to_body));
+        }
+        None => unmatched_from_bodies.push(from_body),
+      }
+    }
+
+    let mut unmatched_to_bodies = to_bodies.into_iter().map(|(_, to_body)| to_body);
+    for from_body in unmatched_from_bodies {
+      self.with_body(
+        location.clone(),
+        Some(from_body),
+        unmatched_to_bodies.next(),
+      );
+    }
+    for to_body in unmatched_to_bodies {
+      self.with_body(location.clone(), None, Some(to_body));
+    }
+  }
+
+  fn with_body(
+    &mut self,
+    location: ChangeLocation,
+    from_body: Option<BodyFields>,
+    to_body: Option<BodyFields>,
+  ) {
+    let (from_fields, to_fields) = match (from_body, to_body) {
+      (Some(from_body), Some(to_body)) => (from_body, to_body),
+      (Some(_), None) => return self.push(location, SpecChangeKind::Removed),
+      (None, Some(_)) => return self.push(location, SpecChangeKind::Added),
+      (None, None) => return,
+    };
+
+    // only the outermost of added, removed or changed fields is reported
+    let mut changed_paths: Vec<&Vec<String>> = vec![];
+    let is_within_changed = |changed_paths: &Vec<&Vec<String>>, json_path: &Vec<String>| {
+      changed_paths
+        .iter()
+        .any(|changed_path| json_path.starts_with(changed_path))
+    };
+
+    let json_paths = from_fields
+      .keys()
+      .chain(to_fields.keys())
+      .collect::<BTreeSet<_>>();
+    for json_path in json_paths {
+      if is_within_changed(&changed_paths, json_path) {
+        continue;
+      }
+
+      let kind = match (from_fields.get(json_path), to_fields.get(json_path)) {
+        (Some(from_field), Some(to_field)) if from_field.shape_kind != to_field.shape_kind => {
+          SpecChangeKind::ShapeKindChanged {
+            json_path: json_path.clone(),
+            from_kind: from_field.shape_kind.clone(),
+            to_kind: to_field.shape_kind.clone(),
+          }
+        }
+        (Some(from_field), Some(to_field)) => {
+          // the field itself stays, so whatever it contains is still compared
+          let required_change = match (from_field.is_required, to_field.is_required) {
+            (true, false) => Some(SpecChangeKind::FieldMadeOptional {
+              json_path: json_path.clone(),
+            }),
+            (false, true) => Some(SpecChangeKind::FieldMadeRequired {
+              json_path: json_path.clone(),
+            }),
+            _ => None,
+          };
+          let nullable_change = match (from_field.is_nullable, to_field.is_nullable) {
+            (false, true) => Some(SpecChangeKind::FieldMadeNullable {
+              json_path: json_path.clone(),
+            }),
+            (true, false) => Some(SpecChangeKind::FieldMadeNonNullable {
+              json_path: json_path.clone(),
+            }),
+            _ => None,
+          };
+          for kind in required_change.into_iter().chain(nullable_change) {
+            self.push(location.clone(), kind);
+          }
+          continue;
+        }
+        (Some(_), None) => SpecChangeKind::FieldRemoved {
+          json_path: json_path.clone(),
+        },
+        (None, Some(to_field)) => SpecChangeKind::FieldAdded {
+          json_path: json_path.clone(),
+          is_required: to_field.is_required,
+        },
+        (None, None) => unreachable!("json path should be of a field of either body"),
+      };
+
+      changed_paths.push(json_path);
+      self.push(location.clone(), kind);
+    }
+  }
+}
+
+// Endpoints
+// ---------
+
+struct Endpoints {
+  endpoints: BTreeMap<(String, HttpMethod), Endpoint>,
+}
+
+#[derive(Default)]
+struct Endpoint {
+  requests: BTreeMap<Option<HttpContentType>, Bodies>,
+  responses: BTreeMap<(HttpStatusSpecifier, Option<HttpContentType>), Bodies>,
+}
+
+/// The bodies at a location, each with the id of the request or response it belongs to
+type Bodies = Vec<(String, BodyFields)>;
+
+/// The fields of a body by their json path, with the body itself at the empty path
+type BodyFields = BTreeMap<Vec<String>, BodyField>;
+
+#[derive(Debug)]
+struct BodyField {
+  shape_kind: ShapeKind,
+  is_required: bool,
+  is_nullable: bool,
+}
+
+impl From<&SpecProjection> for Endpoints {
+  fn from(spec_projection: &SpecProjection) -> Self {
+    let mut collector = EndpointsCollector {
+      endpoint_projection: spec_projection.endpoint(),
+      endpoint_queries: EndpointQueries::new(spec_projection.endpoint()),
+      shape_queries: ShapeQueries::new(spec_projection.shape()),
+      endpoints: BTreeMap::new(),
+    };
+    collector.collect_paths(&String::from(ROOT_PATH_ID), String::new());
+
+    Self {
+      endpoints: collector.endpoints,
+    }
+  }
+}
+
+struct EndpointsCollector<'a> {
+  endpoint_projection: &'a EndpointProjection,
+  endpoint_queries: EndpointQueries<'a>,
+  shape_queries: ShapeQueries<'a>,
+  endpoints: BTreeMap<(String, HttpMethod), Endpoint>,
+}
+
+impl<'a> EndpointsCollector<'a> {
+  fn collect_paths(&mut self, path_id: &PathComponentId, absolute_path_pattern: String) {
+    let methods = self
+      .endpoint_projection
+      .get_http_methods(path_id)
+      .into_iter()
+      .flatten()
+      .cloned()
+      .collect::<BTreeSet<_>>();
+    for http_method in methods {
+      let endpoint = self.endpoint(path_id, &http_method);
+      if !endpoint.requests.is_empty() || !endpoint.responses.is_empty() {
+        let absolute_path_pattern = if absolute_path_pattern.is_empty() {
+          String::from("/")
+        } else {
+          absolute_path_pattern.clone()
+        };
+        self
+          .endpoints
+          .insert((absolute_path_pattern, http_method), endpoint);
+      }
+    }
+
+    let children = self
+      .endpoint_projection
+      .get_child_path_component_nodes(path_id)
+      .into_iter()
+      .flatten()
+      .filter_map(|node| match node {
+        EndpointNode::PathComponent(child_id, descriptor) => Some((child_id, descriptor)),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    for (child_id, descriptor) in children {
+      // path parameters match regardless of their names
+      let child_path_pattern = if descriptor.is_parameter {
+        format!("{}/{{}}", absolute_path_pattern)
+      } else {
+        format!("{}/{}", absolute_path_pattern, descriptor.name)
+      };
+      self.collect_paths(child_id, child_path_pattern);
+    }
+  }
+
+  fn endpoint(&self, path_id: &PathComponentId, http_method: &HttpMethod) -> Endpoint {
+    let mut endpoint = Endpoint::default();
+
+    for (request_id, descriptor) in self
+      .endpoint_queries
+      .resolve_requests(path_id, http_method)
+      .into_iter()
+      .flatten()
+    {
+      let (http_content_type, fields) = self.body_fields(descriptor.body.as_ref());
+      endpoint
+        .requests
+        .entry(http_content_type)
+        .or_default()
+        .push((request_id.clone(), fields));
+    }
+
+    for (response_id, descriptor) in self
+      .endpoint_queries
+      .resolve_responses(path_id, http_method)
+      .into_iter()
+      .flatten()
+    {
+      let http_status_code = match self
+        .endpoint_projection
+        .get_response_status_specifier(response_id)
+      {
+        Some(http_status_code) => http_status_code,
+        None => continue,
+      };
+      let (http_content_type, fields) = self.body_fields(descriptor.body.as_ref());
+      endpoint
+        .responses
+        .entry((http_status_code, http_content_type))
+        .or_default()
+        .push((response_id.clone(), fields));
+    }
+
+    endpoint
+  }
+
+  fn body_fields(&self, body: Option<&BodyDescriptor>) -> (Option<HttpContentType>, BodyFields) {
+    let mut fields = BodyFields::new();
+    match body {
+      Some(body) => {
+        self.collect_fields(
+          &body.root_shape_id,
+          vec![],
+          true,
+          false,
+          &mut vec![],
+          &mut fields,
+        );
+        (Some(body.http_content_type.clone()), fields)
+      }
+      None => (None, fields),
+    }
+  }
+
+  fn collect_fields(
+    &self,
+    shape_id: &ShapeId,
+    json_path: Vec<String>,
+    is_required: bool,
+    is_nullable: bool,
+    visited_shape_ids: &mut Vec<ShapeId>,
+    fields: &mut BodyFields,
+  ) {
+    if let Some(base_shape_id) = self.shape_queries.resolve_base_shape_id(shape_id) {
+      return self.collect_fields(
+        base_shape_id,
+        json_path,
+        is_required,
+        is_nullable,
+        visited_shape_ids,
+        fields,
+      );
+    }
+
+    let shape_kind = self.shape_queries.resolve_to_core_shape(shape_id).clone();
+    match shape_kind {
+      ShapeKind::OptionalKind | ShapeKind::NullableKind | ShapeKind::IdentifierKind => {
+        let inner_shape_id = self.parameter_shape_id(shape_id, &shape_kind);
+        return self.collect_fields(
+          &inner_shape_id,
+          json_path,
+          is_required && shape_kind != ShapeKind::OptionalKind,
+          is_nullable || shape_kind == ShapeKind::NullableKind,
+          visited_shape_ids,
+          fields,
+        );
+      }
+      _ => {}
+    }
+
+    fields.insert(
+      json_path.clone(),
+      BodyField {
+        shape_kind: shape_kind.clone(),
+        is_required,
+        is_nullable,
+      },
+    );
+
+    // recursive shapes are only followed once
+    if visited_shape_ids.contains(shape_id) {
+      return;
+    }
+    visited_shape_ids.push(shape_id.clone());
+
+    let mut nested_shapes = vec![];
+    match shape_kind {
+      ShapeKind::ObjectKind => {
+        for (field_id, name) in self
+          .shape_queries
+          .resolve_shape_field_id_and_names(shape_id)
+        {
+          if let Some(field_shape_id) = self
+            .shape_queries
+            .resolve_field_shape_in_trail(&ShapeTrail::new(shape_id.clone()), field_id)
+          {
+            nested_shapes.push((name.clone(), field_shape_id));
+          }
+        }
+      }
+      ShapeKind::ListKind => {
+        let item_shape_id = self.parameter_shape_id(shape_id, &shape_kind);
+        nested_shapes.push((String::from("[]"), item_shape_id));
+      }
+      ShapeKind::MapKind => {
+        let value_shape_id = self.parameter_shape_id(shape_id, &shape_kind);
+        nested_shapes.push((String::from("{}"), value_shape_id));
+      }
+      ShapeKind::OneOfKind => {
+        // shapes are numbered in the order their parameters were added in, so adding or changing
+        // one leaves the others where they are
+        let shape_projection = self.shape_queries.shape_projection;
+        let mut branch_shape_ids = self.shape_queries.resolve_parameters_to_shapes(shape_id);
+        branch_shape_ids.sort_by_key(|(parameter_id, _)| {
+          shape_projection
+            .get_shape_parameter_node_index(parameter_id)
+            .copied()
+        });
+        for (index, (_, branch_shape_id)) in branch_shape_ids.into_iter().enumerate() {
+          nested_shapes.push((format!("oneOf[{}]", index), branch_shape_id));
+        }
+      }
+      _ => {}
+    }
+
+    for (name, nested_shape_id) in nested_shapes {
+      let mut nested_json_path = json_path.clone();
+      nested_json_path.push(name);
+      self.collect_fields(
+        &nested_shape_id,
+        nested_json_path,
+        true,
+        false,
+        visited_shape_ids,
+        fields,
+      );
+    }
+
+    visited_shape_ids.pop();
+  }
+
+  fn parameter_shape_id(&self, shape_id: &ShapeId, shape_kind: &ShapeKind) -> ShapeId {
+    let parameter_id = shape_kind
+      .get_parameter_descriptor()
+      .expect("expected shape kind to have a parameter descriptor")
+      .shape_parameter_id;
+    self
+      .shape_queries
+      .resolve_parameter_to_shape(shape_id, &String::from(parameter_id))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::events::SpecEvent;
+  use insta::assert_debug_snapshot;
+  use serde_json::json;
+
+  fn user_spec_events() -> Vec<SpecEvent> {
+    serde_json::from_value(json!([
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+      {"PathParameterAdded":{"pathId":"path_2","parentPathId":"path_1","name":"userId"}},
+
+      {"ShapeAdded":{"shapeId":"string_1","baseShapeId":"$string","name":""}},
+      {"ShapeAdded":{"shapeId":"number_1","baseShapeId":"$number","name":""}},
+      {"ShapeAdded":{"shapeId":"optional_1","baseShapeId":"$optional","name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"optional_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_1"}},"consumingParameterId":"$optionalInner"}}}},
+      {"ShapeAdded":{"shapeId":"user_1","baseShapeId":"$object","name":""}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"user_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_1"}}}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"user_1","name":"nickname","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"optional_1"}}}},
+      {"FieldAdded":{"fieldId":"field_3","shapeId":"user_1","name":"age","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_3","shapeId":"number_1"}}}},
+
+      {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"POST"}},
+      {"RequestBodySet":{"requestId":"request_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"user_1","isRemoved":false}}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_1","pathId":"path_2","httpMethod":"GET","httpStatusCode":200}},
+      {"ResponseBodySet":{"responseId":"response_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"user_1","isRemoved":false}}},
+      {"RequestAdded":{"requestId":"request_2","pathId":"path_2","httpMethod":"DELETE"}},
+    ]))
+    .expect("should be able to deserialize test events")
+  }
+
+  #[test]
+  pub fn can_compare_specs() {
+    let from_spec = SpecProjection::from(user_spec_events());
+    let changed_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_3","shapeId":"string_1"}}}},
+      {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"string_1"}}}},
+      {"FieldAdded":{"fieldId":"field_4","shapeId":"user_1","name":"email","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_4","shapeId":"string_1"}}}},
+      {"RequestRemoved":{"requestId":"request_2"}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_2","pathId":"path_2","httpMethod":"GET","httpStatusCode":404}},
+      {"RequestAdded":{"requestId":"request_3","pathId":"path_1","httpMethod":"GET"}},
+    ]))
+    .expect("should be able to deserialize test events");
+    let to_spec = SpecProjection::from(
+      user_spec_events()
+        .into_iter()
+        .chain(changed_events)
+        .collect::<Vec<_>>(),
+    );
+
+    let changes = compare(&from_spec, &to_spec);
+    assert_debug_snapshot!("can_compare_specs__changes", changes);
+    assert_eq!(
+      changes.iter().filter(|change| change.is_breaking).count(),
+      5
+    );
+
+    assert!(compare(&to_spec, &to_spec).is_empty());
+  }
+
+  #[test]
+  pub fn can_compare_nested_shapes() {
+    let nested_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"ShapeAdded":{"shapeId":"nullable_1","baseShapeId":"$nullable","name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"nullable_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_1"}},"consumingParameterId":"$nullableInner"}}}},
+      {"FieldAdded":{"fieldId":"field_5","shapeId":"user_1","name":"bio","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_5","shapeId":"string_1"}}}},
+      {"FieldAdded":{"fieldId":"field_6","shapeId":"user_1","name":"title","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_6","shapeId":"nullable_1"}}}},
+
+      {"ShapeAdded":{"shapeId":"map_1","baseShapeId":"$map","name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"map_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_1"}},"consumingParameterId":"$mapValue"}}}},
+      {"FieldAdded":{"fieldId":"field_7","shapeId":"user_1","name":"metadata","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_7","shapeId":"map_1"}}}},
+
+      {"ShapeAdded":{"shapeId":"one_of_1","baseShapeId":"$oneOf","name":""}},
+      {"ShapeParameterAdded":{"shapeParameterId":"one_of_parameter_1","shapeId":"one_of_1","name":"","shapeDescriptor":{"ProviderInShape":{"shapeId":"one_of_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"one_of_parameter_1"}}}},
+      {"ShapeParameterAdded":{"shapeParameterId":"one_of_parameter_2","shapeId":"one_of_1","name":"","shapeDescriptor":{"ProviderInShape":{"shapeId":"one_of_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"one_of_parameter_2"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"one_of_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_1"}},"consumingParameterId":"one_of_parameter_1"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"one_of_1","providerDescriptor":{"ShapeProvider":{"shapeId":"number_1"}},"consumingParameterId":"one_of_parameter_2"}}}},
+      {"FieldAdded":{"fieldId":"field_8","shapeId":"user_1","name":"contact","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_8","shapeId":"one_of_1"}}}},
+    ]))
+    .expect("should be able to deserialize test events");
+    let from_events = user_spec_events()
+      .into_iter()
+      .chain(nested_events)
+      .collect::<Vec<_>>();
+    let from_spec = SpecProjection::from(from_events.clone());
+    let changed_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_5","shapeId":"nullable_1"}}}},
+      {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_6","shapeId":"string_1"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"map_1","providerDescriptor":{"ShapeProvider":{"shapeId":"number_1"}},"consumingParameterId":"$mapValue"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"one_of_1","providerDescriptor":{"ShapeProvider":{"shapeId":"number_1"}},"consumingParameterId":"one_of_parameter_1"}}}},
+      {"ShapeParameterAdded":{"shapeParameterId":"one_of_parameter_3","shapeId":"one_of_1","name":"","shapeDescriptor":{"ProviderInShape":{"shapeId":"one_of_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"one_of_parameter_3"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"one_of_1","providerDescriptor":{"ShapeProvider":{"shapeId":"user_1"}},"consumingParameterId":"one_of_parameter_3"}}}},
+    ]))
+    .expect("should be able to deserialize test events");
+    let to_spec = SpecProjection::from(
+      from_events
+        .into_iter()
+        .chain(changed_events)
+        .collect::<Vec<_>>(),
+    );
+
+    let changes = compare(&from_spec, &to_spec);
+    assert_debug_snapshot!("can_compare_nested_shapes__changes", changes);
+  }
+
+  #[test]
+  pub fn can_compare_bodies_of_the_same_content_type() {
+    let other_request_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"RequestAdded":{"requestId":"request_3","pathId":"path_1","httpMethod":"POST"}},
+      {"RequestBodySet":{"requestId":"request_3","bodyDescriptor":{"httpContentType":"application/json","shapeId":"number_1","isRemoved":false}}},
+    ]))
+    .expect("should be able to deserialize test events");
+    let from_events = user_spec_events()
+      .into_iter()
+      .chain(other_request_events)
+      .collect::<Vec<_>>();
+    let from_spec = SpecProjection::from(from_events.clone());
+    let changed_events: Vec<SpecEvent> = serde_json::from_value(json!([
+      {"RequestBodySet":{"requestId":"request_3","bodyDescriptor":{"httpContentType":"application/json","shapeId":"string_1","isRemoved":false}}},
+    ]))
+    .expect("should be able to deserialize test events");
+    let to_spec = SpecProjection::from(
+      from_events
+        .into_iter()
+        .chain(changed_events)
+        .collect::<Vec<_>>(),
+    );
+
+    let changes = compare(&from_spec, &to_spec);
+    assert_eq!(
+      changes,
+      vec![SpecChange {
+        absolute_path_pattern: String::from("/users"),
+        http_method: String::from("POST"),
+        location: ChangeLocation::Request {
+          http_content_type: Some(String::from("application/json")),
+        },
+        kind: SpecChangeKind::ShapeKindChanged {
+          json_path: vec![],
+          from_kind: ShapeKind::NumberKind,
+          to_kind: ShapeKind::StringKind,
+        },
+        is_breaking: true,
+      }]
+    );
+  }
+}
@@ -0,0 +1,331 @@
+use super::AppendedBatch;
+use crate::commands::{
+  CommandContext, RfcCommand, SpecCommand, SpecCommandError, SpecCommandHandler,
+};
+use crate::events::{RfcEvent, SpecEvent};
+use crate::projections::{CommitId, HistoryProjection, SpecProjection};
+use crate::queries::conflicts::{Conflict, ConflictsQueries};
+use cqrs_core::{Aggregate, AggregateCommand};
+use serde::Serialize;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+/// Merge one branch of the spec's history into another, as a batch commit with the last commits
+/// of both branches as its parents. The commits only the merged branch has are replayed on top
+/// of the other branch, reporting any of their commands that are no longer valid and any conflicts
+/// the merge introduces to the spec. Conflicts in the spec can be resolved by adding commands to
+/// the merge. Invalid commands remain part of the merged branch, so they have to be fixed on that
+/// branch before the merge can be committed.
+///
+/// Expects the spec events in the order their batches were assembled in.
+pub fn merge_branches(
+  spec_events: Vec<SpecEvent>,
+  into_batch_id: CommitId,
+  from_batch_id: CommitId,
+  commit_message: String,
+  batch_command_context: CommandContext,
+) -> Result<MergedBatch, MergeBranchesError> {
+  MergedBatch::new(
+    spec_events,
+    into_batch_id,
+    from_batch_id,
+    commit_message,
+    batch_command_context,
+  )
+}
+
+pub struct MergedBatch {
+  batch: AppendedBatch,
+  conflicts: Vec<MergeConflict>,
+}
+
+#[derive(Debug, Serialize)]
+pub enum MergeConflict {
+  /// A command of the merged branch that's no longer valid
+  #[serde(rename_all = "camelCase")]
+  InvalidCommand {
+    batch_id: CommitId,
+    command: SpecCommand,
+    reason: String,
+  },
+  /// A conflict in the spec that only exists since merging, like both branches adding requests
+  /// for the same path and method
+  Spec(Conflict),
+}
+
+impl MergedBatch {
+  pub fn new(
+    spec_events: Vec<SpecEvent>,
+    into_batch_id: CommitId,
+    from_batch_id: CommitId,
+    commit_message: String,
+    batch_command_context: CommandContext,
+  ) -> Result<Self, MergeBranchesError> {
+    let history = HistoryProjection::from(spec_events.iter().cloned());
+    let into_commit_ids = history
+      .get_ancestor_commit_ids(&into_batch_id)
+      .ok_or_else(|| MergeBranchesError::UnknownBatchCommit(into_batch_id.clone()))?;
+    let from_commit_ids = history
+      .get_ancestor_commit_ids(&from_batch_id)
+      .ok_or_else(|| MergeBranchesError::UnknownBatchCommit(from_batch_id.clone()))?;
+
+    // replay the branch merged into, setting aside the commits only the merged branch has
+    let mut spec_projection = SpecProjection::default();
+    let mut merged_events = vec![];
+    let mut current_batch_id: Option<CommitId> = None;
+    for event in spec_events {
+      if let SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(e)) = &event {
+        current_batch_id = Some(e.batch_id.clone());
+      }
+      let is_batch_end = matches!(&event, SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(_)));
+
+      match &current_batch_id {
+        // events outside of batches are shared by all branches
+        None => spec_projection.apply(event),
+        Some(batch_id) if into_commit_ids.contains(batch_id) => spec_projection.apply(event),
+        Some(batch_id) if from_commit_ids.contains(batch_id) => {
+          merged_events.push((batch_id.clone(), event))
+        }
+        // commits of other branches are left out
+        Some(_) => {}
+      }
+
+      if is_batch_end {
+        current_batch_id = None;
+      }
+    }
+
+    let existing_conflicts = ConflictsQueries::from(spec_projection.conflicts()).list_conflicts();
+
+    let mut conflicts = vec![];
+    for (batch_id, event) in merged_events {
+      if let Ok(command) = SpecCommand::try_from(event.clone()) {
+        if let Err(SpecCommandError::Validation(reason)) = spec_projection.execute(command.clone())
+        {
+          conflicts.push(MergeConflict::InvalidCommand {
+            batch_id,
+            command,
+            reason,
+          });
+          continue;
+        }
+      }
+
+      spec_projection.apply(event);
+    }
+
+    conflicts.extend(
+      ConflictsQueries::from(spec_projection.conflicts())
+        .list_conflicts()
+        .into_iter()
+        .filter(|conflict| !existing_conflicts.contains(conflict))
+        .map(MergeConflict::Spec),
+    );
+
+    let batch_id = batch_command_context.client_command_batch_id.clone();
+    let mut command_handler = SpecCommandHandler::new(batch_command_context, spec_projection);
+    let start_event = command_handler
+      .execute(SpecCommand::from(RfcCommand::merge_batch_commit(
+        batch_id.clone(),
+        into_batch_id,
+        from_batch_id,
+        commit_message,
+      )))?
+      .remove(0);
+
+    command_handler.apply(start_event.clone());
+
+    Ok(Self {
+      batch: AppendedBatch {
+        batch_id,
+        command_handler,
+        new_events: vec![start_event],
+      },
+      conflicts,
+    })
+  }
+
+  pub fn conflicts(&self) -> &Vec<MergeConflict> {
+    &self.conflicts
+  }
+
+  pub fn with_command(&mut self, command: SpecCommand) -> Result<(), SpecCommandError> {
+    self.batch.with_command(command)
+  }
+
+  pub fn commit(self) -> Result<Vec<SpecEvent>, MergeBranchesError> {
+    let invalid_commands_count = self
+      .conflicts
+      .iter()
+      .filter(|conflict| matches!(conflict, MergeConflict::InvalidCommand { .. }))
+      .count();
+    if invalid_commands_count > 0 {
+      return Err(MergeBranchesError::InvalidCommands(invalid_commands_count));
+    }
+
+    Ok(self.batch.commit())
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum MergeBranchesError {
+  #[error("could not find batch commit '{0}' to merge")]
+  UnknownBatchCommit(CommitId),
+  #[error("could not start merge batch commit: {0:?}")]
+  Command(#[from] SpecCommandError),
+  #[error("{0} command(s) of the merged branch are no longer valid")]
+  InvalidCommands(usize),
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::commands::EndpointCommand;
+  use chrono::{TimeZone, Utc};
+  use insta::assert_debug_snapshot;
+  use serde_json::json;
+
+  fn batch_events(batch_id: &str, parent_id: &str, events: serde_json::Value) -> Vec<SpecEvent> {
+    let start_event = json!({"BatchCommitStarted":{"batchId":batch_id,"parentId":parent_id,"commitMessage":"","eventContext":{
+      "clientId":"anonymous","clientSessionId":"session","clientCommandBatchId":batch_id,"createdAt":"2021-01-01T00:00:00Z"
+    }}});
+    let end_event = json!({"BatchCommitEnded":{"batchId":batch_id}});
+    let events = std::iter::once(start_event)
+      .chain(events.as_array().unwrap().iter().cloned())
+      .chain(std::iter::once(end_event))
+      .collect::<Vec<_>>();
+
+    serde_json::from_value(serde_json::Value::Array(events))
+      .expect("example events should be valid spec events")
+  }
+
+  fn merge_command_context() -> CommandContext {
+    CommandContext::new(
+      String::from("batch_4"),
+      String::from("test-client"),
+      String::from("test-session"),
+      Utc.timestamp(0, 0),
+    )
+  }
+
+  // a spec branching after batch_1, into batch_2 and batch_3
+  fn branching_spec_events(merged_branch_events: serde_json::Value) -> Vec<SpecEvent> {
+    vec![
+      serde_json::from_value::<Vec<SpecEvent>>(json!([
+        {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+        {"ShapeAdded":{"shapeId":"shape_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+        {"FieldAdded":{"fieldId":"field_1","shapeId":"shape_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"$string"}}}},
+      ]))
+      .unwrap(),
+      batch_events(
+        "batch_1",
+        "root",
+        json!([
+          {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"GET"}},
+        ]),
+      ),
+      batch_events(
+        "batch_2",
+        "batch_1",
+        json!([
+          {"FieldRemoved":{"fieldId":"field_1"}},
+          {"RequestAdded":{"requestId":"request_2","pathId":"path_1","httpMethod":"POST"}},
+        ]),
+      ),
+      batch_events("batch_3", "batch_1", merged_branch_events),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+  }
+
+  #[test]
+  pub fn can_merge_branches() {
+    let spec_events = branching_spec_events(json!([
+      {"RequestAdded":{"requestId":"request_3","pathId":"path_1","httpMethod":"POST"}},
+      {"PathComponentAdded":{"pathId":"path_2","parentPathId":"root","name":"todos"}},
+    ]));
+
+    let history = HistoryProjection::from(spec_events.clone());
+    assert_eq!(
+      history.get_head_commit_ids().collect::<Vec<_>>(),
+      vec!["batch_2", "batch_3"]
+    );
+
+    let mut merged_batch = merge_branches(
+      spec_events.clone(),
+      String::from("batch_2"),
+      String::from("batch_3"),
+      String::from("merge branches"),
+      merge_command_context(),
+    )
+    .expect("branches should be mergeable");
+    assert_debug_snapshot!("can_merge_branches__conflicts", merged_batch.conflicts());
+
+    merged_batch
+      .with_command(SpecCommand::from(EndpointCommand::remove_request(
+        String::from("request_3"),
+      )))
+      .expect("conflicting request should be removable");
+    let merge_events = merged_batch
+      .commit()
+      .expect("merge without invalid commands should be committable");
+    assert_debug_snapshot!("can_merge_branches__merge_events", merge_events);
+
+    let merged_spec_events = spec_events
+      .into_iter()
+      .chain(merge_events)
+      .collect::<Vec<_>>();
+    let history = HistoryProjection::from(merged_spec_events.clone());
+    assert_eq!(
+      history.get_head_commit_ids().collect::<Vec<_>>(),
+      vec!["batch_4"]
+    );
+    assert_eq!(
+      history.get_parent_commit_ids(&String::from("batch_4")),
+      Some(vec![&String::from("batch_2"), &String::from("batch_3")])
+    );
+
+    let spec_projection = SpecProjection::from(merged_spec_events.clone());
+    let conflicts = ConflictsQueries::from(spec_projection.conflicts()).list_conflicts();
+    assert!(conflicts.is_empty());
+
+    let unknown_batch_result = merge_branches(
+      merged_spec_events,
+      String::from("batch_4"),
+      String::from("not-a-batch"),
+      String::from("merge branches"),
+      merge_command_context(),
+    );
+    assert!(matches!(
+      unknown_batch_result,
+      Err(MergeBranchesError::UnknownBatchCommit(_))
+    ));
+  }
+
+  #[test]
+  pub fn cannot_commit_merge_of_invalid_commands() {
+    let spec_events = branching_spec_events(json!([
+      {"FieldRenamed":{"fieldId":"field_1","name":"fullName"}},
+    ]));
+
+    let merged_batch = merge_branches(
+      spec_events,
+      String::from("batch_2"),
+      String::from("batch_3"),
+      String::from("merge branches"),
+      merge_command_context(),
+    )
+    .expect("branches should be mergeable");
+    assert_debug_snapshot!(
+      "cannot_commit_merge_of_invalid_commands__conflicts",
+      merged_batch.conflicts()
+    );
+
+    let commit_result = merged_batch.commit();
+    assert!(matches!(
+      commit_result,
+      Err(MergeBranchesError::InvalidCommands(1))
+    ));
+  }
+}
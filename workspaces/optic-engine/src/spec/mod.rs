@@ -1,11 +1,65 @@
+mod changelog;
+mod compare;
+mod merge;
+mod revert;
+
 pub use super::{diff_interaction, diff_shape};
 use crate::commands::{
   CommandContext, RfcCommand, SpecCommand, SpecCommandError, SpecCommandHandler,
 };
-use crate::events::SpecEvent;
-use crate::projections::SpecProjection;
+use crate::events::{RfcEvent, SpecEvent};
+use crate::projections::{CommitId, HistoryProjection, SpecProjection};
 use crate::queries::EndpointQueries;
+pub use changelog::{
+  changelog, BatchChangelog, Changelog, ChangelogEntry, ChangelogError, EndpointChangelog,
+};
+pub use compare::{compare, ChangeLocation, SpecChange, SpecChangeKind};
 use cqrs_core::Aggregate;
+pub use merge::{merge_branches, MergeBranchesError, MergeConflict, MergedBatch};
+pub use revert::{revert_batch, revert_batch_commands, RevertBatchError};
+use std::collections::HashSet;
+
+/// The events of the spec as it was at a batch commit: those of the commit and the commits it's
+/// based on, along with any events outside of batches. Returns `None` for unknown batch commits.
+///
+/// Expects the spec events in the order their batches were assembled in.
+pub fn events_at_batch_commit(
+  spec_events: Vec<SpecEvent>,
+  batch_id: &CommitId,
+) -> Option<Vec<SpecEvent>> {
+  let history = HistoryProjection::from(spec_events.iter().cloned());
+  let commit_ids = history
+    .get_ancestor_commit_ids(batch_id)?
+    .into_iter()
+    .cloned()
+    .collect::<HashSet<_>>();
+
+  Some(events_of_commits(spec_events, &commit_ids))
+}
+
+/// The events of the given batch commits, along with any events outside of batches
+fn events_of_commits(
+  spec_events: Vec<SpecEvent>,
+  commit_ids: &HashSet<CommitId>,
+) -> Vec<SpecEvent> {
+  let mut current_batch_id: Option<CommitId> = None;
+  spec_events
+    .into_iter()
+    .filter(|event| {
+      if let SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(e)) = event {
+        current_batch_id = Some(e.batch_id.clone());
+      }
+      let is_included = match &current_batch_id {
+        Some(batch_id) => commit_ids.contains(batch_id),
+        None => true,
+      };
+      if let SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(_)) = event {
+        current_batch_id = None;
+      }
+      is_included
+    })
+    .collect()
+}
 
 /// Create an interface that allows commands to be applied to a spec as part of a single
 /// batch. Will produce events enclosed in `BatchCommitStarted` and `BatchCommitEnded`.
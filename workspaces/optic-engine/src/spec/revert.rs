@@ -0,0 +1,776 @@
+use super::{append_batch, AppendedBatch};
+use crate::commands::endpoint::{
+  RenameHeaderParameter, RenamePathComponent, RenamePathParameter, SetHeaderParameterShape,
+  SetPathParameterShape, SetQueryParametersShape, SetRequestBodyShape, SetResponseBodyShape,
+  UnsetHeaderParameterShape, UnsetRequestBodyShape, UnsetResponseBodyShape,
+};
+use crate::commands::shape::{SetBaseShape, SetFieldShape, SetParameterShape};
+use crate::commands::ShapeCommand;
+use crate::commands::{CommandContext, EndpointCommand, RfcCommand, SpecCommand, SpecCommandError};
+use crate::events::{EndpointEvent, RfcEvent, ShapeEvent, SpecEvent};
+use crate::projections::{CommitId, SpecProjection};
+use crate::state::shape::{FieldShapeDescriptor, ParameterShapeDescriptor};
+use cqrs_core::Event;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// Revert a batch commit by appending a new batch with the commands that undo its changes, like
+/// removing what it added and restoring the names and shapes it changed to what they were before
+/// the batch. Removals can't be undone, as ids can't be assigned again, nor can anything that later
+/// batch commits changed again.
+///
+/// Expects the spec events in the order their batches were assembled in.
+pub fn revert_batch(
+  spec_events: Vec<SpecEvent>,
+  batch_id: &CommitId,
+  commit_message: String,
+  batch_command_context: CommandContext,
+) -> Result<AppendedBatch, RevertBatchError> {
+  let commands = revert_batch_commands(&spec_events, batch_id)?;
+
+  let mut batch = append_batch(
+    SpecProjection::from(spec_events),
+    commit_message,
+    batch_command_context,
+  );
+  for command in commands {
+    batch.with_command(command)?;
+  }
+
+  Ok(batch)
+}
+
+/// The commands restoring the spec's state from before a batch commit, in the order they have to
+/// be applied in
+pub fn revert_batch_commands(
+  spec_events: &[SpecEvent],
+  batch_id: &CommitId,
+) -> Result<Vec<SpecCommand>, RevertBatchError> {
+  let batch_start_index = spec_events
+    .iter()
+    .position(|event| match event {
+      SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(e)) => &e.batch_id == batch_id,
+      _ => false,
+    })
+    .ok_or_else(|| RevertBatchError::UnknownBatchCommit(batch_id.clone()))?;
+  let batch_events = spec_events[batch_start_index + 1..]
+    .iter()
+    .take_while(|event| match event {
+      SpecEvent::RfcEvent(RfcEvent::BatchCommitEnded(e)) => &e.batch_id != batch_id,
+      _ => true,
+    })
+    .collect::<Vec<_>>();
+  let later_events = spec_events
+    .get(batch_start_index + batch_events.len() + 2..)
+    .unwrap_or_default();
+
+  // the commands that would set every property to the value it had before the batch
+  let mut previous_values = HashMap::new();
+  for event in &spec_events[..batch_start_index] {
+    previous_values.extend(setting_commands(event));
+  }
+
+  let added_ids = batch_events
+    .iter()
+    .filter_map(|event| removing_command(event).map(|(id, _)| id))
+    .collect::<HashSet<_>>();
+
+  // the batch commits that changed each property after the batch, which reverting would undo
+  let mut later_batch_ids = HashMap::new();
+  let mut later_batch_id = None;
+  for event in later_events {
+    if let SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(e)) = event {
+      later_batch_id = Some(&e.batch_id);
+    }
+    let later_batch_id = match later_batch_id {
+      Some(later_batch_id) => later_batch_id,
+      None => continue,
+    };
+    for (property, _) in setting_commands(event) {
+      // what was added by the batch can't be removed along with the changes made to it since
+      if property.subject_ids().any(|id| added_ids.contains(id)) {
+        return Err(RevertBatchError::ChangedByLaterBatchCommit(
+          batch_id.clone(),
+          later_batch_id.clone(),
+        ));
+      }
+      later_batch_ids.entry(property).or_insert(later_batch_id);
+    }
+  }
+
+  let mut reverted_properties = HashSet::new();
+  let mut commands = vec![];
+  for event in batch_events.into_iter().rev() {
+    if let Some((_, command)) = removing_command(event) {
+      commands.push((removal_stage(event), command));
+      continue;
+    }
+
+    let properties = setting_commands(event)
+      .into_iter()
+      .map(|(property, _)| property)
+      .collect::<Vec<_>>();
+    if properties.is_empty() {
+      return Err(RevertBatchError::IrreversibleEvent(event.event_type()));
+    }
+
+    for property in properties {
+      // whatever was added by the batch itself is removed as a whole
+      if property.subject_ids().any(|id| added_ids.contains(id)) {
+        continue;
+      }
+      if !reverted_properties.insert(property.clone()) {
+        continue;
+      }
+      if let Some(later_batch_id) = later_batch_ids.get(&property) {
+        return Err(RevertBatchError::ChangedByLaterBatchCommit(
+          batch_id.clone(),
+          (*later_batch_id).clone(),
+        ));
+      }
+
+      let command =
+        match (&property, previous_values.get(&property)) {
+          (_, Some(command)) => command.clone(),
+          // contributions without any value are as good as absent
+          (Property::Contribution(id, key), None) => SpecCommand::from(
+            RfcCommand::add_contribution(id.clone(), key.clone(), String::new()),
+          ),
+          (_, None) => return Err(RevertBatchError::IrreversibleEvent(event.event_type())),
+        };
+      commands.push((RevertStage::Restore, command));
+    }
+  }
+
+  // nothing can be removed while it's still referenced, so removals happen by dependency, while
+  // keeping the reverse order within each stage
+  commands.sort_by_key(|(stage, _)| *stage);
+
+  Ok(commands.into_iter().map(|(_, command)| command).collect())
+}
+
+#[derive(Debug, Error)]
+pub enum RevertBatchError {
+  #[error("could not find batch commit '{0}' to revert")]
+  UnknownBatchCommit(CommitId),
+  #[error("events of type '{0}' can not be reverted")]
+  IrreversibleEvent(&'static str),
+  #[error("batch commit '{0}' can not be reverted, as batch commit '{1}' changed it since")]
+  ChangedByLaterBatchCommit(CommitId, CommitId),
+  #[error("could not revert batch commit: {0:?}")]
+  Command(#[from] SpecCommandError),
+}
+
+// Properties
+// ----------
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Property {
+  Name(String),
+  Shape(String),
+  BaseShape(String),
+  ParameterShape(String, String),
+  Contribution(String, String),
+}
+
+impl Property {
+  fn subject_ids(&self) -> impl Iterator<Item = &String> {
+    let (id, other_id) = match self {
+      Property::Name(id) | Property::Shape(id) | Property::BaseShape(id) => (id, None),
+      Property::ParameterShape(id, parameter_id) => (id, Some(parameter_id)),
+      Property::Contribution(id, _) => (id, None),
+    };
+    std::iter::once(id).chain(other_id)
+  }
+}
+
+// Stages
+// ------
+
+/// When a compensating command has to be applied, relative to the others
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum RevertStage {
+  /// Restoring names and shapes, which releases whatever the batch had them refer to
+  Restore,
+  /// Removing requests, responses and their parameters, along with the bodies they have
+  RemoveEndpoints,
+  /// Removing shapes and their fields, parameters and constraints
+  RemoveShapes,
+  /// Removing path components and parameters, which requests and responses are added to
+  RemovePaths,
+}
+
+fn removal_stage(event: &SpecEvent) -> RevertStage {
+  match event {
+    SpecEvent::EndpointEvent(
+      EndpointEvent::PathComponentAdded(_) | EndpointEvent::PathParameterAdded(_),
+    ) => RevertStage::RemovePaths,
+    SpecEvent::EndpointEvent(_) => RevertStage::RemoveEndpoints,
+    SpecEvent::ShapeEvent(_) => RevertStage::RemoveShapes,
+    SpecEvent::RfcEvent(_) => RevertStage::Restore,
+  }
+}
+
+/// The id of what an event adds, with the command removing it again
+fn removing_command(event: &SpecEvent) -> Option<(&String, SpecCommand)> {
+  match event {
+    SpecEvent::EndpointEvent(endpoint_event) => endpoint_removing_command(endpoint_event)
+      .map(|(id, command)| (id, SpecCommand::from(command))),
+    SpecEvent::ShapeEvent(shape_event) => {
+      shape_removing_command(shape_event).map(|(id, command)| (id, SpecCommand::from(command)))
+    }
+    SpecEvent::RfcEvent(_) => None,
+  }
+}
+
+fn endpoint_removing_command(event: &EndpointEvent) -> Option<(&String, EndpointCommand)> {
+  let removing = match event {
+    EndpointEvent::PathComponentAdded(e) => (
+      &e.path_id,
+      EndpointCommand::remove_path_component(e.path_id.clone()),
+    ),
+    EndpointEvent::PathParameterAdded(e) => (
+      &e.path_id,
+      EndpointCommand::remove_path_parameter(e.path_id.clone()),
+    ),
+    EndpointEvent::QueryParametersAdded(e) => (
+      &e.query_parameters_id,
+      EndpointCommand::remove_query_parameters(e.query_parameters_id.clone()),
+    ),
+    EndpointEvent::HeaderParameterAdded(e) => (
+      &e.parameter_id,
+      EndpointCommand::remove_header_parameter(e.parameter_id.clone()),
+    ),
+    EndpointEvent::ResponseHeaderParameterAdded(e) => (
+      &e.parameter_id,
+      EndpointCommand::remove_header_parameter(e.parameter_id.clone()),
+    ),
+    EndpointEvent::RequestAdded(e) => (
+      &e.request_id,
+      EndpointCommand::remove_request(e.request_id.clone()),
+    ),
+    EndpointEvent::ResponseAddedByPathAndMethod(e) => (
+      &e.response_id,
+      EndpointCommand::remove_response(e.response_id.clone()),
+    ),
+    EndpointEvent::ResponseRangeAddedByPathAndMethod(e) => (
+      &e.response_id,
+      EndpointCommand::remove_response(e.response_id.clone()),
+    ),
+    EndpointEvent::DefaultResponseAddedByPathAndMethod(e) => (
+      &e.response_id,
+      EndpointCommand::remove_response(e.response_id.clone()),
+    ),
+    _ => return None,
+  };
+
+  Some(removing)
+}
+
+fn shape_removing_command(event: &ShapeEvent) -> Option<(&String, ShapeCommand)> {
+  let removing = match event {
+    ShapeEvent::ShapeAdded(e) => (&e.shape_id, ShapeCommand::remove_shape(e.shape_id.clone())),
+    ShapeEvent::ShapeParameterAdded(e) => (
+      &e.shape_parameter_id,
+      ShapeCommand::remove_shape_parameter(e.shape_parameter_id.clone()),
+    ),
+    ShapeEvent::FieldAdded(e) => (&e.field_id, ShapeCommand::remove_field(e.field_id.clone())),
+    ShapeEvent::ShapeConstraintAdded(e) => (
+      &e.shape_constraint_id,
+      ShapeCommand::remove_shape_constraint(e.shape_constraint_id.clone()),
+    ),
+    _ => return None,
+  };
+
+  Some(removing)
+}
+
+/// The properties an event sets, each with the command that would set it to the same value
+fn setting_commands(event: &SpecEvent) -> Vec<(Property, SpecCommand)> {
+  match event {
+    SpecEvent::EndpointEvent(endpoint_event) => endpoint_setting_commands(endpoint_event)
+      .into_iter()
+      .map(|(property, command)| (property, SpecCommand::from(command)))
+      .collect(),
+    SpecEvent::ShapeEvent(shape_event) => shape_setting_commands(shape_event)
+      .into_iter()
+      .map(|(property, command)| (property, SpecCommand::from(command)))
+      .collect(),
+    SpecEvent::RfcEvent(RfcEvent::ContributionAdded(e)) => vec![(
+      Property::Contribution(e.id.clone(), e.key.clone()),
+      SpecCommand::from(RfcCommand::add_contribution(
+        e.id.clone(),
+        e.key.clone(),
+        e.value.clone(),
+      )),
+    )],
+    SpecEvent::RfcEvent(_) => vec![],
+  }
+}
+
+fn endpoint_setting_commands(event: &EndpointEvent) -> Vec<(Property, EndpointCommand)> {
+  let rename_path = |path_id: &String, name: &String| {
+    EndpointCommand::RenamePathComponent(RenamePathComponent {
+      path_id: path_id.clone(),
+      name: name.clone(),
+    })
+  };
+  let rename_path_parameter = |path_id: &String, name: &String| {
+    EndpointCommand::RenamePathParameter(RenamePathParameter {
+      path_id: path_id.clone(),
+      name: name.clone(),
+    })
+  };
+  let rename_header = |parameter_id: &String, name: &String| {
+    EndpointCommand::RenameHeaderParameter(RenameHeaderParameter {
+      parameter_id: parameter_id.clone(),
+      name: name.clone(),
+    })
+  };
+  // header parameters start out without a shape
+  let unset_header_shape = |parameter_id: &String| {
+    EndpointCommand::UnsetHeaderParameterShape(UnsetHeaderParameterShape {
+      parameter_id: parameter_id.clone(),
+    })
+  };
+  // as do requests and responses start out without a body
+  let unset_request_body = |request_id: &String| {
+    (
+      Property::Shape(request_id.clone()),
+      EndpointCommand::UnsetRequestBodyShape(UnsetRequestBodyShape {
+        request_id: request_id.clone(),
+      }),
+    )
+  };
+  let unset_response_body = |response_id: &String| {
+    (
+      Property::Shape(response_id.clone()),
+      EndpointCommand::UnsetResponseBodyShape(UnsetResponseBodyShape {
+        response_id: response_id.clone(),
+      }),
+    )
+  };
+
+  match event {
+    EndpointEvent::PathComponentAdded(e) => {
+      vec![(
+        Property::Name(e.path_id.clone()),
+        rename_path(&e.path_id, &e.name),
+      )]
+    }
+    EndpointEvent::PathComponentRenamed(e) => {
+      vec![(
+        Property::Name(e.path_id.clone()),
+        rename_path(&e.path_id, &e.name),
+      )]
+    }
+    EndpointEvent::PathParameterAdded(e) => vec![(
+      Property::Name(e.path_id.clone()),
+      rename_path_parameter(&e.path_id, &e.name),
+    )],
+    EndpointEvent::PathParameterRenamed(e) => vec![(
+      Property::Name(e.path_id.clone()),
+      rename_path_parameter(&e.path_id, &e.name),
+    )],
+    EndpointEvent::PathParameterShapeSet(e) => vec![(
+      Property::Shape(e.path_id.clone()),
+      EndpointCommand::SetPathParameterShape(SetPathParameterShape {
+        path_id: e.path_id.clone(),
+        shaped_request_parameter_shape_descriptor: e.shape_descriptor.clone(),
+      }),
+    )],
+    EndpointEvent::QueryParametersShapeSet(e) => vec![(
+      Property::Shape(e.query_parameters_id.clone()),
+      EndpointCommand::SetQueryParametersShape(SetQueryParametersShape {
+        query_parameters_id: e.query_parameters_id.clone(),
+        shape_descriptor: e.shape_descriptor.clone(),
+      }),
+    )],
+    EndpointEvent::HeaderParameterAdded(e) => vec![
+      (
+        Property::Name(e.parameter_id.clone()),
+        rename_header(&e.parameter_id, &e.name),
+      ),
+      (
+        Property::Shape(e.parameter_id.clone()),
+        unset_header_shape(&e.parameter_id),
+      ),
+    ],
+    EndpointEvent::ResponseHeaderParameterAdded(e) => vec![
+      (
+        Property::Name(e.parameter_id.clone()),
+        rename_header(&e.parameter_id, &e.name),
+      ),
+      (
+        Property::Shape(e.parameter_id.clone()),
+        unset_header_shape(&e.parameter_id),
+      ),
+    ],
+    EndpointEvent::HeaderParameterRenamed(e) => vec![(
+      Property::Name(e.parameter_id.clone()),
+      rename_header(&e.parameter_id, &e.name),
+    )],
+    EndpointEvent::HeaderParameterShapeSet(e) => vec![(
+      Property::Shape(e.parameter_id.clone()),
+      EndpointCommand::SetHeaderParameterShape(SetHeaderParameterShape {
+        parameter_id: e.parameter_id.clone(),
+        parameter_descriptor: e.parameter_descriptor.clone(),
+      }),
+    )],
+    EndpointEvent::HeaderParameterShapeUnset(e) => vec![(
+      Property::Shape(e.parameter_id.clone()),
+      unset_header_shape(&e.parameter_id),
+    )],
+    EndpointEvent::RequestAdded(e) => vec![unset_request_body(&e.request_id)],
+    EndpointEvent::RequestBodyUnset(e) => vec![unset_request_body(&e.request_id)],
+    EndpointEvent::ResponseAddedByPathAndMethod(e) => vec![unset_response_body(&e.response_id)],
+    EndpointEvent::ResponseRangeAddedByPathAndMethod(e) => {
+      vec![unset_response_body(&e.response_id)]
+    }
+    EndpointEvent::DefaultResponseAddedByPathAndMethod(e) => {
+      vec![unset_response_body(&e.response_id)]
+    }
+    EndpointEvent::ResponseBodyUnset(e) => vec![unset_response_body(&e.response_id)],
+    EndpointEvent::RequestBodySet(e) => vec![(
+      Property::Shape(e.request_id.clone()),
+      EndpointCommand::SetRequestBodyShape(SetRequestBodyShape {
+        request_id: e.request_id.clone(),
+        body_descriptor: e.body_descriptor.clone(),
+      }),
+    )],
+    EndpointEvent::ResponseBodySet(e) => vec![(
+      Property::Shape(e.response_id.clone()),
+      EndpointCommand::SetResponseBodyShape(SetResponseBodyShape {
+        response_id: e.response_id.clone(),
+        body_descriptor: e.body_descriptor.clone(),
+      }),
+    )],
+    _ => vec![],
+  }
+}
+
+fn shape_setting_commands(event: &ShapeEvent) -> Vec<(Property, ShapeCommand)> {
+  let set_field_shape = |shape_descriptor: &FieldShapeDescriptor| {
+    let field_id = match shape_descriptor {
+      FieldShapeDescriptor::FieldShapeFromShape(descriptor) => &descriptor.field_id,
+      FieldShapeDescriptor::FieldShapeFromParameter(descriptor) => &descriptor.field_id,
+    };
+    (
+      Property::Shape(field_id.clone()),
+      ShapeCommand::SetFieldShape(SetFieldShape {
+        shape_descriptor: shape_descriptor.clone(),
+      }),
+    )
+  };
+  let set_parameter_shape = |shape_descriptor: &ParameterShapeDescriptor| {
+    let (id, parameter_id) = match shape_descriptor {
+      ParameterShapeDescriptor::ProviderInShape(descriptor) => {
+        (&descriptor.shape_id, &descriptor.consuming_parameter_id)
+      }
+      ParameterShapeDescriptor::ProviderInField(descriptor) => {
+        (&descriptor.field_id, &descriptor.consuming_parameter_id)
+      }
+    };
+    (
+      Property::ParameterShape(id.clone(), parameter_id.clone()),
+      ShapeCommand::SetParameterShape(SetParameterShape {
+        shape_descriptor: shape_descriptor.clone(),
+      }),
+    )
+  };
+  let set_base_shape = |shape_id: &String, base_shape_id: &String| {
+    (
+      Property::BaseShape(shape_id.clone()),
+      ShapeCommand::SetBaseShape(SetBaseShape {
+        shape_id: shape_id.clone(),
+        base_shape_id: base_shape_id.clone(),
+      }),
+    )
+  };
+
+  match event {
+    ShapeEvent::ShapeAdded(e) => vec![
+      (
+        Property::Name(e.shape_id.clone()),
+        ShapeCommand::rename_shape(e.shape_id.clone(), e.name.clone()),
+      ),
+      set_base_shape(&e.shape_id, &e.base_shape_id),
+    ],
+    ShapeEvent::BaseShapeSet(e) => vec![set_base_shape(&e.shape_id, &e.base_shape_id)],
+    ShapeEvent::ShapeRenamed(e) => vec![(
+      Property::Name(e.shape_id.clone()),
+      ShapeCommand::rename_shape(e.shape_id.clone(), e.name.clone()),
+    )],
+    ShapeEvent::ShapeParameterAdded(e) => vec![
+      (
+        Property::Name(e.shape_parameter_id.clone()),
+        ShapeCommand::rename_shape_parameter(e.shape_parameter_id.clone(), e.name.clone()),
+      ),
+      set_parameter_shape(&e.shape_descriptor),
+    ],
+    ShapeEvent::ShapeParameterShapeSet(e) => vec![set_parameter_shape(&e.shape_descriptor)],
+    ShapeEvent::ShapeParameterRenamed(e) => vec![(
+      Property::Name(e.shape_parameter_id.clone()),
+      ShapeCommand::rename_shape_parameter(e.shape_parameter_id.clone(), e.name.clone()),
+    )],
+    ShapeEvent::FieldAdded(e) => vec![
+      (
+        Property::Name(e.field_id.clone()),
+        ShapeCommand::rename_field(e.field_id.clone(), e.name.clone()),
+      ),
+      set_field_shape(&e.shape_descriptor),
+    ],
+    ShapeEvent::FieldShapeSet(e) => vec![set_field_shape(&e.shape_descriptor)],
+    ShapeEvent::FieldRenamed(e) => vec![(
+      Property::Name(e.field_id.clone()),
+      ShapeCommand::rename_field(e.field_id.clone(), e.name.clone()),
+    )],
+    _ => vec![],
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::queries::shape::ShapeQueries;
+  use chrono::{TimeZone, Utc};
+  use insta::assert_debug_snapshot;
+  use serde_json::json;
+
+  fn batch_events(batch_id: &str, parent_id: &str, events: serde_json::Value) -> Vec<SpecEvent> {
+    let start_event = json!({"BatchCommitStarted":{"batchId":batch_id,"parentId":parent_id,"commitMessage":"","eventContext":{
+      "clientId":"anonymous","clientSessionId":"session","clientCommandBatchId":batch_id,"createdAt":"2021-01-01T00:00:00Z"
+    }}});
+    let end_event = json!({"BatchCommitEnded":{"batchId":batch_id}});
+    let events = std::iter::once(start_event)
+      .chain(events.as_array().unwrap().iter().cloned())
+      .chain(std::iter::once(end_event))
+      .collect::<Vec<_>>();
+
+    serde_json::from_value(serde_json::Value::Array(events))
+      .expect("example events should be valid spec events")
+  }
+
+  fn revert_command_context() -> CommandContext {
+    CommandContext::new(
+      String::from("batch_3"),
+      String::from("test-client"),
+      String::from("test-session"),
+      Utc.timestamp(0, 0),
+    )
+  }
+
+  // a spec with a request for users, changed by batch_2
+  fn changed_spec_events(batch_2_events: serde_json::Value) -> Vec<SpecEvent> {
+    vec![
+      batch_events(
+        "batch_1",
+        "root",
+        json!([
+          {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+          {"ShapeAdded":{"shapeId":"shape_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+          {"FieldAdded":{"fieldId":"field_1","shapeId":"shape_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"$string"}}}},
+          {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"POST"}},
+          {"RequestBodySet":{"requestId":"request_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"shape_1","isRemoved":false}}},
+          {"ContributionAdded":{"id":"request_1","key":"purpose","value":"create a user"}},
+        ]),
+      ),
+      batch_events("batch_2", "batch_1", batch_2_events),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+  }
+
+  #[test]
+  pub fn can_revert_batch() {
+    let spec_events = changed_spec_events(json!([
+      {"FieldRenamed":{"fieldId":"field_1","name":"fullName"}},
+      {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"$number"}}}},
+      {"FieldRenamed":{"fieldId":"field_1","name":"firstName"}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"shape_1","name":"email","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"$string"}}}},
+      {"ShapeAdded":{"shapeId":"shape_2","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"RequestAdded":{"requestId":"request_2","pathId":"path_1","httpMethod":"PUT"}},
+      {"RequestBodySet":{"requestId":"request_2","bodyDescriptor":{"httpContentType":"text/plain","shapeId":"shape_2","isRemoved":false}}},
+      {"ContributionAdded":{"id":"request_1","key":"purpose","value":"register a user"}},
+      {"ContributionAdded":{"id":"request_1","key":"description","value":"as an admin"}},
+    ]));
+
+    let commands = revert_batch_commands(&spec_events, &String::from("batch_2"))
+      .expect("batch should be revertable");
+    assert_debug_snapshot!("can_revert_batch__commands", commands);
+
+    let revert_events = revert_batch(
+      spec_events.clone(),
+      &String::from("batch_2"),
+      String::from("revert batch_2"),
+      revert_command_context(),
+    )
+    .expect("revert commands should apply to the spec")
+    .commit();
+    assert_debug_snapshot!("can_revert_batch__revert_events", revert_events);
+
+    let spec_projection = SpecProjection::from(
+      spec_events
+        .into_iter()
+        .chain(revert_events)
+        .collect::<Vec<_>>(),
+    );
+    let shape_queries = ShapeQueries::new(spec_projection.shape());
+    let fields = shape_queries
+      .resolve_shape_field_id_and_names(&String::from("shape_1"))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      fields,
+      vec![(&String::from("field_1"), &String::from("name"))]
+    );
+  }
+
+  #[test]
+  pub fn reverts_removals_by_dependency() {
+    let spec_events = changed_spec_events(json!([
+      {"PathComponentAdded":{"pathId":"path_2","parentPathId":"path_1","name":"me"}},
+      {"RequestAdded":{"requestId":"request_2","pathId":"path_2","httpMethod":"PUT"}},
+      {"ShapeAdded":{"shapeId":"shape_2","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"shape_2","name":"email","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"$string"}}}},
+      {"RequestBodySet":{"requestId":"request_2","bodyDescriptor":{"httpContentType":"application/json","shapeId":"shape_2","isRemoved":false}}},
+      {"RequestBodySet":{"requestId":"request_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"shape_2","isRemoved":false}}},
+    ]));
+
+    let commands = revert_batch_commands(&spec_events, &String::from("batch_2"))
+      .expect("batch should be revertable");
+    assert_debug_snapshot!("reverts_removals_by_dependency__commands", commands);
+
+    revert_batch(
+      spec_events,
+      &String::from("batch_2"),
+      String::from("revert batch_2"),
+      revert_command_context(),
+    )
+    .expect("shapes should only be removed once nothing refers to them anymore");
+  }
+
+  #[test]
+  pub fn can_revert_bodies_set_on_existing_requests_and_responses() {
+    let spec_events = vec![
+      batch_events(
+        "batch_1",
+        "root",
+        json!([
+          {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+          {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"POST"}},
+          {"ResponseAddedByPathAndMethod":{"responseId":"response_1","pathId":"path_1","httpMethod":"POST","httpStatusCode":201}},
+        ]),
+      ),
+      batch_events(
+        "batch_2",
+        "batch_1",
+        json!([
+          {"ShapeAdded":{"shapeId":"shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+          {"RequestBodySet":{"requestId":"request_1","bodyDescriptor":{"httpContentType":"text/plain","shapeId":"shape_1","isRemoved":false}}},
+          {"ResponseBodySet":{"responseId":"response_1","bodyDescriptor":{"httpContentType":"text/plain","shapeId":"shape_1","isRemoved":false}}},
+        ]),
+      ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    let commands = revert_batch_commands(&spec_events, &String::from("batch_2"))
+      .expect("batch setting bodies should be revertable");
+    assert_debug_snapshot!(
+      "can_revert_bodies_set_on_existing_requests_and_responses__commands",
+      commands
+    );
+
+    let revert_events = revert_batch(
+      spec_events.clone(),
+      &String::from("batch_2"),
+      String::from("revert batch_2"),
+      revert_command_context(),
+    )
+    .expect("revert commands should apply to the spec")
+    .commit();
+
+    let spec_projection = SpecProjection::from(
+      spec_events
+        .into_iter()
+        .chain(revert_events)
+        .collect::<Vec<_>>(),
+    );
+    let endpoint_projection = spec_projection.endpoint();
+    assert!(endpoint_projection
+      .get_referenced_shape_ids()
+      .next()
+      .is_none());
+  }
+
+  #[test]
+  pub fn cannot_revert_what_later_batches_changed() {
+    let mut spec_events = changed_spec_events(json!([
+      {"FieldRenamed":{"fieldId":"field_1","name":"fullName"}},
+    ]));
+    spec_events.extend(batch_events(
+      "batch_3",
+      "batch_2",
+      json!([
+        {"FieldRenamed":{"fieldId":"field_1","name":"displayName"}},
+      ]),
+    ));
+
+    let revert_result = revert_batch_commands(&spec_events, &String::from("batch_2"));
+    assert!(matches!(
+      revert_result,
+      Err(RevertBatchError::ChangedByLaterBatchCommit(batch_id, later_batch_id))
+        if batch_id == "batch_2" && later_batch_id == "batch_3"
+    ));
+
+    // changes to something else don't get in the way
+    let mut spec_events = changed_spec_events(json!([
+      {"FieldRenamed":{"fieldId":"field_1","name":"fullName"}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"shape_1","name":"email","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"$string"}}}},
+    ]));
+    spec_events.extend(batch_events(
+      "batch_3",
+      "batch_2",
+      json!([
+        {"FieldShapeSet":{"shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"$number"}}}},
+      ]),
+    ));
+    assert!(revert_batch_commands(&spec_events, &String::from("batch_2")).is_ok());
+
+    // nor can what the batch added be removed once changed later
+    spec_events.extend(batch_events(
+      "batch_4",
+      "batch_3",
+      json!([
+        {"FieldRenamed":{"fieldId":"field_2","name":"emailAddress"}},
+      ]),
+    ));
+    let revert_result = revert_batch_commands(&spec_events, &String::from("batch_2"));
+    assert!(matches!(
+      revert_result,
+      Err(RevertBatchError::ChangedByLaterBatchCommit(_, later_batch_id))
+        if later_batch_id == "batch_4"
+    ));
+  }
+
+  #[test]
+  pub fn cannot_revert_removals() {
+    let spec_events = changed_spec_events(json!([
+      {"FieldRemoved":{"fieldId":"field_1"}},
+    ]));
+
+    let revert_result = revert_batch_commands(&spec_events, &String::from("batch_2"));
+    assert!(matches!(
+      revert_result,
+      Err(RevertBatchError::IrreversibleEvent("FieldRemoved"))
+    ));
+
+    let unknown_batch_result = revert_batch_commands(&spec_events, &String::from("not-a-batch"));
+    assert!(matches!(
+      unknown_batch_result,
+      Err(RevertBatchError::UnknownBatchCommit(_))
+    ));
+  }
+}
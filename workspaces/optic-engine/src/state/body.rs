@@ -1,24 +1,148 @@
+use super::endpoint::MediaType;
 use crate::shapehash;
 use serde::de::value;
 use serde_json::map::Map as JsonMap;
-use serde_json::Value as JsonValue;
+use serde_json::{Number as JsonNumber, Value as JsonValue};
 use serde_urlencoded;
-use std::collections::{BTreeMap, HashMap};
-
-#[derive(PartialEq, Clone, Debug, Hash, Eq)]
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use thiserror::Error;
+
+/// Describes the shape of a body. Descriptors are compared and hashed by their shape only, so the
+/// primitive values observed never affect their identity, allowing array items of the same shape
+/// to be deduplicated.
+#[derive(Clone, Debug)]
 pub enum BodyDescriptor {
   Object(ObjectDescriptor),
   Array(ItemsDescriptor),
-  String,
-  Number,
+  String(PrimitiveValues<String>),
+  Number(PrimitiveValues<NumberValue>),
   Boolean,
   Null,
 }
 
+impl PartialEq for BodyDescriptor {
+  fn eq(&self, other: &Self) -> bool {
+    match (self, other) {
+      (BodyDescriptor::Object(object), BodyDescriptor::Object(other_object)) => {
+        object == other_object
+      }
+      (BodyDescriptor::Array(items), BodyDescriptor::Array(other_items)) => items == other_items,
+      (BodyDescriptor::String(_), BodyDescriptor::String(_))
+      | (BodyDescriptor::Number(_), BodyDescriptor::Number(_))
+      | (BodyDescriptor::Boolean, BodyDescriptor::Boolean)
+      | (BodyDescriptor::Null, BodyDescriptor::Null) => true,
+      _ => false,
+    }
+  }
+}
+
+impl Eq for BodyDescriptor {}
+
+impl Hash for BodyDescriptor {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    std::mem::discriminant(self).hash(state);
+    match self {
+      BodyDescriptor::Object(object) => object.hash(state),
+      BodyDescriptor::Array(items) => items.hash(state),
+      _ => {}
+    }
+  }
+}
+
 impl BodyDescriptor {
   pub fn empty_object() -> Self {
     Self::Object(ObjectDescriptor::from(std::iter::empty()))
   }
+
+  /// Collect the primitive values of a descriptor of the same shape into this one
+  fn merge_values(&mut self, other: BodyDescriptor) {
+    match (self, other) {
+      (BodyDescriptor::String(values), BodyDescriptor::String(other_values)) => {
+        values.extend(other_values)
+      }
+      (BodyDescriptor::Number(values), BodyDescriptor::Number(other_values)) => {
+        values.extend(other_values)
+      }
+      (BodyDescriptor::Object(object), BodyDescriptor::Object(other_object)) => {
+        for (field, other_field) in object.fields.iter_mut().zip(other_object.fields) {
+          field.1.merge_values(*other_field.1);
+        }
+      }
+      (BodyDescriptor::Array(items), BodyDescriptor::Array(other_items)) => {
+        for ((item, _), (other_item, _)) in
+          items.unique_items.iter_mut().zip(*other_items.unique_items)
+        {
+          item.merge_values(other_item);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Values observed for a primitive, so they can be checked against shape constraints. At most
+/// `MAX_PRIMITIVE_VALUES` are kept, as those are representative enough of the rest. An empty
+/// list means the values are unknown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrimitiveValues<T>(Vec<T>);
+
+/// Maximum amount of values collected for a primitive, so large arrays don't keep all of theirs
+const MAX_PRIMITIVE_VALUES: usize = 100;
+
+impl<T> PrimitiveValues<T> {
+  pub fn unknown() -> Self {
+    Self(vec![])
+  }
+
+  pub fn single(value: T) -> Self {
+    Self(vec![value])
+  }
+
+  pub fn is_unknown(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &T> {
+    self.0.iter()
+  }
+
+  fn extend(&mut self, other: PrimitiveValues<T>) {
+    let remaining = MAX_PRIMITIVE_VALUES.saturating_sub(self.0.len());
+    self.0.extend(other.0.into_iter().take(remaining));
+  }
+}
+
+impl<T> FromIterator<T> for PrimitiveValues<T> {
+  fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+    Self(values.into_iter().take(MAX_PRIMITIVE_VALUES).collect())
+  }
+}
+
+/// A number as observed in a body
+#[derive(PartialEq, Clone, Debug)]
+pub struct NumberValue(JsonNumber);
+
+impl NumberValue {
+  pub fn as_f64(&self) -> Option<f64> {
+    self.0.as_f64()
+  }
+
+  pub fn is_integer(&self) -> bool {
+    self.0.is_i64()
+      || self.0.is_u64()
+      || self
+        .as_f64()
+        .map(|number| number.fract() == 0.0)
+        .unwrap_or(false)
+  }
+}
+
+impl From<JsonNumber> for NumberValue {
+  fn from(number: JsonNumber) -> Self {
+    Self(number)
+  }
 }
 
 #[derive(PartialEq, Clone, Debug, Hash, Eq)]
@@ -40,6 +164,21 @@ impl ObjectDescriptor {
     self.fields.iter().map(|FieldDescriptor(key, body)| key)
   }
 
+  pub fn iter(&self) -> impl Iterator<Item = (&String, &BodyDescriptor)> {
+    self
+      .fields
+      .iter()
+      .map(|FieldDescriptor(key, body)| (key, body.as_ref()))
+  }
+
+  pub fn get(&self, key: &str) -> Option<&BodyDescriptor> {
+    self
+      .fields
+      .iter()
+      .find(|FieldDescriptor(field_key, _)| field_key == key)
+      .map(|FieldDescriptor(_, body)| body.as_ref())
+  }
+
   pub fn entries(self) -> impl Iterator<Item = (String, BodyDescriptor)> {
     self
       .fields
@@ -71,21 +210,22 @@ where
   T: Iterator<Item = BodyDescriptor>,
 {
   fn from(all_items: T) -> Self {
-    let mut unique_items = all_items
-      .enumerate()
-      .fold(HashMap::new(), |mut indexes_by_unique_items, (i, item)| {
-        {
-          let items = indexes_by_unique_items.entry(item).or_insert(vec![]);
-          items.push(i);
-        };
-
-        indexes_by_unique_items
-      })
-      .into_iter()
-      .map(|(item, indexes)| (item.clone(), indexes))
-      .collect::<Vec<_>>();
-
-    unique_items.sort_by_key(|(_, indexes)| *indexes.first().unwrap());
+    let mut unique_items: Vec<(BodyDescriptor, Vec<usize>)> = vec![];
+    let mut positions_by_unique_items: HashMap<BodyDescriptor, usize> = HashMap::new();
+
+    for (i, item) in all_items.enumerate() {
+      match positions_by_unique_items.get(&item) {
+        Some(position) => {
+          let (unique_item, indexes) = &mut unique_items[*position];
+          indexes.push(i);
+          unique_item.merge_values(item);
+        }
+        None => {
+          positions_by_unique_items.insert(item.clone(), unique_items.len());
+          unique_items.push((item, vec![i]));
+        }
+      }
+    }
 
     Self {
       unique_items: Box::new(unique_items),
@@ -141,12 +281,39 @@ impl From<shapehash::ShapeDescriptor> for BodyDescriptor {
       }
       shapehash::ShapeDescriptor_PrimitiveType::BOOLEAN => BodyDescriptor::Boolean,
       shapehash::ShapeDescriptor_PrimitiveType::NULL => BodyDescriptor::Null,
-      shapehash::ShapeDescriptor_PrimitiveType::NUMBER => BodyDescriptor::Number,
-      shapehash::ShapeDescriptor_PrimitiveType::STRING => BodyDescriptor::String,
+      shapehash::ShapeDescriptor_PrimitiveType::NUMBER => {
+        let values = observed_rule_values(&mut shape_hash_descriptor)
+          .filter_map(|value| match value {
+            JsonValue::Number(number) => Some(NumberValue::from(number)),
+            _ => None,
+          })
+          .collect();
+        BodyDescriptor::Number(values)
+      }
+      shapehash::ShapeDescriptor_PrimitiveType::STRING => {
+        let values = observed_rule_values(&mut shape_hash_descriptor)
+          .filter_map(|value| match value {
+            JsonValue::String(string) => Some(string),
+            _ => None,
+          })
+          .collect();
+        BodyDescriptor::String(values)
+      }
     }
   }
 }
 
+// Shape hashes don't carry primitive values by default, but capturing clients can include the
+// observed values of a primitive as JSON encoded rules.
+fn observed_rule_values(
+  shape_hash_descriptor: &mut shapehash::ShapeDescriptor,
+) -> impl Iterator<Item = JsonValue> {
+  shape_hash_descriptor
+    .take_rules()
+    .into_iter()
+    .filter_map(|rule| serde_json::from_str(&rule).ok())
+}
+
 impl From<JsonValue> for BodyDescriptor {
   fn from(json_value: JsonValue) -> Self {
     match json_value {
@@ -166,21 +333,23 @@ impl From<JsonValue> for BodyDescriptor {
       }
       JsonValue::Bool(_) => BodyDescriptor::Boolean,
       JsonValue::Null => BodyDescriptor::Null,
-      JsonValue::Number(_) => BodyDescriptor::Number,
-      JsonValue::String(_) => BodyDescriptor::String,
+      JsonValue::Number(number) => {
+        BodyDescriptor::Number(PrimitiveValues::single(NumberValue::from(number)))
+      }
+      JsonValue::String(string) => BodyDescriptor::String(PrimitiveValues::single(string)),
     }
   }
 }
 
 impl From<String> for BodyDescriptor {
   fn from(string: String) -> Self {
-    BodyDescriptor::String
+    BodyDescriptor::String(PrimitiveValues::single(string))
   }
 }
 
 impl From<&String> for BodyDescriptor {
   fn from(str: &String) -> Self {
-    BodyDescriptor::String
+    BodyDescriptor::String(PrimitiveValues::single(str.clone()))
   }
 }
 
@@ -198,24 +367,389 @@ impl ParsedQueryString {
 
 impl From<ParsedQueryString> for BodyDescriptor {
   fn from(parsed_qs: ParsedQueryString) -> Self {
-    let mut values_by_key = BTreeMap::new();
+    let mut root = QueryField::Fields(BTreeMap::new());
+
+    let entries = parsed_qs
+      .entries
+      .iter()
+      .map(|(key, value)| (key, query_key_segments(key), value))
+      .collect::<Vec<_>>();
+    let value_paths = entries
+      .iter()
+      .map(|(_, (segments, _), _)| segments.as_slice())
+      .collect::<HashSet<_>>();
+
+    for (key, (segments, is_list), value) in &entries {
+      // keys nested below the value of another key are kept as they are, whichever comes first
+      let is_below_value = (1..segments.len()).any(|len| value_paths.contains(&segments[..len]));
+      let is_inserted = if is_below_value {
+        root.insert(&[key.as_str()], false, value.to_string())
+      } else {
+        root.insert(segments, *is_list, value.to_string())
+      };
+      debug_assert!(is_inserted, "query string keys should not conflict");
+    }
+
+    BodyDescriptor::from(root)
+  }
+}
+
+/// The values of a query string, nested by the bracket (`filter[status]=x`) and dot (`a.b=1`)
+/// notation of their keys. Values of keys that occur more than once, or that end in `[]`, are
+/// collected as a list.
+enum QueryField {
+  Values { values: Vec<String>, is_list: bool },
+  Fields(BTreeMap<String, QueryField>),
+}
+
+impl QueryField {
+  /// Insert a value at the path of segments, returning whether it fit the existing fields
+  fn insert(&mut self, segments: &[&str], is_list: bool, value: String) -> bool {
+    match (self, segments.split_first()) {
+      (
+        QueryField::Values {
+          values,
+          is_list: was_list,
+        },
+        None,
+      ) => {
+        values.push(value);
+        *was_list = *was_list || is_list;
+        true
+      }
+      (QueryField::Fields(fields), Some((segment, remaining_segments))) => {
+        let field = fields.entry(String::from(*segment)).or_insert_with(|| {
+          if remaining_segments.is_empty() {
+            QueryField::Values {
+              values: vec![],
+              is_list,
+            }
+          } else {
+            QueryField::Fields(BTreeMap::new())
+          }
+        });
+        field.insert(remaining_segments, is_list, value)
+      }
+      _ => false,
+    }
+  }
+}
+
+impl From<QueryField> for BodyDescriptor {
+  fn from(query_field: QueryField) -> Self {
+    match query_field {
+      QueryField::Values { values, is_list } if values.len() == 1 && !is_list => {
+        BodyDescriptor::from(values.into_iter().next().unwrap())
+      }
+      QueryField::Values { values, .. } => BodyDescriptor::Array(ItemsDescriptor::from(
+        values.into_iter().map(BodyDescriptor::from),
+      )),
+      QueryField::Fields(fields) => BodyDescriptor::Object(ObjectDescriptor::from(
+        fields
+          .into_iter()
+          .map(|(key, field)| (key, BodyDescriptor::from(field))),
+      )),
+    }
+  }
+}
 
-    for (key, value) in parsed_qs.entries {
-      let entry = values_by_key.entry(key).or_insert_with(|| vec![]);
-      entry.push(value);
+/// The segments of a query string key in bracket or dot notation, and whether it denotes a list
+/// by ending in `[]`. Keys that aren't well-formed are kept whole.
+fn query_key_segments(key: &str) -> (Vec<&str>, bool) {
+  let (name, brackets) = match key.find('[') {
+    Some(start) if start > 0 && key.ends_with(']') => {
+      (&key[..start], Some(&key[start + 1..key.len() - 1]))
+    }
+    _ => (key, None),
+  };
+
+  let mut segments = name.split('.').collect::<Vec<_>>();
+  let mut is_list = false;
+  if let Some(brackets) = brackets {
+    segments.extend(brackets.split("]["));
+    if segments.last() == Some(&"") {
+      segments.pop();
+      is_list = true;
     }
+  }
+
+  let is_well_formed = segments
+    .iter()
+    .all(|segment| !segment.is_empty() && !segment.contains(&['[', ']'][..]));
+  if is_well_formed {
+    (segments, is_list)
+  } else {
+    (vec![key], false)
+  }
+}
+
+/// Fields of an object for unique key and value pairs, along with those of keys that may be
+/// repeated. The values of the latter are always collected as the items of an array, so a key
+/// that occurs once is described like one that occurs more often. Fields are ordered by their key.
+fn grouped_fields(
+  entries: impl Iterator<Item = (String, BodyDescriptor)>,
+  repeatable_entries: impl Iterator<Item = (String, BodyDescriptor)>,
+) -> ObjectDescriptor {
+  let mut values_by_key = BTreeMap::new();
+
+  for (key, value) in repeatable_entries {
+    let entry = values_by_key.entry(key).or_insert_with(|| vec![]);
+    entry.push(value);
+  }
+
+  let repeatable_fields = values_by_key.into_iter().map(|(key, values)| {
+    (
+      key,
+      BodyDescriptor::Array(ItemsDescriptor::from(values.into_iter())),
+    )
+  });
+  let mut fields = entries.chain(repeatable_fields).collect::<Vec<_>>();
+  fields.sort_by(|(key, _), (other_key, _)| key.cmp(other_key));
 
-    let fields = values_by_key.into_iter().map(|(key, values)| {
-      let value_descriptor = if values.len() == 1 {
-        BodyDescriptor::String
+  ObjectDescriptor::from(fields.into_iter())
+}
+
+// Multipart
+// ---------
+
+/// The named parts of a `multipart/form-data` body, separated by its boundary
+#[derive(Debug, Default)]
+pub struct ParsedMultipart {
+  parts: Vec<MultipartPart>,
+}
+
+#[derive(Debug)]
+struct MultipartPart {
+  name: String,
+  content_type: Option<String>,
+  content: String,
+}
+
+#[derive(Debug, Error)]
+pub enum MultipartParseError {
+  #[error("multipart body does not contain boundary '{0}'")]
+  UnknownBoundary(String),
+  #[error("multipart body is not terminated by its closing boundary")]
+  Unterminated,
+  #[error("multipart body part has no headers")]
+  PartWithoutHeaders,
+}
+
+impl ParsedMultipart {
+  pub fn from_str(multipart: &str, boundary: &str) -> Result<Self, MultipartParseError> {
+    let delimiter = format!("--{}", boundary);
+    let mut segments = multipart.split(delimiter.as_str());
+
+    // anything before the first boundary is a preamble, to be ignored
+    segments
+      .next()
+      .filter(|_| multipart.contains(&delimiter))
+      .ok_or_else(|| MultipartParseError::UnknownBoundary(String::from(boundary)))?;
+
+    let mut parts = vec![];
+    let mut is_closed = false;
+    for segment in segments {
+      if segment.starts_with("--") {
+        is_closed = true;
+        break;
+      }
+
+      let segment = segment.strip_prefix("\r\n").unwrap_or(segment);
+      let (headers, content) = match segment.find("\r\n\r\n") {
+        Some(index) => (&segment[..index], &segment[index + 4..]),
+        None => match segment.find("\n\n") {
+          Some(index) => (&segment[..index], &segment[index + 2..]),
+          None => return Err(MultipartParseError::PartWithoutHeaders),
+        },
+      };
+      let content = content
+        .strip_suffix("\r\n")
+        .or_else(|| content.strip_suffix('\n'))
+        .unwrap_or(content);
+
+      let mut name = None;
+      let mut content_type = None;
+      for header in headers.lines() {
+        let mut header_parts = header.splitn(2, ':');
+        let header_name = header_parts.next().unwrap_or_default().trim();
+        let header_value = header_parts.next().unwrap_or_default().trim();
+        if header_name.eq_ignore_ascii_case("content-disposition") {
+          name = header_value.split(';').skip(1).find_map(|parameter| {
+            let mut parameter_parts = parameter.splitn(2, '=');
+            match parameter_parts.next()?.trim() {
+              "name" => Some(String::from(
+                parameter_parts.next()?.trim().trim_matches('"'),
+              )),
+              _ => None,
+            }
+          });
+        } else if header_name.eq_ignore_ascii_case("content-type") {
+          content_type = Some(String::from(header_value));
+        }
+      }
+
+      // parts are described by their name, so there's nothing to describe for unnamed ones
+      if let Some(name) = name {
+        parts.push(MultipartPart {
+          name,
+          content_type,
+          content: String::from(content),
+        });
+      }
+    }
+
+    if is_closed {
+      Ok(Self { parts })
+    } else {
+      Err(MultipartParseError::Unterminated)
+    }
+  }
+}
+
+impl From<ParsedMultipart> for BodyDescriptor {
+  fn from(parsed_multipart: ParsedMultipart) -> Self {
+    let entries = parsed_multipart.parts.into_iter().map(|part| {
+      let is_json = part
+        .content_type
+        .as_deref()
+        .and_then(MediaType::parse)
+        .map(|media_type| {
+          media_type.subtype == "json" || media_type.suffix.as_deref() == Some("json")
+        })
+        .unwrap_or(false);
+
+      let json_content = if is_json {
+        serde_json::from_str::<JsonValue>(&part.content).ok()
       } else {
-        let item_descriptor = ItemsDescriptor::from(BodyDescriptor::String); // we only ever expect strings with this parser
-        BodyDescriptor::Array(item_descriptor)
+        None
+      };
+      let value_descriptor = match json_content {
+        Some(json) => BodyDescriptor::from(json),
+        None => BodyDescriptor::from(part.content),
       };
 
-      (key, value_descriptor)
+      (part.name, value_descriptor)
     });
 
+    BodyDescriptor::Object(grouped_fields(std::iter::empty(), entries))
+  }
+}
+
+// XML
+// ---
+
+/// The element tree of an XML document. Elements are described as objects, with attributes as
+/// `@`-prefixed fields, child elements as array fields by their name and any text next to those as
+/// a `#text` field. Elements with neither attributes nor children are described by their text.
+/// Names are qualified by the prefix of their namespace, where they have one.
+#[derive(Debug)]
+pub struct ParsedXml {
+  root: XmlElement,
+}
+
+#[derive(Debug)]
+struct XmlElement {
+  name: String,
+  attributes: Vec<(String, String)>,
+  children: Vec<XmlElement>,
+  text: String,
+}
+
+impl ParsedXml {
+  pub fn from_str(xml: &str) -> Result<Self, roxmltree::Error> {
+    let document = roxmltree::Document::parse(xml)?;
+    Ok(Self {
+      root: XmlElement::from(document.root_element()),
+    })
+  }
+}
+
+impl From<roxmltree::Node<'_, '_>> for XmlElement {
+  fn from(node: roxmltree::Node) -> Self {
+    let attributes = node
+      .attributes()
+      .iter()
+      .map(|attribute| {
+        let name = match attribute.namespace() {
+          Some(namespace) => {
+            qualified_name(node.lookup_prefix(namespace), namespace, attribute.name())
+          }
+          None => String::from(attribute.name()),
+        };
+        (name, String::from(attribute.value()))
+      })
+      .collect();
+    let children = node
+      .children()
+      .filter(|child| child.is_element())
+      .map(XmlElement::from)
+      .collect();
+    let text = node
+      .children()
+      .filter_map(|child| child.text().filter(|_| child.is_text()))
+      .collect::<String>();
+
+    let tag_name = node.tag_name();
+    let name = match tag_name.namespace() {
+      // elements in the default namespace of their parent don't need qualifying
+      Some(namespace)
+        if node.lookup_prefix(namespace).is_some()
+          || matches!(
+            node.parent_element(),
+            Some(parent) if parent.tag_name().namespace() != Some(namespace)
+          ) =>
+      {
+        qualified_name(node.lookup_prefix(namespace), namespace, tag_name.name())
+      }
+      _ => String::from(tag_name.name()),
+    };
+
+    Self {
+      name,
+      attributes,
+      children,
+      text: String::from(text.trim()),
+    }
+  }
+}
+
+/// A name qualified by the prefix of its namespace (`prefix:name`), or by the namespace itself
+/// (`{namespace}name`) where it's a default one, so same-named elements or attributes of different
+/// namespaces are described apart.
+fn qualified_name(prefix: Option<&str>, namespace: &str, name: &str) -> String {
+  match prefix {
+    Some(prefix) => format!("{}:{}", prefix, name),
+    None => format!("{{{}}}{}", namespace, name),
+  }
+}
+
+impl From<XmlElement> for BodyDescriptor {
+  fn from(element: XmlElement) -> Self {
+    if element.attributes.is_empty() && element.children.is_empty() {
+      return BodyDescriptor::from(element.text);
+    }
+
+    let attributes = element
+      .attributes
+      .into_iter()
+      .map(|(name, value)| (format!("@{}", name), BodyDescriptor::from(value)));
+    let children = element
+      .children
+      .into_iter()
+      .map(|child| (child.name.clone(), BodyDescriptor::from(child)));
+    let text = Some(element.text)
+      .filter(|text| !text.is_empty())
+      .map(|text| (String::from("#text"), BodyDescriptor::from(text)));
+
+    BodyDescriptor::Object(grouped_fields(attributes.chain(text), children))
+  }
+}
+
+impl From<ParsedXml> for BodyDescriptor {
+  fn from(parsed_xml: ParsedXml) -> Self {
+    let root = parsed_xml.root;
+    let fields = std::iter::once((root.name.clone(), BodyDescriptor::from(root)));
+
     BodyDescriptor::Object(ObjectDescriptor::from(fields))
   }
 }
@@ -224,6 +758,34 @@ impl From<ParsedQueryString> for BodyDescriptor {
 mod test {
   use super::*;
   use insta::assert_debug_snapshot;
+  use serde_json::json;
+
+  #[test]
+  fn body_descriptors_are_identified_by_shape_not_values() {
+    assert_eq!(
+      BodyDescriptor::from(json!({ "name": "a" })),
+      BodyDescriptor::from(json!({ "name": "b" }))
+    );
+    assert_ne!(
+      BodyDescriptor::from(json!({ "name": "a" })),
+      BodyDescriptor::from(json!({ "name": 1 }))
+    );
+
+    let items = (0..150).map(|i| json!(format!("item-{}", i))).collect();
+    let body_descriptor = BodyDescriptor::from(JsonValue::Array(items));
+    let unique_items = match body_descriptor {
+      BodyDescriptor::Array(items) => items.unique_items,
+      _ => panic!("expected an array descriptor"),
+    };
+    assert_eq!(unique_items.len(), 1);
+
+    let (item, indexes) = &unique_items[0];
+    assert_eq!(indexes.len(), 150);
+    match item {
+      BodyDescriptor::String(values) => assert_eq!(values.iter().count(), MAX_PRIMITIVE_VALUES),
+      _ => panic!("expected a string descriptor"),
+    }
+  }
 
   #[test]
   fn query_string_can_be_parsed_to_body_descriptor() {
@@ -242,4 +804,129 @@ mod test {
       body_descriptor
     );
   }
+
+  #[test]
+  fn query_string_keys_in_bracket_and_dot_notation_are_nested() {
+    let parsed = ParsedQueryString::from_str(
+      "filter[status]=open&filter[owner][name]=ann&tags[]=a&ids[]=1&ids[]=2&page.size=10&[x]=1&a.=2",
+    )
+    .expect("should be able to parse a query string");
+
+    assert_debug_snapshot!(
+      "query_string_keys_in_bracket_and_dot_notation_are_nested__body_descriptor",
+      BodyDescriptor::from(parsed)
+    );
+
+    assert_eq!(
+      query_key_segments("filter[status]"),
+      (vec!["filter", "status"], false)
+    );
+    assert_eq!(query_key_segments("a.b[c][]"), (vec!["a", "b", "c"], true));
+    assert_eq!(query_key_segments("a[b]c]"), (vec!["a[b]c]"], false));
+
+    // keys nested below the value of another are kept as they are, in either order
+    for query_string in ["a.b=1&a=2", "a=2&a.b=1"].iter() {
+      let parsed =
+        ParsedQueryString::from_str(query_string).expect("should be able to parse a query string");
+
+      assert_eq!(
+        BodyDescriptor::from(parsed),
+        BodyDescriptor::from(json!({ "a": "2", "a.b": "1" })),
+        "no value of {} should be dropped",
+        query_string
+      );
+    }
+  }
+
+  #[test]
+  fn multipart_can_be_parsed_to_body_descriptor() {
+    let multipart = [
+      "preamble to be ignored",
+      "--XyZ",
+      "Content-Disposition: form-data; name=\"title\"",
+      "",
+      "Buy milk",
+      "--XyZ",
+      "Content-Disposition: form-data; name=\"tags\"",
+      "",
+      "groceries",
+      "--XyZ",
+      "Content-Disposition: form-data; name=\"tags\"",
+      "",
+      "errands",
+      "--XyZ",
+      "Content-Disposition: form-data; name=\"metadata\"",
+      "Content-Type: application/json",
+      "",
+      "{\"priority\":1,\"done\":false}",
+      "--XyZ",
+      "Content-Disposition: form-data; name=\"attachment\"; filename=\"list.txt\"",
+      "Content-Type: text/plain",
+      "",
+      "milk\r\neggs",
+      "--XyZ--",
+      "",
+    ]
+    .join("\r\n");
+
+    let parsed = ParsedMultipart::from_str(&multipart, "XyZ")
+      .expect("should be able to parse a multipart body");
+
+    assert_debug_snapshot!(
+      "multipart_can_be_parsed_to_body_descriptor__parsed",
+      &parsed
+    );
+
+    let body_descriptor = BodyDescriptor::from(parsed);
+
+    assert_debug_snapshot!(
+      "multipart_can_be_parsed_to_body_descriptor__body_descriptor",
+      body_descriptor
+    );
+
+    assert!(matches!(
+      ParsedMultipart::from_str(&multipart, "other-boundary"),
+      Err(MultipartParseError::UnknownBoundary(_))
+    ));
+    assert!(matches!(
+      ParsedMultipart::from_str(
+        "--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nb",
+        "XyZ"
+      ),
+      Err(MultipartParseError::Unterminated)
+    ));
+  }
+
+  #[test]
+  fn xml_can_be_parsed_to_body_descriptor() {
+    let parsed = ParsedXml::from_str(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+      <todos xmlns="http://example.com/todos" page="1">
+        <todo id="1" done="false">
+          <title>Buy milk</title>
+          <tag>groceries</tag>
+          <tag>errands</tag>
+          <x:tag xmlns:x="http://example.com/labels">urgent</x:tag>
+        </todo>
+        <todo id="2">
+          <title lang="en" xml:space="preserve">Walk the dog</title>
+          <notes/>
+          <notes xmlns="http://example.com/notes">Bring a leash</notes>
+        </todo>
+        <!-- more todos on the next page -->
+      </todos>"#,
+    )
+    .expect("should be able to parse an xml document");
+
+    assert_debug_snapshot!("xml_can_be_parsed_to_body_descriptor__parsed", &parsed);
+
+    let body_descriptor = BodyDescriptor::from(parsed);
+
+    assert_debug_snapshot!(
+      "xml_can_be_parsed_to_body_descriptor__body_descriptor",
+      body_descriptor
+    );
+
+    assert!(ParsedXml::from_str("<todo><title>Buy milk</todo>").is_err());
+  }
 }
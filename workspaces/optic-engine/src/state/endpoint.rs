@@ -1,5 +1,8 @@
 pub use super::shape::ShapeId;
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 
 pub type PathComponentId = String;
 pub type PathComponentIdRef<'a> = &'a str;
@@ -32,3 +35,508 @@ pub struct QueryParametersShapeDescriptor {
   pub shape_id: ShapeId,
   pub is_removed: bool,
 }
+
+// Media types
+// -----------
+
+/// Parameters that describe the encoding of a body rather than its shape, and are therefore
+/// never significant when matching media types.
+const IGNORED_MEDIA_TYPE_PARAMETERS: [&str; 3] = ["charset", "boundary", "q"];
+
+/// A parsed media type, like `application/vnd.api+json; charset=utf-8`. Types, subtypes and
+/// parameter names are case-insensitive, so they're normalized to lower case.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MediaType {
+  pub type_name: String,
+  pub subtype: String,
+  pub suffix: Option<String>,
+  pub parameters: Vec<(String, String)>,
+}
+
+/// How a documented media type (range) matched an observed media type, ordered by precedence.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum MediaTypeMatch {
+  AnyType,
+  AnySubtype,
+  StructuredSuffix,
+  Exact,
+  ExactWithParameters,
+}
+
+impl MediaType {
+  pub fn parse(media_type: &str) -> Option<Self> {
+    let mut parts = media_type.split(';');
+    let essence = parts.next()?.trim();
+    let mut essence_parts = essence.splitn(2, '/');
+    let type_name = essence_parts.next()?.trim().to_lowercase();
+    let full_subtype = essence_parts.next()?.trim().to_lowercase();
+    if type_name.is_empty() || full_subtype.is_empty() {
+      return None;
+    }
+
+    let (subtype, suffix) = match full_subtype.rfind('+') {
+      Some(index) if index > 0 && index < full_subtype.len() - 1 => (
+        String::from(&full_subtype[..index]),
+        Some(String::from(&full_subtype[index + 1..])),
+      ),
+      _ => (full_subtype, None),
+    };
+
+    let parameters = parts
+      .filter_map(|parameter| {
+        let mut parameter_parts = parameter.splitn(2, '=');
+        let name = parameter_parts.next()?.trim().to_lowercase();
+        let value = parameter_parts.next()?.trim().trim_matches('"');
+        if name.is_empty() {
+          None
+        } else {
+          Some((name, String::from(value)))
+        }
+      })
+      .collect();
+
+    Some(Self {
+      type_name,
+      subtype,
+      suffix,
+      parameters,
+    })
+  }
+
+  /// The type and subtype, without any parameters
+  pub fn essence(&self) -> String {
+    match &self.suffix {
+      Some(suffix) => format!("{}/{}+{}", self.type_name, self.subtype, suffix),
+      None => format!("{}/{}", self.type_name, self.subtype),
+    }
+  }
+
+  pub fn parameter(&self, name: &str) -> Option<&String> {
+    self
+      .parameters
+      .iter()
+      .find(|(parameter_name, _)| parameter_name.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value)
+  }
+
+  /// Match an observed media type against this one as documented. Documented media types
+  /// can be ranges (`*/*`, `text/*`) and a structured syntax suffix (`application/problem+json`)
+  /// satisfies its base type (`application/json`). Parameters are significant only when
+  /// documented, and never for encoding parameters like `charset`. Still, matching all parameters
+  /// exactly, encoding ones included, beats matching only the type and subtype.
+  pub fn match_observed(&self, observed: &MediaType) -> Option<MediaTypeMatch> {
+    let essence_match = if self.type_name == "*" {
+      MediaTypeMatch::AnyType
+    } else if self.type_name != observed.type_name {
+      return None;
+    } else if self.subtype == "*" && self.suffix.is_none() {
+      MediaTypeMatch::AnySubtype
+    } else if self.subtype == observed.subtype && self.suffix == observed.suffix {
+      MediaTypeMatch::Exact
+    } else if (self.suffix.is_none() && observed.suffix.as_ref() == Some(&self.subtype))
+      || (self.subtype == "*" && self.suffix.is_some() && self.suffix == observed.suffix)
+    {
+      MediaTypeMatch::StructuredSuffix
+    } else {
+      return None;
+    };
+
+    let parameters_match = self
+      .parameters
+      .iter()
+      .filter(|(name, _)| !IGNORED_MEDIA_TYPE_PARAMETERS.contains(&name.as_str()))
+      .all(|(name, value)| match observed.parameter(name) {
+        Some(observed_value) => observed_value.eq_ignore_ascii_case(value),
+        None => false,
+      });
+
+    if !parameters_match {
+      None
+    } else if essence_match == MediaTypeMatch::Exact && self.has_same_parameters(observed) {
+      Some(MediaTypeMatch::ExactWithParameters)
+    } else {
+      Some(essence_match)
+    }
+  }
+
+  fn has_same_parameters(&self, other: &MediaType) -> bool {
+    self.parameters.len() == other.parameters.len()
+      && self
+        .parameters
+        .iter()
+        .all(|(name, value)| match other.parameter(name) {
+          Some(other_value) => other_value.eq_ignore_ascii_case(value),
+          None => false,
+        })
+  }
+}
+
+/// Match a documented content type against an observed one, see [`MediaType::match_observed`].
+/// Content types that can't be parsed as media types only match when they're identical.
+pub fn match_content_types(documented: &str, observed: &str) -> Option<MediaTypeMatch> {
+  match (MediaType::parse(documented), MediaType::parse(observed)) {
+    (Some(documented), Some(observed)) => documented.match_observed(&observed),
+    _ if documented == observed => Some(MediaTypeMatch::ExactWithParameters),
+    _ => None,
+  }
+}
+
+/// Whether matching a documented content type beats the best match found so far. Of equally
+/// specific matches the first one found wins, so every lookup picks the same documented body.
+pub fn is_better_content_type_match(
+  candidate: MediaTypeMatch,
+  best_match: Option<MediaTypeMatch>,
+) -> bool {
+  match best_match {
+    Some(best_match) => candidate > best_match,
+    None => true,
+  }
+}
+
+/// The documented content type that matches an observed one best, along with how well it matched,
+/// see [`is_better_content_type_match`].
+pub fn best_content_type_match<D, T>(
+  documented: impl IntoIterator<Item = (D, T)>,
+  observed: &str,
+) -> Option<(MediaTypeMatch, T)>
+where
+  D: AsRef<str>,
+{
+  documented
+    .into_iter()
+    .fold(
+      None,
+      |best, (documented_content_type, item)| match match_content_types(
+        documented_content_type.as_ref(),
+        observed,
+      ) {
+        Some(media_type_match)
+          if is_better_content_type_match(
+            media_type_match,
+            best.as_ref().map(|(best_match, _)| *best_match),
+          ) =>
+        {
+          Some((media_type_match, item))
+        }
+        _ => best,
+      },
+    )
+}
+
+// Status codes
+// ------------
+
+/// A range of status codes sharing their first digit, like `4XX` for all client errors.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct HttpStatusRange(u16);
+
+impl HttpStatusRange {
+  /// The range of a status class, from `1` (informational) through `5` (server errors)
+  pub fn from_class(class: u16) -> Option<Self> {
+    if (1..=5).contains(&class) {
+      Some(Self(class))
+    } else {
+      None
+    }
+  }
+
+  pub fn class(&self) -> u16 {
+    self.0
+  }
+
+  pub fn contains(&self, status_code: HttpStatusCode) -> bool {
+    status_code / 100 == self.0
+  }
+}
+
+impl fmt::Display for HttpStatusRange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}XX", self.0)
+  }
+}
+
+impl FromStr for HttpStatusRange {
+  type Err = String;
+
+  fn from_str(range: &str) -> Result<Self, Self::Err> {
+    let mut chars = range.chars();
+    let class = chars.next().and_then(|class| class.to_digit(10));
+    let is_range = chars.as_str().eq_ignore_ascii_case("xx");
+    match class {
+      Some(class) if is_range => Self::from_class(class as u16),
+      _ => None,
+    }
+    .ok_or_else(|| format!("'{}' is not a status code range like '4XX'", range))
+  }
+}
+
+impl TryFrom<String> for HttpStatusRange {
+  type Error = String;
+
+  fn try_from(range: String) -> Result<Self, Self::Error> {
+    range.parse()
+  }
+}
+
+impl From<HttpStatusRange> for String {
+  fn from(range: HttpStatusRange) -> Self {
+    range.to_string()
+  }
+}
+
+/// The status codes a response is documented for: a single code, a range of codes or any code
+/// not documented otherwise (`default`). Serialized as a number for single codes, like status
+/// codes have always been, and as a string like `4XX` or `default` otherwise.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+#[serde(
+  into = "HttpStatusSpecifierValue",
+  try_from = "HttpStatusSpecifierValue"
+)]
+pub enum HttpStatusSpecifier {
+  Code(HttpStatusCode),
+  Range(HttpStatusRange),
+  Default,
+}
+
+/// How a documented status specifier matched an observed status code, ordered by precedence.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum HttpStatusMatch {
+  Default,
+  Range,
+  Exact,
+}
+
+impl HttpStatusSpecifier {
+  pub fn match_status_code(&self, status_code: HttpStatusCode) -> Option<HttpStatusMatch> {
+    match self {
+      HttpStatusSpecifier::Code(code) if *code == status_code => Some(HttpStatusMatch::Exact),
+      HttpStatusSpecifier::Range(range) if range.contains(status_code) => {
+        Some(HttpStatusMatch::Range)
+      }
+      HttpStatusSpecifier::Default => Some(HttpStatusMatch::Default),
+      _ => None,
+    }
+  }
+
+  pub fn status_code(&self) -> Option<HttpStatusCode> {
+    match self {
+      HttpStatusSpecifier::Code(code) => Some(*code),
+      _ => None,
+    }
+  }
+}
+
+impl From<HttpStatusCode> for HttpStatusSpecifier {
+  fn from(status_code: HttpStatusCode) -> Self {
+    HttpStatusSpecifier::Code(status_code)
+  }
+}
+
+impl From<HttpStatusRange> for HttpStatusSpecifier {
+  fn from(range: HttpStatusRange) -> Self {
+    HttpStatusSpecifier::Range(range)
+  }
+}
+
+impl fmt::Display for HttpStatusSpecifier {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      HttpStatusSpecifier::Code(code) => write!(f, "{}", code),
+      HttpStatusSpecifier::Range(range) => write!(f, "{}", range),
+      HttpStatusSpecifier::Default => write!(f, "default"),
+    }
+  }
+}
+
+impl FromStr for HttpStatusSpecifier {
+  type Err = String;
+
+  fn from_str(specifier: &str) -> Result<Self, Self::Err> {
+    if specifier.eq_ignore_ascii_case("default") {
+      Ok(HttpStatusSpecifier::Default)
+    } else if let Ok(code) = specifier.parse::<HttpStatusCode>() {
+      Ok(HttpStatusSpecifier::Code(code))
+    } else {
+      specifier
+        .parse::<HttpStatusRange>()
+        .map(HttpStatusSpecifier::Range)
+        .map_err(|_| format!("'{}' is not a status code, range or 'default'", specifier))
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum HttpStatusSpecifierValue {
+  Code(HttpStatusCode),
+  Specifier(String),
+}
+
+impl TryFrom<HttpStatusSpecifierValue> for HttpStatusSpecifier {
+  type Error = String;
+
+  fn try_from(value: HttpStatusSpecifierValue) -> Result<Self, Self::Error> {
+    match value {
+      HttpStatusSpecifierValue::Code(code) => Ok(HttpStatusSpecifier::Code(code)),
+      HttpStatusSpecifierValue::Specifier(specifier) => specifier.parse(),
+    }
+  }
+}
+
+impl From<HttpStatusSpecifier> for HttpStatusSpecifierValue {
+  fn from(specifier: HttpStatusSpecifier) -> Self {
+    match specifier {
+      HttpStatusSpecifier::Code(code) => HttpStatusSpecifierValue::Code(code),
+      specifier => HttpStatusSpecifierValue::Specifier(specifier.to_string()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn can_parse_media_types() {
+    let media_type = MediaType::parse("Application/VND.API+JSON; Charset=\"UTF-8\"; ext=bulk")
+      .expect("should parse media type");
+    assert_eq!(media_type.type_name, "application");
+    assert_eq!(media_type.subtype, "vnd.api");
+    assert_eq!(media_type.suffix, Some(String::from("json")));
+    assert_eq!(
+      media_type.parameter("charset"),
+      Some(&String::from("UTF-8"))
+    );
+    assert_eq!(media_type.parameter("ext"), Some(&String::from("bulk")));
+    assert_eq!(media_type.essence(), "application/vnd.api+json");
+
+    assert!(MediaType::parse("json").is_none());
+    assert!(MediaType::parse("application/").is_none());
+    assert!(MediaType::parse("").is_none());
+  }
+
+  #[test]
+  fn can_match_content_types() {
+    assert_eq!(
+      match_content_types("application/json", "application/json; charset=utf-8"),
+      Some(MediaTypeMatch::Exact)
+    );
+    assert_eq!(
+      match_content_types("application/json; charset=utf-8", "Application/JSON"),
+      Some(MediaTypeMatch::Exact)
+    );
+    assert_eq!(
+      match_content_types(
+        "application/json; charset=utf-8",
+        "application/json; Charset=UTF-8"
+      ),
+      Some(MediaTypeMatch::ExactWithParameters)
+    );
+    assert_eq!(
+      match_content_types("application/json", "application/json"),
+      Some(MediaTypeMatch::ExactWithParameters)
+    );
+    assert_eq!(
+      match_content_types("application/json", "application/problem+json"),
+      Some(MediaTypeMatch::StructuredSuffix)
+    );
+    assert_eq!(
+      match_content_types("application/*+json", "application/problem+json"),
+      Some(MediaTypeMatch::StructuredSuffix)
+    );
+    assert_eq!(
+      match_content_types("text/*", "text/plain"),
+      Some(MediaTypeMatch::AnySubtype)
+    );
+    assert_eq!(
+      match_content_types("*/*", "image/png"),
+      Some(MediaTypeMatch::AnyType)
+    );
+
+    assert_eq!(match_content_types("application/json", "text/json"), None);
+    assert_eq!(
+      match_content_types("application/problem+json", "application/json"),
+      None
+    );
+    assert_eq!(
+      match_content_types("application/json; version=2", "application/json; version=1"),
+      None
+    );
+    assert_eq!(
+      match_content_types("application/json; version=2", "application/json"),
+      None
+    );
+    assert_eq!(
+      match_content_types("not a media type", "not a media type"),
+      Some(MediaTypeMatch::ExactWithParameters)
+    );
+
+    assert!(MediaTypeMatch::ExactWithParameters > MediaTypeMatch::Exact);
+    assert!(MediaTypeMatch::Exact > MediaTypeMatch::StructuredSuffix);
+    assert!(MediaTypeMatch::StructuredSuffix > MediaTypeMatch::AnySubtype);
+    assert!(MediaTypeMatch::AnySubtype > MediaTypeMatch::AnyType);
+  }
+
+  #[test]
+  fn best_content_type_match_prefers_the_first_of_equal_matches() {
+    let documented = vec![
+      ("text/*", "first"),
+      ("application/json", "json"),
+      ("text/*; charset=utf-8", "second"),
+    ];
+
+    assert_eq!(
+      best_content_type_match(documented.clone(), "text/plain"),
+      Some((MediaTypeMatch::AnySubtype, "first"))
+    );
+    assert_eq!(
+      best_content_type_match(documented, "application/json; charset=utf-8"),
+      Some((MediaTypeMatch::Exact, "json"))
+    );
+  }
+
+  #[test]
+  fn can_match_status_codes() {
+    let range: HttpStatusRange = "4XX".parse().expect("should parse status code range");
+    assert!(range.contains(404));
+    assert!(!range.contains(500));
+    assert_eq!(
+      "5xx".parse::<HttpStatusRange>().map(|range| range.class()),
+      Ok(5)
+    );
+    assert!("6XX".parse::<HttpStatusRange>().is_err());
+    assert!("40X".parse::<HttpStatusRange>().is_err());
+
+    assert_eq!(
+      "default".parse::<HttpStatusSpecifier>(),
+      Ok(HttpStatusSpecifier::Default)
+    );
+    assert_eq!(
+      "201".parse::<HttpStatusSpecifier>(),
+      Ok(HttpStatusSpecifier::Code(201))
+    );
+    assert_eq!(HttpStatusSpecifier::Range(range).to_string(), "4XX");
+
+    assert_eq!(
+      HttpStatusSpecifier::Code(404).match_status_code(404),
+      Some(HttpStatusMatch::Exact)
+    );
+    assert_eq!(
+      HttpStatusSpecifier::Range(range).match_status_code(404),
+      Some(HttpStatusMatch::Range)
+    );
+    assert_eq!(
+      HttpStatusSpecifier::Default.match_status_code(404),
+      Some(HttpStatusMatch::Default)
+    );
+    assert_eq!(HttpStatusSpecifier::Code(400).match_status_code(404), None);
+    assert_eq!(
+      HttpStatusSpecifier::Range(range).match_status_code(200),
+      None
+    );
+
+    assert!(HttpStatusMatch::Exact > HttpStatusMatch::Range);
+    assert!(HttpStatusMatch::Range > HttpStatusMatch::Default);
+  }
+}
@@ -9,6 +9,10 @@ pub mod shape;
 pub trait SpecIdGenerator {
   fn generate_id(&mut self, prefix: &str) -> String;
 
+  fn path(&mut self) -> String {
+    self.generate_id("path_")
+  }
+
   fn field(&mut self) -> String {
     self.generate_id("field_")
   }
@@ -25,6 +29,10 @@ pub trait SpecIdGenerator {
     self.generate_id("response_")
   }
 
+  fn header_param(&mut self) -> String {
+    self.generate_id("header_param_")
+  }
+
   fn shape(&mut self) -> String {
     self.generate_id("shape_")
   }
@@ -32,6 +40,10 @@ pub trait SpecIdGenerator {
   fn shape_param(&mut self) -> String {
     self.generate_id("shape_param_")
   }
+
+  fn shape_constraint(&mut self) -> String {
+    self.generate_id("shape_constraint_")
+  }
 }
 
 #[derive(Debug, Serialize)]
@@ -1,10 +1,15 @@
+use crate::state::body::{BodyDescriptor, NumberValue};
+use chrono::{DateTime, NaiveDate};
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
 
 pub type ShapeId = String;
 pub type ShapeIdRef<'a> = &'a str;
 pub type FieldId = String;
 pub type ShapeParameterId = String;
 pub type ShapeParameterIdRef<'a> = &'a str;
+pub type ShapeConstraintId = String;
 ////////////////////////////////////////////////////////////////////////////////
 #[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
 pub enum FieldShapeDescriptor {
@@ -59,7 +64,11 @@ pub enum ParameterShapeDescriptor {
 }
 #[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct ProviderInField {}
+pub struct ProviderInField {
+  pub field_id: FieldId,
+  pub provider_descriptor: ProviderDescriptor,
+  pub consuming_parameter_id: ShapeParameterId,
+}
 
 #[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -84,7 +93,9 @@ impl Default for ProviderDescriptor {
 
 #[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct ParameterProvider {}
+pub struct ParameterProvider {
+  pub shape_parameter_id: ShapeParameterId,
+}
 
 #[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -97,7 +108,7 @@ pub struct ShapeProvider {
 pub struct NoProvider {}
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Debug, Serialize, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Clone, Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Deserialize)]
 pub enum ShapeKind {
   ObjectKind,
   ListKind,
@@ -177,12 +188,21 @@ impl ShapeKind {
       Self::ListKind => Some(ShapeKindParameterDescriptor {
         shape_parameter_id: "$listItem",
       }),
+      Self::MapKind => Some(ShapeKindParameterDescriptor {
+        shape_parameter_id: "$mapValue",
+      }),
       Self::NullableKind => Some(ShapeKindParameterDescriptor {
         shape_parameter_id: "$nullableInner",
       }),
       Self::OptionalKind => Some(ShapeKindParameterDescriptor {
         shape_parameter_id: "$optionalInner",
       }),
+      Self::IdentifierKind => Some(ShapeKindParameterDescriptor {
+        shape_parameter_id: "$identifierInner",
+      }),
+      Self::ReferenceKind => Some(ShapeKindParameterDescriptor {
+        shape_parameter_id: "$referenceTarget",
+      }),
       _ => None,
     }
   }
@@ -194,3 +214,148 @@ pub struct ShapeKindDescriptor {
   pub base_shape_id: &'static str,
   pub name: &'static str,
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
+pub enum ShapeConstraintDescriptor {
+  StringFormat {
+    format: StringFormat,
+  },
+  EnumValues {
+    values: Vec<JsonValue>,
+  },
+  NumberRange {
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+  },
+  Integer {},
+}
+
+impl ShapeConstraintDescriptor {
+  /// Whether the constraint can be set on shapes of a kind
+  pub fn applies_to(&self, shape_kind: &ShapeKind) -> bool {
+    match self {
+      Self::StringFormat { .. } => matches!(shape_kind, ShapeKind::StringKind),
+      Self::EnumValues { .. } => {
+        matches!(shape_kind, ShapeKind::StringKind | ShapeKind::NumberKind)
+      }
+      Self::NumberRange { .. } | Self::Integer {} => matches!(shape_kind, ShapeKind::NumberKind),
+    }
+  }
+
+  /// Whether all observed values of a primitive satisfy the constraint. Bodies of another type
+  /// or without known values can't violate it.
+  pub fn accepts(&self, body: &BodyDescriptor) -> bool {
+    match body {
+      BodyDescriptor::String(values) => values.iter().all(|value| self.accepts_string(value)),
+      BodyDescriptor::Number(values) => values.iter().all(|value| self.accepts_number(value)),
+      _ => true,
+    }
+  }
+
+  fn accepts_string(&self, value: &str) -> bool {
+    match self {
+      Self::StringFormat { format } => format.accepts(value),
+      Self::EnumValues { values } => values
+        .iter()
+        .any(|enum_value| enum_value.as_str() == Some(value)),
+      _ => true,
+    }
+  }
+
+  fn accepts_number(&self, value: &NumberValue) -> bool {
+    let number = match value.as_f64() {
+      Some(number) => number,
+      None => return true,
+    };
+
+    match self {
+      Self::EnumValues { values } => values
+        .iter()
+        .any(|enum_value| enum_value.as_f64() == Some(number)),
+      Self::NumberRange { minimum, maximum } => {
+        minimum.iter().all(|minimum| number >= *minimum)
+          && maximum.iter().all(|maximum| number <= *maximum)
+      }
+      Self::Integer {} => value.is_integer(),
+      _ => true,
+    }
+  }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Selects the branch of a one-of shape by the value of a field of object bodies, like `type`
+/// in `{"type": "card", ...}`, rather than trying every branch. Branches are identified by the
+/// shape parameter of the one-of they're bound to.
+#[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OneOfDiscriminatorDescriptor {
+  pub field_name: String,
+  pub mapping: BTreeMap<String, ShapeParameterId>,
+}
+
+impl OneOfDiscriminatorDescriptor {
+  /// The branch selected by an observed value of the discriminator field
+  pub fn branch_for(&self, value: &str) -> Option<&ShapeParameterId> {
+    self.mapping.get(value)
+  }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize, Clone, Copy, Hash, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum StringFormat {
+  DateTime,
+  Date,
+  Uuid,
+  Email,
+  Uri,
+}
+
+impl StringFormat {
+  /// The most specific format a string value has, if any
+  pub fn detect(value: &str) -> Option<Self> {
+    [
+      Self::DateTime,
+      Self::Date,
+      Self::Uuid,
+      Self::Email,
+      Self::Uri,
+    ]
+    .iter()
+    .find(|format| format.accepts(value))
+    .copied()
+  }
+
+  pub fn accepts(&self, value: &str) -> bool {
+    match self {
+      Self::DateTime => DateTime::parse_from_rfc3339(value).is_ok(),
+      Self::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+      Self::Uuid => value.len() == 36 && uuid::Uuid::parse_str(value).is_ok(),
+      Self::Email => {
+        let mut parts = value.splitn(2, '@');
+        let local = parts.next().unwrap_or("");
+        let domain = parts.next().unwrap_or("");
+        !local.is_empty()
+          && domain.contains('.')
+          && !domain.starts_with('.')
+          && !domain.ends_with('.')
+          && !value.contains(char::is_whitespace)
+          && !domain.contains('@')
+      }
+      Self::Uri => match value.find("://") {
+        Some(scheme_end) => {
+          let scheme = &value[..scheme_end];
+          scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+              .chars()
+              .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+            && value.len() > scheme_end + 3
+            && !value.contains(char::is_whitespace)
+        }
+        None => false,
+      },
+    }
+  }
+}
@@ -2,6 +2,9 @@ use futures::Stream;
 use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader, Lines};
 use tokio_stream::wrappers::LinesStream;
 
+#[cfg(feature = "avro")]
+pub use avro::avro;
+
 pub fn json_lines<R>(source: R) -> impl Stream<Item = Result<String, std::io::Error>>
 where
   R: AsyncRead,
@@ -9,3 +12,70 @@ where
   // 10 megabytes of capacity, to deal with unbound nature of request and response bodies
   super::json_lines(source, 10 * 1024 * 1024)
 }
+
+#[cfg(feature = "avro")]
+mod avro {
+  use crate::events::{EventLoadingError, HttpInteraction};
+  use futures::Stream;
+  use std::io;
+  use tokio::io::{AsyncRead, AsyncReadExt};
+  use tokio::sync::mpsc;
+  use tokio_stream::wrappers::ReceiverStream;
+
+  /// Stream the interactions of an avro object container file, like those written by capture
+  /// agents. Its blocks of batches are read and decoded one at a time on a blocking thread.
+  pub fn avro<R>(source: R) -> impl Stream<Item = Result<HttpInteraction, EventLoadingError>>
+  where
+    R: AsyncRead + Unpin + Send + 'static,
+  {
+    let (sender, receiver) = mpsc::channel(1024);
+    let decoding_sender = sender.clone();
+
+    let source = BlockingReader { source };
+    let decoding = tokio::task::spawn_blocking(move || {
+      let sender = decoding_sender;
+      let deserializer = HttpInteraction::from_avro();
+      let interactions = match deserializer.reader(source) {
+        Ok(interactions) => interactions,
+        Err(err) => {
+          let _ = sender.blocking_send(Err(err));
+          return;
+        }
+      };
+
+      for interaction in interactions {
+        if sender.blocking_send(interaction).is_err() {
+          break; // stream was dropped, so nobody is interested in the rest
+        }
+      }
+    });
+
+    tokio::spawn(async move {
+      if let Err(err) = decoding.await {
+        let _ = sender
+          .send(Err(EventLoadingError::from(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("avro decoding failed: {}", err),
+          ))))
+          .await;
+      }
+    });
+
+    ReceiverStream::new(receiver)
+  }
+
+  /// Reads an async source by blocking on it, for the decoder which can only read synchronously.
+  /// It's only to be read from blocking threads, like those of `spawn_blocking`.
+  struct BlockingReader<R> {
+    source: R,
+  }
+
+  impl<R> io::Read for BlockingReader<R>
+  where
+    R: AsyncRead + Unpin,
+  {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      futures::executor::block_on(self.source.read(buf))
+    }
+  }
+}
@@ -11,6 +11,7 @@ use tokio_stream::wrappers::LinesStream;
 use tokio_util::codec::{Encoder, FramedWrite};
 
 pub mod diff;
+pub mod har;
 pub mod http_interaction;
 pub mod shape_diff_affordances;
 pub mod spec_chunks;
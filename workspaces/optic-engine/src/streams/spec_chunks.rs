@@ -1,8 +1,10 @@
 use super::{spec_events, JsonLineEncoder, JsonLineEncoderError};
-use crate::events::{EventLoadingError, SpecChunkEvent};
+use crate::events::{
+  EventLoadingError, SpecChunkEvent, SpecChunksEventsReader, SpecEventsReadError,
+};
 use crate::{SpecAssemblerProjection, SpecEvent};
 use fs::{read_dir, read_to_string};
-use futures::{sink::Sink, SinkExt};
+use futures::{sink::Sink, SinkExt, Stream};
 use serde_json;
 use std::path::Path;
 use std::{convert::TryFrom, ffi::OsString, path::PathBuf};
@@ -52,6 +54,15 @@ pub async fn from_api_dir(
   Ok(chunks)
 }
 
+/// Stream the events of a directory of spec chunks in assembled order, reading the chunks as
+/// they're needed rather than loading all of them first
+pub fn stream_from_api_dir(
+  path: impl AsRef<Path>,
+) -> impl Stream<Item = Result<SpecEvent, SpecEventsReadError>> {
+  let path = path.as_ref().to_owned();
+  spec_events::from_events_reader(move || SpecChunksEventsReader::from_api_dir(path))
+}
+
 pub async fn from_root_api_file(
   path: impl AsRef<Path>,
 ) -> Result<Vec<SpecChunkEvent>, SpecChunkLoaderError> {
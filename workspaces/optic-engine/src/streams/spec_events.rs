@@ -1,6 +1,11 @@
 use super::JsonLineEncoder;
-use crate::events::{EventLoadingError, SpecChunkEvent, SpecEvent};
-use crate::projections::{SpecAssemblerError, SpecAssemblerProjection};
+use crate::events::{
+  EventLoadingError, SpecChunkEvent, SpecEvent, SpecEventsReadError, SpecEventsReader,
+};
+use crate::projections::{
+  SnapshottableProjection, SpecAssemblerError, SpecAssemblerProjection, SpecProjection,
+  SpecProjectionSnapshot,
+};
 use futures::sink::{Sink, SinkExt};
 use futures::stream::{Stream, StreamExt};
 use serde::Serialize;
@@ -8,8 +13,9 @@ use std::path::Path;
 use tokio::io::{
   AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, Lines,
 };
+use tokio::sync::mpsc;
 use tokio::{fs::read_to_string, io::AsyncReadExt};
-use tokio_stream::wrappers::LinesStream;
+use tokio_stream::wrappers::{LinesStream, ReceiverStream};
 use tokio_util::codec::FramedWrite;
 
 // TODO: return a stream instead of a Vec
@@ -21,6 +27,85 @@ pub async fn from_file(path: impl AsRef<Path>) -> Result<Vec<SpecEvent>, EventLo
   Ok(events)
 }
 
+/// Stream the events of a spec file as they are parsed, rather than loading all of them first
+pub fn stream_from_file(
+  path: impl AsRef<Path>,
+) -> impl Stream<Item = Result<SpecEvent, SpecEventsReadError>> {
+  let path = path.as_ref().to_owned();
+  from_events_reader(move || SpecEventsReader::from_file(path))
+}
+
+/// Read events on a blocking thread, buffering only a limited amount of them for the stream
+pub(crate) fn from_events_reader<R, F>(
+  open_reader: F,
+) -> impl Stream<Item = Result<SpecEvent, SpecEventsReadError>>
+where
+  F: FnOnce() -> Result<R, SpecEventsReadError> + Send + 'static,
+  R: Iterator<Item = Result<SpecEvent, SpecEventsReadError>>,
+{
+  let (sender, receiver) = mpsc::channel(1024);
+
+  tokio::task::spawn_blocking(move || {
+    let events = match open_reader() {
+      Ok(events) => events,
+      Err(err) => {
+        let _ = sender.blocking_send(Err(err));
+        return;
+      }
+    };
+
+    for event in events {
+      if sender.blocking_send(event).is_err() {
+        break; // stream was dropped, so nobody is interested in the rest
+      }
+    }
+  });
+
+  ReceiverStream::new(receiver)
+}
+
+/// Build a spec projection by folding over a stream of events, stopping at the first error
+pub async fn into_spec_projection<E>(
+  events: impl Stream<Item = Result<SpecEvent, E>>,
+) -> Result<SpecProjection, E> {
+  futures::pin_mut!(events);
+
+  let mut projection = SpecProjection::default();
+  while let Some(event) = events.next().await {
+    cqrs_core::Aggregate::apply(&mut projection, event?);
+  }
+  Ok(projection)
+}
+
+/// Build a projection that can be snapshotted by folding over a stream of events, stopping at the
+/// first error
+pub async fn into_snapshottable_projection<E>(
+  events: impl Stream<Item = Result<SpecEvent, E>>,
+) -> Result<SnapshottableProjection, E> {
+  futures::pin_mut!(events);
+
+  let mut snapshottable = SnapshottableProjection::default();
+  while let Some(event) = events.next().await {
+    snapshottable.apply(event?);
+  }
+  Ok(snapshottable)
+}
+
+/// Restore a snapshotted projection from a stream of all events, applying only those following
+/// the snapshot. Resolves to `None` if the snapshot is stale, see `SpecProjectionSnapshot::restore`.
+pub async fn restore_snapshot<E>(
+  snapshot: SpecProjectionSnapshot,
+  events: impl Stream<Item = Result<SpecEvent, E>>,
+) -> Result<Option<SnapshottableProjection>, E> {
+  futures::pin_mut!(events);
+
+  let mut restoring = snapshot.into_restoring();
+  while let Some(event) = events.next().await {
+    restoring.apply(event?);
+  }
+  Ok(restoring.finish())
+}
+
 pub async fn from_spec_chunks(
   chunks: Vec<SpecChunkEvent>,
 ) -> Result<Vec<SpecEvent>, SpecAssemblerError> {
@@ -1,5 +1,7 @@
+use futures::TryStreamExt;
+use insta::assert_debug_snapshot;
 use optic_engine::streams;
-use optic_engine::HttpInteraction;
+use optic_engine::{BodyDescriptor, HttpInteraction, TaggedInput};
 use tokio_stream::StreamExt;
 
 // fn can_async_read_stream_of_newline_delimited_json() {
@@ -13,7 +15,6 @@ use tokio_stream::StreamExt;
 
 #[cfg(feature = "avro")]
 #[test]
-#[should_panic] // avro deserializing doesn't work yet
 fn can_read_avro_serialized_interactions() {
   let fd = std::fs::File::open(
     std::env::current_dir()
@@ -26,12 +27,74 @@ fn can_read_avro_serialized_interactions() {
 
   let interactions: Vec<_> = HttpInteraction::from_avro()
     .reader(fd)
-    .expect("interactions from capture should deserialize")
-    .collect();
+    .expect("capture header should be readable")
+    .collect::<Result<_, _>>()
+    .expect("interactions from capture should deserialize");
 
   assert!(interactions.len() > 0);
 }
 
+#[cfg(feature = "avro")]
+#[tokio::test]
+async fn can_async_read_stream_of_avro_serialized_interactions() {
+  let fd = tokio::fs::File::open(
+    std::env::current_dir()
+      .unwrap()
+      .join("tests/fixtures/ergast-captures/0.optic-capture.avro")
+      .to_str()
+      .unwrap(),
+  )
+  .await
+  .expect("test capture file should exist");
+
+  let interactions: Vec<HttpInteraction> = streams::http_interaction::avro(fd)
+    .try_collect()
+    .await
+    .expect("interactions from capture should deserialize");
+
+  // the capture holds the first of the interactions of its json lines counterpart
+  let json_lines_fd = tokio::fs::File::open(
+    std::env::current_dir()
+      .unwrap()
+      .join("tests/fixtures/ergast-captures/ergast-simulated-traffic.jsonl")
+      .to_str()
+      .unwrap(),
+  )
+  .await
+  .expect("test fixture should exist");
+  let json_lines_uuids = streams::http_interaction::json_lines(json_lines_fd)
+    .map(|line| {
+      let tagged: TaggedInput<HttpInteraction> = serde_json::from_str(&line.unwrap()).unwrap();
+      tagged.into_input().uuid
+    })
+    .collect::<Vec<_>>()
+    .await;
+
+  let uuids = interactions
+    .into_iter()
+    .map(|interaction| interaction.uuid)
+    .collect::<Vec<_>>();
+  assert!(!uuids.is_empty());
+  assert_eq!(uuids, json_lines_uuids[..uuids.len()]);
+}
+
+#[cfg(feature = "avro")]
+#[tokio::test]
+async fn avro_blocks_of_invalid_sizes_are_rejected() {
+  // a header without metadata, followed by a block claiming a batch in -1 bytes, or -1 batches
+  let marker = [7u8; 16];
+  let header = [&b"Obj\x01"[..], &[0u8], &marker[..]].concat();
+  for block_counts in [[2u8, 1u8], [1u8, 0u8]] {
+    let avro_bytes = [&header[..], &block_counts[..], &marker[..]].concat();
+
+    let result: Result<Vec<HttpInteraction>, _> =
+      streams::http_interaction::avro(std::io::Cursor::new(avro_bytes))
+        .try_collect()
+        .await;
+    assert!(result.is_err());
+  }
+}
+
 #[tokio::test]
 async fn can_async_read_stream_of_newline_delimited_json() {
   let fd = tokio::fs::File::open(
@@ -65,3 +128,53 @@ async fn can_async_read_stream_of_newline_delimited_json() {
   let remaining_events = lines.take(3).collect::<Vec<_>>().await;
   assert_eq!(remaining_events.len(), 2);
 }
+
+#[tokio::test]
+async fn can_read_interactions_from_har() {
+  let fd = tokio::fs::File::open(
+    std::env::current_dir()
+      .unwrap()
+      .join("tests/fixtures/todos.har")
+      .to_str()
+      .unwrap(),
+  )
+  .await
+  .expect("test fixture should exist");
+
+  let interactions: Vec<HttpInteraction> = streams::har::interactions(fd)
+    .try_collect()
+    .await
+    .expect("entries of HAR should be read as interactions");
+
+  // the blocked request without a response is skipped
+  assert_eq!(interactions.len(), 3);
+  assert_debug_snapshot!(
+    "can_read_interactions_from_har__interactions",
+    &interactions
+  );
+
+  let listing = &interactions[0];
+  assert_eq!(listing.request.path, "/todos");
+  assert_eq!(
+    listing.response.headers().get("x-total-count").unwrap(),
+    "12"
+  );
+  assert!(matches!(
+    Option::<BodyDescriptor>::from(&listing.request.query),
+    Some(BodyDescriptor::Object(_))
+  ));
+  assert!(matches!(
+    Option::<BodyDescriptor>::from(&listing.response.body.value),
+    Some(BodyDescriptor::Array(_))
+  ));
+
+  let login = &interactions[2];
+  assert_eq!(
+    login.request.body.value.as_text.as_deref(),
+    Some("username=jane&remember=on")
+  );
+  assert_eq!(
+    login.response.body.value.as_json_string.as_deref(),
+    Some(r#"{"token":"abc"}"#)
+  );
+}
@@ -1,7 +1,8 @@
 use futures::sink::SinkExt;
 use insta::assert_debug_snapshot;
 use optic_engine::{
-  diff_interaction, streams, DiffInteractionConfig, HttpInteraction, SpecEvent, SpecProjection,
+  diff_interaction, streams, DiffInteractionConfig, HttpInteraction, InteractionDiffResult,
+  SpecEvent, SpecProjection,
 };
 use petgraph::dot::Dot;
 use serde_json::json;
@@ -331,3 +332,400 @@ fn can_handle_no_request_and_response_body() {
   );
   assert_eq!(results.len(), 0);
 }
+
+#[test]
+fn can_yield_unmatched_headers() {
+  let events: Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"xyz"}},
+      {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"GET"}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_1", "httpStatusCode":200,"pathId":"path_1","httpMethod":"GET"}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"optional_shape_1","baseShapeId":"$optional","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"HeaderParameterAdded":{"parameterId":"header_1","requestId":"request_1","name":"Authorization"}},
+      {"HeaderParameterShapeSet":{"parameterId":"header_1","parameterDescriptor":{"shapeId":"string_shape_1","isRemoved":false}}},
+      {"HeaderParameterAdded":{"parameterId":"header_2","requestId":"request_1","name":"X-Request-Id"}},
+      {"HeaderParameterShapeSet":{"parameterId":"header_2","parameterDescriptor":{"shapeId":"optional_shape_1","isRemoved":false}}},
+      {"ResponseHeaderParameterAdded":{"parameterId":"header_3","responseId":"response_1","name":"X-RateLimit-Remaining"}},
+      {"ResponseHeaderParameterAdded":{"parameterId":"header_4","responseId":"response_1","name":"Cache-Control"}},
+    ]),
+  ).expect("should be able to deserialize header events as spec events");
+
+  let spec_projection = SpecProjection::from(events);
+
+  let interaction = HttpInteraction::from_json_str(
+    r#"{
+    "uuid": "5",
+    "request": {
+      "host": "localhost",
+      "method": "GET",
+      "path": "/xyz",
+      "query": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "headers": {
+        "asJsonString": "[{\"name\":\"accept\",\"value\":\"*/*\"}]",
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": null,
+        "value": {
+          "asJsonString": null,
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "response": {
+      "statusCode": 200,
+      "headers": {
+        "asJsonString": "[{\"name\":\"X-Ratelimit-Limit\",\"value\":\"100\"},{\"name\":\"cache-control\",\"value\":\"no-cache\"}]",
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": null,
+        "value": {
+          "asJsonString": null,
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "tags": []
+  }"#,
+  )
+  .expect("example http interaction should deserialize");
+
+  let results = diff_interaction(
+    &spec_projection,
+    interaction.clone(),
+    &DiffInteractionConfig::default(),
+  );
+
+  assert_debug_snapshot!("can_yield_unmatched_headers__results", results);
+  assert_eq!(results.len(), 2);
+
+  // headers are ignored by their name, like top-level keys of a json trail
+  let config = DiffInteractionConfig::from_json_str(
+    r#"{"ignoreJsonTrails":["authorization","X-RateLimit-Remaining"]}"#,
+  )
+  .expect("example diff config should deserialize");
+  assert_eq!(
+    diff_interaction(&spec_projection, interaction, &config).len(),
+    0
+  );
+}
+
+#[test]
+fn can_yield_unmatched_header_shapes() {
+  let events: Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"xyz"}},
+      {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"GET"}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_1", "httpStatusCode":200,"pathId":"path_1","httpMethod":"GET"}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"HeaderParameterAdded":{"parameterId":"header_1","requestId":"request_1","name":"X-Page"}},
+      {"HeaderParameterShapeSet":{"parameterId":"header_1","parameterDescriptor":{"shapeId":"number_shape_1","isRemoved":false}}},
+      {"ResponseHeaderParameterAdded":{"parameterId":"header_2","responseId":"response_1","name":"X-RateLimit-Remaining"}},
+      {"HeaderParameterShapeSet":{"parameterId":"header_2","parameterDescriptor":{"shapeId":"number_shape_1","isRemoved":false}}},
+    ]),
+  ).expect("should be able to deserialize header events as spec events");
+
+  let spec_projection = SpecProjection::from(events);
+
+  let interaction = HttpInteraction::from_json_str(
+    r#"{
+    "uuid": "5",
+    "request": {
+      "host": "localhost",
+      "method": "GET",
+      "path": "/xyz",
+      "query": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "headers": {
+        "asJsonString": "[{\"name\":\"x-page\",\"value\":\"last\"}]",
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": null,
+        "value": {
+          "asJsonString": null,
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "response": {
+      "statusCode": 200,
+      "headers": {
+        "asJsonString": "[{\"name\":\"X-RateLimit-Remaining\",\"value\":\"42\"}]",
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": null,
+        "value": {
+          "asJsonString": null,
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "tags": []
+  }"#,
+  )
+  .expect("example http interaction should deserialize");
+
+  let results = diff_interaction(
+    &spec_projection,
+    interaction,
+    &DiffInteractionConfig::default(),
+  );
+
+  // the response header spells out a number, the request header doesn't
+  assert_debug_snapshot!("can_yield_unmatched_header_shapes__results", results);
+  assert_eq!(results.len(), 1);
+  assert!(matches!(
+    results[0],
+    InteractionDiffResult::UnmatchedRequestHeaderShape(_)
+  ));
+}
+
+#[test]
+fn can_yield_unmatched_path_parameter_shape() {
+  let events: Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"todos"}},
+      {"PathParameterAdded":{"pathId":"path_2","parentPathId":"path_1","name":"todoId"}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"PathParameterShapeSet":{"pathId":"path_2","shapeDescriptor":{"shapeId":"number_shape_1","isRemoved":false}}},
+      {"RequestAdded":{"requestId":"request_1","pathId":"path_2","httpMethod":"GET"}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_1", "httpStatusCode":200,"pathId":"path_2","httpMethod":"GET"}},
+    ]),
+  ).expect("should be able to deserialize path parameter events as spec events");
+
+  let spec_projection = SpecProjection::from(events);
+
+  let interaction_with_path = |path: &str| {
+    serde_json::from_value::<HttpInteraction>(json!({
+      "uuid": "5",
+      "request": {
+        "host": "localhost",
+        "method": "GET",
+        "path": path,
+        "query": { "asJsonString": null, "asText": null, "asShapeHashBytes": null },
+        "headers": { "asJsonString": null, "asText": null, "asShapeHashBytes": null },
+        "body": {
+          "contentType": null,
+          "value": { "asJsonString": null, "asText": null, "asShapeHashBytes": null }
+        }
+      },
+      "response": {
+        "statusCode": 200,
+        "headers": { "asJsonString": null, "asText": null, "asShapeHashBytes": null },
+        "body": {
+          "contentType": null,
+          "value": { "asJsonString": null, "asText": null, "asShapeHashBytes": null }
+        }
+      },
+      "tags": []
+    }))
+    .expect("example http interaction should deserialize")
+  };
+
+  let matching_results = diff_interaction(
+    &spec_projection,
+    interaction_with_path("/todos/42"),
+    &DiffInteractionConfig::default(),
+  );
+  assert_eq!(matching_results.len(), 0);
+
+  let results = diff_interaction(
+    &spec_projection,
+    interaction_with_path("/todos/first"),
+    &DiffInteractionConfig::default(),
+  );
+
+  assert_debug_snapshot!("can_yield_unmatched_path_parameter_shape__results", results);
+  assert_eq!(results.len(), 1);
+}
+
+#[test]
+fn can_match_content_types_with_parameters_and_wildcards() {
+  let events: Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"xyz"}},
+      {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"POST"}},
+      {"RequestAdded":{"requestId":"request_2","pathId":"path_1","httpMethod":"POST"}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_1", "httpStatusCode":200,"pathId":"path_1","httpMethod":"POST"}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"RequestBodySet": {"requestId": "request_1", "bodyDescriptor":{"httpContentType":"application/*","shapeId":"number_shape_1","isRemoved":false}}},
+      {"RequestBodySet": {"requestId": "request_2", "bodyDescriptor":{"httpContentType":"application/json","shapeId":"string_shape_1","isRemoved":false}}},
+      {"ResponseBodySet": {"responseId": "response_1", "bodyDescriptor":{"httpContentType":"*/*","shapeId":"string_shape_1","isRemoved":false}}}
+    ]),
+  ).expect("should be able to deserialize body events as spec events");
+
+  let spec_projection = SpecProjection::from(events);
+
+  let interaction = HttpInteraction::from_json_str(
+    r#"{
+    "uuid": "5",
+    "request": {
+      "host": "localhost",
+      "method": "POST",
+      "path": "/xyz",
+      "query": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "headers": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": "Application/JSON; charset=utf-8",
+        "value": {
+          "asJsonString": "\"whatever\"",
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "response": {
+      "statusCode": 200,
+      "headers": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": "text/plain",
+        "value": {
+          "asJsonString": "\"whatever\"",
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "tags": []
+  }"#,
+  )
+  .expect("example http interaction should deserialize");
+
+  let results = diff_interaction(
+    &spec_projection,
+    interaction,
+    &DiffInteractionConfig::default(),
+  );
+
+  assert_debug_snapshot!(
+    "can_match_content_types_with_parameters_and_wildcards__results",
+    results
+  );
+  assert_eq!(results.len(), 0);
+}
+
+#[test]
+fn can_honour_diff_config() {
+  let events: Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"xyz"}},
+      {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"POST"}},
+      {"ResponseAddedByPathAndMethod":{"responseId":"response_1", "httpStatusCode":200,"pathId":"path_1","httpMethod":"POST"}},
+      {"ShapeAdded":{"shapeId":"object_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"object_2","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_2","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"object_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_shape_1"}}}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"object_1","name":"metadata","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"object_2"}}}},
+      {"FieldAdded":{"fieldId":"field_3","shapeId":"object_2","name":"version","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_3","shapeId":"string_shape_2"}}}},
+      {"RequestBodySet": {"requestId": "request_1", "bodyDescriptor":{"httpContentType":"application/json","shapeId":"object_1","isRemoved":false}}}
+    ]),
+  ).expect("should be able to deserialize shape events as spec events");
+
+  let spec_projection = SpecProjection::from(events);
+
+  let interaction = HttpInteraction::from_json_str(
+    r#"{
+    "uuid": "5",
+    "request": {
+      "host": "localhost",
+      "method": "POST",
+      "path": "/xyz",
+      "query": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "headers": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": "application/json",
+        "value": {
+          "asJsonString": "{\"name\":\"a name\",\"metadata\":{\"version\":3},\"extra\":true}",
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "response": {
+      "statusCode": 200,
+      "headers": {
+        "asJsonString": null,
+        "asText": null,
+        "asShapeHashBytes": null
+      },
+      "body": {
+        "contentType": null,
+        "value": {
+          "asJsonString": null,
+          "asText": null,
+          "asShapeHashBytes": null
+        }
+      }
+    },
+    "tags": []
+  }"#,
+  )
+  .expect("example http interaction should deserialize");
+
+  let diff_with_config = |config_json: &str| {
+    let config = DiffInteractionConfig::from_json_str(config_json)
+      .expect("example diff config should deserialize");
+    diff_interaction(&spec_projection, interaction.clone(), &config)
+  };
+
+  let strict_results = diff_with_config("{}");
+  assert_debug_snapshot!("can_honour_diff_config__strict_results", strict_results);
+  assert_eq!(strict_results.len(), 2);
+
+  let lenient_results = diff_with_config(r#"{"strict":false}"#);
+  assert_debug_snapshot!("can_honour_diff_config__lenient_results", lenient_results);
+  assert_eq!(lenient_results.len(), 1);
+
+  assert_eq!(
+    diff_with_config(r#"{"strict":false,"ignoreJsonTrails":["*.version"]}"#).len(),
+    0
+  );
+  assert_eq!(
+    diff_with_config(r#"{"ignoreJsonTrails":["metadata","extra"]}"#).len(),
+    0
+  );
+  assert_eq!(diff_with_config(r#"{"ignorePaths":["/*"]}"#).len(), 0);
+  assert_eq!(diff_with_config(r#"{"ignoreMethods":["post"]}"#).len(), 0);
+  assert_eq!(diff_with_config(r#"{"ignoreMethods":["GET"]}"#).len(), 2);
+}
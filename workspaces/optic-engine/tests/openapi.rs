@@ -0,0 +1,477 @@
+#![recursion_limit = "2560"]
+
+use insta::assert_json_snapshot;
+use optic_engine::{
+  append_batch_to_spec, export_openapi, import_openapi, parse_openapi_document, CommandContext,
+  OpenApiConfig, OpenApiVersion, SpecEvent, SpecIdGenerator, SpecProjection,
+};
+use serde_json::json;
+
+fn users_spec_events() -> Vec<SpecEvent> {
+  serde_json::from_value(json!([
+    {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+    {"PathParameterAdded":{"pathId":"path_2","parentPathId":"path_1","name":"userId"}},
+    {"RequestAdded":{"requestId":"request_1","pathId":"path_2","httpMethod":"GET"}},
+    {"ResponseAddedByPathAndMethod":{"responseId":"response_1","httpStatusCode":200,"pathId":"path_2","httpMethod":"GET"}},
+    {"ContributionAdded":{"id":"path_2.GET","key":"purpose","value":"Get a user"}},
+    {"ContributionAdded":{"id":"path_2.GET","key":"description","value":"Looks up a single user by id"}},
+
+    {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeAdded":{"shapeId":"optional_shape_1","baseShapeId":"$optional","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"optional_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_shape_1"}},"consumingParameterId":"$optionalInner"}}}},
+    {"HeaderParameterAdded":{"parameterId":"header_1","requestId":"request_1","name":"Authorization"}},
+    {"HeaderParameterShapeSet":{"parameterId":"header_1","parameterDescriptor":{"shapeId":"string_shape_1","isRemoved":false}}},
+    {"HeaderParameterAdded":{"parameterId":"header_2","requestId":"request_1","name":"X-Request-Id"}},
+    {"HeaderParameterShapeSet":{"parameterId":"header_2","parameterDescriptor":{"shapeId":"optional_shape_1","isRemoved":false}}},
+    {"ResponseHeaderParameterAdded":{"parameterId":"header_3","responseId":"response_1","name":"Cache-Control"}},
+
+    {"QueryParametersAdded":{"queryParametersId":"query_1","pathId":"path_2","httpMethod":"GET"}},
+    {"ShapeAdded":{"shapeId":"query_object_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeAdded":{"shapeId":"boolean_shape_1","baseShapeId":"$boolean","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeAdded":{"shapeId":"optional_shape_2","baseShapeId":"$optional","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"optional_shape_2","providerDescriptor":{"ShapeProvider":{"shapeId":"boolean_shape_1"}},"consumingParameterId":"$optionalInner"}}}},
+    {"FieldAdded":{"fieldId":"query_field_1","shapeId":"query_object_1","name":"verbose","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"query_field_1","shapeId":"optional_shape_2"}}}},
+    {"QueryParametersShapeSet":{"queryParametersId":"query_1","shapeDescriptor":{"shapeId":"query_object_1","isRemoved":false}}},
+
+    {"ShapeAdded":{"shapeId":"user_object_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeAdded":{"shapeId":"id_string_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeConstraintAdded":{"shapeConstraintId":"shape_constraint_1","shapeId":"id_string_1","descriptor":{"StringFormat":{"format":"uuid"}}}},
+    {"FieldAdded":{"fieldId":"user_field_1","shapeId":"user_object_1","name":"id","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"user_field_1","shapeId":"id_string_1"}}}},
+    {"ContributionAdded":{"id":"user_field_1","key":"description","value":"Unique id of the user"}},
+
+    {"ShapeAdded":{"shapeId":"nickname_string_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeAdded":{"shapeId":"nullable_shape_1","baseShapeId":"$nullable","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"nullable_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"nickname_string_1"}},"consumingParameterId":"$nullableInner"}}}},
+    {"FieldAdded":{"fieldId":"user_field_2","shapeId":"user_object_1","name":"nickname","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"user_field_2","shapeId":"nullable_shape_1"}}}},
+
+    {"ShapeAdded":{"shapeId":"age_number_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeConstraintAdded":{"shapeConstraintId":"shape_constraint_2","shapeId":"age_number_1","descriptor":{"Integer":{}}}},
+    {"ShapeAdded":{"shapeId":"optional_shape_3","baseShapeId":"$optional","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"optional_shape_3","providerDescriptor":{"ShapeProvider":{"shapeId":"age_number_1"}},"consumingParameterId":"$optionalInner"}}}},
+    {"FieldAdded":{"fieldId":"user_field_3","shapeId":"user_object_1","name":"age","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"user_field_3","shapeId":"optional_shape_3"}}}},
+
+    {"ShapeAdded":{"shapeId":"oneof_shape_1","baseShapeId":"$oneOf","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterAdded":{"shapeParameterId":"oneof_parameter_1","shapeId":"oneof_shape_1","name":"","shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"oneof_parameter_1"}}}},
+    {"ShapeParameterAdded":{"shapeParameterId":"oneof_parameter_2","shapeId":"oneof_shape_1","name":"","shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"oneof_parameter_2"}}}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_shape_1"}},"consumingParameterId":"oneof_parameter_1"}}}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"boolean_shape_1"}},"consumingParameterId":"oneof_parameter_2"}}}},
+    {"ShapeAdded":{"shapeId":"list_1","baseShapeId":"$list","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"list_1","providerDescriptor":{"ShapeProvider":{"shapeId":"oneof_shape_1"}},"consumingParameterId":"$listItem"}}}},
+    {"FieldAdded":{"fieldId":"user_field_4","shapeId":"user_object_1","name":"tags","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"user_field_4","shapeId":"list_1"}}}},
+
+    {"ShapeAdded":{"shapeId":"map_1","baseShapeId":"$map","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"map_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_shape_1"}},"consumingParameterId":"$mapValue"}}}},
+    {"FieldAdded":{"fieldId":"user_field_5","shapeId":"user_object_1","name":"metadata","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"user_field_5","shapeId":"map_1"}}}},
+
+    {"ShapeAdded":{"shapeId":"team_object_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"FieldAdded":{"fieldId":"team_field_1","shapeId":"team_object_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"team_field_1","shapeId":"string_shape_1"}}}},
+    {"ShapeAdded":{"shapeId":"reference_1","baseShapeId":"$reference","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"reference_1","providerDescriptor":{"ShapeProvider":{"shapeId":"team_object_1"}},"consumingParameterId":"$referenceTarget"}}}},
+    {"ShapeAdded":{"shapeId":"nullable_shape_2","baseShapeId":"$nullable","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"nullable_shape_2","providerDescriptor":{"ShapeProvider":{"shapeId":"reference_1"}},"consumingParameterId":"$nullableInner"}}}},
+    {"FieldAdded":{"fieldId":"user_field_6","shapeId":"user_object_1","name":"team","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"user_field_6","shapeId":"nullable_shape_2"}}}},
+
+    {"ResponseBodySet":{"responseId":"response_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"user_object_1","isRemoved":false}}},
+  ]))
+  .expect("should be able to deserialize users spec events")
+}
+
+#[test]
+fn can_export_spec_as_openapi_3_0() {
+  let spec_projection = SpecProjection::from(users_spec_events());
+
+  let document = export_openapi(&spec_projection, &OpenApiConfig::default());
+
+  assert_json_snapshot!(document);
+}
+
+#[test]
+fn can_export_spec_as_openapi_3_1() {
+  let spec_projection = SpecProjection::from(users_spec_events());
+  let config = OpenApiConfig {
+    version: OpenApiVersion::V3_1,
+    ..OpenApiConfig::default()
+  };
+
+  let document = export_openapi(&spec_projection, &config);
+
+  assert_json_snapshot!(document);
+}
+
+#[test]
+fn can_export_todos_spec_as_openapi() {
+  let events = SpecEvent::from_file(
+    std::env::current_dir()
+      .unwrap()
+      .join("tests/fixtures/todos-spec.json")
+      .to_str()
+      .unwrap(),
+  )
+  .expect("todos spec should deserialize");
+  let spec_projection = SpecProjection::from(events);
+
+  let document = export_openapi(&spec_projection, &OpenApiConfig::default());
+
+  assert_json_snapshot!(document);
+}
+
+#[test]
+fn can_export_shapes_based_on_other_shapes() {
+  let events: Vec<SpecEvent> = serde_json::from_value(json!([
+    {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"todos"}},
+    {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"GET"}},
+    {"ResponseAddedByPathAndMethod":{"responseId":"response_1","httpStatusCode":200,"pathId":"path_1","httpMethod":"GET"}},
+    {"ShapeAdded":{"shapeId":"todo_shape_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"Todo"}},
+    {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"FieldAdded":{"fieldId":"field_1","shapeId":"todo_shape_1","name":"title","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_shape_1"}}}},
+    {"ShapeAdded":{"shapeId":"todo_instance_1","baseShapeId":"todo_shape_1","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ResponseBodySet":{"responseId":"response_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"todo_instance_1","isRemoved":false}}},
+  ]))
+  .expect("should be able to deserialize events");
+  let spec_projection = SpecProjection::from(events);
+
+  let document = export_openapi(&spec_projection, &OpenApiConfig::default());
+
+  assert_json_snapshot!(document["paths"]);
+}
+
+#[test]
+fn can_export_generic_shapes_with_their_bindings() {
+  let events: Vec<SpecEvent> = serde_json::from_value(json!([
+    {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"users"}},
+    {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"GET"}},
+    {"ResponseAddedByPathAndMethod":{"responseId":"response_1","httpStatusCode":200,"pathId":"path_1","httpMethod":"GET"}},
+    {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeAdded":{"shapeId":"user_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"User"}},
+    {"FieldAdded":{"fieldId":"field_1","shapeId":"user_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_shape_1"}}}},
+
+    // Page<T> { items: List<T>, first: T }
+    {"ShapeAdded":{"shapeId":"page_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"Page"}},
+    {"ShapeParameterAdded":{"shapeParameterId":"page_item_1","shapeId":"page_1","name":"T","shapeDescriptor":{"ProviderInShape":{"shapeId":"page_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"page_item_1"}}}},
+    {"ShapeAdded":{"shapeId":"list_1","baseShapeId":"$list","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"FieldAdded":{"fieldId":"field_2","shapeId":"page_1","name":"items","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"list_1"}}}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInField":{"fieldId":"field_2","providerDescriptor":{"ParameterProvider":{"shapeParameterId":"page_item_1"}},"consumingParameterId":"$listItem"}}}},
+    {"FieldAdded":{"fieldId":"field_3","shapeId":"page_1","name":"first","shapeDescriptor":{"FieldShapeFromParameter":{"fieldId":"field_3","shapeParameterId":"page_item_1"}}}},
+
+    // Page<User>
+    {"ShapeAdded":{"shapeId":"user_page_1","baseShapeId":"$reference","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"user_page_1","providerDescriptor":{"ShapeProvider":{"shapeId":"page_1"}},"consumingParameterId":"$referenceTarget"}}}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"user_page_1","providerDescriptor":{"ShapeProvider":{"shapeId":"user_1"}},"consumingParameterId":"page_item_1"}}}},
+    {"ResponseBodySet":{"responseId":"response_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"user_page_1","isRemoved":false}}},
+  ]))
+  .expect("should be able to deserialize events");
+  let spec_projection = SpecProjection::from(events);
+
+  let document = export_openapi(&spec_projection, &OpenApiConfig::default());
+
+  let schema = &document["paths"]["/users"]["get"]["responses"]["200"]["content"]
+    ["application/json"]["schema"];
+  assert_eq!(
+    schema["properties"]["first"]["properties"]["name"]["type"],
+    "string"
+  );
+  assert_json_snapshot!(schema);
+}
+
+#[test]
+fn can_export_recursive_shapes() {
+  let events: Vec<SpecEvent> = serde_json::from_value(json!([
+    {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"categories"}},
+    {"RequestAdded":{"requestId":"request_1","pathId":"path_1","httpMethod":"GET"}},
+    {"ResponseAddedByPathAndMethod":{"responseId":"response_1","httpStatusCode":200,"pathId":"path_1","httpMethod":"GET"}},
+    {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+
+    // Category { name: string, children: List<Category> }
+    {"ShapeAdded":{"shapeId":"category_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"Category"}},
+    {"FieldAdded":{"fieldId":"field_1","shapeId":"category_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_shape_1"}}}},
+    {"ShapeAdded":{"shapeId":"list_1","baseShapeId":"$list","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"list_1","providerDescriptor":{"ShapeProvider":{"shapeId":"category_1"}},"consumingParameterId":"$listItem"}}}},
+    {"FieldAdded":{"fieldId":"field_2","shapeId":"category_1","name":"children","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"list_1"}}}},
+    {"ResponseBodySet":{"responseId":"response_1","bodyDescriptor":{"httpContentType":"application/json","shapeId":"category_1","isRemoved":false}}},
+  ]))
+  .expect("should be able to deserialize events");
+  let spec_projection = SpecProjection::from(events);
+
+  let document = export_openapi(&spec_projection, &OpenApiConfig::default());
+
+  assert_json_snapshot!(document);
+}
+
+#[test]
+fn can_export_path_parameter_shapes() {
+  let events: Vec<SpecEvent> = serde_json::from_value(json!([
+    {"PathComponentAdded":{"pathId":"path_1","parentPathId":"root","name":"orders"}},
+    {"PathParameterAdded":{"pathId":"path_2","parentPathId":"path_1","name":"orderId"}},
+    {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+    {"PathParameterShapeSet":{"pathId":"path_2","shapeDescriptor":{"shapeId":"number_shape_1","isRemoved":false}}},
+    {"RequestAdded":{"requestId":"request_1","pathId":"path_2","httpMethod":"GET"}},
+    {"ResponseAddedByPathAndMethod":{"responseId":"response_1","httpStatusCode":200,"pathId":"path_2","httpMethod":"GET"}},
+  ]))
+  .expect("should be able to deserialize events");
+  let spec_projection = SpecProjection::from(events);
+
+  let document = export_openapi(&spec_projection, &OpenApiConfig::default());
+
+  let parameters = &document["paths"]["/orders/{orderId}"]["get"]["parameters"];
+  assert_eq!(
+    parameters,
+    &json!([{
+      "name": "orderId",
+      "in": "path",
+      "required": true,
+      "schema": { "type": "number" },
+    }])
+  );
+}
+
+#[test]
+fn can_import_openapi_document_into_spec() {
+  let document = parse_openapi_document(PETS_OPENAPI_YAML).expect("pets document should parse");
+  let spec_projection = SpecProjection::default();
+  let mut id_generator = TestIdGenerator::default();
+
+  let import = import_openapi(&spec_projection, &document, &mut id_generator)
+    .expect("pets document should be importable");
+
+  assert_json_snapshot!(
+    "can_import_openapi_document_into_spec__unsupported",
+    import.unsupported
+  );
+
+  let events = apply_commands(spec_projection, import.commands);
+  let imported_spec = SpecProjection::from(events);
+  let exported = export_openapi(&imported_spec, &OpenApiConfig::default());
+
+  assert_json_snapshot!("can_import_openapi_document_into_spec__exported", exported);
+}
+
+#[test]
+fn openapi_import_reuses_existing_path_components() {
+  let spec_projection = SpecProjection::from(users_spec_events());
+  let document = parse_openapi_document(
+    r#"{
+      "openapi": "3.0.3",
+      "paths": {
+        "/users/{id}": { "get": { "responses": { "200": { "description": "" } } } },
+        "/users/{id}/teams": { "get": { "responses": { "200": { "description": "" } } } }
+      }
+    }"#,
+  )
+  .expect("json document should parse");
+  let mut id_generator = TestIdGenerator::default();
+
+  let import = import_openapi(&spec_projection, &document, &mut id_generator)
+    .expect("document should be importable");
+
+  assert_json_snapshot!(import.unsupported);
+  assert_json_snapshot!(import.commands);
+}
+
+#[test]
+fn openapi_import_overrides_referenced_path_item_parameters() {
+  let document = parse_openapi_document(
+    r##"{
+      "openapi": "3.0.3",
+      "paths": {
+        "/items": {
+          "parameters": [{ "$ref": "#/components/parameters/Limit" }],
+          "get": {
+            "parameters": [
+              { "name": "limit", "in": "query", "required": true, "schema": { "type": "string" } }
+            ],
+            "responses": { "200": { "description": "" } }
+          }
+        }
+      },
+      "components": {
+        "parameters": {
+          "Limit": { "name": "limit", "in": "query", "schema": { "type": "integer" } }
+        }
+      }
+    }"##,
+  )
+  .expect("json document should parse");
+  let mut id_generator = TestIdGenerator::default();
+
+  let import = import_openapi(&SpecProjection::default(), &document, &mut id_generator)
+    .expect("document should be importable");
+  assert!(import.unsupported.is_empty());
+
+  let events = apply_commands(SpecProjection::default(), import.commands);
+  let exported = export_openapi(&SpecProjection::from(events), &OpenApiConfig::default());
+  assert_eq!(
+    exported["paths"]["/items"]["get"]["parameters"],
+    json!([
+      { "in": "query", "name": "limit", "required": true, "schema": { "type": "string" } }
+    ])
+  );
+}
+
+#[test]
+fn openapi_import_rejects_other_documents() {
+  let document =
+    parse_openapi_document("swagger: '2.0'\npaths: {}\n").expect("yaml document should parse");
+  let mut id_generator = TestIdGenerator::default();
+
+  let result = import_openapi(&SpecProjection::default(), &document, &mut id_generator);
+
+  assert!(result.is_err());
+}
+
+fn apply_commands(
+  spec_projection: SpecProjection,
+  commands: Vec<optic_engine::SpecCommand>,
+) -> Vec<SpecEvent> {
+  let mut batch = append_batch_to_spec(
+    spec_projection,
+    String::from("import openapi"),
+    CommandContext::default(),
+  );
+  for command in commands {
+    batch
+      .with_command(command)
+      .expect("imported commands should apply to the spec");
+  }
+  batch.commit()
+}
+
+#[derive(Default)]
+struct TestIdGenerator {
+  counter: usize,
+}
+
+impl SpecIdGenerator for TestIdGenerator {
+  fn generate_id(&mut self, prefix: &str) -> String {
+    let id = format!("{}{}", prefix, self.counter);
+    self.counter += 1;
+    id
+  }
+}
+
+const PETS_OPENAPI_YAML: &str = r#"
+openapi: 3.0.3
+info:
+  title: Pets
+  version: 1.0.0
+paths:
+  /pets:
+    get:
+      summary: List pets
+      parameters:
+        - name: limit
+          in: query
+          description: Maximum number of pets to return
+          schema:
+            type: integer
+            minimum: 1
+            maximum: 100
+        - name: X-Trace
+          in: header
+          required: true
+          schema:
+            type: string
+        - name: session
+          in: cookie
+          schema:
+            type: string
+      responses:
+        '200':
+          description: A page of pets
+          headers:
+            X-Next:
+              schema:
+                type: string
+          content:
+            application/json:
+              schema:
+                type: array
+                items:
+                  $ref: '#/components/schemas/Pet'
+        default:
+          description: Unexpected error
+    post:
+      summary: Add a pet
+      security:
+        - apiKey: []
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/Pet'
+      responses:
+        '201':
+          description: Pet added
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/Pet'
+  /pets/{petId}:
+    parameters:
+      - $ref: '#/components/parameters/PetId'
+    get:
+      description: Look up a single pet
+      responses:
+        '200':
+          description: The pet
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/Pet'
+        4XX:
+          description: Client error
+  /pets/pet-{petId}.json:
+    get:
+      responses:
+        '200':
+          description: Mixed path segments
+components:
+  parameters:
+    PetId:
+      name: petId
+      in: path
+      required: true
+      schema:
+        type: string
+        format: uuid
+  schemas:
+    Pet:
+      type: object
+      required: [id, name]
+      properties:
+        id:
+          type: string
+          format: uuid
+          description: Unique id of the pet
+        name:
+          type: string
+          pattern: '^[a-z]+$'
+        kind:
+          type: string
+          enum: [cat, dog]
+        nickname:
+          type: string
+          nullable: true
+        tags:
+          type: array
+          items:
+            type: string
+        attributes:
+          type: object
+          additionalProperties:
+            type: number
+        owner:
+          oneOf:
+            - type: string
+            - $ref: '#/components/schemas/Owner'
+        parent:
+          $ref: '#/components/schemas/Pet'
+        extra:
+          allOf:
+            - type: object
+            - type: object
+    Owner:
+      type: object
+      properties:
+        name:
+          type: string
+"#;
@@ -1,5 +1,8 @@
 use insta::assert_debug_snapshot;
-use optic_engine::{diff_shape, BodyDescriptor, ShapeProjection, SpecEvent};
+use optic_engine::{
+  diff_query_parameters_shape, diff_shape, BodyDescriptor, DiffInteractionConfig, ShapeProjection,
+  SpecEvent,
+};
 use petgraph::dot::Dot;
 use serde_json::json;
 
@@ -19,6 +22,7 @@ fn can_match_primitive_json() {
     &shape_projection,
     Some(BodyDescriptor::from(string_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
 
   assert_eq!(
@@ -28,6 +32,54 @@ fn can_match_primitive_json() {
   );
 }
 
+#[test]
+fn can_match_query_parameter_strings_against_numbers_and_booleans() {
+  let events: Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"ShapeAdded":{"shapeId":"object_shape_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"boolean_shape_1","baseShapeId":"$boolean","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"object_shape_1","name":"page","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"number_shape_1"}}}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"object_shape_1","name":"done","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"boolean_shape_1"}}}},
+      {"FieldAdded":{"fieldId":"field_3","shapeId":"object_shape_1","name":"q","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_3","shapeId":"string_shape_1"}}}},
+    ]),
+  ).expect("should be able to deserialize shape added events as spec events");
+  let shape_projection = ShapeProjection::from(events);
+  let shape_id = String::from("object_shape_1");
+
+  let typed_query = json!({ "page": "42", "done": "true", "q": "7" });
+  let results = diff_query_parameters_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(typed_query.clone())),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_eq!(results.len(), 0);
+
+  // only query parameters are coerced, as bodies can contain actual numbers and booleans
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(typed_query)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_eq!(results.len(), 2);
+
+  let untyped_query = json!({ "page": "last", "done": "yes", "q": "7" });
+  let results = diff_query_parameters_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(untyped_query)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_debug_snapshot!(
+    "can_match_query_parameter_strings_against_numbers_and_booleans__results",
+    results
+  );
+  assert_eq!(results.len(), 2);
+}
+
 #[test]
 fn can_diff_primitive_json_and_yield_unmatched_shape() {
   let events: Vec<SpecEvent> = serde_json::from_value(
@@ -47,6 +99,7 @@ fn can_diff_primitive_json_and_yield_unmatched_shape() {
     &shape_projection,
     Some(BodyDescriptor::from(number_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -87,6 +140,7 @@ fn can_diff_primitive_json_and_yield_unspecified_shape() {
     &shape_projection,
     Some(BodyDescriptor::from(string_body)),
     &unknown_shape_id,
+    &DiffInteractionConfig::default(),
   );
   assert_eq!(results.len(), 1);
   assert_debug_snapshot!(
@@ -117,6 +171,7 @@ fn can_match_array_json() {
     &shape_projection,
     Some(BodyDescriptor::from(array_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -128,6 +183,157 @@ fn can_match_array_json() {
   assert_debug_snapshot!("can_match_array_json__fingerprints", fingerprints);
 }
 
+#[test]
+fn can_diff_map_json() {
+  let events : Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"ShapeAdded":{"shapeId":"map_1","baseShapeId":"$map","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"map_1","providerDescriptor":{"ShapeProvider":{"shapeId":"number_shape_1"}},"consumingParameterId":"$mapValue"}}}},
+      ])
+  ).expect("should be able to deserialize shape added events as spec events");
+
+  let shape_projection = ShapeProjection::from(events);
+  let shape_id = String::from("map_1");
+
+  let matching_body = json!({"user_0001": 4, "user_0002": 6, "user_0003": 8});
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(matching_body)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_eq!(results.len(), 0);
+
+  let empty_body = json!({});
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(empty_body)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_eq!(results.len(), 0);
+
+  let unmatched_body = json!({"user_0001": 4, "user_0002": "six", "user_0003": 8});
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(unmatched_body)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_debug_snapshot!("can_diff_map_json__unmatched_results", results);
+  assert_eq!(results.len(), 1);
+}
+
+#[test]
+fn can_diff_identifiers_and_references() {
+  let events : Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"user_id_1","baseShapeId":"$identifier","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"user_id_1","providerDescriptor":{"ShapeProvider":{"shapeId":"string_shape_1"}},"consumingParameterId":"$identifierInner"}}}},
+      {"ShapeAdded":{"shapeId":"user_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"User"}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"user_1","name":"id","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"user_id_1"}}}},
+      {"ShapeAdded":{"shapeId":"author_id_1","baseShapeId":"$reference","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"author_id_1","providerDescriptor":{"ShapeProvider":{"shapeId":"user_id_1"}},"consumingParameterId":"$referenceTarget"}}}},
+      ])
+  ).expect("should be able to deserialize shape added events as spec events");
+
+  let shape_projection = ShapeProjection::from(events);
+  let diff = |body: serde_json::Value, shape_id: &str| {
+    diff_shape(
+      &shape_projection,
+      Some(BodyDescriptor::from(body)),
+      &String::from(shape_id),
+      &DiffInteractionConfig::default(),
+    )
+  };
+
+  assert_eq!(diff(json!({ "id": "user-1" }), "user_1").len(), 0);
+  assert_eq!(diff(json!("user-1"), "author_id_1").len(), 0);
+
+  let unmatched_identifier_results = diff(json!({ "id": 1 }), "user_1");
+  assert_debug_snapshot!(
+    "can_diff_identifiers_and_references__unmatched_identifier_results",
+    unmatched_identifier_results
+  );
+  assert_eq!(unmatched_identifier_results.len(), 1);
+
+  let unmatched_reference_results = diff(json!({ "id": "user-1" }), "author_id_1");
+  assert_debug_snapshot!(
+    "can_diff_identifiers_and_references__unmatched_reference_results",
+    unmatched_reference_results
+  );
+  assert_eq!(unmatched_reference_results.len(), 1);
+}
+
+#[test]
+fn can_diff_generic_shapes() {
+  let events : Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"user_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"User"}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"user_1","name":"name","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_shape_1"}}}},
+
+      // Page<T> { items: List<T>, first: T }
+      {"ShapeAdded":{"shapeId":"page_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":"Page"}},
+      {"ShapeParameterAdded":{"shapeParameterId":"page_item_1","shapeId":"page_1","name":"T","shapeDescriptor":{"ProviderInShape":{"shapeId":"page_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"page_item_1"}}}},
+      {"ShapeAdded":{"shapeId":"list_1","baseShapeId":"$list","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"page_1","name":"items","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"list_1"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInField":{"fieldId":"field_2","providerDescriptor":{"ParameterProvider":{"shapeParameterId":"page_item_1"}},"consumingParameterId":"$listItem"}}}},
+      {"FieldAdded":{"fieldId":"field_3","shapeId":"page_1","name":"first","shapeDescriptor":{"FieldShapeFromParameter":{"fieldId":"field_3","shapeParameterId":"page_item_1"}}}},
+
+      // Page<User>
+      {"ShapeAdded":{"shapeId":"user_page_1","baseShapeId":"$reference","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"user_page_1","providerDescriptor":{"ShapeProvider":{"shapeId":"page_1"}},"consumingParameterId":"$referenceTarget"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"user_page_1","providerDescriptor":{"ShapeProvider":{"shapeId":"user_1"}},"consumingParameterId":"page_item_1"}}}},
+      ])
+  ).expect("should be able to deserialize shape added events as spec events");
+
+  let shape_projection = ShapeProjection::from(events);
+  let diff = |body: serde_json::Value, shape_id: &str| {
+    diff_shape(
+      &shape_projection,
+      Some(BodyDescriptor::from(body)),
+      &String::from(shape_id),
+      &DiffInteractionConfig::default(),
+    )
+  };
+
+  let user_page = json!({
+    "items": [{ "name": "Homer" }, { "name": "Marge" }],
+    "first": { "name": "Homer" }
+  });
+  assert_eq!(diff(user_page, "user_page_1").len(), 0);
+
+  let unmatched_user_page_results = diff(
+    json!({
+      "items": [{ "name": "Homer" }, { "name": 3 }],
+      "first": { "name": false }
+    }),
+    "user_page_1",
+  );
+  assert_debug_snapshot!(
+    "can_diff_generic_shapes__unmatched_user_page_results",
+    unmatched_user_page_results
+  );
+  assert_eq!(unmatched_user_page_results.len(), 2);
+
+  // without anything providing its parameter, the page's items are of unknown shape
+  let unbound_page_results = diff(
+    json!({
+      "items": [{ "name": 3 }],
+      "first": false
+    }),
+    "page_1",
+  );
+  assert_debug_snapshot!(
+    "can_diff_generic_shapes__unbound_page_results",
+    unbound_page_results
+  );
+  assert_eq!(unbound_page_results.len(), 1);
+}
+
 #[test]
 fn can_yield_unmatched_shape_for_array_body() {
   let events : Vec<SpecEvent> = serde_json::from_value(
@@ -148,6 +354,7 @@ fn can_yield_unmatched_shape_for_array_body() {
     &shape_projection,
     Some(BodyDescriptor::from(array_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -184,6 +391,7 @@ fn can_yield_unmatched_shape_for_mismatched_array_items() {
     &shape_projection,
     Some(BodyDescriptor::from(array_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -223,6 +431,7 @@ fn yields_unmatched_shape_once_for_each_uniquely_shaped_array_item() {
     &shape_projection,
     Some(BodyDescriptor::from(array_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -264,6 +473,7 @@ fn can_match_shape_for_nested_arrays() {
     &shape_projection,
     Some(BodyDescriptor::from(array_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -311,6 +521,7 @@ fn can_yield_unmatched_shape_for_field() {
     &shape_projection,
     Some(BodyDescriptor::from(object_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -353,6 +564,7 @@ fn can_yield_unmatched_shape_for_missing_field() {
     &shape_projection,
     Some(BodyDescriptor::from(object_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -394,6 +606,7 @@ fn can_diff_nullable() {
     &shape_projection,
     Some(BodyDescriptor::from(array_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
 
   assert_debug_snapshot!("can_diff_nullable__results", results);
@@ -433,6 +646,7 @@ fn can_diff_optional() {
     &shape_projection,
     Some(BodyDescriptor::from(body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
 
   assert_debug_snapshot!("can_diff_optional__results", results);
@@ -495,12 +709,69 @@ fn can_diff_one_of() {
     &shape_projection,
     Some(BodyDescriptor::from(body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
 
   assert_debug_snapshot!("can_diff_one_of__results", results);
   assert_eq!(results.len(), 3);
 }
 
+#[test]
+fn can_diff_one_of_by_discriminator() {
+  let events : Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"card_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"card_field_1","shapeId":"card_1","name":"type","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"card_field_1","shapeId":"string_shape_1"}}}},
+      {"FieldAdded":{"fieldId":"card_field_2","shapeId":"card_1","name":"last4","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"card_field_2","shapeId":"string_shape_1"}}}},
+      {"ShapeAdded":{"shapeId":"bank_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"FieldAdded":{"fieldId":"bank_field_1","shapeId":"bank_1","name":"type","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"bank_field_1","shapeId":"string_shape_1"}}}},
+      {"FieldAdded":{"fieldId":"bank_field_2","shapeId":"bank_1","name":"iban","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"bank_field_2","shapeId":"string_shape_1"}}}},
+      {"ShapeAdded":{"shapeId":"oneof_shape_1","baseShapeId":"$oneOf","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterAdded":{"shapeParameterId":"oneof_parameter_1","shapeId":"oneof_shape_1","name":"","shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"oneof_parameter_1"}}}},
+      {"ShapeParameterAdded":{"shapeParameterId":"oneof_parameter_2","shapeId":"oneof_shape_1","name":"","shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"NoProvider":{}},"consumingParameterId":"oneof_parameter_2"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"card_1"}},"consumingParameterId":"oneof_parameter_1"}}}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"oneof_shape_1","providerDescriptor":{"ShapeProvider":{"shapeId":"bank_1"}},"consumingParameterId":"oneof_parameter_2"}}}},
+      {"ShapeAdded":{"shapeId":"list_1","baseShapeId":"$list","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeParameterShapeSet":{"shapeDescriptor":{"ProviderInShape":{"shapeId":"list_1","providerDescriptor":{"ShapeProvider":{"shapeId":"oneof_shape_1"}},"consumingParameterId":"$listItem"}}}},
+    ])
+  ).expect("should be able to deserialize shape added events as spec events");
+  let discriminator_events: Vec<SpecEvent> = serde_json::from_value(json!([
+    {"OneOfDiscriminatorSet":{"shapeId":"oneof_shape_1","descriptor":{"fieldName":"type","mapping":{"card":"oneof_parameter_1","bank":"oneof_parameter_2"}}}}
+  ]))
+  .expect("should be able to deserialize discriminator set events as spec events");
+
+  let body = json!([{
+    "type": "card",
+    "last4": 4242
+  }, {
+    "type": "bank",
+    "iban": "NL91ABNA0417164300"
+  }]);
+  let shape_id = String::from("list_1");
+
+  // without a discriminator, every object is diffed against both branches
+  let shape_projection = ShapeProjection::from(events.clone());
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(body.clone())),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert!(results.len() > 1);
+
+  let shape_projection = ShapeProjection::from(events.into_iter().chain(discriminator_events));
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(body)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+
+  assert_debug_snapshot!("can_diff_one_of_by_discriminator__results", results);
+  assert_eq!(results.len(), 1);
+}
+
 #[test]
 fn can_handle_base_shape_changes() {
   let events : Vec<SpecEvent> = serde_json::from_value(
@@ -538,6 +809,7 @@ fn can_handle_base_shape_changes() {
     &shape_projection,
     Some(BodyDescriptor::from(object_body)),
     &shape_id,
+    &DiffInteractionConfig::default(),
   );
   let fingerprints = results
     .iter()
@@ -548,3 +820,46 @@ fn can_handle_base_shape_changes() {
   assert_eq!(results.len(), 0);
   assert_debug_snapshot!("can_handle_base_shape_changes__fingerprints", fingerprints);
 }
+
+#[test]
+fn can_diff_shape_constraints() {
+  let events : Vec<SpecEvent> = serde_json::from_value(
+    json!([
+      {"ShapeAdded":{"shapeId":"object_1","baseShapeId":"$object","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeAdded":{"shapeId":"string_shape_1","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeConstraintAdded":{"shapeConstraintId":"shape_constraint_1","shapeId":"string_shape_1","descriptor":{"StringFormat":{"format":"date-time"}}}},
+      {"FieldAdded":{"fieldId":"field_1","shapeId":"object_1","name":"createdAt","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_1","shapeId":"string_shape_1"}}}},
+      {"ShapeAdded":{"shapeId":"string_shape_2","baseShapeId":"$string","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeConstraintAdded":{"shapeConstraintId":"shape_constraint_2","shapeId":"string_shape_2","descriptor":{"EnumValues":{"values":["active","inactive"]}}}},
+      {"FieldAdded":{"fieldId":"field_2","shapeId":"object_1","name":"status","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_2","shapeId":"string_shape_2"}}}},
+      {"ShapeAdded":{"shapeId":"number_shape_1","baseShapeId":"$number","parameters":{"DynamicParameterList":{"shapeParameterIds":[]}},"name":""}},
+      {"ShapeConstraintAdded":{"shapeConstraintId":"shape_constraint_3","shapeId":"number_shape_1","descriptor":{"Integer":{}}}},
+      {"ShapeConstraintAdded":{"shapeConstraintId":"shape_constraint_4","shapeId":"number_shape_1","descriptor":{"NumberRange":{"minimum":1.0,"maximum":100.0}}}},
+      {"FieldAdded":{"fieldId":"field_3","shapeId":"object_1","name":"count","shapeDescriptor":{"FieldShapeFromShape":{"fieldId":"field_3","shapeId":"number_shape_1"}}}},
+      {"ShapeConstraintRemoved":{"shapeConstraintId":"shape_constraint_4"}},
+      ])
+  ).expect("should be able to deserialize shape constraint events as spec events");
+
+  let shape_projection = ShapeProjection::from(events);
+  let shape_id = String::from("object_1");
+
+  let matching_body =
+    json!({"createdAt": "2021-03-04T12:30:00Z", "status": "active", "count": 400});
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(matching_body)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_eq!(results.len(), 0);
+
+  let unmatched_body = json!({"createdAt": "yesterday", "status": "deleted", "count": 2.5});
+  let results = diff_shape(
+    &shape_projection,
+    Some(BodyDescriptor::from(unmatched_body)),
+    &shape_id,
+    &DiffInteractionConfig::default(),
+  );
+  assert_debug_snapshot!("can_diff_shape_constraints__unmatched_results", results);
+  assert_eq!(results.len(), 3);
+}
@@ -1,6 +1,10 @@
 use insta::assert_debug_snapshot;
-use optic_engine::{SpecAssemblerProjection, SpecChunkEvent, SpecEvent};
+use optic_engine::{
+  compare_specs, spec_events_at_batch_commit, ChangeLocation, RfcEvent, SpecAssemblerProjection,
+  SpecChangeKind, SpecChunkEvent, SpecEvent, SpecProjection,
+};
 use serde_json::json;
+use std::fs;
 
 #[test]
 pub fn can_assemble_spec_events_from_serialized_chunks() {
@@ -82,3 +86,67 @@ pub fn can_assemble_spec_events_from_serialized_chunks() {
     assembled_events
   );
 }
+
+#[test]
+pub fn can_assemble_branching_spec_chunks_in_topological_order() {
+  let api_dir_path = std::env::current_dir()
+    .unwrap()
+    .join("tests/fixtures/branching-spec-changes/");
+  let spec_chunk_events: Vec<SpecChunkEvent> = fs::read_dir(&api_dir_path)
+    .unwrap()
+    .map(|entry| {
+      let entry = entry.unwrap();
+      let name = entry.file_name().into_string().unwrap();
+      let events: Vec<SpecEvent> =
+        serde_json::from_slice(&fs::read(entry.path()).unwrap()).unwrap();
+      let is_root = name == "specification.json";
+
+      SpecChunkEvent::from((name, is_root, events))
+    })
+    .collect();
+
+  let assembled_events = SpecAssemblerProjection::from(spec_chunk_events)
+    .into_events()
+    .expect("example chunks should assemble");
+
+  let batch_ids = assembled_events
+    .iter()
+    .filter_map(|event| match event {
+      SpecEvent::RfcEvent(RfcEvent::BatchCommitStarted(e)) => Some(e.batch_id.as_str()),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  // the merge commit comes after both branches it merges, despite its chunk's name
+  assert_eq!(
+    batch_ids,
+    vec!["batch-1", "batch-2", "batch-3", "batch-4", "batch-5"]
+  );
+
+  let spec_projection = SpecProjection::from(assembled_events.clone());
+  let history = spec_projection.history();
+  assert_eq!(
+    history.get_head_commit_ids().collect::<Vec<_>>(),
+    vec!["batch-5"]
+  );
+  assert_eq!(
+    history.get_last_batch_commit_id(),
+    Some(&String::from("batch-5"))
+  );
+
+  // the spec on the branch of batch-3 doesn't have /users yet
+  let branch_events = spec_events_at_batch_commit(assembled_events, &String::from("batch-3"))
+    .expect("batch-3 should be a known batch commit");
+  let branch_projection = SpecProjection::from(branch_events);
+  assert_eq!(
+    branch_projection
+      .history()
+      .get_head_commit_ids()
+      .collect::<Vec<_>>(),
+    vec!["batch-3"]
+  );
+  let changes = compare_specs(&branch_projection, &spec_projection);
+  assert_eq!(changes.len(), 1);
+  assert_eq!(changes[0].absolute_path_pattern, "/users");
+  assert_eq!(changes[0].location, ChangeLocation::Endpoint);
+  assert_eq!(changes[0].kind, SpecChangeKind::Added);
+}